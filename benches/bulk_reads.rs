@@ -0,0 +1,143 @@
+//! Timings of the bulk read paths over a generated backlog.
+//!
+//! Run with `cargo bench --bench bulk_reads`. The backlog holds 50,000 stories with three
+//! acceptance criteria each unless `BENCH_STORIES` asks for another number of stories.
+
+use mcp_user_stories::database::{initialize_database, DbPool};
+use mcp_user_stories::repositories::Repositories;
+use mcp_user_stories::services::Services;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+const DEFAULT_STORIES: i64 = 50_000;
+const CRITERIA_PER_STORY: i64 = 3;
+const PERSONAS: i64 = 20;
+const RUNS: u32 = 3;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let stories = match std::env::var("BENCH_STORIES") {
+        Ok(value) => value.parse()?,
+        Err(_) => DEFAULT_STORIES,
+    };
+
+    let dir = tempfile::tempdir()?;
+    let url = format!("sqlite://{}", dir.path().join("bench.db").display());
+    let pool = initialize_database(&url).await?;
+
+    let started = Instant::now();
+    generate_backlog(&pool, stories).await?;
+    println!(
+        "generated {} stories with {} criteria in {:.2?}",
+        stories,
+        stories * CRITERIA_PER_STORY,
+        started.elapsed()
+    );
+
+    let services = Services::new(Repositories::new(pool));
+
+    bench("get_all_with_criteria", || async {
+        let stories = services.user_stories.get_all_with_criteria().await?;
+        Ok(stories.len())
+    })
+    .await?;
+    bench("user story statistics", || async {
+        let statistics = services.user_stories.get_statistics().await?;
+        Ok(statistics.total_stories as usize)
+    })
+    .await?;
+    bench("acceptance criteria statistics", || async {
+        let statistics = services.acceptance_criteria.get_statistics().await?;
+        Ok(statistics.total_criteria as usize)
+    })
+    .await?;
+    bench("get_grouped_by_persona", || async {
+        let grouped = services.user_stories.get_grouped_by_persona().await?;
+        Ok(grouped.len())
+    })
+    .await?;
+    bench("export_backlog", || async {
+        let document = services.backlog.export_backlog().await?;
+        Ok(document.user_stories.len())
+    })
+    .await?;
+    bench("lint_backlog", || async {
+        let report = services.quality.lint_backlog().await?;
+        Ok(report.stories.len())
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Fill the database with personas, stories and criteria in a few set-based statements
+async fn generate_backlog(pool: &DbPool, stories: i64) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < ?1)
+        INSERT INTO personas (name) SELECT 'Persona ' || i FROM n
+        "#,
+    )
+    .bind(PERSONAS)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < ?1)
+        INSERT INTO user_stories (id, title, description, persona_id, created_at, updated_at)
+        SELECT
+            printf('US-%06d', i),
+            'Story ' || i,
+            'As a user, I want feature ' || i || ' so that I get value ' || i,
+            (SELECT id FROM personas WHERE name = 'Persona ' || (i % ?2 + 1)),
+            datetime('2024-01-01', '+' || i || ' seconds'),
+            datetime('2024-01-01', '+' || i || ' seconds')
+        FROM n
+        "#,
+    )
+    .bind(stories)
+    .bind(PERSONAS)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < ?1)
+        INSERT INTO acceptance_criteria (id, user_story_id, description, created_at, updated_at)
+        SELECT
+            printf('AC-%07d', i),
+            printf('US-%06d', (i - 1) / ?2 + 1),
+            'Given a user, When they use feature ' || i || ', Then it works',
+            datetime('2024-01-01', '+' || i || ' seconds'),
+            datetime('2024-01-01', '+' || i || ' seconds')
+        FROM n
+        "#,
+    )
+    .bind(stories * CRITERIA_PER_STORY)
+    .bind(CRITERIA_PER_STORY)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await
+}
+
+/// Time a read path over a few runs and print the fastest, with the number of items it
+/// returned
+async fn bench<F, Fut>(name: &str, run: F) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<usize, Box<dyn std::error::Error>>>,
+{
+    let mut fastest = Duration::MAX;
+    let mut items = 0;
+    for _ in 0..RUNS {
+        let started = Instant::now();
+        items = run().await?;
+        fastest = fastest.min(started.elapsed());
+    }
+    println!("{name:<32} {fastest:>10.2?}  ({items} items)");
+    Ok(())
+}
@@ -8,7 +8,6 @@ use rmcp::{
     model::{CallToolResult, Implementation, ProtocolVersion, ServerCapabilities, ServerInfo},
     schemars,
     service::RoleServer,
-    tool, tool_router,
     transport::stdio,
     ErrorData, Json, ServiceExt,
 };
@@ -21,6 +20,7 @@ use tokio::sync::Mutex;
 pub struct SimpleCounter {
     count: Arc<Mutex<i32>>,
     messages: Arc<Mutex<HashMap<String, String>>>,
+    #[allow(dead_code)]
     tool_router: rmcp::handler::server::tool::ToolRouter<Self>,
 }
 
@@ -56,6 +56,12 @@ pub struct MessageResponse {
     pub message: String,
 }
 
+impl Default for SimpleCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // This would use #[tool_router] in a working implementation
 impl SimpleCounter {
     pub fn new() -> Self {
@@ -1,7 +1,13 @@
+-- Test personas fixtures
+INSERT INTO personas (id, name, description, goals, pain_points, created_at, updated_at) VALUES
+(1, 'Registered User', 'A user with an existing account', '["Reach their account quickly"]', '["Forgotten passwords"]', '2024-01-01 10:00:00', '2024-01-01 10:00:00'),
+(2, 'New User', 'A visitor signing up for the first time', '["Get started without friction"]', '[]', '2024-01-01 11:00:00', '2024-01-01 11:00:00'),
+(3, 'End User', '', '[]', '[]', '2024-01-01 14:00:00', '2024-01-01 14:00:00');
+
 -- Test user stories fixtures
-INSERT INTO user_stories (id, title, description, persona, created_at, updated_at) VALUES
-('US-001', 'User Login Feature', 'As a registered user, I want to log into the system so that I can access my account', 'Registered User', '2024-01-01 10:00:00', '2024-01-01 10:00:00'),
-('US-002', 'User Registration', 'As a new user, I want to create an account so that I can access the platform', 'New User', '2024-01-01 11:00:00', '2024-01-01 11:00:00'),
-('US-003', 'Password Reset', 'As a user, I want to reset my password so that I can regain access to my account', 'Registered User', '2024-01-01 12:00:00', '2024-01-01 12:00:00'),
-('US-004', 'Profile Management', 'As a user, I want to update my profile information so that it stays current', 'Registered User', '2024-01-01 13:00:00', '2024-01-01 13:00:00'),
-('US-005', 'Search Functionality', 'As a user, I want to search for content so that I can find relevant information', 'End User', '2024-01-01 14:00:00', '2024-01-01 14:00:00');
+INSERT INTO user_stories (id, title, description, persona_id, created_at, updated_at) VALUES
+('US-001', 'User Login Feature', 'As a registered user, I want to log into the system so that I can access my account', 1, '2024-01-01 10:00:00', '2024-01-01 10:00:00'),
+('US-002', 'User Registration', 'As a new user, I want to create an account so that I can access the platform', 2, '2024-01-01 11:00:00', '2024-01-01 11:00:00'),
+('US-003', 'Password Reset', 'As a user, I want to reset my password so that I can regain access to my account', 1, '2024-01-01 12:00:00', '2024-01-01 12:00:00'),
+('US-004', 'Profile Management', 'As a user, I want to update my profile information so that it stays current', 1, '2024-01-01 13:00:00', '2024-01-01 13:00:00'),
+('US-005', 'Search Functionality', 'As a user, I want to search for content so that I can find relevant information', 3, '2024-01-01 14:00:00', '2024-01-01 14:00:00');
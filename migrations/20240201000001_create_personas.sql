@@ -0,0 +1,34 @@
+-- Create personas table; names are unique regardless of case
+CREATE TABLE personas (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    description TEXT NOT NULL DEFAULT '',
+    -- JSON arrays of strings
+    goals TEXT NOT NULL DEFAULT '[]',
+    pain_points TEXT NOT NULL DEFAULT '[]',
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- One persona per distinct persona string ignoring case, named after its oldest story
+INSERT INTO personas (name, created_at, updated_at)
+SELECT s.persona, s.created_at, s.created_at
+FROM user_stories s
+WHERE s.rowid = (
+    SELECT first.rowid
+    FROM user_stories first
+    WHERE first.persona = s.persona COLLATE NOCASE
+    ORDER BY first.created_at, first.rowid
+    LIMIT 1
+)
+ORDER BY s.created_at, s.rowid;
+
+-- Link stories to their persona and drop the free-text column
+ALTER TABLE user_stories ADD COLUMN persona_id INTEGER REFERENCES personas(id);
+
+UPDATE user_stories
+SET persona_id = (SELECT p.id FROM personas p WHERE p.name = user_stories.persona);
+
+ALTER TABLE user_stories DROP COLUMN persona;
+
+CREATE INDEX idx_user_stories_persona_id ON user_stories(persona_id);
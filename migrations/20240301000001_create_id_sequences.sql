@@ -0,0 +1,5 @@
+-- Last number handed out for each id prefix, e.g. 'US-' or 'AC-105-'
+CREATE TABLE id_sequences (
+    prefix TEXT PRIMARY KEY NOT NULL,
+    last_value INTEGER NOT NULL
+);
@@ -0,0 +1,3 @@
+-- Version counters for optimistic concurrency; every write increments them
+ALTER TABLE user_stories ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE acceptance_criteria ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,7 @@
+-- Deleted rows stay in the trash until restored or purged; NULL means live
+ALTER TABLE user_stories ADD COLUMN deleted_at DATETIME;
+ALTER TABLE acceptance_criteria ADD COLUMN deleted_at DATETIME;
+
+-- Only the trash is indexed; live rows are read through the existing indexes
+CREATE INDEX idx_user_stories_deleted_at ON user_stories(deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX idx_acceptance_criteria_deleted_at ON acceptance_criteria(deleted_at) WHERE deleted_at IS NOT NULL;
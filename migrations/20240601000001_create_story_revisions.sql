@@ -0,0 +1,10 @@
+-- Named snapshots of a user story with its acceptance criteria, numbered per story
+CREATE TABLE story_revisions (
+    user_story_id TEXT NOT NULL,
+    revision INTEGER NOT NULL,
+    note TEXT NOT NULL DEFAULT '',
+    snapshot TEXT NOT NULL, -- JSON of the story with its criteria
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_story_id, revision),
+    FOREIGN KEY (user_story_id) REFERENCES user_stories(id) ON DELETE CASCADE
+);
@@ -0,0 +1,21 @@
+-- Discussion on a user story or one of its acceptance criteria. Replies point at the
+-- top-level comment that starts their thread; only threads are resolved.
+CREATE TABLE comments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    user_story_id TEXT NOT NULL,
+    acceptance_criteria_id TEXT, -- NULL for comments on the story itself
+    parent_id INTEGER,
+    author TEXT NOT NULL,
+    body TEXT NOT NULL,
+    mentions TEXT NOT NULL DEFAULT '[]', -- JSON array of names mentioned with @
+    resolved_by TEXT,
+    resolved_at DATETIME,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_story_id) REFERENCES user_stories(id) ON DELETE CASCADE,
+    FOREIGN KEY (acceptance_criteria_id) REFERENCES acceptance_criteria(id) ON DELETE CASCADE,
+    FOREIGN KEY (parent_id) REFERENCES comments(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_comments_user_story_id ON comments(user_story_id);
+CREATE INDEX idx_comments_parent_id ON comments(parent_id);
@@ -0,0 +1,14 @@
+-- People and agents that stories are assigned to or reviewed by
+CREATE TABLE members (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    kind TEXT NOT NULL DEFAULT 'human' CHECK (kind IN ('human', 'agent')),
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+ALTER TABLE user_stories ADD COLUMN assignee_id INTEGER REFERENCES members(id);
+ALTER TABLE user_stories ADD COLUMN reviewer_id INTEGER REFERENCES members(id);
+
+CREATE INDEX idx_user_stories_assignee_id ON user_stories(assignee_id);
+CREATE INDEX idx_user_stories_reviewer_id ON user_stories(reviewer_id);
@@ -0,0 +1,10 @@
+-- Leases agents take on user stories while working on them; a claim past expires_at is void
+CREATE TABLE story_claims (
+    user_story_id TEXT PRIMARY KEY NOT NULL REFERENCES user_stories(id) ON DELETE CASCADE,
+    agent_id TEXT NOT NULL,
+    claimed_at DATETIME NOT NULL,
+    heartbeat_at DATETIME NOT NULL,
+    expires_at DATETIME NOT NULL
+);
+
+CREATE INDEX idx_story_claims_expires_at ON story_claims(expires_at);
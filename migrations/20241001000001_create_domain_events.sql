@@ -0,0 +1,104 @@
+-- Outbox of changes to user stories and acceptance criteria. The triggers below record every
+-- change in the same transaction as the write itself, whichever code path made it.
+CREATE TABLE domain_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    kind TEXT NOT NULL,
+    user_story_id TEXT NOT NULL,
+    acceptance_criteria_id TEXT,
+    payload TEXT NOT NULL, -- JSON of the story or criterion after the change
+    occurred_at DATETIME NOT NULL
+);
+
+CREATE INDEX idx_domain_events_user_story_id ON domain_events(user_story_id);
+
+CREATE TABLE webhooks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    event_kinds TEXT NOT NULL DEFAULT '[]', -- JSON array of kinds; empty for every kind
+    last_event_id INTEGER NOT NULL DEFAULT 0, -- events up to this one have been queued
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE webhook_deliveries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    webhook_id INTEGER NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+    event_id INTEGER NOT NULL REFERENCES domain_events(id) ON DELETE CASCADE,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'delivered', 'dead')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at DATETIME NOT NULL,
+    last_error TEXT,
+    delivered_at DATETIME,
+    UNIQUE (webhook_id, event_id)
+);
+
+CREATE INDEX idx_webhook_deliveries_due ON webhook_deliveries(status, next_attempt_at);
+
+CREATE TRIGGER user_stories_created AFTER INSERT ON user_stories
+BEGIN
+    INSERT INTO domain_events (kind, user_story_id, payload, occurred_at)
+    VALUES ('story_created', NEW.id, json_object(
+        'id', NEW.id,
+        'title', NEW.title,
+        'description', NEW.description,
+        'persona', (SELECT name FROM personas WHERE id = NEW.persona_id),
+        'assignee', (SELECT name FROM members WHERE id = NEW.assignee_id),
+        'reviewer', (SELECT name FROM members WHERE id = NEW.reviewer_id),
+        'version', NEW.version
+    ), strftime('%Y-%m-%d %H:%M:%f', 'now'));
+END;
+
+CREATE TRIGGER user_stories_changed AFTER UPDATE ON user_stories
+BEGIN
+    INSERT INTO domain_events (kind, user_story_id, payload, occurred_at)
+    VALUES (
+        CASE
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'story_deleted'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'story_restored'
+            ELSE 'story_updated'
+        END,
+        NEW.id,
+        json_object(
+            'id', NEW.id,
+            'title', NEW.title,
+            'description', NEW.description,
+            'persona', (SELECT name FROM personas WHERE id = NEW.persona_id),
+            'assignee', (SELECT name FROM members WHERE id = NEW.assignee_id),
+            'reviewer', (SELECT name FROM members WHERE id = NEW.reviewer_id),
+            'version', NEW.version
+        ),
+        strftime('%Y-%m-%d %H:%M:%f', 'now')
+    );
+END;
+
+CREATE TRIGGER acceptance_criteria_created AFTER INSERT ON acceptance_criteria
+BEGIN
+    INSERT INTO domain_events (kind, user_story_id, acceptance_criteria_id, payload, occurred_at)
+    VALUES ('criteria_added', NEW.user_story_id, NEW.id, json_object(
+        'id', NEW.id,
+        'user_story_id', NEW.user_story_id,
+        'description', NEW.description,
+        'version', NEW.version
+    ), strftime('%Y-%m-%d %H:%M:%f', 'now'));
+END;
+
+CREATE TRIGGER acceptance_criteria_changed AFTER UPDATE ON acceptance_criteria
+BEGIN
+    INSERT INTO domain_events (kind, user_story_id, acceptance_criteria_id, payload, occurred_at)
+    VALUES (
+        CASE
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'criteria_deleted'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'criteria_restored'
+            ELSE 'criteria_updated'
+        END,
+        NEW.user_story_id,
+        NEW.id,
+        json_object(
+            'id', NEW.id,
+            'user_story_id', NEW.user_story_id,
+            'description', NEW.description,
+            'version', NEW.version
+        ),
+        strftime('%Y-%m-%d %H:%M:%f', 'now')
+    );
+END;
@@ -0,0 +1,35 @@
+-- Stories and criteria written before the outbox existed have no events, so a change feed read
+-- from cursor 0 would leave them out. Record a creation event for each live one that has none,
+-- stories before their criteria.
+INSERT INTO domain_events (kind, user_story_id, payload, occurred_at)
+SELECT 'story_created', s.id, json_object(
+    'id', s.id,
+    'title', s.title,
+    'description', s.description,
+    'persona', (SELECT name FROM personas WHERE id = s.persona_id),
+    'assignee', (SELECT name FROM members WHERE id = s.assignee_id),
+    'reviewer', (SELECT name FROM members WHERE id = s.reviewer_id),
+    'version', s.version
+), strftime('%Y-%m-%d %H:%M:%f', 'now')
+FROM user_stories s
+WHERE s.deleted_at IS NULL
+    AND NOT EXISTS (
+        SELECT 1 FROM domain_events e
+        WHERE e.user_story_id = s.id AND e.acceptance_criteria_id IS NULL
+    )
+ORDER BY s.created_at, s.id;
+
+INSERT INTO domain_events (kind, user_story_id, acceptance_criteria_id, payload, occurred_at)
+SELECT 'criteria_added', c.user_story_id, c.id, json_object(
+    'id', c.id,
+    'user_story_id', c.user_story_id,
+    'description', c.description,
+    'version', c.version
+), strftime('%Y-%m-%d %H:%M:%f', 'now')
+FROM acceptance_criteria c
+JOIN user_stories s ON s.id = c.user_story_id
+WHERE c.deleted_at IS NULL AND s.deleted_at IS NULL
+    AND NOT EXISTS (
+        SELECT 1 FROM domain_events e WHERE e.acceptance_criteria_id = c.id
+    )
+ORDER BY c.created_at, c.id;
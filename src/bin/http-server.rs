@@ -0,0 +1,27 @@
+use mcp_user_stories::database::DatabaseConfig;
+use mcp_user_stories::http::serve;
+use mcp_user_stories::services::ServiceConfig;
+use std::env;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Get database URL and listen address from environment variables or use defaults
+    let database_url =
+        env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite://./user_stories.db".to_string());
+    let address = env::var("HTTP_ADDR").unwrap_or_else(|_| "127.0.0.1:8080".to_string());
+
+    eprintln!(
+        "Starting User Stories HTTP API with database: {}",
+        database_url
+    );
+
+    serve(
+        &database_url,
+        &DatabaseConfig::from_env()?,
+        &address,
+        ServiceConfig::from_env()?,
+    )
+    .await?;
+
+    Ok(())
+}
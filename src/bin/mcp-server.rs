@@ -1,4 +1,6 @@
+use mcp_user_stories::database::DatabaseConfig;
 use mcp_user_stories::mcp::run_server;
+use mcp_user_stories::services::ServiceConfig;
 use std::env;
 
 #[tokio::main]
@@ -13,7 +15,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     );
 
     // Run the MCP server
-    run_server(&database_url).await?;
+    run_server(
+        &database_url,
+        DatabaseConfig::from_env()?,
+        ServiceConfig::from_env()?,
+    )
+    .await?;
 
     Ok(())
 }
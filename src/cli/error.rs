@@ -0,0 +1,117 @@
+use crate::database::DatabaseConfigError;
+use crate::formats::CsvError;
+use crate::models::ValidationPolicyError;
+use crate::services::acceptance_criteria_service::AcceptanceCriteriaServiceError;
+use crate::services::backlog_service::BacklogServiceError;
+use crate::services::markdown_service::MarkdownServiceError;
+use crate::services::user_story_service::UserStoryServiceError;
+use std::process::ExitCode;
+use thiserror::Error;
+
+/// Process exit codes reported by the command line interface
+pub mod exit_code {
+    pub const SUCCESS: u8 = 0;
+    /// Database, I/O or other unexpected failures
+    pub const FAILURE: u8 = 1;
+    /// Invalid arguments (also used by the argument parser itself)
+    pub const USAGE: u8 = 2;
+    pub const NOT_FOUND: u8 = 3;
+    /// The id already exists, an import hit a conflicting id, the row changed concurrently or
+    /// another agent holds the claim on the story
+    pub const CONFLICT: u8 = 4;
+    /// Input rejected by validation or a business rule
+    pub const INVALID: u8 = 5;
+}
+
+#[derive(Error, Debug)]
+pub enum CliError {
+    #[error(transparent)]
+    UserStory(#[from] UserStoryServiceError),
+    #[error(transparent)]
+    AcceptanceCriteria(#[from] AcceptanceCriteriaServiceError),
+    #[error(transparent)]
+    Backlog(#[from] BacklogServiceError),
+    #[error(transparent)]
+    Markdown(#[from] MarkdownServiceError),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error(transparent)]
+    DatabaseConfig(#[from] DatabaseConfigError),
+    #[error("Invalid CSV: {0}")]
+    Csv(#[from] CsvError),
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Policy(#[from] ValidationPolicyError),
+    #[error("{0}")]
+    Usage(String),
+    /// Input that was read successfully but rejected as a whole
+    #[error("{0}")]
+    Rejected(String),
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            CliError::UserStory(e) => CliError::exit_code_of_user_story(e),
+            CliError::AcceptanceCriteria(e) => CliError::exit_code_of_acceptance_criteria(e),
+            CliError::Backlog(e) => match e {
+                BacklogServiceError::NotFound { .. } => exit_code::NOT_FOUND,
+                BacklogServiceError::Conflict { .. } | BacklogServiceError::Claimed { .. } => {
+                    exit_code::CONFLICT
+                }
+                BacklogServiceError::Validation { .. }
+                | BacklogServiceError::UnsupportedVersion { .. }
+                | BacklogServiceError::Csv(_) => exit_code::INVALID,
+                BacklogServiceError::Database(_) => exit_code::FAILURE,
+            },
+            CliError::Markdown(e) => match e {
+                MarkdownServiceError::Parse(_) => exit_code::INVALID,
+                MarkdownServiceError::UserStory(e) => CliError::exit_code_of_user_story(e),
+                MarkdownServiceError::AcceptanceCriteria(e) => {
+                    CliError::exit_code_of_acceptance_criteria(e)
+                }
+            },
+            CliError::Csv(_) | CliError::Json(_) | CliError::Rejected(_) => exit_code::INVALID,
+            CliError::Usage(_) | CliError::Policy(_) | CliError::DatabaseConfig(_) => {
+                exit_code::USAGE
+            }
+            CliError::Database(_) | CliError::Io(_) => exit_code::FAILURE,
+        }
+    }
+
+    fn exit_code_of_user_story(error: &UserStoryServiceError) -> u8 {
+        match error {
+            UserStoryServiceError::NotFound { .. }
+            | UserStoryServiceError::MemberNotFound { .. } => exit_code::NOT_FOUND,
+            UserStoryServiceError::AlreadyExists { .. }
+            | UserStoryServiceError::PossibleDuplicate { .. }
+            | UserStoryServiceError::Conflict { .. }
+            | UserStoryServiceError::Claimed { .. } => exit_code::CONFLICT,
+            UserStoryServiceError::Validation { .. }
+            | UserStoryServiceError::BusinessRule { .. } => exit_code::INVALID,
+            UserStoryServiceError::Database(_) => exit_code::FAILURE,
+        }
+    }
+
+    fn exit_code_of_acceptance_criteria(error: &AcceptanceCriteriaServiceError) -> u8 {
+        match error {
+            AcceptanceCriteriaServiceError::NotFound { .. }
+            | AcceptanceCriteriaServiceError::UserStoryNotFound { .. } => exit_code::NOT_FOUND,
+            AcceptanceCriteriaServiceError::AlreadyExists { .. }
+            | AcceptanceCriteriaServiceError::Conflict { .. }
+            | AcceptanceCriteriaServiceError::Claimed { .. } => exit_code::CONFLICT,
+            AcceptanceCriteriaServiceError::Validation { .. }
+            | AcceptanceCriteriaServiceError::BusinessRule { .. } => exit_code::INVALID,
+            AcceptanceCriteriaServiceError::Database(_) => exit_code::FAILURE,
+        }
+    }
+}
+
+impl From<&CliError> for ExitCode {
+    fn from(error: &CliError) -> Self {
+        ExitCode::from(error.exit_code())
+    }
+}
@@ -0,0 +1,838 @@
+//! Command line interface of the `mcp-user-stories` binary.
+//!
+//! Every command goes through `Services`, prints human-readable text by default and
+//! pretty JSON with `--json`, and exits with a code from [`error::exit_code`].
+
+pub mod error;
+
+pub use error::{exit_code, CliError};
+
+use crate::database::{initialize_database_with, DatabaseConfig};
+use crate::formats::{CsvMapping, CsvPreset};
+use crate::models::{
+    AcceptanceCriteria, BacklogDocument, ConflictPolicy, CreateAcceptanceCriteriaRequest,
+    CreateUserStoryRequest, IdScheme, ImportOptions, UpdateAcceptanceCriteriaRequest,
+    UpdateUserStoryRequest, UserStory, UserStoryWithCriteria, ValidationPolicy, DEFAULT_PAGE_SIZE,
+};
+use crate::repositories::Repositories;
+use crate::services::{ServiceConfig, Services};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+pub type Result<T> = std::result::Result<T, CliError>;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "mcp-user-stories",
+    version,
+    about = "Manage user stories and acceptance criteria"
+)]
+pub struct Cli {
+    /// Database URL
+    #[arg(
+        long,
+        global = true,
+        env = "DATABASE_URL",
+        default_value = "sqlite://./user_stories.db"
+    )]
+    pub db: String,
+
+    /// Print results as JSON for scripting
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Prefix of generated user story ids
+    #[arg(long, global = true, env = "STORY_ID_PREFIX", default_value = "US-")]
+    pub story_prefix: String,
+
+    /// Prefix of generated acceptance criteria ids
+    #[arg(long, global = true, env = "CRITERIA_ID_PREFIX", default_value = "AC-")]
+    pub criteria_prefix: String,
+
+    /// JSON file with the validation policy (length limits, id patterns, required fields)
+    #[arg(long, global = true, env = "VALIDATION_POLICY")]
+    pub validation_policy: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Create, read, update, delete and search user stories
+    #[command(subcommand)]
+    Story(StoryCommand),
+    /// Manage the acceptance criteria of a user story
+    #[command(subcommand)]
+    Criteria(CriteriaCommand),
+    /// Show user story and acceptance criteria statistics
+    Stats,
+    /// Export the backlog as JSON, Gherkin .feature files or CSV
+    Export(ExportArgs),
+    /// Import a backlog from JSON, Gherkin .feature files or CSV
+    Import(ImportArgs),
+    /// Apply pending database migrations
+    Migrate,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum StoryCommand {
+    /// Create a user story, optionally with acceptance criteria
+    Create {
+        /// User story id, e.g. US-001; generated when omitted
+        id: Option<String>,
+        #[arg(long)]
+        title: String,
+        #[arg(long)]
+        description: String,
+        #[arg(long)]
+        persona: String,
+        /// Acceptance criterion text, numbered AC-<story>-<n>; may be repeated
+        #[arg(long = "criterion")]
+        criteria: Vec<String>,
+    },
+    /// Show a user story with its acceptance criteria
+    Get { id: String },
+    /// List user stories
+    List {
+        /// Only stories for this persona
+        #[arg(long)]
+        persona: Option<String>,
+        /// Maximum number of stories to show
+        #[arg(long)]
+        limit: Option<i64>,
+        /// Continue after the page that printed this cursor
+        #[arg(long, conflicts_with = "persona")]
+        cursor: Option<String>,
+    },
+    /// Update fields of a user story
+    Update {
+        id: String,
+        #[arg(long)]
+        title: Option<String>,
+        #[arg(long)]
+        description: Option<String>,
+        #[arg(long)]
+        persona: Option<String>,
+        /// Fail with a conflict unless the story is still at this version
+        #[arg(long)]
+        expected_version: Option<i64>,
+    },
+    /// Delete a user story and its acceptance criteria
+    Delete {
+        id: String,
+        /// Fail with a conflict unless the story is still at this version
+        #[arg(long)]
+        expected_version: Option<i64>,
+    },
+    /// Search titles, descriptions and personas
+    Search { query: String },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CriteriaCommand {
+    /// Add an acceptance criterion to a user story
+    Add {
+        user_story_id: String,
+        /// Acceptance criterion id, e.g. AC-001; generated when omitted
+        #[arg(long)]
+        id: Option<String>,
+        #[arg(long)]
+        description: String,
+    },
+    /// List the acceptance criteria of a user story
+    List { user_story_id: String },
+    /// Update the description of an acceptance criterion
+    Update {
+        id: String,
+        #[arg(long)]
+        description: String,
+        /// Fail with a conflict unless the criterion is still at this version
+        #[arg(long)]
+        expected_version: Option<i64>,
+    },
+    /// Delete an acceptance criterion
+    Delete {
+        id: String,
+        /// Fail with a conflict unless the criterion is still at this version
+        #[arg(long)]
+        expected_version: Option<i64>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BacklogFormat {
+    Json,
+    Feature,
+    Csv,
+}
+
+#[derive(Debug, Args)]
+pub struct ExportArgs {
+    #[arg(long, value_enum, default_value_t = BacklogFormat::Json)]
+    pub format: BacklogFormat,
+    /// CSV column layout: native, jira or azure_devops
+    #[arg(long, default_value = "native")]
+    pub preset: CsvPreset,
+    /// Output file (a directory for .feature files); stdout when omitted
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct ImportArgs {
+    /// Files to import; JSON and CSV take exactly one
+    #[arg(required = true)]
+    pub files: Vec<PathBuf>,
+    #[arg(long, value_enum, default_value_t = BacklogFormat::Json)]
+    pub format: BacklogFormat,
+    /// CSV column layout: native, jira or azure_devops
+    #[arg(long, default_value = "native")]
+    pub preset: CsvPreset,
+    /// Persona for CSV rows without one
+    #[arg(long)]
+    pub default_persona: Option<String>,
+    /// How to handle existing ids: skip, overwrite, fail or rename_ids
+    #[arg(long, default_value = "fail")]
+    pub policy: ConflictPolicy,
+    /// Report what would change without writing anything
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// Parse the process arguments, run the command and report errors on stderr
+pub async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let json = cli.json;
+
+    match run(cli).await {
+        Ok(()) => ExitCode::from(exit_code::SUCCESS),
+        Err(e) => {
+            if json {
+                let error =
+                    serde_json::json!({ "error": e.to_string(), "exit_code": e.exit_code() });
+                eprintln!("{}", error);
+            } else {
+                eprintln!("Error: {}", e);
+            }
+            ExitCode::from(&e)
+        }
+    }
+}
+
+/// Run a parsed command against the database it names. Pending migrations are
+/// applied whenever the database is opened.
+pub async fn run(cli: Cli) -> Result<()> {
+    let pool = initialize_database_with(&cli.db, &DatabaseConfig::from_env()?).await?;
+    let config = ServiceConfig {
+        id_scheme: IdScheme {
+            story_prefix: cli.story_prefix,
+            criteria_prefix: cli.criteria_prefix,
+            ..Default::default()
+        },
+        validation: match cli.validation_policy {
+            Some(path) => ValidationPolicy::from_file(path)?,
+            None => ValidationPolicy::default(),
+        },
+    };
+    let services = Services::with_config(Repositories::new(pool), config);
+
+    execute(&services, cli.command, Output { json: cli.json }).await
+}
+
+/// Run a command against already constructed services
+pub async fn execute(services: &Services, command: Command, output: Output) -> Result<()> {
+    match command {
+        Command::Story(command) => story(services, command, output).await,
+        Command::Criteria(command) => criteria(services, command, output).await,
+        Command::Stats => {
+            let stories = services.user_stories.get_statistics().await?;
+            let criteria = services.acceptance_criteria.get_statistics().await?;
+            let stats = serde_json::json!({
+                "user_stories": stories,
+                "acceptance_criteria": criteria,
+            });
+            output.print(&stats, || {
+                let mut personas: Vec<_> = stories.stories_by_persona.iter().collect();
+                personas.sort();
+                let mut text = format!(
+                    "User stories: {}\nAcceptance criteria: {}\nPersonas: {}\nAverage criteria per story: {:.2}",
+                    stories.total_stories,
+                    stories.total_criteria,
+                    stories.personas_count,
+                    stories.avg_criteria_per_story
+                );
+                for (persona, count) in personas {
+                    text.push_str(&format!("\n  {}: {}", persona, count));
+                }
+                let mut assignees: Vec<_> = stories.stories_by_assignee.iter().collect();
+                assignees.sort();
+                text.push_str(&format!("\nUnassigned stories: {}", stories.unassigned_stories));
+                for (assignee, count) in assignees {
+                    text.push_str(&format!("\n  {}: {}", assignee, count));
+                }
+                text
+            })
+        }
+        Command::Export(args) => export(services, args, output).await,
+        Command::Import(args) => import(services, args, output).await,
+        // Services only exist for a migrated database, so there is nothing left to do
+        Command::Migrate => output.print(&serde_json::json!({ "migrated": true }), || {
+            "Database is up to date".to_string()
+        }),
+    }
+}
+
+async fn story(services: &Services, command: StoryCommand, output: Output) -> Result<()> {
+    match command {
+        StoryCommand::Create {
+            id,
+            title,
+            description,
+            persona,
+            criteria,
+        } => {
+            // Criteria ids are generated as AC-<story>-<n> once the story id is known
+            let criteria = criteria
+                .into_iter()
+                .map(|description| CreateAcceptanceCriteriaRequest {
+                    id: None,
+                    user_story_id: String::new(),
+                    description,
+                })
+                .collect();
+            let request = CreateUserStoryRequest {
+                id,
+                title,
+                description,
+                persona,
+            };
+
+            let story = services
+                .user_stories
+                .create_with_criteria(request, criteria)
+                .await?;
+            output.print(&story, || {
+                format!(
+                    "Created {}\n\n{}",
+                    story.user_story.id,
+                    story_details(&story)
+                )
+            })
+        }
+        StoryCommand::Get { id } => {
+            let story = services.user_stories.get_with_criteria(&id).await?;
+            output.print(&story, || story_details(&story))
+        }
+        StoryCommand::List {
+            persona,
+            limit,
+            cursor,
+        } => {
+            if let Some(persona) = persona {
+                let stories = services.user_stories.get_by_persona(&persona).await?;
+                let limit = limit.map(|limit| limit as usize).unwrap_or(usize::MAX);
+                let stories: Vec<_> = stories.into_iter().take(limit).collect();
+                return output.print(&stories, || story_table(&stories));
+            }
+            if limit.is_none() && cursor.is_none() {
+                let stories = services.user_stories.get_all().await?;
+                return output.print(&stories, || story_table(&stories));
+            }
+
+            let page = services
+                .user_stories
+                .get_page(cursor.as_deref(), limit.unwrap_or(DEFAULT_PAGE_SIZE))
+                .await?;
+            output.print(&page, || {
+                let mut text = story_table(&page.items);
+                if let Some(next_cursor) = &page.next_cursor {
+                    text.push_str(&format!("\nMore stories: --cursor {}", next_cursor));
+                }
+                text
+            })
+        }
+        StoryCommand::Update {
+            id,
+            title,
+            description,
+            persona,
+            expected_version,
+        } => {
+            if title.is_none() && description.is_none() && persona.is_none() {
+                return Err(CliError::Usage(
+                    "Nothing to update: pass --title, --description or --persona".to_string(),
+                ));
+            }
+            let story = services
+                .user_stories
+                .update(
+                    &id,
+                    UpdateUserStoryRequest {
+                        title,
+                        description,
+                        persona,
+                        expected_version,
+                    },
+                )
+                .await?;
+            output.print(&story, || format!("Updated {}", story.id))
+        }
+        StoryCommand::Delete {
+            id,
+            expected_version,
+        } => {
+            services
+                .user_stories
+                .delete_at_version(&id, expected_version)
+                .await?;
+            output.print(&serde_json::json!({ "deleted": id }), || {
+                format!("Deleted {}", id)
+            })
+        }
+        StoryCommand::Search { query } => {
+            let stories = services.user_stories.search(&query).await?;
+            output.print(&stories, || story_table(&stories))
+        }
+    }
+}
+
+async fn criteria(services: &Services, command: CriteriaCommand, output: Output) -> Result<()> {
+    match command {
+        CriteriaCommand::Add {
+            user_story_id,
+            id,
+            description,
+        } => {
+            let criteria = services
+                .acceptance_criteria
+                .create(CreateAcceptanceCriteriaRequest {
+                    id,
+                    user_story_id,
+                    description,
+                })
+                .await?;
+            output.print(&criteria, || {
+                format!("Added {} to {}", criteria.id, criteria.user_story_id)
+            })
+        }
+        CriteriaCommand::List { user_story_id } => {
+            let criteria = services
+                .acceptance_criteria
+                .get_by_user_story_id(&user_story_id)
+                .await?;
+            output.print(&criteria, || criteria_list(&criteria))
+        }
+        CriteriaCommand::Update {
+            id,
+            description,
+            expected_version,
+        } => {
+            let criteria = services
+                .acceptance_criteria
+                .update(
+                    &id,
+                    UpdateAcceptanceCriteriaRequest {
+                        description: Some(description),
+                        expected_version,
+                    },
+                )
+                .await?;
+            output.print(&criteria, || format!("Updated {}", criteria.id))
+        }
+        CriteriaCommand::Delete {
+            id,
+            expected_version,
+        } => {
+            services
+                .acceptance_criteria
+                .delete_at_version(&id, expected_version)
+                .await?;
+            output.print(&serde_json::json!({ "deleted": id }), || {
+                format!("Deleted {}", id)
+            })
+        }
+    }
+}
+
+async fn export(services: &Services, args: ExportArgs, output: Output) -> Result<()> {
+    let (content, count) = match args.format {
+        BacklogFormat::Json => {
+            let document = services.backlog.export_backlog().await?;
+            let count = document.user_stories.len();
+            (serde_json::to_string_pretty(&document)?, count)
+        }
+        BacklogFormat::Csv => {
+            let count = services.user_stories.get_all().await?.len();
+            let csv = services
+                .backlog
+                .export_csv(&CsvMapping::preset(args.preset))
+                .await?;
+            (csv, count)
+        }
+        BacklogFormat::Feature => {
+            let features = services.backlog.export_features().await?;
+            let count = features.len();
+
+            if let Some(directory) = &args.output {
+                std::fs::create_dir_all(directory)?;
+                for feature in &features {
+                    std::fs::write(directory.join(&feature.file_name), &feature.content)?;
+                }
+                return output.print(&exported(count, directory), || {
+                    format!("Exported {} user stories to {}", count, directory.display())
+                });
+            }
+
+            let content = features
+                .iter()
+                .map(|feature| feature.content.as_str())
+                .collect::<Vec<_>>()
+                .join("\n");
+            (content, count)
+        }
+    };
+
+    match &args.output {
+        Some(path) => {
+            std::fs::write(path, content)?;
+            output.print(&exported(count, path), || {
+                format!("Exported {} user stories to {}", count, path.display())
+            })
+        }
+        None => {
+            // The export itself is the machine-readable output, so --json does not wrap it
+            print!("{}", content);
+            if !content.ends_with('\n') {
+                println!();
+            }
+            Ok(())
+        }
+    }
+}
+
+fn exported(count: usize, path: &Path) -> serde_json::Value {
+    serde_json::json!({ "exported": count, "path": path })
+}
+
+async fn import(services: &Services, args: ImportArgs, output: Output) -> Result<()> {
+    let options = ImportOptions {
+        policy: args.policy,
+        dry_run: args.dry_run,
+    };
+
+    let single_file = || match args.files.as_slice() {
+        [path] => Ok(path),
+        _ => Err(CliError::Usage(format!(
+            "{:?} import takes exactly one file",
+            args.format
+        ))),
+    };
+
+    match args.format {
+        BacklogFormat::Json => {
+            let document: BacklogDocument =
+                serde_json::from_str(&std::fs::read_to_string(single_file()?)?)?;
+            let report = services.backlog.import_backlog(document, options).await?;
+            output.print(&report, || {
+                import_summary(
+                    report.dry_run,
+                    report.created.len(),
+                    report.overwritten.len(),
+                    report.skipped.len(),
+                    report.renamed.len(),
+                )
+            })
+        }
+        BacklogFormat::Feature => {
+            let features = args
+                .files
+                .iter()
+                .map(std::fs::read_to_string)
+                .collect::<std::io::Result<Vec<_>>>()?;
+            let report = services.backlog.import_features(&features, options).await?;
+            output.print(&report, || {
+                import_summary(
+                    report.dry_run,
+                    report.created.len(),
+                    report.overwritten.len(),
+                    report.skipped.len(),
+                    report.renamed.len(),
+                )
+            })
+        }
+        BacklogFormat::Csv => {
+            let input = std::fs::read_to_string(single_file()?)?;
+            let mut mapping = CsvMapping::preset(args.preset);
+            if args.default_persona.is_some() {
+                mapping.default_persona = args.default_persona.clone();
+            }
+
+            let report = services
+                .backlog
+                .import_csv(&input, &mapping, options)
+                .await?;
+            output.print(&report, || {
+                let mut text = import_summary(
+                    report.import.dry_run,
+                    report.import.created.len(),
+                    report.import.overwritten.len(),
+                    report.import.skipped.len(),
+                    report.import.renamed.len(),
+                );
+                for error in &report.row_errors {
+                    text.push_str(&format!("\nrow {}: {}", error.row, error.message));
+                }
+                text
+            })?;
+
+            if report.row_errors.is_empty() {
+                Ok(())
+            } else {
+                Err(CliError::Rejected(format!(
+                    "{} CSV rows were rejected, nothing was imported",
+                    report.row_errors.len()
+                )))
+            }
+        }
+    }
+}
+
+fn import_summary(
+    dry_run: bool,
+    created: usize,
+    overwritten: usize,
+    skipped: usize,
+    renamed: usize,
+) -> String {
+    format!(
+        "{}created {}, overwritten {}, skipped {}, renamed {}",
+        if dry_run { "Dry run: " } else { "" },
+        created,
+        overwritten,
+        skipped,
+        renamed
+    )
+}
+
+/// Prints either pretty JSON or the human-readable rendering of a result
+#[derive(Debug, Clone, Copy)]
+pub struct Output {
+    pub json: bool,
+}
+
+impl Output {
+    fn print<T: Serialize>(&self, value: &T, text: impl FnOnce() -> String) -> Result<()> {
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(value)?);
+        } else {
+            println!("{}", text());
+        }
+        Ok(())
+    }
+}
+
+fn story_details(story: &UserStoryWithCriteria) -> String {
+    let user_story = &story.user_story;
+    let mut text = format!(
+        "{}: {}\nPersona: {}\n",
+        user_story.id, user_story.title, user_story.persona
+    );
+    if let Some(assignee) = &user_story.assignee {
+        text.push_str(&format!("Assignee: {}\n", assignee));
+    }
+    if let Some(reviewer) = &user_story.reviewer {
+        text.push_str(&format!("Reviewer: {}\n", reviewer));
+    }
+    text.push_str(&format!("\n{}\n", user_story.description));
+    if !story.acceptance_criteria.is_empty() {
+        text.push_str("\nAcceptance criteria:\n");
+        text.push_str(&criteria_list(&story.acceptance_criteria));
+    }
+    text.trim_end().to_string()
+}
+
+fn story_table(stories: &[UserStory]) -> String {
+    if stories.is_empty() {
+        return "No user stories found".to_string();
+    }
+    stories
+        .iter()
+        .map(|story| format!("{}\t{}\t{}", story.id, story.persona, story.title))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn criteria_list(criteria: &[AcceptanceCriteria]) -> String {
+    if criteria.is_empty() {
+        return "No acceptance criteria found".to_string();
+    }
+    criteria
+        .iter()
+        .map(|criteria| format!("  {}: {}", criteria.id, criteria.description))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn services(pool: sqlx::SqlitePool) -> Services {
+        Services::new(Repositories::new(pool))
+    }
+
+    fn parse(args: &[&str]) -> Cli {
+        Cli::try_parse_from(std::iter::once("mcp-user-stories").chain(args.iter().copied()))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_global_flags() {
+        let cli = parse(&[
+            "story",
+            "get",
+            "US-001",
+            "--json",
+            "--db",
+            "sqlite::memory:",
+        ]);
+        assert!(cli.json);
+        assert_eq!(cli.db, "sqlite::memory:");
+        assert!(matches!(
+            cli.command,
+            Command::Story(StoryCommand::Get { id }) if id == "US-001"
+        ));
+
+        let cli = parse(&["import", "a.csv", "--format", "csv", "--preset", "jira"]);
+        let Command::Import(args) = cli.command else {
+            panic!("expected import");
+        };
+        assert_eq!(args.format, BacklogFormat::Csv);
+        assert_eq!(args.preset, CsvPreset::Jira);
+        assert_eq!(args.policy, ConflictPolicy::Fail);
+    }
+
+    #[sqlx::test]
+    async fn test_story_create_with_criteria(pool: sqlx::SqlitePool) {
+        let services = services(pool);
+        let command = parse(&[
+            "story",
+            "create",
+            "US-200",
+            "--title",
+            "Checkout",
+            "--description",
+            "As a shopper, I want to pay",
+            "--persona",
+            "Shopper",
+            "--criterion",
+            "Card payments work",
+            "--criterion",
+            "Receipts are emailed",
+        ])
+        .command;
+
+        execute(&services, command, Output { json: true })
+            .await
+            .unwrap();
+
+        let story = services
+            .user_stories
+            .get_with_criteria("US-200")
+            .await
+            .unwrap();
+        let ids: Vec<&str> = story
+            .acceptance_criteria
+            .iter()
+            .map(|c| c.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["AC-200-1", "AC-200-2"]);
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_exit_codes(pool: sqlx::SqlitePool) {
+        let services = services(pool);
+        let output = Output { json: false };
+        let exit_code = |result: Result<()>| result.unwrap_err().exit_code();
+
+        let not_found = execute(
+            &services,
+            parse(&["story", "get", "US-999"]).command,
+            output,
+        );
+        assert_eq!(exit_code(not_found.await), exit_code::NOT_FOUND);
+
+        let missing_story = parse(&[
+            "criteria",
+            "add",
+            "US-999",
+            "--id",
+            "AC-9",
+            "--description",
+            "x",
+        ]);
+        assert_eq!(
+            exit_code(execute(&services, missing_story.command, output).await),
+            exit_code::NOT_FOUND
+        );
+
+        let duplicate = parse(&[
+            "story",
+            "create",
+            "US-001",
+            "--title",
+            "t",
+            "--description",
+            "d",
+            "--persona",
+            "p",
+        ]);
+        assert_eq!(
+            exit_code(execute(&services, duplicate.command, output).await),
+            exit_code::CONFLICT
+        );
+
+        let invalid = parse(&["criteria", "update", "AC-001", "--description", " "]);
+        assert_eq!(
+            exit_code(execute(&services, invalid.command, output).await),
+            exit_code::INVALID
+        );
+
+        let empty_update = parse(&["story", "update", "US-001"]);
+        assert_eq!(
+            exit_code(execute(&services, empty_update.command, output).await),
+            exit_code::USAGE
+        );
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_export_import_round_trip(pool: sqlx::SqlitePool) {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("backlog.csv");
+        let path_arg = path.to_str().unwrap();
+
+        let export = parse(&["export", "--format", "csv", "--output", path_arg]);
+        execute(&services(pool), export.command, Output { json: true })
+            .await
+            .unwrap();
+
+        let target = services(crate::database::create_test_db().await.unwrap());
+        let import = parse(&["import", path_arg, "--format", "csv"]);
+        execute(&target, import.command, Output { json: true })
+            .await
+            .unwrap();
+
+        let stats = target.user_stories.get_statistics().await.unwrap();
+        assert_eq!(stats.total_stories, 5);
+        assert_eq!(stats.total_criteria, 10);
+    }
+}
@@ -0,0 +1,152 @@
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqliteSynchronous};
+use std::str::FromStr;
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum DatabaseConfigError {
+    #[error("Invalid value '{value}' for {name}")]
+    Invalid { name: &'static str, value: String },
+}
+
+/// How connections to the SQLite database are opened and pooled
+#[derive(Debug, Clone)]
+pub struct DatabaseConfig {
+    pub journal_mode: SqliteJournalMode,
+    pub synchronous: SqliteSynchronous,
+    /// How long a statement waits for another connection's lock before failing with
+    /// SQLITE_BUSY
+    pub busy_timeout: Duration,
+    pub max_connections: u32,
+    /// Enforce foreign keys; deleting a story cascades to its acceptance criteria only when on
+    pub foreign_keys: bool,
+    /// Open the database read-only: it is neither created nor migrated, and every write fails
+    pub read_only: bool,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            journal_mode: SqliteJournalMode::Wal,
+            synchronous: SqliteSynchronous::Normal,
+            busy_timeout: Duration::from_secs(5),
+            max_connections: 10,
+            foreign_keys: true,
+            read_only: false,
+        }
+    }
+}
+
+impl DatabaseConfig {
+    /// Defaults overridden by `DB_JOURNAL_MODE`, `DB_SYNCHRONOUS`, `DB_BUSY_TIMEOUT_MS`,
+    /// `DB_MAX_CONNECTIONS`, `DB_FOREIGN_KEYS` and `DB_READ_ONLY`, where set
+    pub fn from_env() -> Result<Self, DatabaseConfigError> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, DatabaseConfigError> {
+        let mut config = Self::default();
+        if let Some(value) = var("DB_JOURNAL_MODE") {
+            config.journal_mode = parse("DB_JOURNAL_MODE", value)?;
+        }
+        if let Some(value) = var("DB_SYNCHRONOUS") {
+            config.synchronous = parse("DB_SYNCHRONOUS", value)?;
+        }
+        if let Some(value) = var("DB_BUSY_TIMEOUT_MS") {
+            config.busy_timeout = Duration::from_millis(parse("DB_BUSY_TIMEOUT_MS", value)?);
+        }
+        if let Some(value) = var("DB_MAX_CONNECTIONS") {
+            config.max_connections = match parse("DB_MAX_CONNECTIONS", value.clone())? {
+                0 => return Err(invalid("DB_MAX_CONNECTIONS", value)),
+                max => max,
+            };
+        }
+        if let Some(value) = var("DB_FOREIGN_KEYS") {
+            config.foreign_keys = parse_flag("DB_FOREIGN_KEYS", value)?;
+        }
+        if let Some(value) = var("DB_READ_ONLY") {
+            config.read_only = parse_flag("DB_READ_ONLY", value)?;
+        }
+        Ok(config)
+    }
+
+    /// Options for connections to the database at `database_url`
+    pub fn connect_options(&self, database_url: &str) -> Result<SqliteConnectOptions, sqlx::Error> {
+        let options = SqliteConnectOptions::from_str(database_url)?
+            .create_if_missing(!self.read_only)
+            .read_only(self.read_only)
+            .synchronous(self.synchronous)
+            .busy_timeout(self.busy_timeout)
+            .foreign_keys(self.foreign_keys);
+
+        // Switching the journal mode writes to the database file
+        if self.read_only {
+            Ok(options)
+        } else {
+            Ok(options.journal_mode(self.journal_mode))
+        }
+    }
+}
+
+fn parse<T: FromStr>(name: &'static str, value: String) -> Result<T, DatabaseConfigError> {
+    value.trim().parse().map_err(|_| invalid(name, value))
+}
+
+fn parse_flag(name: &'static str, value: String) -> Result<bool, DatabaseConfigError> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "on" | "yes" => Ok(true),
+        "0" | "false" | "off" | "no" => Ok(false),
+        _ => Err(invalid(name, value)),
+    }
+}
+
+fn invalid(name: &'static str, value: String) -> DatabaseConfigError {
+    DatabaseConfigError::Invalid { name, value }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn from_vars(vars: &[(&str, &str)]) -> Result<DatabaseConfig, DatabaseConfigError> {
+        let vars: HashMap<_, _> = vars.iter().copied().collect();
+        DatabaseConfig::from_vars(|name| vars.get(name).map(|value| value.to_string()))
+    }
+
+    #[test]
+    fn test_from_vars() {
+        let config = from_vars(&[]).unwrap();
+        assert_eq!(config.journal_mode, SqliteJournalMode::Wal);
+        assert!(config.foreign_keys);
+        assert!(!config.read_only);
+
+        let config = from_vars(&[
+            ("DB_JOURNAL_MODE", "delete"),
+            ("DB_SYNCHRONOUS", "FULL"),
+            ("DB_BUSY_TIMEOUT_MS", "250"),
+            ("DB_MAX_CONNECTIONS", "2"),
+            ("DB_FOREIGN_KEYS", "off"),
+            ("DB_READ_ONLY", "true"),
+        ])
+        .unwrap();
+        assert_eq!(config.journal_mode, SqliteJournalMode::Delete);
+        assert_eq!(config.synchronous, SqliteSynchronous::Full);
+        assert_eq!(config.busy_timeout, Duration::from_millis(250));
+        assert_eq!(config.max_connections, 2);
+        assert!(!config.foreign_keys);
+        assert!(config.read_only);
+
+        for (name, value) in [
+            ("DB_JOURNAL_MODE", "fast"),
+            ("DB_BUSY_TIMEOUT_MS", "-1"),
+            ("DB_MAX_CONNECTIONS", "0"),
+            ("DB_READ_ONLY", "maybe"),
+        ] {
+            assert!(matches!(
+                from_vars(&[(name, value)]),
+                Err(DatabaseConfigError::Invalid { name: invalid, .. }) if invalid == name
+            ));
+        }
+    }
+}
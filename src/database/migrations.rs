@@ -15,6 +15,9 @@ pub async fn migrate(pool: &DbPool) -> Result<(), MigrateError> {
 mod tests {
     use super::*;
     use crate::database::create_test_db;
+    use crate::models::Change;
+    use crate::repositories::Repositories;
+    use crate::services::Services;
 
     #[tokio::test]
     async fn test_migrations() {
@@ -26,4 +29,104 @@ mod tests {
         let result = migrate(&pool).await;
         assert!(result.is_ok(), "Migrations should run successfully");
     }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_persona_migration_deduplicates_names(pool: sqlx::SqlitePool) {
+        sqlx::raw_sql(include_str!(
+            "../../migrations/20240101000001_create_user_stories.sql"
+        ))
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::raw_sql(
+            r#"
+            INSERT INTO user_stories (id, title, description, persona, created_at, updated_at) VALUES
+            ('US-001', 't', 'd', 'registered user', '2024-01-02 10:00:00', '2024-01-02 10:00:00'),
+            ('US-002', 't', 'd', 'Registered User', '2024-01-01 10:00:00', '2024-01-01 10:00:00'),
+            ('US-003', 't', 'd', 'REGISTERED USER', '2024-01-03 10:00:00', '2024-01-03 10:00:00'),
+            ('US-004', 't', 'd', 'Admin', '2024-01-04 10:00:00', '2024-01-04 10:00:00');
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        sqlx::raw_sql(include_str!(
+            "../../migrations/20240201000001_create_personas.sql"
+        ))
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let personas: Vec<(i64, String)> =
+            sqlx::query_as("SELECT id, name FROM personas ORDER BY id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        let names: Vec<&str> = personas.iter().map(|(_, name)| name.as_str()).collect();
+        // The spelling of the oldest story wins
+        assert_eq!(names, vec!["Registered User", "Admin"]);
+
+        let links: Vec<(String, i64)> =
+            sqlx::query_as("SELECT id, persona_id FROM user_stories ORDER BY id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        let registered = personas[0].0;
+        let admin = personas[1].0;
+        assert_eq!(
+            links,
+            vec![
+                ("US-001".to_string(), registered),
+                ("US-002".to_string(), registered),
+                ("US-003".to_string(), registered),
+                ("US-004".to_string(), admin),
+            ]
+        );
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_domain_events_backfill(pool: sqlx::SqlitePool) {
+        // A backlog written before the outbox existed: US-005 is in the trash and nothing has
+        // an event yet
+        sqlx::raw_sql(
+            r#"
+            UPDATE user_stories SET deleted_at = '2024-02-01 10:00:00' WHERE id = 'US-005';
+            UPDATE acceptance_criteria SET deleted_at = '2024-02-01 10:00:00'
+            WHERE user_story_id = 'US-005';
+            DELETE FROM domain_events;
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let backfill = include_str!("../../migrations/20241101000001_backfill_domain_events.sql");
+        sqlx::raw_sql(backfill).execute(&pool).await.unwrap();
+        // Entities with events are left alone
+        sqlx::raw_sql(backfill).execute(&pool).await.unwrap();
+
+        let services = Services::new(Repositories::new(pool));
+        let feed = services.events.changes_since(0, 100).await.unwrap();
+        assert!(!feed.has_more);
+        let stories = feed
+            .changes
+            .iter()
+            .filter(|change| matches!(change, Change::StoryUpserted { .. }))
+            .count();
+        let criteria = feed
+            .changes
+            .iter()
+            .filter(|change| matches!(change, Change::CriteriaUpserted { .. }))
+            .count();
+        assert_eq!((stories, criteria), (4, 8));
+        assert_eq!(feed.changes.len(), 12);
+        assert!(matches!(
+            &feed.changes[0],
+            Change::StoryUpserted { user_story, .. } if user_story.id == "US-001"
+        ));
+    }
 }
@@ -1,24 +1,47 @@
-use sqlx::{Pool, Sqlite, SqlitePool};
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::{Pool, Sqlite, Transaction};
 use std::path::Path;
 
+pub mod config;
 pub mod migrations;
 
+pub use config::{DatabaseConfig, DatabaseConfigError};
+
 pub type DbPool = Pool<Sqlite>;
 
 pub async fn create_connection_pool(database_url: &str) -> Result<DbPool, sqlx::Error> {
+    create_connection_pool_with(database_url, &DatabaseConfig::default()).await
+}
+
+/// Open a pool of connections set up as `config` asks
+pub async fn create_connection_pool_with(
+    database_url: &str,
+    config: &DatabaseConfig,
+) -> Result<DbPool, sqlx::Error> {
     // Create the database file if it doesn't exist
-    if let Some(parent) = Path::new(
-        database_url
-            .strip_prefix("sqlite://")
-            .unwrap_or(database_url),
-    )
-    .parent()
-    {
-        std::fs::create_dir_all(parent).map_err(sqlx::Error::Io)?;
+    if !config.read_only {
+        if let Some(parent) = Path::new(
+            database_url
+                .strip_prefix("sqlite://")
+                .unwrap_or(database_url),
+        )
+        .parent()
+        {
+            std::fs::create_dir_all(parent).map_err(sqlx::Error::Io)?;
+        }
     }
 
-    let pool = SqlitePool::connect(database_url).await?;
-    Ok(pool)
+    SqlitePoolOptions::new()
+        .max_connections(config.max_connections)
+        .connect_with(config.connect_options(database_url)?)
+        .await
+}
+
+/// Start a transaction that takes the write lock up front. A deferred transaction that reads
+/// before it writes fails at once with SQLITE_BUSY when another connection wrote in between,
+/// without waiting out the busy timeout.
+pub async fn begin_write(pool: &DbPool) -> Result<Transaction<'static, Sqlite>, sqlx::Error> {
+    pool.begin_with("BEGIN IMMEDIATE").await
 }
 
 pub async fn run_migrations(pool: &DbPool) -> Result<(), sqlx::migrate::MigrateError> {
@@ -27,18 +50,193 @@ pub async fn run_migrations(pool: &DbPool) -> Result<(), sqlx::migrate::MigrateE
 }
 
 pub async fn initialize_database(database_url: &str) -> Result<DbPool, sqlx::Error> {
-    let pool = create_connection_pool(database_url).await?;
-    run_migrations(&pool)
-        .await
-        .map_err(|e| sqlx::Error::Migrate(Box::new(e)))?;
+    initialize_database_with(database_url, &DatabaseConfig::default()).await
+}
+
+/// Open the database as `config` asks and apply pending migrations, unless it is opened
+/// read-only
+pub async fn initialize_database_with(
+    database_url: &str,
+    config: &DatabaseConfig,
+) -> Result<DbPool, sqlx::Error> {
+    let pool = create_connection_pool_with(database_url, config).await?;
+    if !config.read_only {
+        run_migrations(&pool)
+            .await
+            .map_err(|e| sqlx::Error::Migrate(Box::new(e)))?;
+    }
     Ok(pool)
 }
 
 #[cfg(test)]
 pub async fn create_test_db() -> Result<DbPool, sqlx::Error> {
-    let pool = SqlitePool::connect(":memory:").await?;
+    let pool = DbPool::connect(":memory:").await?;
     run_migrations(&pool)
         .await
         .map_err(|e| sqlx::Error::Migrate(Box::new(e)))?;
     Ok(pool)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CreateAcceptanceCriteriaRequest, CreateUserStoryRequest};
+    use crate::repositories::Repositories;
+
+    fn story_request(id: String) -> CreateUserStoryRequest {
+        CreateUserStoryRequest {
+            id: Some(id),
+            title: "Concurrent write".to_string(),
+            description: "As a user, I want my writes to land so that nothing is lost".to_string(),
+            persona: "User".to_string(),
+        }
+    }
+
+    async fn open(config: &DatabaseConfig) -> (tempfile::TempDir, DbPool) {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}", dir.path().join("stories.db").display());
+        let pool = initialize_database_with(&url, config).await.unwrap();
+        (dir, pool)
+    }
+
+    async fn story_with_criteria(repositories: &Repositories) {
+        repositories
+            .user_stories
+            .create(story_request("US-001".to_string()))
+            .await
+            .unwrap();
+        repositories
+            .acceptance_criteria
+            .create(
+                CreateAcceptanceCriteriaRequest {
+                    id: Some("AC-001".to_string()),
+                    user_story_id: "US-001".to_string(),
+                    description: "Given a story, When it is deleted, Then so is this".to_string(),
+                },
+                None,
+            )
+            .await
+            .unwrap();
+    }
+
+    async fn delete_story(pool: &DbPool) {
+        sqlx::query("DELETE FROM user_stories WHERE id = 'US-001'")
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pragmas_applied() {
+        let (_dir, pool) = open(&DatabaseConfig::default()).await;
+
+        let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(journal_mode, "wal");
+        let foreign_keys: i64 = sqlx::query_scalar("PRAGMA foreign_keys")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(foreign_keys, 1);
+        let busy_timeout: i64 = sqlx::query_scalar("PRAGMA busy_timeout")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(busy_timeout, 5000);
+    }
+
+    #[tokio::test]
+    async fn test_delete_cascades_to_criteria() {
+        let (_dir, pool) = open(&DatabaseConfig::default()).await;
+        let repositories = Repositories::new(pool.clone());
+        story_with_criteria(&repositories).await;
+
+        delete_story(&pool).await;
+        assert_eq!(repositories.acceptance_criteria.count().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_delete_without_foreign_keys_leaves_criteria() {
+        let config = DatabaseConfig {
+            foreign_keys: false,
+            ..Default::default()
+        };
+        let (_dir, pool) = open(&config).await;
+        let repositories = Repositories::new(pool.clone());
+        story_with_criteria(&repositories).await;
+
+        delete_story(&pool).await;
+        let orphans: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM acceptance_criteria")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(orphans, 1);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_writers() {
+        let (dir, pool) = open(&DatabaseConfig::default()).await;
+        // A second pool stands in for another process writing to the same file
+        let url = format!("sqlite://{}", dir.path().join("stories.db").display());
+        let other = create_connection_pool_with(&url, &DatabaseConfig::default())
+            .await
+            .unwrap();
+
+        let mut writers = Vec::new();
+        for writer in 0..8 {
+            let repositories = Repositories::new(if writer % 2 == 0 {
+                pool.clone()
+            } else {
+                other.clone()
+            });
+            writers.push(tokio::spawn(async move {
+                let mut restored = 0;
+                for story in 0..25 {
+                    let id = format!("US-{writer}-{story}");
+                    repositories
+                        .user_stories
+                        .create(story_request(id.clone()))
+                        .await?;
+                    repositories.user_stories.delete(&id, None, None).await?;
+                    // Restoring reads the story before it writes
+                    if repositories.trash.restore_user_story(&id).await? {
+                        restored += 1;
+                    }
+                }
+                Ok::<_, sqlx::Error>(restored)
+            }));
+        }
+        for writer in writers {
+            assert_eq!(writer.await.unwrap().unwrap(), 25);
+        }
+
+        let stories = Repositories::new(pool).user_stories.count().await.unwrap();
+        assert_eq!(stories, 200);
+    }
+
+    #[tokio::test]
+    async fn test_read_only() {
+        let (dir, pool) = open(&DatabaseConfig::default()).await;
+        story_with_criteria(&Repositories::new(pool)).await;
+
+        let config = DatabaseConfig {
+            read_only: true,
+            ..Default::default()
+        };
+        let url = format!("sqlite://{}", dir.path().join("stories.db").display());
+        let repositories =
+            Repositories::new(initialize_database_with(&url, &config).await.unwrap());
+        assert_eq!(repositories.user_stories.count().await.unwrap(), 1);
+        assert!(repositories
+            .user_stories
+            .create(story_request("US-002".to_string()))
+            .await
+            .is_err());
+
+        // A missing database is not created
+        let missing = format!("sqlite://{}", dir.path().join("missing.db").display());
+        assert!(initialize_database_with(&missing, &config).await.is_err());
+    }
+}
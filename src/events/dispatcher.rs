@@ -0,0 +1,401 @@
+use crate::events::{
+    sign, EventBus, EVENT_ID_HEADER, EVENT_KIND_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER,
+};
+use crate::models::DispatchReport;
+use crate::repositories::event_repository::DueDelivery;
+use crate::repositories::Repositories;
+use chrono::{NaiveDateTime, Utc};
+use std::time::Duration;
+
+/// Longest wait between attempts, whatever `DispatcherConfig::max_backoff` says
+pub const BACKOFF_LIMIT: Duration = Duration::from_secs(7 * 24 * 3600);
+
+/// How the dispatcher polls, retries and gives up
+#[derive(Debug, Clone)]
+pub struct DispatcherConfig {
+    /// Attempts per delivery before it moves to the dead letters
+    pub max_attempts: i64,
+    /// Wait after the first failed attempt; doubled after each further one
+    pub initial_backoff: Duration,
+    /// Longest wait between attempts; capped at [`BACKOFF_LIMIT`]
+    pub max_backoff: Duration,
+    pub request_timeout: Duration,
+    /// How often the outbox is checked when no service rings the bus
+    pub poll_interval: Duration,
+    /// Events and deliveries handled per round
+    pub batch_size: i64,
+}
+
+impl Default for DispatcherConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 8,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(3600),
+            request_timeout: Duration::from_secs(10),
+            poll_interval: Duration::from_secs(5),
+            batch_size: 100,
+        }
+    }
+}
+
+impl DispatcherConfig {
+    /// Wait before the next attempt after `attempts` failed ones
+    pub fn backoff(&self, attempts: i64) -> Duration {
+        let doublings = attempts.saturating_sub(1).clamp(0, 31) as u32;
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(doublings))
+            .min(self.max_backoff)
+            .min(BACKOFF_LIMIT)
+    }
+
+    /// When to try again after `attempts` failed ones, counting from `now`
+    fn retry_at(&self, attempts: i64, now: NaiveDateTime) -> NaiveDateTime {
+        let backoff = chrono::Duration::from_std(self.backoff(attempts))
+            .unwrap_or_else(|_| chrono::Duration::weeks(1));
+        now.checked_add_signed(backoff)
+            .unwrap_or(NaiveDateTime::MAX)
+    }
+}
+
+/// Relays outbox events to the [`EventBus`] and delivers them to webhooks
+pub struct WebhookDispatcher {
+    repositories: Repositories,
+    bus: EventBus,
+    client: reqwest::Client,
+    config: DispatcherConfig,
+    /// Last event handed to the bus; starts at the end of the outbox on the first round
+    published_up_to: Option<i64>,
+}
+
+impl WebhookDispatcher {
+    pub fn new(repositories: Repositories, bus: EventBus) -> Self {
+        Self::with_config(repositories, bus, DispatcherConfig::default())
+    }
+
+    pub fn with_config(
+        repositories: Repositories,
+        bus: EventBus,
+        config: DispatcherConfig,
+    ) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(config.request_timeout)
+            .build()
+            .unwrap_or_default();
+        Self {
+            repositories,
+            bus,
+            client,
+            config,
+            published_up_to: None,
+        }
+    }
+
+    /// Publish new events, queue them for the webhooks that want them and attempt every
+    /// delivery that is due
+    pub async fn run_once(&mut self) -> Result<DispatchReport, sqlx::Error> {
+        let mut report = DispatchReport {
+            published: self.publish().await?,
+            ..Default::default()
+        };
+
+        self.repositories.events.enqueue_deliveries().await?;
+
+        let due = self
+            .repositories
+            .events
+            .get_due(self.config.batch_size)
+            .await?;
+        for delivery in due {
+            match self.send(&delivery).await {
+                Ok(()) => {
+                    self.repositories.events.mark_delivered(delivery.id).await?;
+                    report.delivered += 1;
+                }
+                Err(error) => {
+                    let attempts = delivery.attempts + 1;
+                    let retry_at = (attempts < self.config.max_attempts)
+                        .then(|| self.config.retry_at(attempts, Utc::now().naive_utc()));
+                    if retry_at.is_some() {
+                        report.retrying += 1;
+                    } else {
+                        report.dead += 1;
+                    }
+                    self.repositories
+                        .events
+                        .mark_failed(delivery.id, &error, retry_at)
+                        .await?;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Dispatch until the process stops, waking when a service rings the bus or the poll
+    /// interval passes
+    pub async fn run(mut self) {
+        loop {
+            if let Err(e) = self.run_once().await {
+                eprintln!("Webhook dispatch failed: {}", e);
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(self.config.poll_interval) => {}
+                _ = self.bus.notified() => {}
+            }
+        }
+    }
+
+    /// Run the dispatcher in the background
+    pub fn spawn(self) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(self.run())
+    }
+
+    async fn publish(&mut self) -> Result<usize, sqlx::Error> {
+        let Some(mut after) = self.published_up_to else {
+            self.published_up_to = Some(self.repositories.events.latest_id().await?);
+            return Ok(0);
+        };
+
+        let mut published = 0;
+        loop {
+            let events = self
+                .repositories
+                .events
+                .get_after(after, self.config.batch_size)
+                .await?;
+            let Some(last) = events.last() else {
+                break;
+            };
+            after = last.id;
+            published += events.len();
+            for event in events {
+                self.bus.publish(event);
+            }
+        }
+        self.published_up_to = Some(after);
+        Ok(published)
+    }
+
+    async fn send(&self, delivery: &DueDelivery) -> Result<(), String> {
+        let body = serde_json::to_vec(&delivery.event).map_err(|e| e.to_string())?;
+        let timestamp = Utc::now().timestamp();
+
+        let response = self
+            .client
+            .post(&delivery.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, sign(&delivery.secret, timestamp, &body))
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(EVENT_ID_HEADER, delivery.event.id.to_string())
+            .header(EVENT_KIND_HEADER, delivery.event.kind.as_str())
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            Err(format!("Endpoint answered {}", status))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::verify;
+    use crate::models::{DeliveryStatus, DomainEvent, DomainEventKind, UpdateUserStoryRequest};
+    use crate::services::UserStoryService;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicU16, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    struct ReceivedRequest {
+        headers: HashMap<String, String>,
+        body: Vec<u8>,
+    }
+
+    /// A local HTTP endpoint that records requests and answers with a settable status
+    struct Receiver {
+        url: String,
+        requests: Arc<Mutex<Vec<ReceivedRequest>>>,
+        status: Arc<AtomicU16>,
+    }
+
+    async fn receiver(status: u16) -> Receiver {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hooks", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let status = Arc::new(AtomicU16::new(status));
+
+        let (recorded, answer) = (requests.clone(), status.clone());
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                let mut reader = BufReader::new(socket);
+                let mut headers = HashMap::new();
+                let mut line = String::new();
+                reader.read_line(&mut line).await.unwrap();
+                loop {
+                    line.clear();
+                    reader.read_line(&mut line).await.unwrap();
+                    match line.trim_end().split_once(':') {
+                        Some((name, value)) => {
+                            headers.insert(name.to_ascii_lowercase(), value.trim().to_string());
+                        }
+                        None => break,
+                    }
+                }
+                let length = headers
+                    .get("content-length")
+                    .map_or(0, |length| length.parse().unwrap());
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).await.unwrap();
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push(ReceivedRequest { headers, body });
+
+                let response = format!(
+                    "HTTP/1.1 {} Status\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                    answer.load(Ordering::SeqCst)
+                );
+                reader
+                    .into_inner()
+                    .write_all(response.as_bytes())
+                    .await
+                    .unwrap();
+            }
+        });
+
+        Receiver {
+            url,
+            requests,
+            status,
+        }
+    }
+
+    fn retitle(title: &str) -> UpdateUserStoryRequest {
+        UpdateUserStoryRequest {
+            title: Some(title.to_string()),
+            description: None,
+            persona: None,
+            expected_version: None,
+        }
+    }
+
+    #[test]
+    fn test_backoff() {
+        let config = DispatcherConfig::default();
+        assert_eq!(config.backoff(1), Duration::from_secs(1));
+        assert_eq!(config.backoff(4), Duration::from_secs(8));
+        assert_eq!(config.backoff(100), config.max_backoff);
+
+        let config = DispatcherConfig {
+            max_backoff: Duration::MAX,
+            ..Default::default()
+        };
+        assert_eq!(config.backoff(100), BACKOFF_LIMIT);
+        let now = Utc::now().naive_utc();
+        assert_eq!(config.retry_at(100, now), now + chrono::Duration::weeks(1));
+        assert_eq!(config.retry_at(100, NaiveDateTime::MAX), NaiveDateTime::MAX);
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_delivers_signed_events(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let stories = UserStoryService::new(repositories.clone());
+        let receiver = receiver(200).await;
+        let events = repositories.events.clone();
+        events
+            .create_webhook(&receiver.url, "s3cret", &[])
+            .await
+            .unwrap();
+        events
+            .create_webhook(&receiver.url, "s3cret", &[DomainEventKind::CriteriaAdded])
+            .await
+            .unwrap();
+
+        let bus = EventBus::new();
+        let mut subscriber = bus.subscribe();
+        let mut dispatcher = WebhookDispatcher::new(repositories.clone(), bus);
+        // Events recorded before the webhooks existed are not sent
+        assert_eq!(
+            dispatcher.run_once().await.unwrap(),
+            DispatchReport::default()
+        );
+
+        stories.update("US-001", retitle("Signed")).await.unwrap();
+        let report = dispatcher.run_once().await.unwrap();
+        assert_eq!((report.published, report.delivered), (1, 1));
+
+        let event = subscriber.try_recv().unwrap();
+        assert_eq!(event.kind, DomainEventKind::StoryUpdated);
+        assert_eq!(event.payload["title"], "Signed");
+
+        let requests = receiver.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        let request = &requests[0];
+        let timestamp: i64 = request.headers[TIMESTAMP_HEADER].parse().unwrap();
+        assert!(verify(
+            "s3cret",
+            timestamp,
+            &request.body,
+            &request.headers[SIGNATURE_HEADER]
+        ));
+        assert_eq!(request.headers[EVENT_KIND_HEADER], "story_updated");
+        let delivered: DomainEvent = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(delivered, event);
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_retries_then_dead_letters(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let stories = UserStoryService::new(repositories.clone());
+        let receiver = receiver(500).await;
+        let events = repositories.events.clone();
+        events
+            .create_webhook(&receiver.url, "s3cret", &[])
+            .await
+            .unwrap();
+
+        let config = DispatcherConfig {
+            max_attempts: 2,
+            initial_backoff: Duration::ZERO,
+            ..Default::default()
+        };
+        let mut dispatcher =
+            WebhookDispatcher::with_config(repositories.clone(), EventBus::new(), config);
+
+        stories.update("US-001", retitle("Unlucky")).await.unwrap();
+        assert_eq!(dispatcher.run_once().await.unwrap().retrying, 1);
+        assert_eq!(dispatcher.run_once().await.unwrap().dead, 1);
+        assert_eq!(dispatcher.run_once().await.unwrap().dead, 0);
+
+        let dead = events.get_deliveries(DeliveryStatus::Dead).await.unwrap();
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].attempts, 2);
+        assert!(dead[0].last_error.as_deref().unwrap().contains("500"));
+
+        receiver.status.store(200, Ordering::SeqCst);
+        assert!(events.retry_dead(dead[0].id).await.unwrap());
+        assert_eq!(dispatcher.run_once().await.unwrap().delivered, 1);
+        assert_eq!(receiver.requests.lock().unwrap().len(), 3);
+
+        let delivered = events
+            .get_deliveries(DeliveryStatus::Delivered)
+            .await
+            .unwrap();
+        assert_eq!(delivered[0].event_kind, DomainEventKind::StoryUpdated);
+        assert!(events
+            .get_deliveries(DeliveryStatus::Dead)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
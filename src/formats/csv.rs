@@ -0,0 +1,640 @@
+//! CSV export and import with configurable column mapping.
+//!
+//! Presets cover this crate's own layout plus the issue exports of Jira and Azure DevOps.
+//! Acceptance criteria are either written one row per criterion (story columns repeated)
+//! or joined into a single cell, depending on `CsvMapping::criteria_layout`.
+
+use crate::models::{AcceptanceCriteria, CsvRowError, UserStory, UserStoryWithCriteria};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CsvError {
+    #[error("CSV error: {0}")]
+    Csv(#[from] ::csv::Error),
+    #[error("Missing required column '{0}'")]
+    MissingColumn(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CriteriaLayout {
+    /// One row per acceptance criterion, repeating the story columns
+    RowPerCriterion,
+    /// All acceptance criteria of a story joined in a single cell
+    JoinedCell,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CsvPreset {
+    Native,
+    Jira,
+    AzureDevops,
+}
+
+impl fmt::Display for CsvPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CsvPreset::Native => "native",
+            CsvPreset::Jira => "jira",
+            CsvPreset::AzureDevops => "azure_devops",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for CsvPreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "native" | "default" => Ok(CsvPreset::Native),
+            "jira" => Ok(CsvPreset::Jira),
+            "azure_devops" | "azure" | "ado" => Ok(CsvPreset::AzureDevops),
+            other => Err(format!(
+                "Unknown CSV preset '{}'. Expected one of: native, jira, azure_devops",
+                other
+            )),
+        }
+    }
+}
+
+/// Which CSV columns hold which user story fields
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CsvMapping {
+    pub id_column: String,
+    pub title_column: String,
+    pub description_column: String,
+    /// Column holding the persona; stories fall back to `default_persona` without it
+    pub persona_column: Option<String>,
+    pub default_persona: Option<String>,
+    pub criteria_column: String,
+    /// Column holding criteria ids, only meaningful with `RowPerCriterion`
+    pub criteria_id_column: Option<String>,
+    pub criteria_layout: CriteriaLayout,
+    /// Separator between criteria in a `JoinedCell` layout
+    pub criteria_separator: String,
+    /// Prefix added to imported ids that lack the `US-` prefix, e.g. Jira keys
+    pub id_prefix: String,
+    /// Cells contain HTML (Azure DevOps rich text fields)
+    pub html: bool,
+    /// Constant columns written on export, such as the issue type
+    pub fixed_columns: Vec<(String, String)>,
+}
+
+impl Default for CsvMapping {
+    fn default() -> Self {
+        Self::preset(CsvPreset::Native)
+    }
+}
+
+impl CsvMapping {
+    pub fn preset(preset: CsvPreset) -> Self {
+        match preset {
+            CsvPreset::Native => Self {
+                id_column: "id".to_string(),
+                title_column: "title".to_string(),
+                description_column: "description".to_string(),
+                persona_column: Some("persona".to_string()),
+                default_persona: None,
+                criteria_column: "acceptance_criteria".to_string(),
+                criteria_id_column: Some("acceptance_criteria_id".to_string()),
+                criteria_layout: CriteriaLayout::RowPerCriterion,
+                criteria_separator: "\n".to_string(),
+                id_prefix: "US-".to_string(),
+                html: false,
+                fixed_columns: Vec::new(),
+            },
+            CsvPreset::Jira => Self {
+                id_column: "Issue key".to_string(),
+                title_column: "Summary".to_string(),
+                description_column: "Description".to_string(),
+                persona_column: Some("Custom field (Persona)".to_string()),
+                default_persona: None,
+                criteria_column: "Custom field (Acceptance Criteria)".to_string(),
+                criteria_id_column: None,
+                criteria_layout: CriteriaLayout::JoinedCell,
+                criteria_separator: "\n".to_string(),
+                id_prefix: "US-".to_string(),
+                html: false,
+                fixed_columns: vec![("Issue Type".to_string(), "Story".to_string())],
+            },
+            CsvPreset::AzureDevops => Self {
+                id_column: "ID".to_string(),
+                title_column: "Title".to_string(),
+                description_column: "Description".to_string(),
+                persona_column: Some("Persona".to_string()),
+                default_persona: None,
+                criteria_column: "Acceptance Criteria".to_string(),
+                criteria_id_column: None,
+                criteria_layout: CriteriaLayout::JoinedCell,
+                criteria_separator: "\n".to_string(),
+                id_prefix: "US-".to_string(),
+                html: true,
+                fixed_columns: vec![("Work Item Type".to_string(), "User Story".to_string())],
+            },
+        }
+    }
+}
+
+/// Write user stories as CSV using the given mapping
+pub fn write_csv(
+    stories: &[UserStoryWithCriteria],
+    mapping: &CsvMapping,
+) -> Result<String, CsvError> {
+    let mut header: Vec<&str> = mapping
+        .fixed_columns
+        .iter()
+        .map(|(column, _)| column.as_str())
+        .collect();
+    header.extend([
+        mapping.id_column.as_str(),
+        mapping.title_column.as_str(),
+        mapping.description_column.as_str(),
+    ]);
+    if let Some(column) = &mapping.persona_column {
+        header.push(column);
+    }
+    let with_criteria_ids = mapping.criteria_layout == CriteriaLayout::RowPerCriterion
+        && mapping.criteria_id_column.is_some();
+    if with_criteria_ids {
+        header.push(mapping.criteria_id_column.as_deref().unwrap());
+    }
+    header.push(&mapping.criteria_column);
+
+    let mut writer = ::csv::Writer::from_writer(Vec::new());
+    writer.write_record(&header)?;
+
+    for story in stories {
+        let user_story = &story.user_story;
+        let mut story_columns: Vec<String> = mapping
+            .fixed_columns
+            .iter()
+            .map(|(_, value)| value.clone())
+            .collect();
+        story_columns.push(user_story.id.clone());
+        story_columns.push(user_story.title.clone());
+        story_columns.push(to_cell(&user_story.description, mapping.html));
+        if mapping.persona_column.is_some() {
+            story_columns.push(user_story.persona.clone());
+        }
+
+        match mapping.criteria_layout {
+            CriteriaLayout::JoinedCell => {
+                let descriptions: Vec<&str> = story
+                    .acceptance_criteria
+                    .iter()
+                    .map(|criteria| criteria.description.as_str())
+                    .collect();
+                let cell = if mapping.html {
+                    html_list(&descriptions)
+                } else {
+                    descriptions.join(&mapping.criteria_separator)
+                };
+                story_columns.push(cell);
+                writer.write_record(&story_columns)?;
+            }
+            CriteriaLayout::RowPerCriterion => {
+                if story.acceptance_criteria.is_empty() {
+                    let mut row = story_columns.clone();
+                    if with_criteria_ids {
+                        row.push(String::new());
+                    }
+                    row.push(String::new());
+                    writer.write_record(&row)?;
+                }
+                for criteria in &story.acceptance_criteria {
+                    let mut row = story_columns.clone();
+                    if with_criteria_ids {
+                        row.push(criteria.id.clone());
+                    }
+                    row.push(to_cell(&criteria.description, mapping.html));
+                    writer.write_record(&row)?;
+                }
+            }
+        }
+    }
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| ::csv::Error::from(e.into_error()))?;
+    Ok(String::from_utf8(bytes).expect("CSV writer only receives UTF-8 strings"))
+}
+
+/// A story read from CSV along with the row it started on
+#[derive(Debug, Clone)]
+pub struct CsvStory {
+    pub row: usize,
+    pub story: UserStoryWithCriteria,
+}
+
+/// Read user stories from CSV. Rows that cannot be turned into a story are reported
+/// individually instead of aborting the whole file.
+pub fn read_csv(
+    input: &str,
+    mapping: &CsvMapping,
+    timestamp: NaiveDateTime,
+) -> Result<(Vec<CsvStory>, Vec<CsvRowError>), CsvError> {
+    let mut reader = ::csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(input.as_bytes());
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|header| header.trim() == name);
+    let required = |name: &str| column(name).ok_or_else(|| CsvError::MissingColumn(name.into()));
+
+    let id_index = required(&mapping.id_column)?;
+    let title_index = required(&mapping.title_column)?;
+    let description_index = required(&mapping.description_column)?;
+    let criteria_index = column(&mapping.criteria_column);
+    let persona_index = mapping.persona_column.as_deref().and_then(column);
+    let criteria_id_index = mapping.criteria_id_column.as_deref().and_then(column);
+
+    let mut stories: Vec<CsvStory> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut errors = Vec::new();
+
+    for (index, record) in reader.records().enumerate() {
+        let row = index + 2;
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                errors.push(CsvRowError {
+                    row,
+                    message: e.to_string(),
+                });
+                continue;
+            }
+        };
+        let cell = |index: Option<usize>| {
+            index
+                .and_then(|index| record.get(index))
+                .map(|value| from_cell(value, mapping.html))
+                .unwrap_or_default()
+        };
+
+        let source_id = cell(Some(id_index));
+        if source_id.is_empty() {
+            errors.push(CsvRowError {
+                row,
+                message: format!("'{}' is empty", mapping.id_column),
+            });
+            continue;
+        }
+        let id = if source_id.starts_with(&mapping.id_prefix) {
+            source_id
+        } else {
+            format!("{}{}", mapping.id_prefix, source_id)
+        };
+
+        let persona = Some(cell(persona_index))
+            .filter(|persona| !persona.is_empty())
+            .or_else(|| mapping.default_persona.clone())
+            .unwrap_or_default();
+
+        let position = match positions.get(&id) {
+            Some(&position) => position,
+            None => {
+                positions.insert(id.clone(), stories.len());
+                stories.push(CsvStory {
+                    row,
+                    story: UserStoryWithCriteria {
+                        user_story: UserStory {
+                            id: id.clone(),
+                            title: cell(Some(title_index)),
+                            description: cell(Some(description_index)),
+                            persona,
+                            created_at: timestamp,
+                            updated_at: timestamp,
+                            version: 1,
+                            assignee: None,
+                            reviewer: None,
+                        },
+                        acceptance_criteria: Vec::new(),
+                    },
+                });
+                stories.len() - 1
+            }
+        };
+
+        let criteria_cell = criteria_index
+            .and_then(|index| record.get(index))
+            .unwrap_or_default();
+        let descriptions: Vec<String> = match mapping.criteria_layout {
+            CriteriaLayout::RowPerCriterion => vec![from_cell(criteria_cell, mapping.html)],
+            CriteriaLayout::JoinedCell if mapping.html => html_list_items(criteria_cell),
+            CriteriaLayout::JoinedCell => criteria_cell
+                .split(mapping.criteria_separator.as_str())
+                .map(strip_bullet)
+                .collect(),
+        };
+
+        let story = &mut stories[position].story;
+        for description in descriptions.into_iter().filter(|d| !d.is_empty()) {
+            let criteria_id = Some(cell(criteria_id_index))
+                .filter(|id| {
+                    !id.is_empty() && mapping.criteria_layout == CriteriaLayout::RowPerCriterion
+                })
+                .unwrap_or_else(|| {
+                    format!(
+                        "AC-{}-{}",
+                        id.strip_prefix("US-").unwrap_or(&id),
+                        story.acceptance_criteria.len() + 1
+                    )
+                });
+            story.acceptance_criteria.push(AcceptanceCriteria {
+                id: criteria_id,
+                user_story_id: id.clone(),
+                description,
+                created_at: timestamp,
+                updated_at: timestamp,
+                version: 1,
+            });
+        }
+    }
+
+    Ok((stories, errors))
+}
+
+fn strip_bullet(line: &str) -> String {
+    let line = line.trim();
+    ["- ", "* ", "• ", "# "]
+        .iter()
+        .find_map(|bullet| line.strip_prefix(bullet))
+        .unwrap_or(line)
+        .trim()
+        .to_string()
+}
+
+fn to_cell(text: &str, html: bool) -> String {
+    if html {
+        text.lines()
+            .map(|line| format!("<div>{}</div>", escape_html(line)))
+            .collect()
+    } else {
+        text.to_string()
+    }
+}
+
+fn from_cell(value: &str, html: bool) -> String {
+    if html {
+        html_to_text(value)
+    } else {
+        value.trim().to_string()
+    }
+}
+
+fn html_list(items: &[&str]) -> String {
+    if items.is_empty() {
+        return String::new();
+    }
+    let items: String = items
+        .iter()
+        .map(|item| format!("<li>{}</li>", escape_html(item)))
+        .collect();
+    format!("<ul>{}</ul>", items)
+}
+
+/// Split an HTML cell into list items, falling back to line breaks and blocks
+fn html_list_items(value: &str) -> Vec<String> {
+    let separators: &[&str] = if find_ignore_ascii_case(value, "<li").is_some() {
+        &["</li>"]
+    } else {
+        &["<br>", "<br/>", "<br />", "</div>", "</p>"]
+    };
+
+    let mut items = vec![value.to_string()];
+    for separator in separators {
+        items = items
+            .iter()
+            .flat_map(|item| split_case_insensitive(item, separator))
+            .collect();
+    }
+
+    items
+        .iter()
+        .map(|item| strip_bullet(&html_to_text(item)))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn split_case_insensitive(text: &str, separator: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    while let Some(offset) = find_ignore_ascii_case(&text[start..], separator) {
+        parts.push(text[start..start + offset].to_string());
+        start += offset + separator.len();
+    }
+    parts.push(text[start..].to_string());
+    parts
+}
+
+/// Byte offset of an ASCII `needle` in `text`, ignoring ASCII case. Offsets index the
+/// original string, so non-ASCII text around the match is never split
+fn find_ignore_ascii_case(text: &str, needle: &str) -> Option<usize> {
+    text.as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+/// Convert rich text to plain text: block ends become line breaks, tags are dropped
+/// and the common entities decoded
+fn html_to_text(value: &str) -> String {
+    let mut text = String::new();
+    let mut tag = String::new();
+    let mut in_tag = false;
+
+    for c in value.chars() {
+        match (in_tag, c) {
+            (false, '<') => {
+                in_tag = true;
+                tag.clear();
+            }
+            (true, '>') => {
+                in_tag = false;
+                let name = tag.trim_start_matches('/').to_lowercase();
+                let name = name.split_whitespace().next().unwrap_or_default();
+                if matches!(name, "br" | "br/" | "div" | "p" | "li") && !text.ends_with('\n') {
+                    text.push('\n');
+                }
+            }
+            (true, c) => tag.push(c),
+            (false, c) => text.push(c),
+        }
+    }
+
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn timestamp() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(10, 0, 0)
+            .unwrap()
+    }
+
+    fn create_test_story() -> UserStoryWithCriteria {
+        let criteria = |id: &str, description: &str| AcceptanceCriteria {
+            id: id.to_string(),
+            user_story_id: "US-001".to_string(),
+            description: description.to_string(),
+            created_at: timestamp(),
+            updated_at: timestamp(),
+            version: 1,
+        };
+
+        UserStoryWithCriteria {
+            user_story: UserStory {
+                id: "US-001".to_string(),
+                title: "User Login".to_string(),
+                description: "As a user, I want to log in, so that I see my account".to_string(),
+                persona: "Registered User".to_string(),
+                created_at: timestamp(),
+                updated_at: timestamp(),
+                version: 1,
+                assignee: None,
+                reviewer: None,
+            },
+            acceptance_criteria: vec![
+                criteria("AC-001", "Valid credentials log me in"),
+                criteria("AC-002", "Invalid credentials show an error"),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_native_round_trip() {
+        let story = create_test_story();
+        let mapping = CsvMapping::default();
+
+        let csv = write_csv(std::slice::from_ref(&story), &mapping).unwrap();
+        assert_eq!(csv.lines().count(), 3); // header and one row per criterion
+
+        let (stories, errors) = read_csv(&csv, &mapping, timestamp()).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(stories.len(), 1);
+        assert_eq!(
+            serde_json::to_value(&stories[0].story).unwrap(),
+            serde_json::to_value(&story).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_read_jira_export() {
+        let input = "Issue Type,Issue key,Summary,Description,Custom field (Acceptance Criteria)\n\
+Story,SHOP-12,Guest checkout,\"As a guest, I want to pay\",\"* Card payments work\n* Receipts are emailed\"\n";
+        let mut mapping = CsvMapping::preset(CsvPreset::Jira);
+        mapping.default_persona = Some("Guest".to_string());
+
+        let (stories, errors) = read_csv(input, &mapping, timestamp()).unwrap();
+        assert!(errors.is_empty());
+
+        let story = &stories[0].story;
+        assert_eq!(story.user_story.id, "US-SHOP-12");
+        assert_eq!(story.user_story.persona, "Guest");
+        let criteria: Vec<(&str, &str)> = story
+            .acceptance_criteria
+            .iter()
+            .map(|c| (c.id.as_str(), c.description.as_str()))
+            .collect();
+        assert_eq!(
+            criteria,
+            vec![
+                ("AC-SHOP-12-1", "Card payments work"),
+                ("AC-SHOP-12-2", "Receipts are emailed")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_azure_devops_round_trip() {
+        let story = create_test_story();
+        let mapping = CsvMapping::preset(CsvPreset::AzureDevops);
+
+        let csv = write_csv(std::slice::from_ref(&story), &mapping).unwrap();
+        assert!(csv.starts_with("Work Item Type,ID,Title,Description,Persona,Acceptance Criteria"));
+        assert!(csv.contains("<ul><li>Valid credentials log me in</li>"));
+
+        let (stories, errors) = read_csv(&csv, &mapping, timestamp()).unwrap();
+        assert!(errors.is_empty());
+        let parsed = &stories[0].story;
+        assert_eq!(parsed.user_story.description, story.user_story.description);
+        assert_eq!(parsed.acceptance_criteria.len(), 2);
+        assert_eq!(
+            parsed.acceptance_criteria[1].description,
+            "Invalid credentials show an error"
+        );
+    }
+
+    #[test]
+    fn test_azure_devops_non_ascii_list_items() {
+        let mapping = CsvMapping::preset(CsvPreset::AzureDevops);
+        let input = "Work Item Type,ID,Title,Description,Persona,Acceptance Criteria\n\
+User Story,7,İstanbul,Text,User,\"<UL><LI>İ</LI><li>Straße ölçü</li></ul>\"\n";
+
+        let (stories, errors) = read_csv(input, &mapping, timestamp()).unwrap();
+        assert!(errors.is_empty());
+        let criteria: Vec<&str> = stories[0]
+            .story
+            .acceptance_criteria
+            .iter()
+            .map(|c| c.description.as_str())
+            .collect();
+        assert_eq!(criteria, vec!["İ", "Straße ölçü"]);
+    }
+
+    #[test]
+    fn test_row_errors_and_missing_columns() {
+        let mapping = CsvMapping::default();
+
+        let missing = read_csv("id,title\nUS-1,Title\n", &mapping, timestamp());
+        assert!(matches!(missing, Err(CsvError::MissingColumn(column)) if column == "description"));
+
+        let input = "id,title,description,persona,acceptance_criteria\n,No id,Text,User,\nUS-2,Ok,Text,User,Done\n";
+        let (stories, errors) = read_csv(input, &mapping, timestamp()).unwrap();
+        assert_eq!(stories.len(), 1);
+        assert_eq!(
+            errors,
+            vec![CsvRowError {
+                row: 2,
+                message: "'id' is empty".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_html_to_text() {
+        assert_eq!(
+            html_to_text("<div>Fish &amp; chips</div><div>second&nbsp;line<br/></div>"),
+            "Fish & chips\nsecond line"
+        );
+    }
+}
@@ -0,0 +1,488 @@
+//! Conversion between user stories and Gherkin `.feature` files.
+//!
+//! A story becomes a `Feature` tagged with its id, the persona and description form the
+//! feature narrative, and every acceptance criterion becomes a `Scenario` tagged with its
+//! own id. Criteria written as "Given ..., When ..., Then ..." are split into one step per
+//! clause; anything else is kept line by line as `*` steps so it survives a round trip.
+//! A scenario named other than by its id keeps that name as the first line of the criterion.
+
+use crate::models::{AcceptanceCriteria, IdScheme, UserStory, UserStoryWithCriteria};
+use chrono::NaiveDateTime;
+use std::fmt::Write;
+use thiserror::Error;
+
+const STEP_KEYWORDS: [&str; 5] = ["Given", "When", "Then", "And", "But"];
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("line {line}: {message}")]
+pub struct GherkinError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl GherkinError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+/// Render a user story and its acceptance criteria as a `.feature` document
+pub fn render_feature(story: &UserStoryWithCriteria) -> String {
+    let mut out = String::new();
+
+    writeln!(out, "@{}", story.user_story.id).unwrap();
+    writeln!(out, "Feature: {}", story.user_story.title).unwrap();
+    writeln!(out, "  Persona: {}", story.user_story.persona).unwrap();
+    for line in story.user_story.description.lines() {
+        write_indented(&mut out, 2, line);
+    }
+
+    for criteria in &story.acceptance_criteria {
+        writeln!(out).unwrap();
+        writeln!(out, "  @{}", criteria.id).unwrap();
+        writeln!(out, "  Scenario: {}", criteria.id).unwrap();
+        for step in split_steps(&criteria.description) {
+            write_indented(&mut out, 4, &step);
+        }
+    }
+
+    out
+}
+
+/// Parse a `.feature` document back into a user story with its acceptance criteria.
+///
+/// The story id comes from a tag with the story prefix of `id_scheme` on the feature
+/// (`@US-...` by default) and each criterion id from a tag with its criteria prefix on the
+/// scenario; untagged scenarios are numbered `AC-<story>-<n>`. The persona is read from a
+/// `Persona:` line, falling back to the "As a ..." narrative.
+pub fn parse_feature(
+    input: &str,
+    timestamp: NaiveDateTime,
+    id_scheme: &IdScheme,
+) -> Result<UserStoryWithCriteria, GherkinError> {
+    let mut pending_tags: Vec<String> = Vec::new();
+    let mut feature: Option<(String, String)> = None;
+    let mut persona: Option<String> = None;
+    let mut description: Vec<String> = Vec::new();
+    let mut scenarios: Vec<ParsedScenario> = Vec::new();
+
+    for (index, raw_line) in input.lines().enumerate() {
+        let line_number = index + 1;
+        let line = raw_line.trim();
+
+        if line.starts_with('#') {
+            continue;
+        }
+
+        if line.is_empty() {
+            if feature.is_some() && scenarios.is_empty() && !description.is_empty() {
+                description.push(String::new());
+            }
+            continue;
+        }
+
+        if line.starts_with('@') {
+            pending_tags.extend(
+                line.split_whitespace()
+                    .map(|tag| tag.trim_start_matches('@').to_string()),
+            );
+            continue;
+        }
+
+        if let Some(title) = line.strip_prefix("Feature:") {
+            if feature.is_some() {
+                return Err(GherkinError::new(
+                    line_number,
+                    "only one Feature per file is supported",
+                ));
+            }
+            let id = take_tag(&mut pending_tags, &id_scheme.story_prefix).ok_or_else(|| {
+                GherkinError::new(
+                    line_number,
+                    format!("Feature is missing a @{}... id tag", id_scheme.story_prefix),
+                )
+            })?;
+            feature = Some((id, title.trim().to_string()));
+            continue;
+        }
+
+        if let Some(name) = line
+            .strip_prefix("Scenario:")
+            .or_else(|| line.strip_prefix("Example:"))
+        {
+            if feature.is_none() {
+                return Err(GherkinError::new(
+                    line_number,
+                    "Scenario found before Feature",
+                ));
+            }
+            scenarios.push(ParsedScenario {
+                id: take_tag(&mut pending_tags, &id_scheme.criteria_prefix),
+                name: name.trim().to_string(),
+                steps: Vec::new(),
+            });
+            continue;
+        }
+
+        if let Some(keyword) = ["Background:", "Scenario Outline:", "Examples:", "Rule:"]
+            .iter()
+            .find(|keyword| line.starts_with(*keyword))
+        {
+            return Err(GherkinError::new(
+                line_number,
+                format!(
+                    "'{}' sections are not supported",
+                    keyword.trim_end_matches(':')
+                ),
+            ));
+        }
+
+        if let Some(scenario) = scenarios.last_mut() {
+            match parse_step(line) {
+                Some(step) => scenario.steps.push(step),
+                None => {
+                    return Err(GherkinError::new(
+                        line_number,
+                        format!("expected a Given/When/Then step, found '{}'", line),
+                    ))
+                }
+            }
+        } else if feature.is_some() {
+            match line.strip_prefix("Persona:") {
+                Some(value) => persona = Some(value.trim().to_string()),
+                None => description.push(line.to_string()),
+            }
+        } else {
+            return Err(GherkinError::new(
+                line_number,
+                format!("expected a Feature, found '{}'", line),
+            ));
+        }
+    }
+
+    let (id, title) = feature.ok_or_else(|| GherkinError::new(1, "no Feature found"))?;
+
+    while description.last().is_some_and(|line| line.is_empty()) {
+        description.pop();
+    }
+    let description = description.join("\n");
+
+    let persona = persona
+        .or_else(|| persona_from_narrative(&description))
+        .ok_or_else(|| {
+            GherkinError::new(
+                1,
+                "Feature has no 'Persona:' line and no \"As a ...\" narrative",
+            )
+        })?;
+
+    let acceptance_criteria = scenarios
+        .into_iter()
+        .enumerate()
+        .map(|(index, scenario)| AcceptanceCriteria {
+            id: scenario
+                .id
+                .clone()
+                .unwrap_or_else(|| id_scheme.criteria_id(&id, index as i64 + 1)),
+            user_story_id: id.clone(),
+            description: scenario.description(),
+            created_at: timestamp,
+            updated_at: timestamp,
+            version: 1,
+        })
+        .collect();
+
+    Ok(UserStoryWithCriteria {
+        user_story: UserStory {
+            id,
+            title,
+            description,
+            persona,
+            created_at: timestamp,
+            updated_at: timestamp,
+            version: 1,
+            assignee: None,
+            reviewer: None,
+        },
+        acceptance_criteria,
+    })
+}
+
+struct ParsedScenario {
+    id: Option<String>,
+    name: String,
+    steps: Vec<(Option<&'static str>, String)>,
+}
+
+impl ParsedScenario {
+    /// Rebuild the criterion text: keyword steps are joined into one sentence,
+    /// `*` steps are kept as separate lines, and the scenario name comes first unless it is
+    /// just the criterion id, as in rendered features
+    fn description(&self) -> String {
+        let name = Some(self.name.as_str())
+            .filter(|name| !name.is_empty() && self.id.as_deref() != Some(*name));
+        if self.steps.is_empty() {
+            return name.unwrap_or_default().to_string();
+        }
+
+        let steps = if self.steps.iter().all(|(keyword, _)| keyword.is_none()) {
+            self.steps
+                .iter()
+                .map(|(_, text)| text.as_str())
+                .collect::<Vec<_>>()
+                .join("\n")
+        } else {
+            self.steps
+                .iter()
+                .map(|(keyword, text)| match keyword {
+                    Some(keyword) => format!("{} {}", keyword, text),
+                    None => text.clone(),
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        match name {
+            Some(name) => format!("{}\n{}", name, steps),
+            None => steps,
+        }
+    }
+}
+
+fn write_indented(out: &mut String, indent: usize, line: &str) {
+    if line.trim().is_empty() {
+        writeln!(out).unwrap();
+    } else {
+        writeln!(out, "{:indent$}{}", "", line.trim_end(), indent = indent).unwrap();
+    }
+}
+
+/// Split a criterion into Gherkin step lines
+fn split_steps(description: &str) -> Vec<String> {
+    let is_structured = !description.contains('\n')
+        && ["Given ", "When ", "Then "]
+            .iter()
+            .any(|keyword| description.starts_with(keyword));
+
+    if !is_structured {
+        return description
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| format!("* {}", line.trim()))
+            .collect();
+    }
+
+    let mut steps = Vec::new();
+    let mut rest = description;
+    while let Some(split_at) = next_clause(rest) {
+        steps.push(rest[..split_at].to_string());
+        rest = &rest[split_at + 2..];
+    }
+    steps.push(rest.to_string());
+    steps
+}
+
+/// Position of the next ", <Keyword> " clause boundary
+fn next_clause(text: &str) -> Option<usize> {
+    STEP_KEYWORDS
+        .iter()
+        .filter_map(|keyword| text.find(&format!(", {} ", keyword)))
+        .min()
+}
+
+fn parse_step(line: &str) -> Option<(Option<&'static str>, String)> {
+    if let Some(text) = line.strip_prefix("* ") {
+        return Some((None, text.trim().to_string()));
+    }
+
+    STEP_KEYWORDS.iter().find_map(|keyword| {
+        line.strip_prefix(keyword)
+            .and_then(|rest| rest.strip_prefix(' '))
+            .map(|text| (Some(*keyword), text.trim().to_string()))
+    })
+}
+
+fn take_tag(tags: &mut Vec<String>, prefix: &str) -> Option<String> {
+    let id = tags.iter().find(|tag| tag.starts_with(prefix)).cloned();
+    tags.clear();
+    id
+}
+
+/// Extract "Registered User" from "As a Registered User, I want ..."
+fn persona_from_narrative(description: &str) -> Option<String> {
+    let text = description.trim_start();
+    let rest = ["As an ", "As a ", "as an ", "as a "]
+        .iter()
+        .find_map(|prefix| text.strip_prefix(prefix))?;
+    let end = rest.find([',', '\n'])?;
+    let persona = rest[..end].trim();
+    (!persona.is_empty()).then(|| persona.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn timestamp() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(10, 0, 0)
+            .unwrap()
+    }
+
+    fn create_test_story() -> UserStoryWithCriteria {
+        let criteria = |id: &str, description: &str| AcceptanceCriteria {
+            id: id.to_string(),
+            user_story_id: "US-001".to_string(),
+            description: description.to_string(),
+            created_at: timestamp(),
+            updated_at: timestamp(),
+            version: 1,
+        };
+
+        UserStoryWithCriteria {
+            user_story: UserStory {
+                id: "US-001".to_string(),
+                title: "User Login Feature".to_string(),
+                description: "As a registered user, I want to log into the system so that I can access my account".to_string(),
+                persona: "Registered User".to_string(),
+                created_at: timestamp(),
+                updated_at: timestamp(),
+                version: 1,
+                assignee: None,
+                reviewer: None,
+            },
+            acceptance_criteria: vec![
+                criteria(
+                    "AC-001",
+                    "Given I am on the login page, When I enter valid credentials, Then I should be logged in successfully",
+                ),
+                criteria(
+                    "AC-002",
+                    "I can combine multiple filters and the results update accordingly.",
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_render_feature() {
+        let feature = render_feature(&create_test_story());
+
+        assert!(feature.starts_with("@US-001\nFeature: User Login Feature\n"));
+        assert!(feature.contains("  Persona: Registered User\n"));
+        assert!(feature.contains(
+            "  @AC-001\n  Scenario: AC-001\n    Given I am on the login page\n    When I enter valid credentials\n    Then I should be logged in successfully\n"
+        ));
+        assert!(feature.contains("    * I can combine multiple filters"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let story = create_test_story();
+        let parsed =
+            parse_feature(&render_feature(&story), timestamp(), &IdScheme::default()).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&parsed).unwrap(),
+            serde_json::to_value(&story).unwrap()
+        );
+
+        // Scenario names survive an export and import
+        let titled = "@US-002\nFeature: Checkout\n  Persona: Shopper\n\n  @AC-010\n  Scenario: Pay by card\n    Given a cart\n    When I pay by card\n    Then I get a receipt\n";
+        let story = parse_feature(titled, timestamp(), &IdScheme::default()).unwrap();
+        let reparsed =
+            parse_feature(&render_feature(&story), timestamp(), &IdScheme::default()).unwrap();
+        assert_eq!(
+            reparsed.acceptance_criteria[0].description,
+            "Pay by card\nGiven a cart, When I pay by card, Then I get a receipt"
+        );
+        assert_eq!(
+            serde_json::to_value(&reparsed).unwrap(),
+            serde_json::to_value(&story).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_round_trip_custom_prefixes() {
+        let scheme = IdScheme {
+            story_prefix: "SHOP-".to_string(),
+            criteria_prefix: "SHOP-AC-".to_string(),
+            story_number_width: 1,
+        };
+        let mut story = create_test_story();
+        story.user_story.id = "SHOP-1".to_string();
+        for (n, criteria) in story.acceptance_criteria.iter_mut().enumerate() {
+            criteria.id = scheme.criteria_id("SHOP-1", n as i64 + 1);
+            criteria.user_story_id = "SHOP-1".to_string();
+        }
+
+        let parsed = parse_feature(&render_feature(&story), timestamp(), &scheme).unwrap();
+        assert_eq!(
+            serde_json::to_value(&parsed).unwrap(),
+            serde_json::to_value(&story).unwrap()
+        );
+
+        let untagged =
+            "@SHOP-2\nFeature: Wishlist\n  Persona: Shopper\n\n  Scenario: Save an item\n";
+        let parsed = parse_feature(untagged, timestamp(), &scheme).unwrap();
+        assert_eq!(parsed.acceptance_criteria[0].id, "SHOP-AC-2-1");
+    }
+
+    #[test]
+    fn test_parse_untagged_scenarios_and_narrative_persona() {
+        let input = "\
+# language: en
+@US-042 @checkout
+Feature: Guest checkout
+  As a guest shopper, I want to check out without an account
+
+  Scenario: Pay as guest
+    Given I have items in my cart
+    When I choose guest checkout
+    Then I can pay without registering
+
+  Scenario: Remember me is not offered
+";
+        let story = parse_feature(input, timestamp(), &IdScheme::default()).unwrap();
+
+        assert_eq!(story.user_story.id, "US-042");
+        assert_eq!(story.user_story.persona, "guest shopper");
+        assert_eq!(story.acceptance_criteria.len(), 2);
+        assert_eq!(story.acceptance_criteria[0].id, "AC-042-1");
+        assert_eq!(
+            story.acceptance_criteria[0].description,
+            "Pay as guest\nGiven I have items in my cart, When I choose guest checkout, Then I can pay without registering"
+        );
+        assert_eq!(
+            story.acceptance_criteria[1].description,
+            "Remember me is not offered"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let scheme = IdScheme::default();
+        let missing_tag =
+            parse_feature("Feature: No id\n  Persona: Someone\n", timestamp(), &scheme);
+        assert_eq!(missing_tag.unwrap_err().line, 1);
+
+        let bad_step = parse_feature(
+            "@US-001\nFeature: Login\n  Persona: User\n  Scenario: One\n    Clicks the button\n",
+            timestamp(),
+            &scheme,
+        );
+        assert_eq!(bad_step.unwrap_err().line, 5);
+
+        let outline = parse_feature(
+            "@US-001\nFeature: Login\n  Persona: User\n  Scenario Outline: One\n",
+            timestamp(),
+            &scheme,
+        );
+        assert!(outline.unwrap_err().message.contains("not supported"));
+    }
+}
@@ -0,0 +1,398 @@
+//! Markdown rendering of user stories and parsing of edited documents.
+//!
+//! A rendered story looks like:
+//!
+//! ```markdown
+//! ---
+//! id: US-001
+//! persona: Registered User
+//! version: 3
+//! created_at: 2024-01-01 10:00:00
+//! updated_at: 2024-01-01 10:00:00
+//! ---
+//!
+//! # User Login Feature
+//!
+//! As a registered user, I want to log into the system so that I can access my account
+//!
+//! ## Acceptance Criteria
+//!
+//! - [ ] AC-001: Given I am on the login page, When I enter valid credentials, Then ...
+//! - [ ] A new criterion without an id yet
+//! ```
+//!
+//! Criteria continue onto indented lines, and items without an id prefix (`AC-...:` under the
+//! default id scheme) are new.
+//! `version` is the story version the document was rendered from; applying it once the
+//! story has moved on fails with a conflict.
+
+use crate::models::{IdScheme, UserStoryWithCriteria};
+use std::fmt::Write;
+use thiserror::Error;
+
+const CRITERIA_HEADING: &str = "## Acceptance Criteria";
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("line {line}: {message}")]
+pub struct MarkdownError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl MarkdownError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+/// The editable content of a story document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownStory {
+    pub id: String,
+    pub title: String,
+    pub persona: String,
+    /// Story version the document was rendered from; `None` for documents written without one
+    pub version: Option<i64>,
+    pub description: String,
+    pub acceptance_criteria: Vec<MarkdownCriterion>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownCriterion {
+    /// `None` for criteria added in the document that have not been stored yet
+    pub id: Option<String>,
+    pub description: String,
+}
+
+/// Render a user story and its acceptance criteria as a Markdown document
+pub fn render_markdown(story: &UserStoryWithCriteria) -> String {
+    let user_story = &story.user_story;
+    let mut out = String::new();
+
+    writeln!(out, "---").unwrap();
+    writeln!(out, "id: {}", user_story.id).unwrap();
+    writeln!(out, "persona: {}", user_story.persona).unwrap();
+    writeln!(out, "version: {}", user_story.version).unwrap();
+    writeln!(out, "created_at: {}", user_story.created_at).unwrap();
+    writeln!(out, "updated_at: {}", user_story.updated_at).unwrap();
+    writeln!(out, "---").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "# {}", user_story.title).unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "{}", user_story.description.trim_end()).unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "{}", CRITERIA_HEADING).unwrap();
+    writeln!(out).unwrap();
+
+    for criteria in &story.acceptance_criteria {
+        let mut lines = criteria.description.lines();
+        writeln!(
+            out,
+            "- [ ] {}: {}",
+            criteria.id,
+            lines.next().unwrap_or_default()
+        )
+        .unwrap();
+        for line in lines {
+            writeln!(out, "  {}", line).unwrap();
+        }
+    }
+
+    out
+}
+
+/// Parse a (possibly hand-edited) story document, recognizing criteria ids by the
+/// criteria prefix of `id_scheme`
+pub fn parse_markdown(input: &str, id_scheme: &IdScheme) -> Result<MarkdownStory, MarkdownError> {
+    let lines: Vec<&str> = input.lines().collect();
+    let mut position = 0;
+
+    while position < lines.len() && lines[position].trim().is_empty() {
+        position += 1;
+    }
+
+    if lines.get(position).map(|line| line.trim()) != Some("---") {
+        return Err(MarkdownError::new(
+            position + 1,
+            "document must start with a '---' front matter block",
+        ));
+    }
+    position += 1;
+
+    let mut id = None;
+    let mut persona = None;
+    let mut version = None;
+    loop {
+        let Some(line) = lines.get(position) else {
+            return Err(MarkdownError::new(
+                position,
+                "front matter is not closed with '---'",
+            ));
+        };
+        position += 1;
+
+        let line = line.trim();
+        if line == "---" {
+            break;
+        }
+        if line.is_empty() {
+            continue;
+        }
+
+        let (key, value) = line.split_once(':').ok_or_else(|| {
+            MarkdownError::new(position, format!("expected 'key: value', found '{}'", line))
+        })?;
+        match key.trim() {
+            "id" => id = Some(value.trim().to_string()),
+            "persona" => persona = Some(value.trim().to_string()),
+            "version" => {
+                version = Some(value.trim().parse().map_err(|_| {
+                    MarkdownError::new(
+                        position,
+                        format!("expected a number for 'version', found '{}'", value.trim()),
+                    )
+                })?)
+            }
+            // Timestamps are informational and other keys are left for other tools
+            _ => {}
+        }
+    }
+
+    let id = id.ok_or_else(|| MarkdownError::new(1, "front matter has no 'id'"))?;
+    let persona = persona.ok_or_else(|| MarkdownError::new(1, "front matter has no 'persona'"))?;
+
+    let mut title = None;
+    let mut description: Vec<&str> = Vec::new();
+    let mut criteria: Option<Vec<MarkdownCriterion>> = None;
+
+    for (index, raw_line) in lines.iter().enumerate().skip(position) {
+        let line_number = index + 1;
+
+        if let Some(criteria) = criteria.as_mut() {
+            if raw_line.trim().is_empty() {
+                continue;
+            }
+
+            if let Some(item) = checklist_item(raw_line) {
+                criteria.push(parse_criterion(item, id_scheme));
+            } else if raw_line.starts_with("  ") && !criteria.is_empty() {
+                let last = criteria.last_mut().unwrap();
+                last.description.push('\n');
+                last.description.push_str(raw_line.trim());
+            } else {
+                return Err(MarkdownError::new(
+                    line_number,
+                    format!(
+                        "expected a '- [ ]' criteria item, found '{}'",
+                        raw_line.trim()
+                    ),
+                ));
+            }
+            continue;
+        }
+
+        if raw_line.trim() == CRITERIA_HEADING {
+            criteria = Some(Vec::new());
+        } else if title.is_none() {
+            if let Some(heading) = raw_line.strip_prefix("# ") {
+                title = Some(heading.trim().to_string());
+            } else if !raw_line.trim().is_empty() {
+                return Err(MarkdownError::new(
+                    line_number,
+                    "expected a '# Title' heading after the front matter",
+                ));
+            }
+        } else {
+            description.push(raw_line.trim_end());
+        }
+    }
+
+    let title = title.ok_or_else(|| MarkdownError::new(lines.len(), "document has no title"))?;
+
+    Ok(MarkdownStory {
+        id,
+        title,
+        persona,
+        version,
+        description: description.join("\n").trim().to_string(),
+        acceptance_criteria: criteria.unwrap_or_default(),
+    })
+}
+
+/// The text of a `- [ ]`, `- [x]` or plain `- ` list item
+fn checklist_item(line: &str) -> Option<&str> {
+    let item = line
+        .strip_prefix("- ")
+        .or_else(|| line.strip_prefix("* "))?;
+    let item = item
+        .strip_prefix("[ ] ")
+        .or_else(|| item.strip_prefix("[x] "))
+        .or_else(|| item.strip_prefix("[X] "))
+        .unwrap_or(item);
+    Some(item.trim())
+}
+
+fn parse_criterion(item: &str, id_scheme: &IdScheme) -> MarkdownCriterion {
+    if let Some((id, description)) = item.split_once(':') {
+        let id = id.trim();
+        if id.starts_with(id_scheme.criteria_prefix.as_str()) && !id.contains(char::is_whitespace) {
+            return MarkdownCriterion {
+                id: Some(id.to_string()),
+                description: description.trim().to_string(),
+            };
+        }
+    }
+
+    MarkdownCriterion {
+        id: None,
+        description: item.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AcceptanceCriteria, UserStory};
+    use chrono::NaiveDate;
+
+    fn create_test_story() -> UserStoryWithCriteria {
+        let timestamp = NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(10, 0, 0)
+            .unwrap();
+
+        UserStoryWithCriteria {
+            user_story: UserStory {
+                id: "US-001".to_string(),
+                title: "User Login Feature".to_string(),
+                description:
+                    "As a registered user, I want to log in\n\nSo that I can access my account"
+                        .to_string(),
+                persona: "Registered User".to_string(),
+                created_at: timestamp,
+                updated_at: timestamp,
+                version: 1,
+                assignee: None,
+                reviewer: None,
+            },
+            acceptance_criteria: vec![
+                AcceptanceCriteria {
+                    id: "AC-001".to_string(),
+                    user_story_id: "US-001".to_string(),
+                    description: "Given valid credentials, Then I am logged in".to_string(),
+                    created_at: timestamp,
+                    updated_at: timestamp,
+                    version: 1,
+                },
+                AcceptanceCriteria {
+                    id: "AC-002".to_string(),
+                    user_story_id: "US-001".to_string(),
+                    description: "Errors are shown\nfor invalid credentials".to_string(),
+                    created_at: timestamp,
+                    updated_at: timestamp,
+                    version: 1,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let markdown = render_markdown(&create_test_story());
+
+        assert!(markdown.starts_with("---\nid: US-001\npersona: Registered User\n"));
+        assert!(markdown.contains("\n# User Login Feature\n"));
+        assert!(markdown.contains("- [ ] AC-001: Given valid credentials, Then I am logged in\n"));
+        assert!(markdown.contains("- [ ] AC-002: Errors are shown\n  for invalid credentials\n"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let story = create_test_story();
+        let parsed = parse_markdown(&render_markdown(&story), &IdScheme::default()).unwrap();
+
+        assert_eq!(parsed.id, "US-001");
+        assert_eq!(parsed.title, story.user_story.title);
+        assert_eq!(parsed.persona, story.user_story.persona);
+        assert_eq!(parsed.version, Some(1));
+        assert_eq!(parsed.description, story.user_story.description);
+        assert_eq!(parsed.acceptance_criteria.len(), 2);
+        assert_eq!(
+            parsed.acceptance_criteria[1].description,
+            story.acceptance_criteria[1].description
+        );
+    }
+
+    #[test]
+    fn test_round_trip_custom_prefix() {
+        let scheme = IdScheme {
+            story_prefix: "SHOP-".to_string(),
+            criteria_prefix: "SHOP-AC-".to_string(),
+            story_number_width: 1,
+        };
+        let mut story = create_test_story();
+        story.user_story.id = "SHOP-1".to_string();
+        for (n, criteria) in story.acceptance_criteria.iter_mut().enumerate() {
+            criteria.id = scheme.criteria_id("SHOP-1", n as i64 + 1);
+            criteria.user_story_id = "SHOP-1".to_string();
+        }
+
+        let parsed = parse_markdown(&render_markdown(&story), &scheme).unwrap();
+        let ids: Vec<Option<&str>> = parsed
+            .acceptance_criteria
+            .iter()
+            .map(|c| c.id.as_deref())
+            .collect();
+        assert_eq!(ids, vec![Some("SHOP-AC-1-1"), Some("SHOP-AC-1-2")]);
+        assert_eq!(
+            parsed.acceptance_criteria[0].description,
+            story.acceptance_criteria[0].description
+        );
+    }
+
+    #[test]
+    fn test_parse_new_and_checked_criteria() {
+        let input = "---\nid: US-002\npersona: Shopper\n---\n# Checkout\n\nAs a shopper, I want to pay\n\n## Acceptance Criteria\n\n- [x] AC-010: Card payments work\n- [ ] Receipts are emailed\n";
+        let parsed = parse_markdown(input, &IdScheme::default()).unwrap();
+
+        assert_eq!(parsed.version, None);
+        assert_eq!(
+            parsed.acceptance_criteria,
+            vec![
+                MarkdownCriterion {
+                    id: Some("AC-010".to_string()),
+                    description: "Card payments work".to_string(),
+                },
+                MarkdownCriterion {
+                    id: None,
+                    description: "Receipts are emailed".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let scheme = IdScheme::default();
+        let no_front_matter = parse_markdown("# Title\n", &scheme);
+        assert_eq!(no_front_matter.unwrap_err().line, 1);
+
+        let no_id = parse_markdown("---\npersona: User\n---\n# Title\n", &scheme);
+        assert!(no_id.unwrap_err().message.contains("'id'"));
+
+        let bad_version = parse_markdown(
+            "---\nid: US-001\npersona: User\nversion: two\n---\n# Title\n",
+            &scheme,
+        );
+        assert_eq!(bad_version.unwrap_err().line, 4);
+
+        let stray_text = parse_markdown(
+            "---\nid: US-001\npersona: User\n---\n# Title\n\n## Acceptance Criteria\nNot a list item\n",
+            &scheme,
+        );
+        assert_eq!(stray_text.unwrap_err().line, 8);
+    }
+}
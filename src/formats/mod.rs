@@ -0,0 +1,7 @@
+pub mod csv;
+pub mod gherkin;
+pub mod markdown;
+
+pub use csv::{read_csv, write_csv, CriteriaLayout, CsvError, CsvMapping, CsvPreset};
+pub use gherkin::{parse_feature, render_feature, GherkinError};
+pub use markdown::{parse_markdown, render_markdown, MarkdownError};
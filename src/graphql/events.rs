@@ -0,0 +1,82 @@
+use crate::models::{DomainEvent, DomainEventKind};
+use async_graphql::futures_util::{stream, Stream};
+use async_graphql::{Enum, SimpleObject};
+use chrono::NaiveDateTime;
+use tokio::sync::broadcast;
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Created,
+    Updated,
+    Deleted,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityKind {
+    UserStory,
+    AcceptanceCriterion,
+}
+
+#[derive(SimpleObject, Debug, Clone, PartialEq, Eq)]
+pub struct ChangeEvent {
+    pub kind: ChangeKind,
+    pub entity: EntityKind,
+    /// Id of the changed story or criterion
+    pub id: String,
+    /// The user story the change belongs to
+    pub user_story_id: String,
+    pub occurred_at: NaiveDateTime,
+}
+
+impl From<DomainEvent> for ChangeEvent {
+    fn from(event: DomainEvent) -> Self {
+        // A restored story or criterion reappears to subscribers as if created again
+        let kind = match event.kind {
+            DomainEventKind::StoryCreated
+            | DomainEventKind::StoryRestored
+            | DomainEventKind::CriteriaAdded
+            | DomainEventKind::CriteriaRestored => ChangeKind::Created,
+            DomainEventKind::StoryUpdated | DomainEventKind::CriteriaUpdated => ChangeKind::Updated,
+            DomainEventKind::StoryDeleted | DomainEventKind::CriteriaDeleted => ChangeKind::Deleted,
+        };
+        let (entity, id) = match event.acceptance_criteria_id {
+            Some(id) => (EntityKind::AcceptanceCriterion, id),
+            None => (EntityKind::UserStory, event.user_story_id.clone()),
+        };
+
+        Self {
+            kind,
+            entity,
+            id,
+            user_story_id: event.user_story_id,
+            occurred_at: event.occurred_at,
+        }
+    }
+}
+
+/// Stream of the outbox events relayed to `receiver`, optionally only for one user story.
+/// A subscriber that falls too far behind skips the events it missed.
+pub fn subscribe(
+    receiver: broadcast::Receiver<DomainEvent>,
+    user_story_id: Option<String>,
+) -> impl Stream<Item = ChangeEvent> {
+    stream::unfold(receiver, move |mut receiver| {
+        let user_story_id = user_story_id.clone();
+        async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => {
+                        if user_story_id
+                            .as_ref()
+                            .is_none_or(|id| id == &event.user_story_id)
+                        {
+                            return Some((event.into(), receiver));
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,713 @@
+//! GraphQL schema over `Services`, served by the HTTP API at `/graphql` (queries and
+//! mutations) and `/graphql/ws` (subscriptions).
+//!
+//! `UserStory.acceptanceCriteria` is resolved through a [`DataLoader`], so a page of stories
+//! costs one criteria query in total instead of one per story.
+
+pub mod events;
+
+pub use events::{ChangeEvent, ChangeKind, EntityKind};
+
+use crate::models::{
+    AcceptanceCriteria, CreateAcceptanceCriteriaRequest, CreateUserStoryRequest, StoryQuery,
+    UpdateAcceptanceCriteriaRequest, UpdateUserStoryRequest, UserStory,
+};
+use crate::services::acceptance_criteria_service::AcceptanceCriteriaServiceError;
+use crate::services::user_story_service::UserStoryServiceError;
+use crate::services::Services;
+use async_graphql::dataloader::{DataLoader, Loader};
+use async_graphql::futures_util::Stream;
+use async_graphql::{
+    ComplexObject, Context, Error, ErrorExtensions, InputObject, Object, Schema, SimpleObject,
+    Subscription,
+};
+use chrono::NaiveDateTime;
+use std::collections::HashMap;
+
+pub type UserStoriesSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
+
+/// Build the schema around the given services; subscriptions follow the outbox events their
+/// dispatcher relays
+pub fn schema(services: Services) -> UserStoriesSchema {
+    Schema::build(QueryRoot, MutationRoot, SubscriptionRoot)
+        .data(DataLoader::new(
+            CriteriaLoader {
+                services: services.clone(),
+            },
+            tokio::spawn,
+        ))
+        .data(services)
+        .finish()
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "UserStory", complex)]
+pub struct UserStoryObject {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub persona: String,
+    pub assignee: Option<String>,
+    pub reviewer: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    /// Pass back as `expectedVersion` to reject the write if someone else got there first
+    pub version: i32,
+}
+
+impl From<UserStory> for UserStoryObject {
+    fn from(story: UserStory) -> Self {
+        Self {
+            id: story.id,
+            title: story.title,
+            description: story.description,
+            persona: story.persona,
+            assignee: story.assignee,
+            reviewer: story.reviewer,
+            created_at: story.created_at,
+            updated_at: story.updated_at,
+            version: story.version as i32,
+        }
+    }
+}
+
+#[ComplexObject]
+impl UserStoryObject {
+    async fn acceptance_criteria(
+        &self,
+        ctx: &Context<'_>,
+    ) -> async_graphql::Result<Vec<AcceptanceCriterionObject>> {
+        let loader = ctx.data_unchecked::<DataLoader<CriteriaLoader>>();
+        let criteria = loader.load_one(self.id.clone()).await?.unwrap_or_default();
+        Ok(criteria.into_iter().map(Into::into).collect())
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "AcceptanceCriterion")]
+pub struct AcceptanceCriterionObject {
+    pub id: String,
+    pub user_story_id: String,
+    pub description: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    /// Pass back as `expectedVersion` to reject the write if someone else got there first
+    pub version: i32,
+}
+
+impl From<AcceptanceCriteria> for AcceptanceCriterionObject {
+    fn from(criteria: AcceptanceCriteria) -> Self {
+        Self {
+            id: criteria.id,
+            user_story_id: criteria.user_story_id,
+            description: criteria.description,
+            created_at: criteria.created_at,
+            updated_at: criteria.updated_at,
+            version: criteria.version as i32,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct UserStoryPage {
+    pub items: Vec<UserStoryObject>,
+    /// Pass back as `after` for the next page; null on the last page
+    pub next_cursor: Option<String>,
+    /// Number of stories matching the filters, across all pages
+    pub total_count: i32,
+}
+
+#[derive(SimpleObject)]
+pub struct PersonaCount {
+    pub persona: String,
+    pub count: i32,
+}
+
+#[derive(SimpleObject)]
+pub struct AssigneeCount {
+    pub assignee: String,
+    pub count: i32,
+}
+
+#[derive(SimpleObject)]
+pub struct Statistics {
+    pub total_stories: i32,
+    pub total_criteria: i32,
+    pub personas_count: i32,
+    pub avg_criteria_per_story: f64,
+    pub stories_by_persona: Vec<PersonaCount>,
+    pub stories_by_assignee: Vec<AssigneeCount>,
+    pub unassigned_stories: i32,
+}
+
+#[derive(InputObject)]
+pub struct CreateUserStoryInput {
+    /// Generated when absent
+    pub id: Option<String>,
+    pub title: String,
+    pub description: String,
+    pub persona: String,
+}
+
+#[derive(InputObject)]
+pub struct UpdateUserStoryInput {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub persona: Option<String>,
+    /// Fail with `VERSION_CONFLICT` unless the story is still at this version
+    pub expected_version: Option<i32>,
+}
+
+#[derive(InputObject)]
+pub struct CreateAcceptanceCriterionInput {
+    /// Generated when absent
+    pub id: Option<String>,
+    pub user_story_id: String,
+    pub description: String,
+}
+
+/// Loads the acceptance criteria of many user stories in one query
+pub struct CriteriaLoader {
+    services: Services,
+}
+
+impl Loader<String> for CriteriaLoader {
+    type Value = Vec<AcceptanceCriteria>;
+    type Error = Error;
+
+    async fn load(
+        &self,
+        keys: &[String],
+    ) -> Result<HashMap<String, Vec<AcceptanceCriteria>>, Error> {
+        self.services
+            .acceptance_criteria
+            .get_by_user_story_ids(keys)
+            .await
+            .map_err(criteria_error)
+    }
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// A user story by id, or null when it does not exist
+    async fn user_story(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+    ) -> async_graphql::Result<Option<UserStoryObject>> {
+        let services = ctx.data_unchecked::<Services>();
+        match services.user_stories.get_by_id(&id).await {
+            Ok(story) => Ok(Some(story.into())),
+            Err(UserStoryServiceError::NotFound { .. }) => Ok(None),
+            Err(e) => Err(user_story_error(e)),
+        }
+    }
+
+    /// A page of user stories, newest first, optionally restricted to a persona or a search
+    /// term. Pass the `nextCursor` of a page as `after` for the next one.
+    async fn user_stories(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 20)] limit: i32,
+        after: Option<String>,
+        persona: Option<String>,
+        search: Option<String>,
+    ) -> async_graphql::Result<UserStoryPage> {
+        let services = ctx.data_unchecked::<Services>();
+
+        let (page, total_count) = match (persona, search) {
+            (None, None) => {
+                let page = services
+                    .user_stories
+                    .get_page(after.as_deref(), limit as i64)
+                    .await
+                    .map_err(user_story_error)?;
+                let total = services
+                    .user_stories
+                    .count()
+                    .await
+                    .map_err(user_story_error)?;
+                (page, total as i32)
+            }
+            (persona, search) => {
+                let query = StoryQuery {
+                    personas: persona.into_iter().collect(),
+                    text: search,
+                    limit: Some(limit as i64),
+                    cursor: after,
+                    ..Default::default()
+                };
+                let total = services
+                    .user_stories
+                    .count_query(query.clone())
+                    .await
+                    .map_err(user_story_error)?;
+                let page = services
+                    .user_stories
+                    .query(query)
+                    .await
+                    .map_err(user_story_error)?;
+                (page, total as i32)
+            }
+        };
+
+        Ok(UserStoryPage {
+            items: page.items.into_iter().map(Into::into).collect(),
+            next_cursor: page.next_cursor,
+            total_count,
+        })
+    }
+
+    /// An acceptance criterion by id, or null when it does not exist
+    async fn acceptance_criterion(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+    ) -> async_graphql::Result<Option<AcceptanceCriterionObject>> {
+        let services = ctx.data_unchecked::<Services>();
+        match services.acceptance_criteria.get_by_id(&id).await {
+            Ok(criteria) => Ok(Some(criteria.into())),
+            Err(AcceptanceCriteriaServiceError::NotFound { .. }) => Ok(None),
+            Err(e) => Err(criteria_error(e)),
+        }
+    }
+
+    async fn statistics(&self, ctx: &Context<'_>) -> async_graphql::Result<Statistics> {
+        let services = ctx.data_unchecked::<Services>();
+        let stats = services
+            .user_stories
+            .get_statistics()
+            .await
+            .map_err(user_story_error)?;
+
+        let mut stories_by_persona: Vec<PersonaCount> = stats
+            .stories_by_persona
+            .into_iter()
+            .map(|(persona, count)| PersonaCount {
+                persona,
+                count: count as i32,
+            })
+            .collect();
+        stories_by_persona.sort_by(|a, b| a.persona.cmp(&b.persona));
+        let mut stories_by_assignee: Vec<AssigneeCount> = stats
+            .stories_by_assignee
+            .into_iter()
+            .map(|(assignee, count)| AssigneeCount {
+                assignee,
+                count: count as i32,
+            })
+            .collect();
+        stories_by_assignee.sort_by(|a, b| a.assignee.cmp(&b.assignee));
+
+        Ok(Statistics {
+            total_stories: stats.total_stories as i32,
+            total_criteria: stats.total_criteria as i32,
+            personas_count: stats.personas_count as i32,
+            avg_criteria_per_story: stats.avg_criteria_per_story,
+            stories_by_persona,
+            stories_by_assignee,
+            unassigned_stories: stats.unassigned_stories as i32,
+        })
+    }
+}
+
+pub struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    async fn create_user_story(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateUserStoryInput,
+    ) -> async_graphql::Result<UserStoryObject> {
+        let services = ctx.data_unchecked::<Services>();
+        let story = services
+            .user_stories
+            .create(CreateUserStoryRequest {
+                id: input.id,
+                title: input.title,
+                description: input.description,
+                persona: input.persona,
+            })
+            .await
+            .map_err(user_story_error)?;
+
+        Ok(story.into())
+    }
+
+    async fn update_user_story(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+        input: UpdateUserStoryInput,
+    ) -> async_graphql::Result<UserStoryObject> {
+        let services = ctx.data_unchecked::<Services>();
+        let story = services
+            .user_stories
+            .update(
+                &id,
+                UpdateUserStoryRequest {
+                    title: input.title,
+                    description: input.description,
+                    persona: input.persona,
+                    expected_version: input.expected_version.map(i64::from),
+                },
+            )
+            .await
+            .map_err(user_story_error)?;
+
+        Ok(story.into())
+    }
+
+    /// Delete a user story and its acceptance criteria, returning the deleted id
+    async fn delete_user_story(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+        expected_version: Option<i32>,
+    ) -> async_graphql::Result<String> {
+        let services = ctx.data_unchecked::<Services>();
+        services
+            .user_stories
+            .delete_at_version(&id, expected_version.map(i64::from))
+            .await
+            .map_err(user_story_error)?;
+
+        Ok(id)
+    }
+
+    async fn create_acceptance_criterion(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateAcceptanceCriterionInput,
+    ) -> async_graphql::Result<AcceptanceCriterionObject> {
+        let services = ctx.data_unchecked::<Services>();
+        let criteria = services
+            .acceptance_criteria
+            .create(CreateAcceptanceCriteriaRequest {
+                id: input.id,
+                user_story_id: input.user_story_id,
+                description: input.description,
+            })
+            .await
+            .map_err(criteria_error)?;
+
+        Ok(criteria.into())
+    }
+
+    async fn update_acceptance_criterion(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+        description: String,
+        expected_version: Option<i32>,
+    ) -> async_graphql::Result<AcceptanceCriterionObject> {
+        let services = ctx.data_unchecked::<Services>();
+        let criteria = services
+            .acceptance_criteria
+            .update(
+                &id,
+                UpdateAcceptanceCriteriaRequest {
+                    description: Some(description),
+                    expected_version: expected_version.map(i64::from),
+                },
+            )
+            .await
+            .map_err(criteria_error)?;
+
+        Ok(criteria.into())
+    }
+
+    /// Delete an acceptance criterion, returning the deleted id
+    async fn delete_acceptance_criterion(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+        expected_version: Option<i32>,
+    ) -> async_graphql::Result<String> {
+        let services = ctx.data_unchecked::<Services>();
+        services
+            .acceptance_criteria
+            .delete_at_version(&id, expected_version.map(i64::from))
+            .await
+            .map_err(criteria_error)?;
+
+        Ok(id)
+    }
+}
+
+pub struct SubscriptionRoot;
+
+#[Subscription]
+impl SubscriptionRoot {
+    /// Changes recorded in the outbox from now on, whichever interface made them, optionally
+    /// only those touching one user story
+    async fn changes(
+        &self,
+        ctx: &Context<'_>,
+        user_story_id: Option<String>,
+    ) -> impl Stream<Item = ChangeEvent> {
+        let services = ctx.data_unchecked::<Services>();
+        events::subscribe(services.events.subscribe(), user_story_id)
+    }
+}
+
+fn user_story_error(error: UserStoryServiceError) -> Error {
+    let code = match &error {
+        UserStoryServiceError::NotFound { .. } | UserStoryServiceError::MemberNotFound { .. } => {
+            "NOT_FOUND"
+        }
+        UserStoryServiceError::AlreadyExists { .. } => "ALREADY_EXISTS",
+        UserStoryServiceError::PossibleDuplicate { .. } => "POSSIBLE_DUPLICATE",
+        UserStoryServiceError::Conflict { .. } => "VERSION_CONFLICT",
+        UserStoryServiceError::Claimed { .. } => "CLAIMED",
+        UserStoryServiceError::Validation { .. } => "VALIDATION",
+        UserStoryServiceError::BusinessRule { .. } => "BUSINESS_RULE",
+        UserStoryServiceError::Database(_) => "DATABASE",
+    };
+    let current_version = match &error {
+        UserStoryServiceError::Conflict { current, .. } => Some(current.version),
+        _ => None,
+    };
+    Error::new(error.to_string()).extend_with(|_, extensions| {
+        extensions.set("code", code);
+        if let Some(version) = current_version {
+            extensions.set("currentVersion", version);
+        }
+    })
+}
+
+fn criteria_error(error: AcceptanceCriteriaServiceError) -> Error {
+    let code = match &error {
+        AcceptanceCriteriaServiceError::NotFound { .. }
+        | AcceptanceCriteriaServiceError::UserStoryNotFound { .. } => "NOT_FOUND",
+        AcceptanceCriteriaServiceError::AlreadyExists { .. } => "ALREADY_EXISTS",
+        AcceptanceCriteriaServiceError::Conflict { .. } => "VERSION_CONFLICT",
+        AcceptanceCriteriaServiceError::Claimed { .. } => "CLAIMED",
+        AcceptanceCriteriaServiceError::Validation { .. } => "VALIDATION",
+        AcceptanceCriteriaServiceError::BusinessRule { .. } => "BUSINESS_RULE",
+        AcceptanceCriteriaServiceError::Database(_) => "DATABASE",
+    };
+    let current_version = match &error {
+        AcceptanceCriteriaServiceError::Conflict { current, .. } => Some(current.version),
+        _ => None,
+    };
+    Error::new(error.to_string()).extend_with(|_, extensions| {
+        extensions.set("code", code);
+        if let Some(version) = current_version {
+            extensions.set("currentVersion", version);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::DispatcherConfig;
+    use crate::repositories::Repositories;
+    use async_graphql::futures_util::StreamExt;
+    use async_graphql::{Request, Variables};
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn create_schema(pool: sqlx::SqlitePool) -> UserStoriesSchema {
+        schema(Services::new(Repositories::new(pool)))
+    }
+
+    async fn execute(schema: &UserStoriesSchema, query: &str) -> serde_json::Value {
+        let response = schema.execute(query).await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        response.data.into_json().unwrap()
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_stories_with_criteria(pool: sqlx::SqlitePool) {
+        let schema = create_schema(pool);
+
+        let data = execute(
+            &schema,
+            "{ userStories(limit: 10) { totalCount items { id acceptanceCriteria { id } } } }",
+        )
+        .await;
+        let page = &data["userStories"];
+        assert_eq!(page["totalCount"], 5);
+
+        let criteria_counts: Vec<(String, usize)> = page["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|story| {
+                (
+                    story["id"].as_str().unwrap().to_string(),
+                    story["acceptanceCriteria"].as_array().unwrap().len(),
+                )
+            })
+            .collect();
+        assert!(criteria_counts.contains(&("US-001".to_string(), 3)));
+        assert!(criteria_counts.contains(&("US-004".to_string(), 1)));
+        assert_eq!(criteria_counts.iter().map(|(_, n)| n).sum::<usize>(), 10);
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_loader_batches_keys(pool: sqlx::SqlitePool) {
+        let loader = CriteriaLoader {
+            services: Services::new(Repositories::new(pool)),
+        };
+
+        let keys = vec![
+            "US-001".to_string(),
+            "US-002".to_string(),
+            "US-999".to_string(),
+        ];
+        let loaded = loader.load(&keys).await.unwrap();
+        assert_eq!(loaded["US-001"].len(), 3);
+        assert_eq!(loaded["US-002"].len(), 2);
+        assert!(loaded["US-999"].is_empty());
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_filters(pool: sqlx::SqlitePool) {
+        let schema = create_schema(pool);
+
+        let data = execute(
+            &schema,
+            r#"{ userStories(persona: "Registered User", limit: 2) { totalCount nextCursor items { id } } }"#,
+        )
+        .await;
+        assert_eq!(data["userStories"]["totalCount"], 3);
+        assert_eq!(data["userStories"]["items"].as_array().unwrap().len(), 2);
+
+        let query = format!(
+            r#"{{ userStories(persona: "Registered User", limit: 2, after: "{}") {{ nextCursor items {{ id }} }} }}"#,
+            data["userStories"]["nextCursor"].as_str().unwrap()
+        );
+        let data = execute(&schema, &query).await;
+        assert_eq!(data["userStories"]["items"][0]["id"], "US-001");
+        assert!(data["userStories"]["nextCursor"].is_null());
+
+        let data = execute(
+            &schema,
+            r#"{ userStories(search: "login") { totalCount } }"#,
+        )
+        .await;
+        assert!(data["userStories"]["totalCount"].as_i64().unwrap() >= 1);
+    }
+
+    #[sqlx::test]
+    async fn test_mutations(pool: sqlx::SqlitePool) {
+        let schema = create_schema(pool);
+
+        execute(
+            &schema,
+            r#"mutation {
+                createUserStory(input: { id: "US-100", title: "Checkout", description: "As a shopper, I want to pay", persona: "Shopper" }) { id }
+                createAcceptanceCriterion(input: { id: "AC-100-1", userStoryId: "US-100", description: "Card payments work" }) { id }
+            }"#,
+        )
+        .await;
+
+        let data = execute(
+            &schema,
+            r#"mutation {
+                updateUserStory(id: "US-100", input: { title: "Guest checkout" }) { title }
+                updateAcceptanceCriterion(id: "AC-100-1", description: "Cards and wallets work") { description }
+            }"#,
+        )
+        .await;
+        assert_eq!(data["updateUserStory"]["title"], "Guest checkout");
+
+        let data = execute(
+            &schema,
+            r#"{ userStory(id: "US-100") { title acceptanceCriteria { description } } }"#,
+        )
+        .await;
+        assert_eq!(
+            data["userStory"]["acceptanceCriteria"][0]["description"],
+            "Cards and wallets work"
+        );
+
+        execute(
+            &schema,
+            r#"mutation { deleteAcceptanceCriterion(id: "AC-100-1") deleteUserStory(id: "US-100") }"#,
+        )
+        .await;
+        let data = execute(&schema, r#"{ userStory(id: "US-100") { id } }"#).await;
+        assert!(data["userStory"].is_null());
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_error_codes(pool: sqlx::SqlitePool) {
+        let schema = create_schema(pool);
+
+        let request = Request::new(
+            "mutation($input: CreateUserStoryInput!) { createUserStory(input: $input) { id } }",
+        )
+        .variables(Variables::from_json(json!({
+            "input": { "id": "US-001", "title": "t", "description": "d", "persona": "p" }
+        })));
+        let response = schema.execute(request).await;
+        let error = serde_json::to_value(&response.errors[0]).unwrap();
+        assert_eq!(error["extensions"]["code"], "ALREADY_EXISTS");
+
+        let response = schema
+            .execute(r#"mutation { deleteUserStory(id: "US-999") }"#)
+            .await;
+        let error = serde_json::to_value(&response.errors[0]).unwrap();
+        assert_eq!(error["extensions"]["code"], "NOT_FOUND");
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_change_subscription(pool: sqlx::SqlitePool) {
+        let services = Services::new(Repositories::new(pool));
+        let schema = schema(services.clone());
+        let mut dispatcher = services.events.dispatcher(DispatcherConfig::default());
+        // The first round only marks where the outbox ends
+        dispatcher.run_once().await.unwrap();
+
+        let mut stream = schema.execute_stream(
+            r#"subscription { changes(userStoryId: "US-002") { kind entity id userStoryId } }"#,
+        );
+
+        // Poll once so the subscription is registered before the changes happen
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), stream.next())
+                .await
+                .is_err()
+        );
+
+        // Changes made outside GraphQL reach subscribers through the outbox
+        for (id, title) in [("US-001", "Ignored"), ("US-002", "Watched")] {
+            services
+                .user_stories
+                .update(
+                    id,
+                    UpdateUserStoryRequest {
+                        title: Some(title.to_string()),
+                        description: None,
+                        persona: None,
+                        expected_version: None,
+                    },
+                )
+                .await
+                .unwrap();
+        }
+        dispatcher.run_once().await.unwrap();
+
+        let response = tokio::time::timeout(Duration::from_secs(1), stream.next())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            response.data.into_json().unwrap()["changes"],
+            json!({ "kind": "UPDATED", "entity": "USER_STORY", "id": "US-002", "userStoryId": "US-002" })
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use crate::services::acceptance_criteria_service::AcceptanceCriteriaServiceError;
+use crate::services::user_story_service::UserStoryServiceError;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Error body returned by every endpoint
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ErrorBody {
+    /// Machine-readable error kind, e.g. `not_found` or `validation`
+    pub code: String,
+    pub message: String,
+    /// The row as it is now, for `version_conflict` errors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current: Option<serde_json::Value>,
+}
+
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+    pub current: Option<serde_json::Value>,
+}
+
+impl ApiError {
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            code: "bad_request",
+            message: message.into(),
+            current: None,
+        }
+    }
+
+    fn new(status: StatusCode, code: &'static str, message: String) -> Self {
+        Self {
+            status,
+            code,
+            message,
+            current: None,
+        }
+    }
+
+    fn conflict(message: String, current: impl Serialize) -> Self {
+        Self {
+            current: serde_json::to_value(current).ok(),
+            ..Self::new(StatusCode::CONFLICT, "version_conflict", message)
+        }
+    }
+}
+
+impl From<UserStoryServiceError> for ApiError {
+    fn from(error: UserStoryServiceError) -> Self {
+        let message = error.to_string();
+        match error {
+            UserStoryServiceError::NotFound { .. }
+            | UserStoryServiceError::MemberNotFound { .. } => {
+                Self::new(StatusCode::NOT_FOUND, "not_found", message)
+            }
+            UserStoryServiceError::AlreadyExists { .. } => {
+                Self::new(StatusCode::CONFLICT, "already_exists", message)
+            }
+            UserStoryServiceError::PossibleDuplicate { .. } => {
+                Self::new(StatusCode::CONFLICT, "possible_duplicate", message)
+            }
+            UserStoryServiceError::Conflict { current, .. } => Self::conflict(message, current),
+            UserStoryServiceError::Claimed { .. } => {
+                Self::new(StatusCode::LOCKED, "claimed", message)
+            }
+            UserStoryServiceError::Validation { .. } => {
+                Self::new(StatusCode::BAD_REQUEST, "validation", message)
+            }
+            UserStoryServiceError::BusinessRule { .. } => {
+                Self::new(StatusCode::UNPROCESSABLE_ENTITY, "business_rule", message)
+            }
+            UserStoryServiceError::Database(_) => {
+                Self::new(StatusCode::INTERNAL_SERVER_ERROR, "database", message)
+            }
+        }
+    }
+}
+
+impl From<AcceptanceCriteriaServiceError> for ApiError {
+    fn from(error: AcceptanceCriteriaServiceError) -> Self {
+        let message = error.to_string();
+        match error {
+            AcceptanceCriteriaServiceError::NotFound { .. }
+            | AcceptanceCriteriaServiceError::UserStoryNotFound { .. } => {
+                Self::new(StatusCode::NOT_FOUND, "not_found", message)
+            }
+            AcceptanceCriteriaServiceError::AlreadyExists { .. } => {
+                Self::new(StatusCode::CONFLICT, "already_exists", message)
+            }
+            AcceptanceCriteriaServiceError::Conflict { current, .. } => {
+                Self::conflict(message, current)
+            }
+            AcceptanceCriteriaServiceError::Claimed { .. } => {
+                Self::new(StatusCode::LOCKED, "claimed", message)
+            }
+            AcceptanceCriteriaServiceError::Validation { .. } => {
+                Self::new(StatusCode::BAD_REQUEST, "validation", message)
+            }
+            AcceptanceCriteriaServiceError::BusinessRule { .. } => {
+                Self::new(StatusCode::UNPROCESSABLE_ENTITY, "business_rule", message)
+            }
+            AcceptanceCriteriaServiceError::Database(_) => {
+                Self::new(StatusCode::INTERNAL_SERVER_ERROR, "database", message)
+            }
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ErrorBody {
+            code: self.code.to_string(),
+            message: self.message,
+            current: self.current,
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
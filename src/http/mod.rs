@@ -0,0 +1,515 @@
+//! REST API over `Services` for clients that do not speak MCP.
+//!
+//! | Method | Path | |
+//! |---|---|---|
+//! | GET, POST | `/stories` | list (paginated) or create user stories |
+//! | GET, PATCH, DELETE | `/stories/{id}` | a user story with its criteria |
+//! | GET, POST | `/stories/{id}/criteria` | acceptance criteria of a story |
+//! | GET, PATCH, DELETE | `/criteria/{id}` | a single acceptance criterion |
+//! | GET | `/search?q=` | search stories and criteria |
+//! | GET | `/statistics` | story and criteria statistics |
+//! | GET | `/openapi.json` | the OpenAPI document for all of the above |
+//!
+//! With the `graphql` feature the same router also serves `/graphql` and `/graphql/ws`.
+
+pub mod error;
+pub mod openapi;
+
+pub use error::{ApiError, ErrorBody};
+
+use crate::database::{initialize_database_with, DatabaseConfig};
+use crate::events::DispatcherConfig;
+use crate::models::{
+    AcceptanceCriteria, CreateAcceptanceCriteriaRequest, CreateUserStoryRequest,
+    UpdateAcceptanceCriteriaRequest, UpdateUserStoryRequest, UserStory, UserStoryWithCriteria,
+    DEFAULT_PAGE_SIZE,
+};
+use crate::repositories::Repositories;
+use crate::services::acceptance_criteria_service::AcceptanceCriteriaStatistics;
+use crate::services::user_story_service::UserStoryStatistics;
+use crate::services::{ServiceConfig, Services};
+use axum::extract::rejection::{JsonRejection, QueryRejection};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+pub type Result<T> = std::result::Result<T, ApiError>;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PageParams {
+    /// Number of stories to return (default 20, at most 1000)
+    pub limit: Option<i64>,
+    /// `next_cursor` of the previous page
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct StoryPage {
+    pub items: Vec<UserStory>,
+    /// Pass back as `cursor` for the next page; absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    /// Total number of stories
+    pub total: i64,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SearchParams {
+    /// Text to look for in titles, descriptions, personas and criteria
+    pub q: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DeleteParams {
+    /// Refuse the delete with a conflict unless the row is still at this version
+    pub expected_version: Option<i64>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SearchResults {
+    pub user_stories: Vec<UserStory>,
+    pub acceptance_criteria: Vec<AcceptanceCriteria>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Statistics {
+    pub user_stories: UserStoryStatistics,
+    pub acceptance_criteria: AcceptanceCriteriaStatistics,
+}
+
+/// Body of `POST /stories/{id}/criteria`; the story comes from the path
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct NewAcceptanceCriteria {
+    /// Generated from the id scheme when absent
+    #[serde(default)]
+    pub id: Option<String>,
+    pub description: String,
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        ApiError::bad_request(rejection.body_text())
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        ApiError::bad_request(rejection.body_text())
+    }
+}
+
+/// Build the API router around the given services
+pub fn router(services: Services) -> Router {
+    let router = Router::new();
+
+    #[cfg(feature = "graphql")]
+    let router = {
+        use async_graphql_axum::{GraphQL, GraphQLSubscription};
+
+        let schema = crate::graphql::schema(services.clone());
+        router
+            .route_service("/graphql", GraphQL::new(schema.clone()))
+            .route_service("/graphql/ws", GraphQLSubscription::new(schema))
+    };
+
+    router
+        .route("/stories", get(list_stories).post(create_story))
+        .route(
+            "/stories/{id}",
+            get(get_story).patch(update_story).delete(delete_story),
+        )
+        .route(
+            "/stories/{id}/criteria",
+            get(list_criteria).post(create_criteria),
+        )
+        .route(
+            "/criteria/{id}",
+            get(get_criteria)
+                .patch(update_criteria)
+                .delete(delete_criteria),
+        )
+        .route("/search", get(search))
+        .route("/statistics", get(statistics))
+        .route("/openapi.json", get(openapi_document))
+        .with_state(services)
+}
+
+/// Open the database and serve the API on `address` until the process is stopped
+pub async fn serve(
+    database_url: &str,
+    database: &DatabaseConfig,
+    address: &str,
+    config: ServiceConfig,
+) -> std::io::Result<()> {
+    let pool = initialize_database_with(database_url, database)
+        .await
+        .map_err(std::io::Error::other)?;
+    let services = Services::with_config(Repositories::new(pool), config);
+    if !database.read_only {
+        services
+            .events
+            .dispatcher(DispatcherConfig::default())
+            .spawn();
+    }
+
+    let listener = tokio::net::TcpListener::bind(address).await?;
+    eprintln!(
+        "User Stories HTTP API listening on {}",
+        listener.local_addr()?
+    );
+    axum::serve(listener, router(services)).await
+}
+
+async fn list_stories(
+    State(services): State<Services>,
+    params: std::result::Result<Query<PageParams>, QueryRejection>,
+) -> Result<Json<StoryPage>> {
+    let Query(params) = params?;
+    let limit = params.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+
+    let page = services
+        .user_stories
+        .get_page(params.cursor.as_deref(), limit)
+        .await?;
+    let total = services.user_stories.count().await?;
+
+    Ok(Json(StoryPage {
+        items: page.items,
+        next_cursor: page.next_cursor,
+        total,
+    }))
+}
+
+async fn create_story(
+    State(services): State<Services>,
+    request: std::result::Result<Json<CreateUserStoryRequest>, JsonRejection>,
+) -> Result<(StatusCode, Json<UserStory>)> {
+    let Json(request) = request?;
+    let story = services.user_stories.create(request).await?;
+    Ok((StatusCode::CREATED, Json(story)))
+}
+
+async fn get_story(
+    State(services): State<Services>,
+    Path(id): Path<String>,
+) -> Result<Json<UserStoryWithCriteria>> {
+    Ok(Json(services.user_stories.get_with_criteria(&id).await?))
+}
+
+async fn update_story(
+    State(services): State<Services>,
+    Path(id): Path<String>,
+    request: std::result::Result<Json<UpdateUserStoryRequest>, JsonRejection>,
+) -> Result<Json<UserStory>> {
+    let Json(request) = request?;
+    Ok(Json(services.user_stories.update(&id, request).await?))
+}
+
+async fn delete_story(
+    State(services): State<Services>,
+    Path(id): Path<String>,
+    params: std::result::Result<Query<DeleteParams>, QueryRejection>,
+) -> Result<StatusCode> {
+    let Query(params) = params?;
+    services
+        .user_stories
+        .delete_at_version(&id, params.expected_version)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_criteria(
+    State(services): State<Services>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<AcceptanceCriteria>>> {
+    Ok(Json(
+        services
+            .acceptance_criteria
+            .get_by_user_story_id(&id)
+            .await?,
+    ))
+}
+
+async fn create_criteria(
+    State(services): State<Services>,
+    Path(id): Path<String>,
+    request: std::result::Result<Json<NewAcceptanceCriteria>, JsonRejection>,
+) -> Result<(StatusCode, Json<AcceptanceCriteria>)> {
+    let Json(request) = request?;
+    let criteria = services
+        .acceptance_criteria
+        .create(CreateAcceptanceCriteriaRequest {
+            id: request.id,
+            user_story_id: id,
+            description: request.description,
+        })
+        .await?;
+    Ok((StatusCode::CREATED, Json(criteria)))
+}
+
+async fn get_criteria(
+    State(services): State<Services>,
+    Path(id): Path<String>,
+) -> Result<Json<AcceptanceCriteria>> {
+    Ok(Json(services.acceptance_criteria.get_by_id(&id).await?))
+}
+
+async fn update_criteria(
+    State(services): State<Services>,
+    Path(id): Path<String>,
+    request: std::result::Result<Json<UpdateAcceptanceCriteriaRequest>, JsonRejection>,
+) -> Result<Json<AcceptanceCriteria>> {
+    let Json(request) = request?;
+    Ok(Json(
+        services.acceptance_criteria.update(&id, request).await?,
+    ))
+}
+
+async fn delete_criteria(
+    State(services): State<Services>,
+    Path(id): Path<String>,
+    params: std::result::Result<Query<DeleteParams>, QueryRejection>,
+) -> Result<StatusCode> {
+    let Query(params) = params?;
+    services
+        .acceptance_criteria
+        .delete_at_version(&id, params.expected_version)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn search(
+    State(services): State<Services>,
+    params: std::result::Result<Query<SearchParams>, QueryRejection>,
+) -> Result<Json<SearchResults>> {
+    let Query(params) = params?;
+    Ok(Json(SearchResults {
+        user_stories: services.user_stories.search(&params.q).await?,
+        acceptance_criteria: services.acceptance_criteria.search(&params.q).await?,
+    }))
+}
+
+async fn statistics(State(services): State<Services>) -> Result<Json<Statistics>> {
+    Ok(Json(Statistics {
+        user_stories: services.user_stories.get_statistics().await?,
+        acceptance_criteria: services.acceptance_criteria.get_statistics().await?,
+    }))
+}
+
+async fn openapi_document() -> Json<serde_json::Value> {
+    Json(openapi::document())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    async fn send(
+        pool: &sqlx::SqlitePool,
+        method: &str,
+        uri: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let app = router(Services::new(Repositories::new(pool.clone())));
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json");
+        let request = match body {
+            Some(body) => request.body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        }
+        .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let value = if bytes.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(&bytes).unwrap()
+        };
+        (status, value)
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_list_stories_paginated(pool: sqlx::SqlitePool) {
+        let (status, page) = send(&pool, "GET", "/stories?limit=2", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page["items"].as_array().unwrap().len(), 2);
+        assert_eq!(page["total"], 5);
+
+        let uri = format!(
+            "/stories?limit=3&cursor={}",
+            page["next_cursor"].as_str().unwrap()
+        );
+        let (status, rest) = send(&pool, "GET", &uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(rest["items"].as_array().unwrap().len(), 3);
+        assert_eq!(rest["items"][2]["id"], "US-001");
+        assert!(rest.get("next_cursor").is_none());
+
+        let (status, error) = send(&pool, "GET", "/stories?cursor=US-001", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["code"], "validation");
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_story_lifecycle(pool: sqlx::SqlitePool) {
+        let story = json!({
+            "id": "US-100",
+            "title": "Checkout",
+            "description": "As a shopper, I want to pay",
+            "persona": "Shopper"
+        });
+        let (status, created) = send(&pool, "POST", "/stories", Some(story.clone())).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["id"], "US-100");
+
+        let (status, error) = send(&pool, "POST", "/stories", Some(story)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(error["code"], "already_exists");
+
+        let criteria = json!({ "id": "AC-100-1", "description": "Card payments work" });
+        let (status, _) = send(&pool, "POST", "/stories/US-100/criteria", Some(criteria)).await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let update = json!({ "title": "Guest checkout" });
+        let (status, updated) = send(&pool, "PATCH", "/stories/US-100", Some(update)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(updated["title"], "Guest checkout");
+
+        let (status, fetched) = send(&pool, "GET", "/stories/US-100", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(fetched["acceptance_criteria"][0]["id"], "AC-100-1");
+
+        let (status, _) = send(&pool, "DELETE", "/stories/US-100", None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let (status, error) = send(&pool, "GET", "/stories/US-100", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(error["code"], "not_found");
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_criteria_endpoints(pool: sqlx::SqlitePool) {
+        let (status, criteria) = send(&pool, "GET", "/stories/US-001/criteria", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(criteria.as_array().unwrap().len(), 3);
+
+        let (status, _) = send(&pool, "GET", "/stories/US-999/criteria", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, error) = send(
+            &pool,
+            "PATCH",
+            "/criteria/AC-001",
+            Some(json!({ "description": "" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["code"], "validation");
+
+        let (status, _) = send(&pool, "DELETE", "/criteria/AC-001", None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let (status, _) = send(&pool, "GET", "/criteria/AC-001", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_version_conflict(pool: sqlx::SqlitePool) {
+        let update = json!({ "title": "Sign in", "expected_version": 1 });
+        let (status, updated) = send(&pool, "PATCH", "/stories/US-001", Some(update)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(updated["version"], 2);
+
+        let stale = json!({ "title": "Log in", "expected_version": 1 });
+        let (status, error) = send(&pool, "PATCH", "/stories/US-001", Some(stale)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(error["code"], "version_conflict");
+        assert_eq!(error["current"]["title"], "Sign in");
+        assert_eq!(error["current"]["version"], 2);
+
+        let (status, _) = send(&pool, "DELETE", "/criteria/AC-001?expected_version=2", None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let (status, _) = send(&pool, "DELETE", "/criteria/AC-001?expected_version=1", None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_search_and_statistics(pool: sqlx::SqlitePool) {
+        let (status, results) = send(&pool, "GET", "/search?q=login", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(!results["user_stories"].as_array().unwrap().is_empty());
+
+        let (status, error) = send(&pool, "GET", "/search", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["code"], "bad_request");
+
+        let (status, stats) = send(&pool, "GET", "/statistics", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(stats["user_stories"]["total_stories"], 5);
+        assert_eq!(stats["acceptance_criteria"]["total_criteria"], 10);
+    }
+
+    #[sqlx::test]
+    async fn test_invalid_json_body(pool: sqlx::SqlitePool) {
+        let (status, error) = send(&pool, "POST", "/stories", Some(json!({ "id": 1 }))).await;
+        assert!(status.is_client_error());
+        assert_eq!(error["code"], "bad_request");
+    }
+
+    #[sqlx::test]
+    async fn test_openapi_document(pool: sqlx::SqlitePool) {
+        let (status, document) = send(&pool, "GET", "/openapi.json", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(document["openapi"], "3.0.3");
+
+        for path in [
+            "/stories",
+            "/stories/{id}",
+            "/stories/{id}/criteria",
+            "/criteria/{id}",
+            "/search",
+            "/statistics",
+        ] {
+            assert!(document["paths"][path].is_object(), "missing {}", path);
+        }
+
+        // Every schema reference points at a component
+        let text = document.to_string();
+        for reference in text.split("\"$ref\":\"#/components/schemas/").skip(1) {
+            let name = &reference[..reference.find('"').unwrap()];
+            assert!(
+                document["components"]["schemas"][name].is_object(),
+                "unresolved {}",
+                name
+            );
+        }
+    }
+}
@@ -0,0 +1,298 @@
+//! OpenAPI 3 document for the REST API.
+//!
+//! Component schemas are derived from the request and response types with `schemars`,
+//! so they follow the models; the operations below mirror the routes in [`super::router`].
+
+use super::{ErrorBody, NewAcceptanceCriteria, SearchResults, Statistics, StoryPage};
+use crate::models::{
+    AcceptanceCriteria, CreateUserStoryRequest, UpdateAcceptanceCriteriaRequest,
+    UpdateUserStoryRequest, UserStory, UserStoryWithCriteria, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE,
+};
+use schemars::generate::SchemaSettings;
+use schemars::{JsonSchema, SchemaGenerator};
+use serde_json::{json, Map, Value};
+
+struct Operation {
+    method: &'static str,
+    path: &'static str,
+    summary: &'static str,
+    parameters: Vec<Value>,
+    request_body: Option<Value>,
+    /// Success status, description and response schema
+    success: (u16, &'static str, Option<Value>),
+    /// Error statuses this operation can return
+    errors: &'static [u16],
+}
+
+/// Build the OpenAPI document describing every endpoint
+pub fn document() -> Value {
+    let mut generator = SchemaSettings::openapi3().into_generator();
+    let operations = operations(&mut generator);
+
+    let mut paths = Map::new();
+    for operation in operations {
+        let mut responses = Map::new();
+        let (status, description, schema) = operation.success;
+        responses.insert(status.to_string(), response(description, schema));
+        for status in operation.errors {
+            responses.insert(
+                status.to_string(),
+                response(
+                    error_description(*status),
+                    Some(schema_for::<ErrorBody>(&mut generator)),
+                ),
+            );
+        }
+
+        let mut value = json!({
+            "summary": operation.summary,
+            "responses": responses,
+        });
+        if !operation.parameters.is_empty() {
+            value["parameters"] = Value::Array(operation.parameters);
+        }
+        if let Some(schema) = operation.request_body {
+            value["requestBody"] = json!({
+                "required": true,
+                "content": { "application/json": { "schema": schema } },
+            });
+        }
+
+        paths
+            .entry(operation.path)
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+            .unwrap()
+            .insert(operation.method.to_string(), value);
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "User Stories API",
+            "version": crate::VERSION,
+            "description": "User stories and acceptance criteria",
+        },
+        "paths": paths,
+        "components": { "schemas": generator.take_definitions(true) },
+    })
+}
+
+fn operations(generator: &mut SchemaGenerator) -> Vec<Operation> {
+    let story_id = path_parameter("id", "User story id, e.g. US-001");
+    let criteria_id = path_parameter("id", "Acceptance criteria id, e.g. AC-001");
+    let expected_version = query_parameter(
+        "expected_version",
+        "Fail with 409 unless the row is still at this version",
+        "integer",
+        false,
+    );
+
+    vec![
+        Operation {
+            method: "get",
+            path: "/stories",
+            summary: "List user stories a page at a time",
+            parameters: vec![
+                query_parameter(
+                    "limit",
+                    &format!(
+                        "Page size (default {}, at most {})",
+                        DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE
+                    ),
+                    "integer",
+                    false,
+                ),
+                query_parameter(
+                    "cursor",
+                    "next_cursor of the previous page",
+                    "string",
+                    false,
+                ),
+            ],
+            request_body: None,
+            success: (
+                200,
+                "A page of user stories",
+                Some(schema_for::<StoryPage>(generator)),
+            ),
+            errors: &[400, 500],
+        },
+        Operation {
+            method: "post",
+            path: "/stories",
+            summary: "Create a user story",
+            parameters: vec![],
+            request_body: Some(schema_for::<CreateUserStoryRequest>(generator)),
+            success: (
+                201,
+                "The created user story",
+                Some(schema_for::<UserStory>(generator)),
+            ),
+            errors: &[400, 409, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/stories/{id}",
+            summary: "Get a user story with its acceptance criteria",
+            parameters: vec![story_id.clone()],
+            request_body: None,
+            success: (
+                200,
+                "The user story",
+                Some(schema_for::<UserStoryWithCriteria>(generator)),
+            ),
+            errors: &[404, 500],
+        },
+        Operation {
+            method: "patch",
+            path: "/stories/{id}",
+            summary: "Update fields of a user story",
+            parameters: vec![story_id.clone()],
+            request_body: Some(schema_for::<UpdateUserStoryRequest>(generator)),
+            success: (
+                200,
+                "The updated user story",
+                Some(schema_for::<UserStory>(generator)),
+            ),
+            errors: &[400, 404, 409, 423, 500],
+        },
+        Operation {
+            method: "delete",
+            path: "/stories/{id}",
+            summary: "Delete a user story and its acceptance criteria",
+            parameters: vec![story_id.clone(), expected_version.clone()],
+            request_body: None,
+            success: (204, "Deleted", None),
+            errors: &[400, 404, 409, 423, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/stories/{id}/criteria",
+            summary: "List the acceptance criteria of a user story",
+            parameters: vec![story_id.clone()],
+            request_body: None,
+            success: (
+                200,
+                "The acceptance criteria",
+                Some(array_of::<AcceptanceCriteria>(generator)),
+            ),
+            errors: &[404, 500],
+        },
+        Operation {
+            method: "post",
+            path: "/stories/{id}/criteria",
+            summary: "Add an acceptance criterion to a user story",
+            parameters: vec![story_id],
+            request_body: Some(schema_for::<NewAcceptanceCriteria>(generator)),
+            success: (
+                201,
+                "The created acceptance criterion",
+                Some(schema_for::<AcceptanceCriteria>(generator)),
+            ),
+            errors: &[400, 404, 409, 422, 423, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/criteria/{id}",
+            summary: "Get an acceptance criterion",
+            parameters: vec![criteria_id.clone()],
+            request_body: None,
+            success: (
+                200,
+                "The acceptance criterion",
+                Some(schema_for::<AcceptanceCriteria>(generator)),
+            ),
+            errors: &[404, 500],
+        },
+        Operation {
+            method: "patch",
+            path: "/criteria/{id}",
+            summary: "Update an acceptance criterion",
+            parameters: vec![criteria_id.clone()],
+            request_body: Some(schema_for::<UpdateAcceptanceCriteriaRequest>(generator)),
+            success: (
+                200,
+                "The updated acceptance criterion",
+                Some(schema_for::<AcceptanceCriteria>(generator)),
+            ),
+            errors: &[400, 404, 409, 423, 500],
+        },
+        Operation {
+            method: "delete",
+            path: "/criteria/{id}",
+            summary: "Delete an acceptance criterion",
+            parameters: vec![criteria_id, expected_version],
+            request_body: None,
+            success: (204, "Deleted", None),
+            errors: &[400, 404, 409, 423, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/search",
+            summary: "Search user stories and acceptance criteria",
+            parameters: vec![query_parameter("q", "Text to search for", "string", true)],
+            request_body: None,
+            success: (200, "Matches", Some(schema_for::<SearchResults>(generator))),
+            errors: &[400, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/statistics",
+            summary: "User story and acceptance criteria statistics",
+            parameters: vec![],
+            request_body: None,
+            success: (200, "Statistics", Some(schema_for::<Statistics>(generator))),
+            errors: &[500],
+        },
+    ]
+}
+
+fn schema_for<T: JsonSchema>(generator: &mut SchemaGenerator) -> Value {
+    generator.subschema_for::<T>().to_value()
+}
+
+fn array_of<T: JsonSchema>(generator: &mut SchemaGenerator) -> Value {
+    json!({ "type": "array", "items": schema_for::<T>(generator) })
+}
+
+fn path_parameter(name: &str, description: &str) -> Value {
+    json!({
+        "name": name,
+        "in": "path",
+        "required": true,
+        "description": description,
+        "schema": { "type": "string" },
+    })
+}
+
+fn query_parameter(name: &str, description: &str, kind: &str, required: bool) -> Value {
+    json!({
+        "name": name,
+        "in": "query",
+        "required": required,
+        "description": description,
+        "schema": { "type": kind },
+    })
+}
+
+fn response(description: &str, schema: Option<Value>) -> Value {
+    match schema {
+        Some(schema) => json!({
+            "description": description,
+            "content": { "application/json": { "schema": schema } },
+        }),
+        None => json!({ "description": description }),
+    }
+}
+
+fn error_description(status: u16) -> &'static str {
+    match status {
+        400 => "Invalid request or validation error",
+        404 => "Not found",
+        409 => "The id already exists or the row is at another version than expected",
+        422 => "A business rule was violated",
+        423 => "Another agent holds the claim on the user story",
+        _ => "Database error",
+    }
+}
@@ -8,9 +8,23 @@
 //! - CRUD operations for user stories and acceptance criteria
 //! - Compile-time checked SQL queries with SQLx
 //! - Database migrations support
+//! - Configurable connection pool and SQLite pragmas (WAL, busy timeout, read-only mode)
 //! - Business logic validation
-//! - Search and filtering capabilities
+//! - Search and composable story queries with filters and sorting on any column
 //! - Statistics and reporting
+//! - Transactional outbox of change events delivered to webhooks with HMAC signatures and retries
+//! - Cursor-based change feed with upserts and tombstones for incremental sync
+//! - Expiring agent claims on stories with a work queue of unclaimed stories
+//! - Assignees and reviewers from registered human and agent members, with workload counts
+//! - Threaded comments on stories and criteria with @mentions and resolved state
+//! - Soft delete into a trash that can be restored or purged
+//! - Story revisions with field- and criteria-level diffs and atomic revert
+//! - Story quality linting against INVEST and the "As a ... I want ... so that ..." template
+//! - JSON backlog export/import, Gherkin `.feature` files and Jira/Azure DevOps CSV
+//! - Command line interface with JSON output and meaningful exit codes
+//! - REST API with an OpenAPI document (`http` feature)
+//! - GraphQL endpoint with batched criteria loading and change subscriptions (`graphql` feature)
+//! - Keyset cursor pagination of stories, criteria, MCP tools and story resources
 //! - Full async/await support
 //!
 //! ## Example Usage
@@ -34,7 +48,7 @@
 //!
 //!     // Create a user story
 //!     let user_story_request = CreateUserStoryRequest {
-//!         id: "US-001".to_string(),
+//!         id: Some("US-001".to_string()),
 //!         title: "User Login".to_string(),
 //!         description: "As a user, I want to login to access my account".to_string(),
 //!         persona: "End User".to_string(),
@@ -49,14 +63,22 @@
 
 #![allow(dead_code)]
 
+pub mod cli;
 pub mod database;
+pub mod events;
+pub mod formats;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+#[cfg(feature = "http")]
+pub mod http;
 pub mod mcp;
 pub mod models;
+pub mod quality;
 pub mod repositories;
 pub mod services;
 
 // Re-export commonly used types for convenience
-pub use database::{initialize_database, DbPool};
+pub use database::{initialize_database, DatabaseConfig, DbPool};
 pub use models::*;
 pub use repositories::Repositories;
 pub use services::Services;
@@ -85,14 +107,14 @@ mod tests {
 
         // Create a user story with acceptance criteria
         let user_story_request = CreateUserStoryRequest {
-            id: "US-TEST".to_string(),
+            id: Some("US-TEST".to_string()),
             title: "Integration Test Story".to_string(),
             description: "Testing the full integration flow".to_string(),
             persona: "Test User".to_string(),
         };
 
         let criteria_requests = vec![CreateAcceptanceCriteriaRequest {
-            id: "AC-TEST-1".to_string(),
+            id: Some("AC-TEST-1".to_string()),
             user_story_id: "US-TEST".to_string(),
             description:
                 "Given the system is running, when I create a story, then it should succeed"
@@ -127,13 +149,13 @@ mod tests {
         // Create multiple user stories
         let stories = vec![
             CreateUserStoryRequest {
-                id: "US-001".to_string(),
+                id: Some("US-001".to_string()),
                 title: "Login Feature".to_string(),
                 description: "User authentication system".to_string(),
                 persona: "End User".to_string(),
             },
             CreateUserStoryRequest {
-                id: "US-002".to_string(),
+                id: Some("US-002".to_string()),
                 title: "Search Products".to_string(),
                 description: "Product search functionality".to_string(),
                 persona: "Customer".to_string(),
@@ -1,13 +1,24 @@
 use crate::{
-    database::initialize_database,
+    database::{initialize_database_with, DatabaseConfig},
+    events::DispatcherConfig,
+    formats::{CsvMapping, CsvPreset},
     models::*,
     repositories::Repositories,
-    services::{user_story_service::UserStoryStatistics, Services},
+    services::{
+        acceptance_criteria_service::AcceptanceCriteriaServiceError,
+        backlog_service::BacklogServiceError,
+        claim_service::{ClaimServiceError, DEFAULT_CLAIM_TTL_SECONDS},
+        revision_service::RevisionServiceError,
+        trash_service::TrashServiceError,
+        user_story_service::{UserStoryServiceError, UserStoryStatistics},
+        ServiceConfig, Services,
+    },
 };
 use rmcp::{
     handler::server::{tool::ToolRouter, wrapper::Parameters, ServerHandler},
     model::{
-        CallToolResult, Content, Implementation, ProtocolVersion, ServerCapabilities, ServerInfo,
+        AnnotateAble, CallToolResult, Content, Implementation, ProtocolVersion, ServerCapabilities,
+        ServerInfo,
     },
     schemars,
     service::RoleServer,
@@ -19,23 +30,37 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+/// Page size of story and criteria listings when no limit is given
+const MCP_PAGE_SIZE: i64 = 100;
+
+/// Tools listed per `tools/list` page
+const TOOLS_PAGE_SIZE: usize = 50;
+
+/// Each user story is a resource at this prefix followed by its id
+const USER_STORY_URI_PREFIX: &str = "user-story://";
+
 #[derive(Clone)]
 pub struct UserStoryServer {
     services: Arc<Mutex<Services>>,
+    config: ServiceConfig,
     tool_router: ToolRouter<Self>,
 }
 
 // Request types for structured parameters
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct CreateUserStoryParams {
-    /// Unique identifier for the user story
-    pub id: String,
+    /// Unique identifier for the user story; the next free US-<n> when omitted
+    pub id: Option<String>,
     /// Title of the user story
     pub title: String,
     /// Description of the user story
     pub description: String,
     /// Persona associated with the user story
     pub persona: String,
+    /// What to do about existing stories that look alike: off, warn (default) or reject
+    pub duplicate_check: Option<String>,
+    /// Similarity from 0 to 1 from which a story counts as a likely duplicate (default 0.4)
+    pub similarity_threshold: Option<f64>,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
@@ -44,26 +69,467 @@ pub struct GetUserStoryParams {
     pub id: String,
 }
 
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct UpdateUserStoryParams {
+    /// ID of the user story to update
+    pub id: String,
+    /// New title
+    pub title: Option<String>,
+    /// New description
+    pub description: Option<String>,
+    /// New persona name
+    pub persona: Option<String>,
+    /// Version the story was read at; the update fails with a version conflict if it has changed
+    pub expected_version: Option<i64>,
+    /// Agent making the change; required when the story is claimed, and must hold the claim
+    pub agent_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct DeleteUserStoryParams {
+    /// ID of the user story to delete
+    pub id: String,
+    /// Version the story was read at; the delete fails with a version conflict if it has changed
+    pub expected_version: Option<i64>,
+    /// Agent making the change; required when the story is claimed, and must hold the claim
+    pub agent_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct UpdateAcceptanceCriteriaParams {
+    /// ID of the acceptance criterion to update
+    pub id: String,
+    /// New description
+    pub description: String,
+    /// Version the criterion was read at; the update fails with a version conflict if it has
+    /// changed
+    pub expected_version: Option<i64>,
+    /// Agent making the change; required when the story is claimed, and must hold the claim
+    pub agent_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct DeleteAcceptanceCriteriaParams {
+    /// ID of the acceptance criterion to delete
+    pub id: String,
+    /// Version the criterion was read at; the delete fails with a version conflict if it has
+    /// changed
+    pub expected_version: Option<i64>,
+    /// Agent making the change; required when the story is claimed, and must hold the claim
+    pub agent_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ListUserStoriesParams {
+    /// next_cursor of the previous page; omitted for the first page
+    pub cursor: Option<String>,
+    /// Most stories to return (default 100, at most 1000)
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ListAcceptanceCriteriaParams {
+    /// Only the criteria of this user story; all criteria when omitted
+    pub user_story_id: Option<String>,
+    /// next_cursor of the previous page; omitted for the first page
+    pub cursor: Option<String>,
+    /// Most criteria to return (default 100, at most 1000)
+    pub limit: Option<i64>,
+}
+
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct SearchUserStoriesParams {
     /// Search query text
     pub query: String,
 }
 
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ImportBacklogParams {
+    /// Backlog document as produced by export_backlog
+    pub document: serde_json::Value,
+    /// How to handle ids that already exist: skip, overwrite, fail (default) or rename_ids
+    pub policy: Option<String>,
+    /// Report what would change without writing anything
+    pub dry_run: Option<bool>,
+    /// Agent making the change; required when the story is claimed, and must hold the claim
+    pub agent_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ImportFeaturesParams {
+    /// Contents of one or more .feature files, one user story per file
+    pub features: Vec<String>,
+    /// How to handle ids that already exist: skip, overwrite, fail (default) or rename_ids
+    pub policy: Option<String>,
+    /// Report what would change without writing anything
+    pub dry_run: Option<bool>,
+    /// Agent making the change; required when the story is claimed, and must hold the claim
+    pub agent_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ExportCsvParams {
+    /// Column layout preset: native (default), jira or azure_devops
+    pub preset: Option<String>,
+    /// Mapping fields overriding the preset, e.g. {"persona_column": "Team"}
+    pub mapping: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ImportCsvParams {
+    /// CSV file contents including the header row
+    pub csv: String,
+    /// Column layout preset: native (default), jira or azure_devops
+    pub preset: Option<String>,
+    /// Mapping fields overriding the preset, e.g. {"default_persona": "Customer"}
+    pub mapping: Option<serde_json::Value>,
+    /// How to handle ids that already exist: skip, overwrite, fail (default) or rename_ids
+    pub policy: Option<String>,
+    /// Report what would change without writing anything
+    pub dry_run: Option<bool>,
+    /// Agent making the change; required when the story is claimed, and must hold the claim
+    pub agent_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ApplyMarkdownParams {
+    /// Markdown document as produced by render_user_story_markdown, with edits
+    pub markdown: String,
+    /// Report what would change without writing anything
+    pub dry_run: Option<bool>,
+    /// Agent making the change; required when the story is claimed, and must hold the claim
+    pub agent_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct CreatePersonaParams {
+    /// Name of the persona, unique regardless of case
+    pub name: String,
+    /// Who the persona is
+    pub description: Option<String>,
+    /// What the persona wants to achieve
+    pub goals: Option<Vec<String>>,
+    /// What frustrates the persona today
+    pub pain_points: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct GetPersonaParams {
+    /// ID of the persona to retrieve
+    pub id: Option<i64>,
+    /// Name of the persona to retrieve, ignoring case (used when no id is given)
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct UpdatePersonaParams {
+    /// ID of the persona to update
+    pub id: i64,
+    /// New name; stories written for the persona follow the rename
+    pub name: Option<String>,
+    /// New description
+    pub description: Option<String>,
+    /// Replacement list of goals
+    pub goals: Option<Vec<String>>,
+    /// Replacement list of pain points
+    pub pain_points: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct DeletePersonaParams {
+    /// ID of the persona to delete
+    pub id: i64,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct AnalyzeCriteriaParams {
+    /// Analyze every acceptance criterion of this user story
+    pub user_story_id: Option<String>,
+    /// Analyze a single stored acceptance criterion
+    pub acceptance_criteria_id: Option<String>,
+    /// Analyze a draft criterion description before creating it
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct FindSimilarParams {
+    /// ID of a stored user story, or the text of a draft story
+    pub id_or_text: String,
+    /// Minimum similarity from 0 to 1 (default 0.4)
+    pub threshold: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct AddCommentParams {
+    /// Start a thread on this user story
+    pub user_story_id: Option<String>,
+    /// Start a thread on this acceptance criterion
+    pub acceptance_criteria_id: Option<String>,
+    /// Reply to this comment's thread
+    pub parent_id: Option<i64>,
+    /// Who is commenting, such as the agent's name
+    pub author: String,
+    /// Comment text; mention others as @name
+    pub body: String,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ListCommentsParams {
+    /// Threads on this user story and its acceptance criteria
+    pub user_story_id: Option<String>,
+    /// Threads on this acceptance criterion only
+    pub acceptance_criteria_id: Option<String>,
+    /// Include resolved threads (default false)
+    pub include_resolved: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct EditCommentParams {
+    /// ID of the comment
+    pub id: i64,
+    /// Author of the comment; nobody else can edit it
+    pub author: String,
+    /// New comment text
+    pub body: String,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct DeleteCommentParams {
+    /// ID of the comment; deleting the first comment of a thread deletes its replies
+    pub id: i64,
+    /// Author of the comment; nobody else can delete it
+    pub author: String,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ResolveCommentParams {
+    /// ID of the first comment of the thread
+    pub id: i64,
+    /// Who is resolving the thread
+    pub author: String,
+    /// false to open a resolved thread again (default true)
+    pub resolved: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct GetMentionsParams {
+    /// Name mentioned as @name, with or without the @
+    pub name: String,
+    /// Include comments in resolved threads (default false)
+    pub include_resolved: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct SnapshotStoryParams {
+    /// ID of the user story to snapshot
+    pub id: String,
+    /// What the revision is for, such as "Agreed with the product owner"
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ListRevisionsParams {
+    /// ID of the user story
+    pub id: String,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct DiffRevisionsParams {
+    /// ID of the user story
+    pub id: String,
+    /// Revision to compare from
+    pub from: i64,
+    /// Revision to compare to; the story as it is now when omitted
+    pub to: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct RevertStoryParams {
+    /// ID of the user story
+    pub id: String,
+    /// Revision to put the story back to
+    pub revision: i64,
+    /// Agent making the change; required when the story is claimed, and must hold the claim
+    pub agent_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct RestoreFromTrashParams {
+    /// ID of the deleted user story or acceptance criterion
+    pub id: String,
+    /// Agent making the change; required when the story is claimed, and must hold the claim
+    pub agent_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct PurgeTrashParams {
+    /// Keep items deleted within this many days (default 30); 0 empties the trash
+    pub retention_days: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct CreateMemberParams {
+    /// Name of the member, unique regardless of case
+    pub name: String,
+    /// Whether the member is a human or an agent (default human)
+    pub kind: Option<MemberKind>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct DeleteMemberParams {
+    /// Name of the member
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct AssignUserStoryParams {
+    /// ID of the user story
+    pub id: String,
+    /// Name of a registered member
+    pub member: String,
+    /// Attach the member as assignee (default) or reviewer
+    pub role: Option<AssignmentRole>,
+    /// Only assign if the story is still at this version
+    pub expected_version: Option<i64>,
+    /// Agent making the change; required when the story is claimed, and must hold the claim
+    pub agent_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct UnassignUserStoryParams {
+    /// ID of the user story
+    pub id: String,
+    /// Clear the assignee (default) or the reviewer
+    pub role: Option<AssignmentRole>,
+    /// Only unassign if the story is still at this version
+    pub expected_version: Option<i64>,
+    /// Agent making the change; required when the story is claimed, and must hold the claim
+    pub agent_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct GetMemberStoriesParams {
+    /// Name of a registered member
+    pub member: String,
+    /// Only stories the member is assignee or reviewer of; both when omitted
+    pub role: Option<AssignmentRole>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ClaimStoryParams {
+    /// ID of the user story to work on
+    pub id: String,
+    /// Agent taking the claim; pass the same id to writes on the story
+    pub agent_id: String,
+    /// Seconds until the claim expires unless renewed (default 900)
+    pub ttl_seconds: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct HeartbeatClaimParams {
+    /// ID of the claimed user story
+    pub id: String,
+    /// Agent holding the claim
+    pub agent_id: String,
+    /// Seconds from now until the renewed claim expires (default 900)
+    pub ttl_seconds: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ReleaseStoryParams {
+    /// ID of the claimed user story
+    pub id: String,
+    /// Agent holding the claim
+    pub agent_id: String,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct NextUnclaimedStoryParams {
+    /// Only stories written for this persona
+    pub persona: Option<String>,
+    /// Only stories assigned to this member
+    pub assignee: Option<String>,
+    /// Only stories nobody is assigned to
+    pub unassigned: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct RegisterWebhookParams {
+    /// http or https endpoint that receives events as signed POST requests
+    pub url: String,
+    /// Key the x-signature-256 header is made with
+    pub secret: String,
+    /// Kinds of event to send, e.g. story_updated; every kind when omitted
+    pub event_kinds: Option<Vec<DomainEventKind>>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct WebhookIdParams {
+    /// ID of the webhook
+    pub id: i64,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ListEventsParams {
+    /// Only events after this one; from the start of the outbox when omitted
+    pub after_id: Option<i64>,
+    /// Most events to return (default 100, at most 1000)
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ChangesSinceParams {
+    /// next_cursor of the previous call; 0 or omitted for a full copy of the backlog
+    pub cursor: Option<i64>,
+    /// Most stories and criteria to return (default 100, at most 1000)
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct RetryDeadLetterParams {
+    /// ID of the dead delivery
+    pub id: i64,
+}
+
 #[derive(Debug, Serialize, schemars::JsonSchema)]
 pub struct UserStoryResponse {
     pub id: String,
     pub title: String,
     pub description: String,
     pub persona: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reviewer: Option<String>,
     pub created_at: String,
     pub updated_at: String,
+    /// Pass back as expected_version to refuse the write if the story changed meanwhile
+    pub version: i64,
+}
+
+/// A user story with the persona it is written for, so agents see who they are writing for
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct UserStoryWithPersonaResponse {
+    #[serde(flatten)]
+    pub user_story: UserStoryResponse,
+    pub persona_context: Persona,
+}
+
+/// A created user story with the existing stories it resembles
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct CreatedUserStoryResponse {
+    #[serde(flatten)]
+    pub user_story: UserStoryResponse,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub possible_duplicates: Vec<SimilarStory>,
 }
 
 #[derive(Debug, Serialize, schemars::JsonSchema)]
 pub struct StatisticsResponse {
     pub total_stories: i64,
     pub stories_by_persona: Vec<(String, i64)>,
+    pub stories_by_assignee: Vec<(String, i64)>,
+    pub unassigned_stories: i64,
 }
 
 impl From<UserStory> for UserStoryResponse {
@@ -73,8 +539,29 @@ impl From<UserStory> for UserStoryResponse {
             title: story.title,
             description: story.description,
             persona: story.persona,
+            assignee: story.assignee,
+            reviewer: story.reviewer,
             created_at: story.created_at.to_string(),
             updated_at: story.updated_at.to_string(),
+            version: story.version,
+        }
+    }
+}
+
+impl From<UserStoryWithPersona> for UserStoryWithPersonaResponse {
+    fn from(story: UserStoryWithPersona) -> Self {
+        Self {
+            user_story: story.user_story.into(),
+            persona_context: story.persona_context,
+        }
+    }
+}
+
+impl From<CreatedUserStory> for CreatedUserStoryResponse {
+    fn from(created: CreatedUserStory) -> Self {
+        Self {
+            user_story: created.user_story.into(),
+            possible_duplicates: created.possible_duplicates,
         }
     }
 }
@@ -84,39 +571,62 @@ impl From<UserStoryStatistics> for StatisticsResponse {
         Self {
             total_stories: stats.total_stories,
             stories_by_persona: stats.stories_by_persona.into_iter().collect(),
+            stories_by_assignee: stats.stories_by_assignee.into_iter().collect(),
+            unassigned_stories: stats.unassigned_stories,
         }
     }
 }
 
 #[tool_router]
 impl UserStoryServer {
-    pub async fn new(database_url: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let pool = initialize_database(database_url).await?;
+    pub async fn new(
+        database_url: &str,
+        database: &DatabaseConfig,
+        config: ServiceConfig,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let pool = initialize_database_with(database_url, database).await?;
         let repositories = Repositories::new(pool);
-        let services = Services::new(repositories);
+        let services = Services::with_config(repositories, config.clone());
 
         Ok(Self {
             services: Arc::new(Mutex::new(services)),
+            config,
             tool_router: Self::tool_router(),
         })
     }
 
-    #[tool(description = "Create a new user story with ID, title, description, and persona")]
+    #[tool(
+        description = "Create a new user story with title, description, persona and an optional ID (the next free US-<n> when omitted). Existing stories that look alike are listed in possible_duplicates; set duplicate_check to reject to refuse likely duplicates instead"
+    )]
     async fn create_user_story(
         &self,
         params: Parameters<CreateUserStoryParams>,
     ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let mut check = DuplicateCheck {
+            mode: DuplicateMode::Warn,
+            ..Default::default()
+        };
+        if let Some(mode) = params.duplicate_check {
+            check.mode = mode
+                .parse()
+                .map_err(|e: String| ErrorData::invalid_params(e, None))?;
+        }
+        if let Some(threshold) = params.similarity_threshold {
+            check.threshold = threshold;
+        }
+
         let request = CreateUserStoryRequest {
-            id: params.0.id,
-            title: params.0.title,
-            description: params.0.description,
-            persona: params.0.persona,
+            id: params.id,
+            title: params.title,
+            description: params.description,
+            persona: params.persona,
         };
 
         let services = self.services.lock().await;
-        match services.user_stories.create(request).await {
-            Ok(story) => {
-                let response: UserStoryResponse = story.into();
+        match services.user_stories.create_checked(request, check).await {
+            Ok(created) => {
+                let response: CreatedUserStoryResponse = created.into();
                 Ok(CallToolResult::success(vec![Content::text(
                     serde_json::to_string_pretty(&response).unwrap(),
                 )]))
@@ -129,15 +639,17 @@ impl UserStoryServer {
         }
     }
 
-    #[tool(description = "Retrieve a user story by its ID")]
+    #[tool(
+        description = "Retrieve a user story by its ID, including the persona it is written for (description, goals and pain points)"
+    )]
     async fn get_user_story(
         &self,
         params: Parameters<GetUserStoryParams>,
     ) -> Result<CallToolResult, ErrorData> {
         let services = self.services.lock().await;
-        match services.user_stories.get_by_id(&params.0.id).await {
+        match services.user_stories.get_with_persona(&params.0.id).await {
             Ok(story) => {
-                let response: UserStoryResponse = story.into();
+                let response: UserStoryWithPersonaResponse = story.into();
                 Ok(CallToolResult::success(vec![Content::text(
                     serde_json::to_string_pretty(&response).unwrap(),
                 )]))
@@ -150,10 +662,169 @@ impl UserStoryServer {
         }
     }
 
-    #[tool(description = "Get all user stories in the system")]
-    async fn get_all_user_stories(&self) -> Result<CallToolResult, ErrorData> {
+    #[tool(
+        description = "Update the title, description or persona of a user story. Pass the version you read as expected_version to fail with a version conflict (carrying the current story) instead of overwriting someone else's change"
+    )]
+    async fn update_user_story(
+        &self,
+        params: Parameters<UpdateUserStoryParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let request = UpdateUserStoryRequest {
+            title: params.title,
+            description: params.description,
+            persona: params.persona,
+            expected_version: params.expected_version,
+        };
+
+        let services = self.services.lock().await;
+        let services = acting_as(&services, params.agent_id.as_deref());
+        match services.user_stories.update(&params.id, request).await {
+            Ok(story) => {
+                let response: UserStoryResponse = story.into();
+                Ok(CallToolResult::success(vec![Content::text(
+                    serde_json::to_string_pretty(&response).unwrap(),
+                )]))
+            }
+            Err(e) => Err(user_story_error(e)),
+        }
+    }
+
+    #[tool(
+        description = "Move a user story and its acceptance criteria to the trash, optionally only if it is still at expected_version. Use restore_from_trash to bring it back"
+    )]
+    async fn delete_user_story(
+        &self,
+        params: Parameters<DeleteUserStoryParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let services = self.services.lock().await;
+        let services = acting_as(&services, params.agent_id.as_deref());
+        match services
+            .user_stories
+            .delete_at_version(&params.id, params.expected_version)
+            .await
+        {
+            Ok(()) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "User story {} deleted",
+                params.id
+            ))])),
+            Err(e) => Err(user_story_error(e)),
+        }
+    }
+
+    #[tool(
+        description = "Update the description of an acceptance criterion, optionally only if it is still at expected_version"
+    )]
+    async fn update_acceptance_criteria(
+        &self,
+        params: Parameters<UpdateAcceptanceCriteriaParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let request = UpdateAcceptanceCriteriaRequest {
+            description: Some(params.description),
+            expected_version: params.expected_version,
+        };
+
+        let services = self.services.lock().await;
+        let services = acting_as(&services, params.agent_id.as_deref());
+        match services
+            .acceptance_criteria
+            .update(&params.id, request)
+            .await
+        {
+            Ok(criteria) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&criteria).unwrap(),
+            )])),
+            Err(e) => Err(criteria_error(e)),
+        }
+    }
+
+    #[tool(
+        description = "Move an acceptance criterion to the trash, optionally only if it is still at expected_version"
+    )]
+    async fn delete_acceptance_criteria(
+        &self,
+        params: Parameters<DeleteAcceptanceCriteriaParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let services = self.services.lock().await;
+        let services = acting_as(&services, params.agent_id.as_deref());
+        match services
+            .acceptance_criteria
+            .delete_at_version(&params.id, params.expected_version)
+            .await
+        {
+            Ok(()) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Acceptance criterion {} deleted",
+                params.id
+            ))])),
+            Err(e) => Err(criteria_error(e)),
+        }
+    }
+
+    #[tool(
+        description = "List user stories a page at a time, newest first. Pass next_cursor back as cursor for the next page; it is absent on the last page"
+    )]
+    async fn get_all_user_stories(
+        &self,
+        params: Parameters<ListUserStoriesParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let services = self.services.lock().await;
+        match services
+            .user_stories
+            .get_page(
+                params.cursor.as_deref(),
+                params.limit.unwrap_or(MCP_PAGE_SIZE),
+            )
+            .await
+        {
+            Ok(page) => {
+                let response = Page::<UserStoryResponse> {
+                    items: page.items.into_iter().map(|s| s.into()).collect(),
+                    next_cursor: page.next_cursor,
+                };
+                Ok(CallToolResult::success(vec![Content::text(
+                    serde_json::to_string_pretty(&response).unwrap(),
+                )]))
+            }
+            Err(e) => Err(user_story_error(e)),
+        }
+    }
+
+    #[tool(
+        description = "List acceptance criteria a page at a time, oldest first, of one user story or of all. Pass next_cursor back as cursor for the next page; it is absent on the last page"
+    )]
+    async fn list_acceptance_criteria(
+        &self,
+        params: Parameters<ListAcceptanceCriteriaParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let services = self.services.lock().await;
+        match services
+            .acceptance_criteria
+            .get_page(
+                params.user_story_id.as_deref(),
+                params.cursor.as_deref(),
+                params.limit.unwrap_or(MCP_PAGE_SIZE),
+            )
+            .await
+        {
+            Ok(page) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&page).unwrap(),
+            )])),
+            Err(e) => Err(criteria_error(e)),
+        }
+    }
+
+    #[tool(description = "Search user stories by text in title, description, or persona")]
+    async fn search_user_stories(
+        &self,
+        params: Parameters<SearchUserStoriesParams>,
+    ) -> Result<CallToolResult, ErrorData> {
         let services = self.services.lock().await;
-        match services.user_stories.get_all().await {
+        match services.user_stories.search(&params.0.query).await {
             Ok(stories) => {
                 let responses: Vec<UserStoryResponse> =
                     stories.into_iter().map(|s| s.into()).collect();
@@ -169,18 +840,22 @@ impl UserStoryServer {
         }
     }
 
-    #[tool(description = "Search user stories by text in title, description, or persona")]
-    async fn search_user_stories(
+    #[tool(
+        description = "Query user stories with composable filters: personas, text in title, description or persona, created and updated time ranges (e.g. 2024-01-31T00:00:00), has_criteria and a min/max criteria count. Sort by id, title, persona, assignee, reviewer, created_at, updated_at, version or criteria_count, asc or desc. With a limit, pass next_cursor back as cursor for the next page; it is absent on the last page. Limits above 1000 are cut down to 1000"
+    )]
+    async fn query_user_stories(
         &self,
-        params: Parameters<SearchUserStoriesParams>,
+        params: Parameters<StoryQuery>,
     ) -> Result<CallToolResult, ErrorData> {
         let services = self.services.lock().await;
-        match services.user_stories.search(&params.0.query).await {
-            Ok(stories) => {
-                let responses: Vec<UserStoryResponse> =
-                    stories.into_iter().map(|s| s.into()).collect();
+        match services.user_stories.query(params.0).await {
+            Ok(page) => {
+                let response = Page::<UserStoryResponse> {
+                    items: page.items.into_iter().map(|s| s.into()).collect(),
+                    next_cursor: page.next_cursor,
+                };
                 Ok(CallToolResult::success(vec![Content::text(
-                    serde_json::to_string_pretty(&responses).unwrap(),
+                    serde_json::to_string_pretty(&response).unwrap(),
                 )]))
             }
             Err(e) => Err(ErrorData {
@@ -208,32 +883,1377 @@ impl UserStoryServer {
             }),
         }
     }
-}
 
-impl ServerHandler for UserStoryServer {
-    fn get_info(&self) -> ServerInfo {
-        ServerInfo {
-            protocol_version: ProtocolVersion::V_2024_11_05,
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
-            server_info: Implementation::from_build_env(),
-            instructions: Some(
-                "A comprehensive user story and acceptance criteria management system. \
-                Use the available tools to create, read, update, delete, and search user stories, \
-                as well as manage their acceptance criteria. Perfect for agile development teams \
-                and AI-assisted project management."
-                    .to_string(),
-            ),
+    #[tool(
+        description = "Export the whole backlog (all user stories with their acceptance criteria and timestamps) as a versioned JSON document"
+    )]
+    async fn export_backlog(&self) -> Result<CallToolResult, ErrorData> {
+        let services = self.services.lock().await;
+        match services.backlog.export_backlog().await {
+            Ok(document) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&document).unwrap(),
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
         }
     }
 
-    async fn list_tools(
+    #[tool(
+        description = "Import a backlog document produced by export_backlog. Conflicting ids are handled by the policy (skip, overwrite, fail, rename_ids); use dry_run to preview the changes"
+    )]
+    async fn import_backlog(
         &self,
-        _request: Option<rmcp::model::PaginatedRequestParam>,
-        _context: rmcp::service::RequestContext<RoleServer>,
-    ) -> Result<rmcp::model::ListToolsResult, ErrorData> {
-        Ok(rmcp::model::ListToolsResult {
-            tools: self.tool_router.list_all(),
-            next_cursor: None,
+        params: Parameters<ImportBacklogParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let document: BacklogDocument = serde_json::from_value(params.0.document).map_err(|e| {
+            ErrorData::invalid_params(format!("Invalid backlog document: {}", e), None)
+        })?;
+        let options = import_options(params.0.policy, params.0.dry_run)?;
+
+        let services = self.services.lock().await;
+        let services = acting_as(&services, params.0.agent_id.as_deref());
+        match services.backlog.import_backlog(document, options).await {
+            Ok(report) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&report).unwrap(),
+            )])),
+            Err(e) => Err(backlog_error(e)),
+        }
+    }
+
+    #[tool(
+        description = "Export a user story and its acceptance criteria as a Gherkin .feature file (one Scenario per criterion, tagged with @US-/@AC- ids)"
+    )]
+    async fn export_feature(
+        &self,
+        params: Parameters<GetUserStoryParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let services = self.services.lock().await;
+        match services.backlog.export_feature(&params.0.id).await {
+            Ok(feature) => Ok(CallToolResult::success(vec![Content::text(
+                feature.content,
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(
+        description = "Import Gherkin .feature files as user stories with acceptance criteria. Ids come from @US-/@AC- tags; conflicts are handled by the policy (skip, overwrite, fail, rename_ids)"
+    )]
+    async fn import_features(
+        &self,
+        params: Parameters<ImportFeaturesParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let options = import_options(params.0.policy, params.0.dry_run)?;
+
+        let services = self.services.lock().await;
+        let services = acting_as(&services, params.0.agent_id.as_deref());
+        match services
+            .backlog
+            .import_features(&params.0.features, options)
+            .await
+        {
+            Ok(report) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&report).unwrap(),
+            )])),
+            Err(e) => Err(backlog_error(e)),
+        }
+    }
+
+    #[tool(
+        description = "Export all user stories and acceptance criteria as CSV. Presets match the native layout (one row per criterion), Jira or Azure DevOps issue exports"
+    )]
+    async fn export_csv(
+        &self,
+        params: Parameters<ExportCsvParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let mapping = csv_mapping(params.0.preset, params.0.mapping)?;
+
+        let services = self.services.lock().await;
+        match services.backlog.export_csv(&mapping).await {
+            Ok(csv) => Ok(CallToolResult::success(vec![Content::text(csv)])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(
+        description = "Import user stories and acceptance criteria from CSV (native, Jira or Azure DevOps layout). Invalid rows are reported by row number and nothing is written while any row is rejected"
+    )]
+    async fn import_csv(
+        &self,
+        params: Parameters<ImportCsvParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let mapping = csv_mapping(params.preset, params.mapping)?;
+        let options = import_options(params.policy, params.dry_run)?;
+
+        let services = self.services.lock().await;
+        let services = acting_as(&services, params.agent_id.as_deref());
+        match services
+            .backlog
+            .import_csv(&params.csv, &mapping, options)
+            .await
+        {
+            Ok(report) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&report).unwrap(),
+            )])),
+            Err(e) => Err(backlog_error(e)),
+        }
+    }
+
+    #[tool(
+        description = "Render a user story with its acceptance criteria as a Markdown document (front matter, description and a criteria checklist) that can be edited and applied back"
+    )]
+    async fn render_user_story_markdown(
+        &self,
+        params: Parameters<GetUserStoryParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let services = self.services.lock().await;
+        match services.markdown.render(&params.0.id).await {
+            Ok(document) => Ok(CallToolResult::success(vec![Content::text(document)])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(
+        description = "Apply an edited Markdown story document: changed fields are updated, criteria removed from the checklist are deleted and new checklist items are created"
+    )]
+    async fn apply_user_story_markdown(
+        &self,
+        params: Parameters<ApplyMarkdownParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let services = self.services.lock().await;
+        let services = acting_as(&services, params.0.agent_id.as_deref());
+        match services
+            .markdown
+            .apply(&params.0.markdown, params.0.dry_run.unwrap_or(false))
+            .await
+        {
+            Ok(report) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&report).unwrap(),
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(
+        description = "Create a persona with a name, description, goals and pain points. Names are unique regardless of case"
+    )]
+    async fn create_persona(
+        &self,
+        params: Parameters<CreatePersonaParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let request = CreatePersonaRequest {
+            name: params.name,
+            description: params.description.unwrap_or_default(),
+            goals: params.goals.unwrap_or_default(),
+            pain_points: params.pain_points.unwrap_or_default(),
+        };
+
+        let services = self.services.lock().await;
+        match services.personas.create(request).await {
+            Ok(persona) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&persona).unwrap(),
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(description = "Retrieve a persona by its ID or by its name")]
+    async fn get_persona(
+        &self,
+        params: Parameters<GetPersonaParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let services = self.services.lock().await;
+        let result = match (params.0.id, params.0.name) {
+            (Some(id), _) => services.personas.get_by_id(id).await,
+            (None, Some(name)) => services.personas.get_by_name(&name).await,
+            (None, None) => {
+                return Err(ErrorData::invalid_params(
+                    "Either id or name is required",
+                    None,
+                ))
+            }
+        };
+
+        match result {
+            Ok(persona) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&persona).unwrap(),
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(description = "List all personas with the number of user stories written for each")]
+    async fn list_personas(&self) -> Result<CallToolResult, ErrorData> {
+        let services = self.services.lock().await;
+        match services.personas.get_all().await {
+            Ok(personas) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&personas).unwrap(),
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(
+        description = "Update a persona. Renaming it renames it on every user story written for it; goals and pain points are replaced as a whole"
+    )]
+    async fn update_persona(
+        &self,
+        params: Parameters<UpdatePersonaParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let request = UpdatePersonaRequest {
+            name: params.name,
+            description: params.description,
+            goals: params.goals,
+            pain_points: params.pain_points,
+        };
+
+        let services = self.services.lock().await;
+        match services.personas.update(params.id, request).await {
+            Ok(persona) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&persona).unwrap(),
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(description = "Delete a persona that no user story is written for")]
+    async fn delete_persona(
+        &self,
+        params: Parameters<DeletePersonaParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let services = self.services.lock().await;
+        match services.personas.delete(params.0.id).await {
+            Ok(()) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Persona {} deleted",
+                params.0.id
+            ))])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(
+        description = "Lint a user story against INVEST and the \"As a ... I want ... so that ...\" template. Returns a 0-100 score per INVEST quality and findings such as vague wording, missing narrative parts, dependencies and zero or too many acceptance criteria"
+    )]
+    async fn lint_user_story(
+        &self,
+        params: Parameters<GetUserStoryParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let services = self.services.lock().await;
+        match services.quality.lint_story(&params.0.id).await {
+            Ok(report) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&report).unwrap(),
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(
+        description = "Lint every user story in the backlog; stories are listed lowest score first with a count of findings per rule"
+    )]
+    async fn lint_backlog(&self) -> Result<CallToolResult, ErrorData> {
+        let services = self.services.lock().await;
+        match services.quality.lint_backlog().await {
+            Ok(report) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&report).unwrap(),
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(
+        description = "Check acceptance criteria for testability: Given/When/Then structure, an observable outcome, ambiguous quantifiers, vague wording and several scenarios in one criterion. Give a user_story_id, an acceptance_criteria_id or a draft description; each finding comes with a suggestion"
+    )]
+    async fn analyze_acceptance_criteria(
+        &self,
+        params: Parameters<AnalyzeCriteriaParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let services = self.services.lock().await;
+
+        let result = match (
+            params.user_story_id,
+            params.acceptance_criteria_id,
+            params.description,
+        ) {
+            (Some(user_story_id), None, None) => services
+                .quality
+                .analyze_criteria(&user_story_id)
+                .await
+                .map(|report| serde_json::to_string_pretty(&report).unwrap()),
+            (None, Some(id), None) => services
+                .quality
+                .analyze_criterion(&id)
+                .await
+                .map(|report| serde_json::to_string_pretty(&report).unwrap()),
+            (None, None, Some(description)) => Ok(serde_json::to_string_pretty(
+                &services.quality.analyze_draft_criterion(&description),
+            )
+            .unwrap()),
+            _ => {
+                return Err(ErrorData::invalid_params(
+                    "Give exactly one of user_story_id, acceptance_criteria_id or description",
+                    None,
+                ))
+            }
+        };
+
+        match result {
+            Ok(report) => Ok(CallToolResult::success(vec![Content::text(report)])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(
+        description = "Find existing user stories similar to a stored story (by ID) or to draft text, comparing title, description and acceptance criteria. Use it before creating a story to avoid duplicates; results are ordered by similarity score from 0 to 1"
+    )]
+    async fn find_similar_user_stories(
+        &self,
+        params: Parameters<FindSimilarParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let threshold = params
+            .0
+            .threshold
+            .unwrap_or(crate::quality::DEFAULT_SIMILARITY_THRESHOLD);
+
+        let services = self.services.lock().await;
+        match services
+            .user_stories
+            .find_similar(&params.0.id_or_text, threshold)
+            .await
+        {
+            Ok(similar) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&similar).unwrap(),
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(
+        description = "Comment on a user story or one of its acceptance criteria, or reply to a comment, without editing the story. Mention others as @name so they find the comment with get_mentions"
+    )]
+    async fn add_comment(
+        &self,
+        params: Parameters<AddCommentParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let request = CreateCommentRequest {
+            user_story_id: params.user_story_id,
+            acceptance_criteria_id: params.acceptance_criteria_id,
+            parent_id: params.parent_id,
+            author: params.author,
+            body: params.body,
+        };
+
+        let services = self.services.lock().await;
+        match services.comments.create(request).await {
+            Ok(comment) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&comment).unwrap(),
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(
+        description = "List the comment threads on a user story (including its acceptance criteria) or on one acceptance criterion, oldest first. Resolved threads are left out unless include_resolved is true"
+    )]
+    async fn list_comments(
+        &self,
+        params: Parameters<ListCommentsParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let include_resolved = params.include_resolved.unwrap_or(false);
+
+        let services = self.services.lock().await;
+        let result = match (params.user_story_id, params.acceptance_criteria_id) {
+            (Some(id), None) => {
+                services
+                    .comments
+                    .threads_for_story(&id, include_resolved)
+                    .await
+            }
+            (None, Some(id)) => {
+                services
+                    .comments
+                    .threads_for_criteria(&id, include_resolved)
+                    .await
+            }
+            _ => {
+                return Err(ErrorData::invalid_params(
+                    "Give exactly one of user_story_id or acceptance_criteria_id",
+                    None,
+                ))
+            }
+        };
+
+        match result {
+            Ok(threads) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&threads).unwrap(),
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(description = "Change the text of a comment; only its author can")]
+    async fn edit_comment(
+        &self,
+        params: Parameters<EditCommentParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let services = self.services.lock().await;
+        match services
+            .comments
+            .edit(params.id, &params.author, &params.body)
+            .await
+        {
+            Ok(comment) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&comment).unwrap(),
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(
+        description = "Delete a comment; only its author can. Deleting the first comment of a thread deletes its replies"
+    )]
+    async fn delete_comment(
+        &self,
+        params: Parameters<DeleteCommentParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let services = self.services.lock().await;
+        match services.comments.delete(params.id, &params.author).await {
+            Ok(()) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Comment {} deleted",
+                params.id
+            ))])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(
+        description = "Mark a comment thread as resolved once its feedback is addressed, or open it again with resolved false"
+    )]
+    async fn resolve_comment(
+        &self,
+        params: Parameters<ResolveCommentParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let services = self.services.lock().await;
+        match services
+            .comments
+            .set_resolved(params.id, params.resolved.unwrap_or(true), &params.author)
+            .await
+        {
+            Ok(comment) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&comment).unwrap(),
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(
+        description = "List comments that mention a name as @name, newest first; comments in resolved threads are left out unless include_resolved is true"
+    )]
+    async fn get_mentions(
+        &self,
+        params: Parameters<GetMentionsParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let services = self.services.lock().await;
+        match services
+            .comments
+            .mentions(&params.name, params.include_resolved.unwrap_or(false))
+            .await
+        {
+            Ok(comments) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&comments).unwrap(),
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(
+        description = "Save a user story with its acceptance criteria as a numbered revision, with an optional note, so it can be compared against or reverted to later"
+    )]
+    async fn snapshot_story(
+        &self,
+        params: Parameters<SnapshotStoryParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let services = self.services.lock().await;
+        match services
+            .revisions
+            .snapshot_story(&params.id, params.note.as_deref().unwrap_or_default())
+            .await
+        {
+            Ok(revision) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&StoryRevisionSummary::from(&revision)).unwrap(),
+            )])),
+            Err(e) => Err(revision_error(e)),
+        }
+    }
+
+    #[tool(description = "List the revisions of a user story, newest first")]
+    async fn list_revisions(
+        &self,
+        params: Parameters<ListRevisionsParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let services = self.services.lock().await;
+        match services.revisions.list_revisions(&params.0.id).await {
+            Ok(revisions) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&revisions).unwrap(),
+            )])),
+            Err(e) => Err(revision_error(e)),
+        }
+    }
+
+    #[tool(
+        description = "Compare two revisions of a user story, or a revision with the story as it is now: changed fields with old and new values, and added, removed and changed acceptance criteria"
+    )]
+    async fn diff_revisions(
+        &self,
+        params: Parameters<DiffRevisionsParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let services = self.services.lock().await;
+        match services
+            .revisions
+            .diff_revisions(&params.id, params.from, params.to)
+            .await
+        {
+            Ok(diff) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&diff).unwrap(),
+            )])),
+            Err(e) => Err(revision_error(e)),
+        }
+    }
+
+    #[tool(
+        description = "Put a user story and its acceptance criteria back the way they were at a revision, in one transaction. The current state is saved as a new revision first so the revert can be undone"
+    )]
+    async fn revert_story(
+        &self,
+        params: Parameters<RevertStoryParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let services = self.services.lock().await;
+        let services = acting_as(&services, params.agent_id.as_deref());
+        match services
+            .revisions
+            .revert_story(&params.id, params.revision)
+            .await
+        {
+            Ok(story) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&story).unwrap(),
+            )])),
+            Err(e) => Err(revision_error(e)),
+        }
+    }
+
+    #[tool(
+        description = "List deleted user stories (with the acceptance criteria deleted along with them) and acceptance criteria deleted on their own"
+    )]
+    async fn list_trash(&self) -> Result<CallToolResult, ErrorData> {
+        let services = self.services.lock().await;
+        match services.trash.list_trash().await {
+            Ok(trash) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&trash).unwrap(),
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(
+        description = "Restore a deleted user story together with the acceptance criteria deleted along with it, or a single deleted acceptance criterion"
+    )]
+    async fn restore_from_trash(
+        &self,
+        params: Parameters<RestoreFromTrashParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let services = self.services.lock().await;
+        let services = acting_as(&services, params.0.agent_id.as_deref());
+        match services.trash.restore(&params.0.id).await {
+            Ok(restored) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&restored).unwrap(),
+            )])),
+            Err(e) => Err(trash_error(e)),
+        }
+    }
+
+    #[tool(
+        description = "Permanently delete user stories and acceptance criteria that have been in the trash for longer than the retention period"
+    )]
+    async fn purge_trash(
+        &self,
+        params: Parameters<PurgeTrashParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let retention_days = params
+            .0
+            .retention_days
+            .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS);
+
+        let services = self.services.lock().await;
+        match services.trash.purge(retention_days).await {
+            Ok(report) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&report).unwrap(),
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+    #[tool(
+        description = "Register a human or agent that user stories can be assigned to or reviewed by"
+    )]
+    async fn create_member(
+        &self,
+        params: Parameters<CreateMemberParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let request = CreateMemberRequest {
+            name: params.name,
+            kind: params.kind.unwrap_or_default(),
+        };
+
+        let services = self.services.lock().await;
+        match services.members.create(request).await {
+            Ok(member) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&member).unwrap(),
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(
+        description = "List the members with how many live user stories each is assigned to and reviewing"
+    )]
+    async fn list_members(&self) -> Result<CallToolResult, ErrorData> {
+        let services = self.services.lock().await;
+        match services.members.get_all().await {
+            Ok(members) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&members).unwrap(),
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(description = "Remove a member that no live user story is assigned to")]
+    async fn delete_member(
+        &self,
+        params: Parameters<DeleteMemberParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let name = params.0.name;
+        let services = self.services.lock().await;
+        match services.members.delete(&name).await {
+            Ok(()) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Member {} deleted",
+                name
+            ))])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(
+        description = "Assign a user story to a member, or make the member its reviewer, optionally only if it is still at expected_version"
+    )]
+    async fn assign_user_story(
+        &self,
+        params: Parameters<AssignUserStoryParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let role = params.role.unwrap_or(AssignmentRole::Assignee);
+
+        let services = self.services.lock().await;
+        let services = acting_as(&services, params.agent_id.as_deref());
+        match services
+            .user_stories
+            .assign(&params.id, role, &params.member, params.expected_version)
+            .await
+        {
+            Ok(story) => {
+                let response: UserStoryResponse = story.into();
+                Ok(CallToolResult::success(vec![Content::text(
+                    serde_json::to_string_pretty(&response).unwrap(),
+                )]))
+            }
+            Err(e) => Err(user_story_error(e)),
+        }
+    }
+
+    #[tool(description = "Clear the assignee or reviewer of a user story")]
+    async fn unassign_user_story(
+        &self,
+        params: Parameters<UnassignUserStoryParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let role = params.role.unwrap_or(AssignmentRole::Assignee);
+
+        let services = self.services.lock().await;
+        let services = acting_as(&services, params.agent_id.as_deref());
+        match services
+            .user_stories
+            .unassign(&params.id, role, params.expected_version)
+            .await
+        {
+            Ok(story) => {
+                let response: UserStoryResponse = story.into();
+                Ok(CallToolResult::success(vec![Content::text(
+                    serde_json::to_string_pretty(&response).unwrap(),
+                )]))
+            }
+            Err(e) => Err(user_story_error(e)),
+        }
+    }
+
+    #[tool(
+        description = "List the user stories assigned to a member or waiting for their review, for an agent looking up its own work"
+    )]
+    async fn get_member_stories(
+        &self,
+        params: Parameters<GetMemberStoriesParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let services = self.services.lock().await;
+        match services
+            .user_stories
+            .get_by_member(&params.member, params.role)
+            .await
+        {
+            Ok(stories) => {
+                let response: Vec<UserStoryResponse> =
+                    stories.into_iter().map(|s| s.into()).collect();
+                Ok(CallToolResult::success(vec![Content::text(
+                    serde_json::to_string_pretty(&response).unwrap(),
+                )]))
+            }
+            Err(e) => Err(user_story_error(e)),
+        }
+    }
+    #[tool(
+        description = "Claim a user story before working on it. Until the claim is released or expires, writes to the story and its criteria need the holder's agent_id; claiming a story the agent holds renews it"
+    )]
+    async fn claim_story(
+        &self,
+        params: Parameters<ClaimStoryParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let ttl_seconds = params.ttl_seconds.unwrap_or(DEFAULT_CLAIM_TTL_SECONDS);
+
+        let services = self.services.lock().await;
+        match services
+            .claims
+            .claim_story(&params.id, &params.agent_id, ttl_seconds)
+            .await
+        {
+            Ok(claim) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&claim).unwrap(),
+            )])),
+            Err(e) => Err(claim_error(e)),
+        }
+    }
+
+    #[tool(description = "Renew a live claim so it does not expire while the agent keeps working")]
+    async fn heartbeat_claim(
+        &self,
+        params: Parameters<HeartbeatClaimParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let ttl_seconds = params.ttl_seconds.unwrap_or(DEFAULT_CLAIM_TTL_SECONDS);
+
+        let services = self.services.lock().await;
+        match services
+            .claims
+            .heartbeat(&params.id, &params.agent_id, ttl_seconds)
+            .await
+        {
+            Ok(claim) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&claim).unwrap(),
+            )])),
+            Err(e) => Err(claim_error(e)),
+        }
+    }
+
+    #[tool(description = "Release a claim so other agents can pick the user story up")]
+    async fn release_story(
+        &self,
+        params: Parameters<ReleaseStoryParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let services = self.services.lock().await;
+        match services
+            .claims
+            .release_story(&params.id, &params.agent_id)
+            .await
+        {
+            Ok(()) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "User story {} released",
+                params.id
+            ))])),
+            Err(e) => Err(claim_error(e)),
+        }
+    }
+
+    #[tool(description = "List the live claims on user stories, soonest to expire first")]
+    async fn list_claims(&self) -> Result<CallToolResult, ErrorData> {
+        let services = self.services.lock().await;
+        match services.claims.list_claims().await {
+            Ok(claims) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&claims).unwrap(),
+            )])),
+            Err(e) => Err(claim_error(e)),
+        }
+    }
+
+    #[tool(
+        description = "Get the oldest user story no agent has claimed, optionally for a persona or assignee, to claim next. Returns null when the queue is empty"
+    )]
+    async fn next_unclaimed_story(
+        &self,
+        params: Parameters<NextUnclaimedStoryParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let filter = WorkQueueFilter {
+            persona: params.persona,
+            assignee: params.assignee,
+            unassigned: params.unassigned.unwrap_or(false),
+        };
+
+        let services = self.services.lock().await;
+        match services.claims.next_unclaimed_story(&filter).await {
+            Ok(story) => {
+                let response = story.map(UserStoryResponse::from);
+                Ok(CallToolResult::success(vec![Content::text(
+                    serde_json::to_string_pretty(&response).unwrap(),
+                )]))
+            }
+            Err(e) => Err(claim_error(e)),
+        }
+    }
+
+    #[tool(
+        description = "Register a webhook that receives story and criteria change events from now on as POST requests signed with HMAC-SHA256 of '<x-webhook-timestamp>.<body>'. Failed deliveries are retried with backoff, then kept as dead letters"
+    )]
+    async fn register_webhook(
+        &self,
+        params: Parameters<RegisterWebhookParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let request = CreateWebhookRequest {
+            url: params.url,
+            secret: params.secret,
+            event_kinds: params.event_kinds.unwrap_or_default(),
+        };
+
+        let services = self.services.lock().await;
+        match services.events.register_webhook(request).await {
+            Ok(webhook) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&webhook).unwrap(),
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(description = "List the registered webhooks, without their secrets")]
+    async fn list_webhooks(&self) -> Result<CallToolResult, ErrorData> {
+        let services = self.services.lock().await;
+        match services.events.list_webhooks().await {
+            Ok(webhooks) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&webhooks).unwrap(),
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(description = "Delete a webhook along with its pending deliveries")]
+    async fn delete_webhook(
+        &self,
+        params: Parameters<WebhookIdParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let id = params.0.id;
+        let services = self.services.lock().await;
+        match services.events.delete_webhook(id).await {
+            Ok(()) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Webhook {} deleted",
+                id
+            ))])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(
+        description = "List change events from the outbox in the order they happened, after an event id to read only newer ones"
+    )]
+    async fn list_events(
+        &self,
+        params: Parameters<ListEventsParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let services = self.services.lock().await;
+        match services
+            .events
+            .list_events(params.after_id.unwrap_or(0), params.limit.unwrap_or(100))
+            .await
+        {
+            Ok(events) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&events).unwrap(),
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(
+        description = "Sync incrementally: the current state of every user story and acceptance criterion changed after a cursor, with tombstones for deleted ones. Store next_cursor and pass it back as cursor while has_more is true, and on the next sync"
+    )]
+    async fn changes_since(
+        &self,
+        params: Parameters<ChangesSinceParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let services = self.services.lock().await;
+        match services
+            .events
+            .changes_since(params.cursor.unwrap_or(0), params.limit.unwrap_or(100))
+            .await
+        {
+            Ok(feed) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&feed).unwrap(),
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(description = "List webhook deliveries that failed on every attempt")]
+    async fn list_dead_letters(&self) -> Result<CallToolResult, ErrorData> {
+        let services = self.services.lock().await;
+        match services.events.dead_letters().await {
+            Ok(deliveries) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&deliveries).unwrap(),
+            )])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+
+    #[tool(description = "Send a dead letter to its webhook again, with a fresh set of attempts")]
+    async fn retry_dead_letter(
+        &self,
+        params: Parameters<RetryDeadLetterParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let id = params.0.id;
+        let services = self.services.lock().await;
+        match services.events.retry_dead_letter(id).await {
+            Ok(()) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Delivery {} queued for another attempt",
+                id
+            ))])),
+            Err(e) => Err(ErrorData {
+                code: rmcp::model::ErrorCode(-32000),
+                message: e.to_string().into(),
+                data: None,
+            }),
+        }
+    }
+}
+
+/// Error code for writes refused because the row is no longer at the expected version. The
+/// error data holds the row as it is now under `current`.
+pub const VERSION_CONFLICT: rmcp::model::ErrorCode = rmcp::model::ErrorCode(-32009);
+
+fn version_conflict(message: String, current: impl Serialize) -> ErrorData {
+    ErrorData {
+        code: VERSION_CONFLICT,
+        message: message.into(),
+        data: Some(serde_json::json!({ "current": current })),
+    }
+}
+
+/// Error code for writes to a user story another agent holds the claim on. The error data
+/// holds the claim under `claim`.
+pub const STORY_CLAIMED: rmcp::model::ErrorCode = rmcp::model::ErrorCode(-32010);
+
+fn story_claimed(message: String, claim: &StoryClaim) -> ErrorData {
+    ErrorData {
+        code: STORY_CLAIMED,
+        message: message.into(),
+        data: Some(serde_json::json!({ "claim": claim })),
+    }
+}
+
+/// The services, writing as the agent when the caller names one
+fn acting_as(services: &Services, agent_id: Option<&str>) -> Services {
+    match agent_id {
+        Some(agent_id) => services.as_agent(agent_id),
+        None => services.clone(),
+    }
+}
+
+fn user_story_error(error: UserStoryServiceError) -> ErrorData {
+    match error {
+        UserStoryServiceError::Claimed { ref claim } => story_claimed(error.to_string(), claim),
+        UserStoryServiceError::Conflict { ref current, .. } => version_conflict(
+            error.to_string(),
+            UserStoryResponse::from((**current).clone()),
+        ),
+        _ => ErrorData {
+            code: rmcp::model::ErrorCode(-32000),
+            message: error.to_string().into(),
+            data: None,
+        },
+    }
+}
+
+fn criteria_error(error: AcceptanceCriteriaServiceError) -> ErrorData {
+    match error {
+        AcceptanceCriteriaServiceError::Claimed { ref claim } => {
+            story_claimed(error.to_string(), claim)
+        }
+        AcceptanceCriteriaServiceError::Conflict { ref current, .. } => {
+            version_conflict(error.to_string(), current)
+        }
+        _ => ErrorData {
+            code: rmcp::model::ErrorCode(-32000),
+            message: error.to_string().into(),
+            data: None,
+        },
+    }
+}
+
+fn revision_error(error: RevisionServiceError) -> ErrorData {
+    match error {
+        RevisionServiceError::UserStory(e) => user_story_error(e),
+        RevisionServiceError::AcceptanceCriteria(e) => criteria_error(e),
+        _ => ErrorData {
+            code: rmcp::model::ErrorCode(-32000),
+            message: error.to_string().into(),
+            data: None,
+        },
+    }
+}
+
+fn backlog_error(error: BacklogServiceError) -> ErrorData {
+    match error {
+        BacklogServiceError::Claimed { ref claim } => story_claimed(error.to_string(), claim),
+        _ => ErrorData {
+            code: rmcp::model::ErrorCode(-32000),
+            message: error.to_string().into(),
+            data: None,
+        },
+    }
+}
+
+fn trash_error(error: TrashServiceError) -> ErrorData {
+    match error {
+        TrashServiceError::Claimed { ref claim } => story_claimed(error.to_string(), claim),
+        _ => ErrorData {
+            code: rmcp::model::ErrorCode(-32000),
+            message: error.to_string().into(),
+            data: None,
+        },
+    }
+}
+
+fn claim_error(error: ClaimServiceError) -> ErrorData {
+    match error {
+        ClaimServiceError::Claimed { ref claim } => story_claimed(error.to_string(), claim),
+        _ => ErrorData {
+            code: rmcp::model::ErrorCode(-32000),
+            message: error.to_string().into(),
+            data: None,
+        },
+    }
+}
+
+/// Build a CSV mapping from an optional preset name and optional field overrides
+fn csv_mapping(
+    preset: Option<String>,
+    overrides: Option<serde_json::Value>,
+) -> Result<CsvMapping, ErrorData> {
+    let preset = match preset {
+        Some(preset) => preset
+            .parse::<CsvPreset>()
+            .map_err(|e| ErrorData::invalid_params(e, None))?,
+        None => CsvPreset::Native,
+    };
+    let mapping = CsvMapping::preset(preset);
+
+    let Some(overrides) = overrides else {
+        return Ok(mapping);
+    };
+    let serde_json::Value::Object(overrides) = overrides else {
+        return Err(ErrorData::invalid_params(
+            "mapping must be a JSON object",
+            None,
+        ));
+    };
+
+    let mut merged = serde_json::to_value(mapping).unwrap();
+    merged.as_object_mut().unwrap().extend(overrides);
+    serde_json::from_value(merged)
+        .map_err(|e| ErrorData::invalid_params(format!("Invalid CSV mapping: {}", e), None))
+}
+
+/// Build import options from the optional policy name and dry-run flag of an import tool
+fn import_options(
+    policy: Option<String>,
+    dry_run: Option<bool>,
+) -> Result<ImportOptions, ErrorData> {
+    let policy = match policy {
+        Some(policy) => policy
+            .parse::<ConflictPolicy>()
+            .map_err(|e| ErrorData::invalid_params(e, None))?,
+        None => ConflictPolicy::default(),
+    };
+
+    Ok(ImportOptions {
+        policy,
+        dry_run: dry_run.unwrap_or(false),
+    })
+}
+
+impl ServerHandler for UserStoryServer {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            protocol_version: ProtocolVersion::V_2024_11_05,
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_resources()
+                .build(),
+            server_info: Implementation::from_build_env(),
+            instructions: Some(format!(
+                "A comprehensive user story and acceptance criteria management system. \
+                Use the available tools to create, read, update, delete, and search user stories, \
+                as well as manage their acceptance criteria. Perfect for agile development teams \
+                and AI-assisted project management. Validation rules: {}",
+                self.config.validation.describe(&self.config.id_scheme)
+            )),
+        }
+    }
+
+    async fn list_tools(
+        &self,
+        request: Option<rmcp::model::PaginatedRequestParam>,
+        _context: rmcp::service::RequestContext<RoleServer>,
+    ) -> Result<rmcp::model::ListToolsResult, ErrorData> {
+        // Tools are listed by name, so a cursor is the name of the last tool on a page
+        let after = match request.and_then(|request| request.cursor) {
+            Some(cursor) => Some(
+                hex::decode(&cursor)
+                    .ok()
+                    .and_then(|name| String::from_utf8(name).ok())
+                    .ok_or_else(|| {
+                        ErrorData::invalid_params(format!("Invalid cursor: {}", cursor), None)
+                    })?,
+            ),
+            None => None,
+        };
+
+        let mut tools = self.tool_router.list_all();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut tools: Vec<_> = tools
+            .into_iter()
+            .filter(|tool| {
+                after
+                    .as_deref()
+                    .is_none_or(|after| tool.name.as_ref() > after)
+            })
+            .take(TOOLS_PAGE_SIZE + 1)
+            .collect();
+
+        let next_cursor = if tools.len() > TOOLS_PAGE_SIZE {
+            tools.truncate(TOOLS_PAGE_SIZE);
+            tools.last().map(|tool| hex::encode(tool.name.as_bytes()))
+        } else {
+            None
+        };
+        Ok(rmcp::model::ListToolsResult { tools, next_cursor })
+    }
+
+    async fn list_resources(
+        &self,
+        request: Option<rmcp::model::PaginatedRequestParam>,
+        _context: rmcp::service::RequestContext<RoleServer>,
+    ) -> Result<rmcp::model::ListResourcesResult, ErrorData> {
+        let cursor = request.and_then(|request| request.cursor);
+        let services = self.services.lock().await;
+        let page = services
+            .user_stories
+            .get_page(cursor.as_deref(), MCP_PAGE_SIZE)
+            .await
+            .map_err(user_story_error)?;
+
+        let resources = page
+            .items
+            .into_iter()
+            .map(|story| {
+                let mut resource = rmcp::model::RawResource::new(
+                    format!("{}{}", USER_STORY_URI_PREFIX, story.id),
+                    format!("{}: {}", story.id, story.title),
+                );
+                resource.description = Some(format!("User story for {}", story.persona));
+                resource.mime_type = Some("application/json".to_string());
+                resource.no_annotation()
+            })
+            .collect();
+        Ok(rmcp::model::ListResourcesResult {
+            resources,
+            next_cursor: page.next_cursor,
+        })
+    }
+
+    async fn read_resource(
+        &self,
+        request: rmcp::model::ReadResourceRequestParam,
+        _context: rmcp::service::RequestContext<RoleServer>,
+    ) -> Result<rmcp::model::ReadResourceResult, ErrorData> {
+        let Some(id) = request.uri.strip_prefix(USER_STORY_URI_PREFIX) else {
+            return Err(ErrorData::resource_not_found(
+                format!("Unknown resource: {}", request.uri),
+                None,
+            ));
+        };
+
+        let services = self.services.lock().await;
+        let story = match services.user_stories.get_with_criteria(id).await {
+            Ok(story) => story,
+            Err(UserStoryServiceError::NotFound { .. }) => {
+                return Err(ErrorData::resource_not_found(
+                    format!("Unknown resource: {}", request.uri),
+                    None,
+                ))
+            }
+            Err(e) => return Err(user_story_error(e)),
+        };
+
+        Ok(rmcp::model::ReadResourceResult {
+            contents: vec![rmcp::model::ResourceContents::TextResourceContents {
+                uri: request.uri,
+                mime_type: Some("application/json".to_string()),
+                text: serde_json::to_string_pretty(&story).unwrap(),
+                meta: None,
+            }],
         })
     }
 
@@ -249,17 +2269,87 @@ impl ServerHandler for UserStoryServer {
 }
 
 /// Main server runner function using rmcp
-pub async fn run_server(database_url: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let server = UserStoryServer::new(database_url).await?;
+pub async fn run_server(
+    database_url: &str,
+    database: DatabaseConfig,
+    config: ServiceConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let server = UserStoryServer::new(database_url, &database, config).await?;
 
     eprintln!("User Stories MCP Server started");
     eprintln!("Database: {}", database_url);
     eprintln!("Available tools:");
     eprintln!("  - create_user_story");
     eprintln!("  - get_user_story");
+    eprintln!("  - update_user_story");
+    eprintln!("  - delete_user_story");
+    eprintln!("  - update_acceptance_criteria");
+    eprintln!("  - delete_acceptance_criteria");
     eprintln!("  - get_all_user_stories");
+    eprintln!("  - list_acceptance_criteria");
     eprintln!("  - search_user_stories");
+    eprintln!("  - query_user_stories");
     eprintln!("  - get_user_stories_statistics");
+    eprintln!("  - export_backlog");
+    eprintln!("  - import_backlog");
+    eprintln!("  - export_feature");
+    eprintln!("  - import_features");
+    eprintln!("  - export_csv");
+    eprintln!("  - import_csv");
+    eprintln!("  - render_user_story_markdown");
+    eprintln!("  - apply_user_story_markdown");
+    eprintln!("  - create_persona");
+    eprintln!("  - get_persona");
+    eprintln!("  - list_personas");
+    eprintln!("  - update_persona");
+    eprintln!("  - delete_persona");
+    eprintln!("  - lint_user_story");
+    eprintln!("  - lint_backlog");
+    eprintln!("  - analyze_acceptance_criteria");
+    eprintln!("  - find_similar_user_stories");
+    eprintln!("  - add_comment");
+    eprintln!("  - list_comments");
+    eprintln!("  - edit_comment");
+    eprintln!("  - delete_comment");
+    eprintln!("  - resolve_comment");
+    eprintln!("  - get_mentions");
+    eprintln!("  - snapshot_story");
+    eprintln!("  - list_revisions");
+    eprintln!("  - diff_revisions");
+    eprintln!("  - revert_story");
+    eprintln!("  - list_trash");
+    eprintln!("  - restore_from_trash");
+    eprintln!("  - purge_trash");
+    eprintln!("  - create_member");
+    eprintln!("  - list_members");
+    eprintln!("  - delete_member");
+    eprintln!("  - assign_user_story");
+    eprintln!("  - unassign_user_story");
+    eprintln!("  - get_member_stories");
+    eprintln!("  - claim_story");
+    eprintln!("  - heartbeat_claim");
+    eprintln!("  - release_story");
+    eprintln!("  - list_claims");
+    eprintln!("  - next_unclaimed_story");
+    eprintln!("  - register_webhook");
+    eprintln!("  - list_webhooks");
+    eprintln!("  - delete_webhook");
+    eprintln!("  - list_events");
+    eprintln!("  - changes_since");
+    eprintln!("  - list_dead_letters");
+    eprintln!("  - retry_dead_letter");
+
+    // Deliver change events to webhooks while the server runs; a read-only database has
+    // no outbox to drain
+    if !database.read_only {
+        server
+            .services
+            .lock()
+            .await
+            .events
+            .dispatcher(DispatcherConfig::default())
+            .spawn();
+    }
 
     let service = server.serve(stdio()).await?;
     service.waiting().await?;
@@ -1,24 +1,34 @@
+use crate::models::user_story::first_version;
 use chrono::NaiveDateTime;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, JsonSchema)]
 pub struct AcceptanceCriteria {
     pub id: String,
     pub user_story_id: String,
     pub description: String,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    /// Incremented on every write; pass it back as `expected_version` to detect lost updates
+    #[serde(default = "first_version")]
+    pub version: i64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CreateAcceptanceCriteriaRequest {
-    pub id: String,
+    /// Generated from the id scheme when absent
+    #[serde(default)]
+    pub id: Option<String>,
     pub user_story_id: String,
     pub description: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct UpdateAcceptanceCriteriaRequest {
     pub description: Option<String>,
+    /// Reject the update if the criterion is no longer at this version
+    #[serde(default)]
+    pub expected_version: Option<i64>,
 }
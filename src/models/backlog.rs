@@ -0,0 +1,109 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::models::UserStoryWithCriteria;
+
+/// Current version of the backlog document format produced by `export_backlog`
+pub const BACKLOG_FORMAT_VERSION: u32 = 1;
+
+/// Versioned, self-contained snapshot of every user story and its acceptance criteria
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacklogDocument {
+    pub format_version: u32,
+    pub exported_at: NaiveDateTime,
+    pub user_stories: Vec<UserStoryWithCriteria>,
+}
+
+/// How `import_backlog` treats ids that already exist in the target database
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Leave the existing story untouched and ignore the imported one
+    Skip,
+    /// Replace the existing story and its criteria with the imported version
+    Overwrite,
+    /// Abort the whole import on the first conflicting id
+    #[default]
+    Fail,
+    /// Import under a fresh id derived from the conflicting one
+    RenameIds,
+}
+
+impl fmt::Display for ConflictPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ConflictPolicy::Skip => "skip",
+            ConflictPolicy::Overwrite => "overwrite",
+            ConflictPolicy::Fail => "fail",
+            ConflictPolicy::RenameIds => "rename_ids",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "skip" => Ok(ConflictPolicy::Skip),
+            "overwrite" => Ok(ConflictPolicy::Overwrite),
+            "fail" => Ok(ConflictPolicy::Fail),
+            "rename" | "rename_ids" => Ok(ConflictPolicy::RenameIds),
+            other => Err(format!(
+                "Unknown conflict policy '{}'. Expected one of: skip, overwrite, fail, rename_ids",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportOptions {
+    pub policy: ConflictPolicy,
+    /// Report what would change without writing anything
+    pub dry_run: bool,
+}
+
+/// A rendered Gherkin `.feature` file for one user story
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureFile {
+    pub file_name: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenamedId {
+    pub from: String,
+    pub to: String,
+}
+
+/// Outcome of an import, listing user story ids by what happened to them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub created: Vec<String>,
+    pub overwritten: Vec<String>,
+    pub skipped: Vec<String>,
+    /// Story and criteria ids that were changed under `ConflictPolicy::RenameIds`
+    pub renamed: Vec<RenamedId>,
+    pub criteria_imported: usize,
+}
+
+/// A problem with one row of an imported CSV file; the header is row 1
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CsvRowError {
+    pub row: usize,
+    pub message: String,
+}
+
+/// Outcome of a CSV import. When any row is rejected nothing is written and
+/// `import` only reflects the rows that were accepted.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CsvImportReport {
+    #[serde(flatten)]
+    pub import: ImportReport,
+    pub row_errors: Vec<CsvRowError>,
+}
@@ -0,0 +1,33 @@
+use chrono::NaiveDateTime;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// A lease an agent holds on a user story while working on it. Until the claim is released
+/// or expires, only its holder may change the story or its acceptance criteria.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct StoryClaim {
+    pub user_story_id: String,
+    pub agent_id: String,
+    pub claimed_at: NaiveDateTime,
+    /// When the holder last renewed the claim
+    pub heartbeat_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+}
+
+impl StoryClaim {
+    pub fn is_held_by(&self, agent_id: Option<&str>) -> bool {
+        agent_id == Some(self.agent_id.as_str())
+    }
+}
+
+/// Which stories the work queue hands out; all unclaimed stories when left empty
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct WorkQueueFilter {
+    /// Only stories written for this persona
+    pub persona: Option<String>,
+    /// Only stories assigned to this member
+    pub assignee: Option<String>,
+    /// Only stories nobody is assigned to
+    pub unassigned: bool,
+}
@@ -0,0 +1,84 @@
+use chrono::NaiveDateTime;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+/// `@name` preceded by the start of the text or a character that cannot be part of an email
+/// address, so `someone@example.com` mentions nobody
+static MENTION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|[^\w@.])@([A-Za-z0-9][A-Za-z0-9_-]*(?:\.[A-Za-z0-9_-]+)*)").unwrap()
+});
+
+/// A comment on a user story, or on one of its acceptance criteria
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Comment {
+    pub id: i64,
+    pub user_story_id: String,
+    /// Set when the comment is about one acceptance criterion rather than the whole story
+    pub acceptance_criteria_id: Option<String>,
+    /// The comment that starts the thread this one replies to
+    pub parent_id: Option<i64>,
+    pub author: String,
+    pub body: String,
+    /// Names mentioned in the body as `@name`
+    pub mentions: Vec<String>,
+    /// Who resolved the thread, for top-level comments of resolved threads
+    pub resolved_by: Option<String>,
+    pub resolved_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl Comment {
+    pub fn is_resolved(&self) -> bool {
+        self.resolved_at.is_some()
+    }
+}
+
+/// A top-level comment with its replies, oldest first
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CommentThread {
+    #[serde(flatten)]
+    pub comment: Comment,
+    pub replies: Vec<Comment>,
+}
+
+/// A new comment. Give a user story or an acceptance criterion to start a thread, or the
+/// id of a comment to reply to.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct CreateCommentRequest {
+    pub user_story_id: Option<String>,
+    pub acceptance_criteria_id: Option<String>,
+    pub parent_id: Option<i64>,
+    pub author: String,
+    pub body: String,
+}
+
+/// Names mentioned as `@name` in a comment body, in order of first mention and ignoring
+/// repeats regardless of case. A trailing full stop ends the sentence, not the name.
+pub fn parse_mentions(body: &str) -> Vec<String> {
+    let mut mentions: Vec<String> = Vec::new();
+    for capture in MENTION.captures_iter(body) {
+        let name = &capture[1];
+        if !mentions.iter().any(|m| m.eq_ignore_ascii_case(name)) {
+            mentions.push(name.to_string());
+        }
+    }
+    mentions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mentions() {
+        assert_eq!(
+            parse_mentions("@qa-agent please check this. cc @PO, @po and @reviewer.bot."),
+            vec!["qa-agent", "PO", "reviewer.bot"]
+        );
+        assert!(parse_mentions("Mail someone@example.com or use @ alone").is_empty());
+        assert_eq!(parse_mentions("(@tester)"), vec!["tester"]);
+    }
+}
@@ -0,0 +1,208 @@
+use crate::models::{AcceptanceCriteria, UserStory};
+use chrono::NaiveDateTime;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// What happened to a user story or acceptance criterion
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DomainEventKind {
+    StoryCreated,
+    /// Any change to a live story, including its assignee, reviewer and version
+    StoryUpdated,
+    /// The story moved to the trash
+    StoryDeleted,
+    StoryRestored,
+    CriteriaAdded,
+    CriteriaUpdated,
+    CriteriaDeleted,
+    CriteriaRestored,
+}
+
+impl DomainEventKind {
+    pub const ALL: [Self; 8] = [
+        Self::StoryCreated,
+        Self::StoryUpdated,
+        Self::StoryDeleted,
+        Self::StoryRestored,
+        Self::CriteriaAdded,
+        Self::CriteriaUpdated,
+        Self::CriteriaDeleted,
+        Self::CriteriaRestored,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::StoryCreated => "story_created",
+            Self::StoryUpdated => "story_updated",
+            Self::StoryDeleted => "story_deleted",
+            Self::StoryRestored => "story_restored",
+            Self::CriteriaAdded => "criteria_added",
+            Self::CriteriaUpdated => "criteria_updated",
+            Self::CriteriaDeleted => "criteria_deleted",
+            Self::CriteriaRestored => "criteria_restored",
+        }
+    }
+}
+
+impl fmt::Display for DomainEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for DomainEventKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("Unknown event kind '{}'", s))
+    }
+}
+
+/// A change recorded in the outbox, in the order the changes were committed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DomainEvent {
+    /// Position in the outbox; later events have higher ids
+    pub id: i64,
+    pub kind: DomainEventKind,
+    pub user_story_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acceptance_criteria_id: Option<String>,
+    /// The story or criterion as it was after the change
+    pub payload: serde_json::Value,
+    pub occurred_at: NaiveDateTime,
+}
+
+/// An endpoint that receives events as signed HTTP POST requests
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Webhook {
+    pub id: i64,
+    pub url: String,
+    /// Key the request signatures are made with; never shown
+    #[serde(skip)]
+    pub secret: String,
+    /// Kinds of event sent to the endpoint; every kind when empty
+    pub event_kinds: Vec<DomainEventKind>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    pub secret: String,
+    #[serde(default)]
+    pub event_kinds: Vec<DomainEventKind>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// Not delivered yet; tried again at `next_attempt_at`
+    Pending,
+    Delivered,
+    /// Given up on after too many failed attempts
+    Dead,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Delivered => "delivered",
+            Self::Dead => "dead",
+        }
+    }
+}
+
+impl FromStr for DeliveryStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "delivered" => Ok(Self::Delivered),
+            "dead" => Ok(Self::Dead),
+            other => Err(format!("Unknown delivery status '{}'", other)),
+        }
+    }
+}
+
+/// One event on its way to one webhook
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: i64,
+    pub url: String,
+    pub event_id: i64,
+    pub event_kind: DomainEventKind,
+    pub user_story_id: String,
+    pub status: DeliveryStatus,
+    pub attempts: i64,
+    pub next_attempt_at: NaiveDateTime,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delivered_at: Option<NaiveDateTime>,
+}
+
+/// What one round of the webhook dispatcher did
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct DispatchReport {
+    /// New outbox events handed to in-process subscribers
+    pub published: usize,
+    pub delivered: usize,
+    /// Failed attempts that will be tried again
+    pub retrying: usize,
+    /// Failed attempts that used up the last retry
+    pub dead: usize,
+}
+
+/// The current state of a story or criterion that changed after a sync cursor
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Change {
+    StoryUpserted {
+        /// Outbox id of the last change to the story
+        sequence: i64,
+        user_story: UserStory,
+    },
+    /// The story is in the trash or gone; drop it and its criteria
+    StoryDeleted { sequence: i64, id: String },
+    CriteriaUpserted {
+        sequence: i64,
+        acceptance_criteria: AcceptanceCriteria,
+    },
+    CriteriaDeleted {
+        sequence: i64,
+        id: String,
+        user_story_id: String,
+    },
+}
+
+impl Change {
+    pub fn sequence(&self) -> i64 {
+        match self {
+            Self::StoryUpserted { sequence, .. }
+            | Self::StoryDeleted { sequence, .. }
+            | Self::CriteriaUpserted { sequence, .. }
+            | Self::CriteriaDeleted { sequence, .. } => *sequence,
+        }
+    }
+}
+
+/// One page of changes since a cursor, each story and criterion at most once
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ChangeFeed {
+    /// In the order of their last change
+    pub changes: Vec<Change>,
+    /// Pass back as `cursor` to get the changes after this page
+    pub next_cursor: i64,
+    /// More changes are waiting after `next_cursor`
+    pub has_more: bool,
+}
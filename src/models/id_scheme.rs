@@ -0,0 +1,88 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// How ids are generated for user stories and acceptance criteria created without one.
+///
+/// Stories are numbered per prefix (`US-106`); criteria are numbered per story, after the
+/// story number (`AC-105-3` for the third criterion of `US-105`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct IdScheme {
+    pub story_prefix: String,
+    pub criteria_prefix: String,
+    /// Minimum number of digits of a story number, padded with zeros
+    pub story_number_width: usize,
+}
+
+impl Default for IdScheme {
+    fn default() -> Self {
+        Self {
+            story_prefix: "US-".to_string(),
+            criteria_prefix: "AC-".to_string(),
+            story_number_width: 3,
+        }
+    }
+}
+
+impl IdScheme {
+    /// Scheme with the prefixes from `STORY_ID_PREFIX` and `CRITERIA_ID_PREFIX`, where set
+    pub fn from_env() -> Self {
+        let mut scheme = Self::default();
+        if let Ok(prefix) = std::env::var("STORY_ID_PREFIX") {
+            scheme.story_prefix = prefix;
+        }
+        if let Ok(prefix) = std::env::var("CRITERIA_ID_PREFIX") {
+            scheme.criteria_prefix = prefix;
+        }
+        scheme
+    }
+
+    /// Story id for the given number
+    pub fn story_id(&self, number: i64) -> String {
+        format!(
+            "{}{:0width$}",
+            self.story_prefix,
+            number,
+            width = self.story_number_width
+        )
+    }
+
+    /// Prefix shared by the acceptance criteria ids of a story, e.g. `AC-105-`
+    pub fn criteria_prefix_for(&self, user_story_id: &str) -> String {
+        let story_key = user_story_id
+            .strip_prefix(self.story_prefix.as_str())
+            .unwrap_or(user_story_id);
+        format!("{}{}-", self.criteria_prefix, story_key)
+    }
+
+    /// Acceptance criteria id for the given story and number
+    pub fn criteria_id(&self, user_story_id: &str, number: i64) -> String {
+        format!("{}{}", self.criteria_prefix_for(user_story_id), number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_scheme() {
+        let scheme = IdScheme::default();
+        assert_eq!(scheme.story_id(7), "US-007");
+        assert_eq!(scheme.story_id(106), "US-106");
+        assert_eq!(scheme.story_id(1234), "US-1234");
+        assert_eq!(scheme.criteria_id("US-105", 3), "AC-105-3");
+    }
+
+    #[test]
+    fn test_custom_prefixes() {
+        let scheme = IdScheme {
+            story_prefix: "SHOP-".to_string(),
+            criteria_prefix: "SHOP-AC-".to_string(),
+            story_number_width: 1,
+        };
+        assert_eq!(scheme.story_id(42), "SHOP-42");
+        assert_eq!(scheme.criteria_id("SHOP-42", 1), "SHOP-AC-42-1");
+        // Stories with another prefix keep their whole id
+        assert_eq!(scheme.criteria_id("US-001", 2), "SHOP-AC-US-001-2");
+    }
+}
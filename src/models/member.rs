@@ -0,0 +1,96 @@
+use chrono::NaiveDateTime;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Whether a member is a person or an agent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MemberKind {
+    #[default]
+    Human,
+    Agent,
+}
+
+impl MemberKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Human => "human",
+            Self::Agent => "agent",
+        }
+    }
+}
+
+impl fmt::Display for MemberKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for MemberKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "human" => Ok(Self::Human),
+            "agent" => Ok(Self::Agent),
+            other => Err(format!(
+                "Unknown member kind '{}'; expected human or agent",
+                other
+            )),
+        }
+    }
+}
+
+/// Someone, human or agent, that user stories are assigned to or reviewed by. Names are
+/// unique regardless of case.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Member {
+    pub id: i64,
+    pub name: String,
+    pub kind: MemberKind,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CreateMemberRequest {
+    pub name: String,
+    #[serde(default)]
+    pub kind: MemberKind,
+}
+
+/// A member with the number of live user stories assigned to them and waiting for their
+/// review
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MemberWithWorkload {
+    #[serde(flatten)]
+    pub member: Member,
+    pub assigned: i64,
+    pub reviewing: i64,
+}
+
+/// How a member is attached to a user story
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AssignmentRole {
+    /// Owns the story and does the work
+    Assignee,
+    Reviewer,
+}
+
+impl FromStr for AssignmentRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "assignee" | "owner" => Ok(Self::Assignee),
+            "reviewer" => Ok(Self::Reviewer),
+            other => Err(format!(
+                "Unknown role '{}'; expected assignee or reviewer",
+                other
+            )),
+        }
+    }
+}
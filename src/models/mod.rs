@@ -1,5 +1,31 @@
 pub mod acceptance_criteria;
+pub mod backlog;
+pub mod claim;
+pub mod comment;
+pub mod event;
+pub mod id_scheme;
+pub mod member;
+pub mod page;
+pub mod persona;
+pub mod quality;
+pub mod revision;
+pub mod story_query;
+pub mod trash;
 pub mod user_story;
+pub mod validation_policy;
 
 pub use acceptance_criteria::*;
+pub use backlog::*;
+pub use claim::*;
+pub use comment::*;
+pub use event::*;
+pub use id_scheme::*;
+pub use member::*;
+pub use page::*;
+pub use persona::*;
+pub use quality::*;
+pub use revision::*;
+pub use story_query::*;
+pub use trash::*;
 pub use user_story::*;
+pub use validation_policy::*;
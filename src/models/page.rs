@@ -0,0 +1,105 @@
+use chrono::NaiveDateTime;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Page size used when a listing is not given a limit
+pub const DEFAULT_PAGE_SIZE: i64 = 20;
+
+/// Most items on one page; larger limits are cut down to this and continue through
+/// `next_cursor`
+pub const MAX_PAGE_SIZE: i64 = 1000;
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
+/// Position in a listing ordered by `(created_at, id)`: the key of the last item on a page.
+/// Rows created while a client pages through a listing never shift the pages after a cursor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageCursor {
+    pub created_at: NaiveDateTime,
+    pub id: String,
+}
+
+impl PageCursor {
+    pub fn new(created_at: NaiveDateTime, id: impl Into<String>) -> Self {
+        Self {
+            created_at,
+            id: id.into(),
+        }
+    }
+
+    /// Opaque text form handed to clients as `next_cursor`
+    pub fn encode(&self) -> String {
+        hex::encode(format!(
+            "{}|{}",
+            self.created_at.format(TIMESTAMP_FORMAT),
+            self.id
+        ))
+    }
+
+    /// Read back a cursor produced by `encode`; `None` for anything else
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let text = String::from_utf8(hex::decode(cursor).ok()?).ok()?;
+        let (created_at, id) = text.split_once('|')?;
+        let created_at = NaiveDateTime::parse_from_str(created_at, TIMESTAMP_FORMAT).ok()?;
+        Some(Self::new(created_at, id))
+    }
+}
+
+/// One page of a listing
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Pass back as `cursor` for the next page; absent on the last page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Make a page from up to `limit + 1` rows fetched after a cursor; the extra row only
+    /// tells that another page follows
+    pub fn from_rows(mut rows: Vec<T>, limit: i64, key: impl Fn(&T) -> PageCursor) -> Self {
+        let has_more = rows.len() as i64 > limit;
+        rows.truncate(limit as usize);
+        let next_cursor = if has_more {
+            rows.last().map(|last| key(last).encode())
+        } else {
+            None
+        };
+        Self {
+            items: rows,
+            next_cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let created_at = "2024-01-01T10:00:00.250".parse().unwrap();
+        let cursor = PageCursor::new(created_at, "US-|7");
+
+        assert_eq!(PageCursor::decode(&cursor.encode()), Some(cursor));
+        assert_eq!(PageCursor::decode("not a cursor"), None);
+        assert_eq!(PageCursor::decode(&hex::encode("US-001")), None);
+    }
+
+    #[test]
+    fn test_from_rows() {
+        let created_at: NaiveDateTime = "2024-01-01T10:00:00".parse().unwrap();
+        let key = |id: &&str| PageCursor::new(created_at, *id);
+
+        let page = Page::from_rows(vec!["a", "b", "c"], 2, key);
+        assert_eq!(page.items, ["a", "b"]);
+        assert_eq!(
+            page.next_cursor.as_deref().and_then(PageCursor::decode),
+            Some(PageCursor::new(created_at, "b"))
+        );
+
+        let last = Page::from_rows(vec!["a", "b"], 2, key);
+        assert_eq!(last.items, ["a", "b"]);
+        assert!(last.next_cursor.is_none());
+    }
+}
@@ -0,0 +1,45 @@
+use chrono::NaiveDateTime;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Longest persona name accepted, in bytes
+pub const MAX_PERSONA_NAME_LENGTH: usize = 100;
+
+/// Who a user story is written for. Names are unique regardless of case.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Persona {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+    pub goals: Vec<String>,
+    pub pain_points: Vec<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CreatePersonaRequest {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub goals: Vec<String>,
+    #[serde(default)]
+    pub pain_points: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct UpdatePersonaRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub goals: Option<Vec<String>>,
+    pub pain_points: Option<Vec<String>>,
+}
+
+/// A persona together with the number of user stories written for it
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PersonaWithUsage {
+    #[serde(flatten)]
+    pub persona: Persona,
+    pub story_count: i64,
+}
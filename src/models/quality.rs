@@ -0,0 +1,209 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// How much a lint finding matters
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum LintSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// The INVEST qualities of a good user story
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum InvestPrinciple {
+    Independent,
+    Negotiable,
+    Valuable,
+    Estimable,
+    Small,
+    Testable,
+}
+
+/// The check that produced a lint finding
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum LintRule {
+    /// The description does not start with "As a ..."
+    MissingRole,
+    /// The description has no "I want ..." clause
+    MissingGoal,
+    /// The description has no "so that ..." clause
+    MissingBenefit,
+    /// A word such as "fast" or "easy" that cannot be verified
+    VagueWording,
+    /// The story refers to another story it depends on
+    Dependency,
+    /// The story prescribes how to build it instead of what is needed
+    ImplementationDetail,
+    /// The goal combines several things that could be separate stories
+    CompoundGoal,
+    /// The description is too short to estimate
+    TooShort,
+    /// The description is long enough that the story should be split
+    TooLong,
+    NoAcceptanceCriteria,
+    TooManyAcceptanceCriteria,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct LintFinding {
+    pub rule: LintRule,
+    pub severity: LintSeverity,
+    pub principle: InvestPrinciple,
+    /// `title`, `description`, `acceptance_criteria` or the id of a criterion
+    pub field: String,
+    pub message: String,
+}
+
+/// Score from 0 to 100 for each INVEST quality
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct InvestScores {
+    pub independent: u8,
+    pub negotiable: u8,
+    pub valuable: u8,
+    pub estimable: u8,
+    pub small: u8,
+    pub testable: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct StoryLintReport {
+    pub user_story_id: String,
+    /// Average of the INVEST scores, from 0 to 100
+    pub score: u8,
+    pub invest: InvestScores,
+    pub findings: Vec<LintFinding>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct BacklogLintReport {
+    pub story_count: usize,
+    pub average_score: f64,
+    /// Number of findings per rule across the backlog
+    pub rule_counts: BTreeMap<LintRule, usize>,
+    /// One report per story, lowest score first
+    pub stories: Vec<StoryLintReport>,
+}
+
+/// The check that produced a testability finding on an acceptance criterion
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TestabilityRule {
+    /// The criterion is not written as Given/When/Then
+    MissingStructure,
+    /// Nothing a tester could observe tells whether the criterion passed
+    MissingOutcome,
+    /// A quantity such as "some" or "several" instead of a number
+    AmbiguousQuantifier,
+    /// A word such as "fast" or "user friendly" that cannot be verified
+    VagueWording,
+    /// Several scenarios in one criterion
+    CompoundCriterion,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TestabilityFinding {
+    pub rule: TestabilityRule,
+    pub severity: LintSeverity,
+    pub message: String,
+    /// How to rewrite the criterion to resolve the finding
+    pub suggestion: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CriterionTestabilityReport {
+    /// Absent when a draft description was analyzed
+    pub acceptance_criteria_id: Option<String>,
+    pub user_story_id: Option<String>,
+    pub description: String,
+    /// True when there are no warnings or errors
+    pub testable: bool,
+    pub findings: Vec<TestabilityFinding>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct StoryTestabilityReport {
+    pub user_story_id: String,
+    pub criteria_count: usize,
+    pub testable_count: usize,
+    pub criteria: Vec<CriterionTestabilityReport>,
+}
+
+/// A stored story that resembles the story or text it was compared with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SimilarStory {
+    pub user_story_id: String,
+    pub title: String,
+    /// Cosine similarity from 0 (nothing in common) to 1 (same wording)
+    pub score: f64,
+}
+
+/// What creating a user story does about existing stories that look alike
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateMode {
+    /// Do not look for duplicates
+    #[default]
+    Off,
+    /// Create the story and report the stories it resembles
+    Warn,
+    /// Refuse to create a story that resembles an existing one
+    Reject,
+}
+
+impl fmt::Display for DuplicateMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DuplicateMode::Off => "off",
+            DuplicateMode::Warn => "warn",
+            DuplicateMode::Reject => "reject",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for DuplicateMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "off" | "none" => Ok(DuplicateMode::Off),
+            "warn" => Ok(DuplicateMode::Warn),
+            "reject" => Ok(DuplicateMode::Reject),
+            other => Err(format!(
+                "Unknown duplicate check '{}'. Expected one of: off, warn, reject",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DuplicateCheck {
+    pub mode: DuplicateMode,
+    /// Similarity from which an existing story counts as a likely duplicate
+    pub threshold: f64,
+}
+
+impl Default for DuplicateCheck {
+    fn default() -> Self {
+        Self {
+            mode: DuplicateMode::Off,
+            threshold: crate::quality::similarity::DEFAULT_SIMILARITY_THRESHOLD,
+        }
+    }
+}
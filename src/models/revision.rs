@@ -0,0 +1,212 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::models::{AcceptanceCriteria, UserStoryWithCriteria};
+
+/// A user story with its acceptance criteria as it was when the revision was taken
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoryRevision {
+    pub user_story_id: String,
+    /// Number of the revision, counting from 1 for each story
+    pub revision: i64,
+    pub note: String,
+    pub created_at: NaiveDateTime,
+    pub snapshot: UserStoryWithCriteria,
+}
+
+/// A revision without its snapshot, as listed by `list_revisions`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoryRevisionSummary {
+    pub user_story_id: String,
+    pub revision: i64,
+    pub note: String,
+    pub created_at: NaiveDateTime,
+    /// Version of the story the snapshot was taken at
+    pub story_version: i64,
+    pub title: String,
+    pub criteria_count: usize,
+}
+
+impl From<&StoryRevision> for StoryRevisionSummary {
+    fn from(revision: &StoryRevision) -> Self {
+        Self {
+            user_story_id: revision.user_story_id.clone(),
+            revision: revision.revision,
+            note: revision.note.clone(),
+            created_at: revision.created_at,
+            story_version: revision.snapshot.user_story.version,
+            title: revision.snapshot.user_story.title.clone(),
+            criteria_count: revision.snapshot.acceptance_criteria.len(),
+        }
+    }
+}
+
+/// A story field whose value differs between two revisions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// An acceptance criterion present in both revisions with another description
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CriteriaChange {
+    pub id: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// Differences between two states of a story; criteria are matched by id
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StoryDiff {
+    pub fields: Vec<FieldChange>,
+    pub criteria_added: Vec<AcceptanceCriteria>,
+    pub criteria_removed: Vec<AcceptanceCriteria>,
+    pub criteria_changed: Vec<CriteriaChange>,
+}
+
+impl StoryDiff {
+    /// What changed going from `from` to `to`, ignoring timestamps and versions
+    pub fn between(from: &UserStoryWithCriteria, to: &UserStoryWithCriteria) -> Self {
+        let (a, b) = (&from.user_story, &to.user_story);
+        let fields = [
+            ("title", &a.title, &b.title),
+            ("description", &a.description, &b.description),
+            ("persona", &a.persona, &b.persona),
+        ]
+        .into_iter()
+        .filter(|(_, from, to)| from != to)
+        .map(|(field, from, to)| FieldChange {
+            field: field.to_string(),
+            from: from.clone(),
+            to: to.clone(),
+        })
+        .collect();
+
+        let before: HashMap<&str, &AcceptanceCriteria> = from
+            .acceptance_criteria
+            .iter()
+            .map(|c| (c.id.as_str(), c))
+            .collect();
+        let after: HashMap<&str, &AcceptanceCriteria> = to
+            .acceptance_criteria
+            .iter()
+            .map(|c| (c.id.as_str(), c))
+            .collect();
+
+        let mut diff = Self {
+            fields,
+            ..Default::default()
+        };
+        for criteria in &to.acceptance_criteria {
+            match before.get(criteria.id.as_str()) {
+                None => diff.criteria_added.push(criteria.clone()),
+                Some(old) if old.description != criteria.description => {
+                    diff.criteria_changed.push(CriteriaChange {
+                        id: criteria.id.clone(),
+                        from: old.description.clone(),
+                        to: criteria.description.clone(),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        diff.criteria_removed = from
+            .acceptance_criteria
+            .iter()
+            .filter(|c| !after.contains_key(c.id.as_str()))
+            .cloned()
+            .collect();
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+            && self.criteria_added.is_empty()
+            && self.criteria_removed.is_empty()
+            && self.criteria_changed.is_empty()
+    }
+}
+
+/// Differences between a revision and a later revision, or the story as it is now
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevisionDiff {
+    pub user_story_id: String,
+    pub from_revision: i64,
+    /// `None` when compared against the current story
+    pub to_revision: Option<i64>,
+    #[serde(flatten)]
+    pub diff: StoryDiff,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::UserStory;
+
+    fn story(title: &str, criteria: &[(&str, &str)]) -> UserStoryWithCriteria {
+        let timestamp = NaiveDateTime::default();
+        UserStoryWithCriteria {
+            user_story: UserStory {
+                id: "US-001".to_string(),
+                title: title.to_string(),
+                description: "As a user, I want to log in".to_string(),
+                persona: "End User".to_string(),
+                created_at: timestamp,
+                updated_at: timestamp,
+                version: 1,
+                assignee: None,
+                reviewer: None,
+            },
+            acceptance_criteria: criteria
+                .iter()
+                .map(|(id, description)| AcceptanceCriteria {
+                    id: id.to_string(),
+                    user_story_id: "US-001".to_string(),
+                    description: description.to_string(),
+                    created_at: timestamp,
+                    updated_at: timestamp,
+                    version: 1,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_diff_between() {
+        let from = story(
+            "Login",
+            &[("AC-001", "Valid login"), ("AC-002", "Invalid login")],
+        );
+        let mut to = story(
+            "Sign in",
+            &[("AC-002", "Invalid password"), ("AC-003", "Locked account")],
+        );
+        to.user_story.version = 4;
+
+        let diff = StoryDiff::between(&from, &to);
+        assert_eq!(
+            diff.fields,
+            vec![FieldChange {
+                field: "title".to_string(),
+                from: "Login".to_string(),
+                to: "Sign in".to_string(),
+            }]
+        );
+        assert_eq!(diff.criteria_added[0].id, "AC-003");
+        assert_eq!(diff.criteria_removed[0].id, "AC-001");
+        assert_eq!(
+            diff.criteria_changed,
+            vec![CriteriaChange {
+                id: "AC-002".to_string(),
+                from: "Invalid login".to_string(),
+                to: "Invalid password".to_string(),
+            }]
+        );
+
+        assert!(StoryDiff::between(&from, &from).is_empty());
+    }
+}
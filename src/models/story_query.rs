@@ -0,0 +1,129 @@
+use crate::models::UserStory;
+use chrono::NaiveDateTime;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Filters, sort order and page of a user story query; every filter left out matches all
+/// stories
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct StoryQuery {
+    /// Stories written for any of these personas, ignoring case
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub personas: Vec<String>,
+    /// Text contained in the title, description or persona name, ignoring case
+    #[serde(default)]
+    pub text: Option<String>,
+    /// Stories created at or after this time
+    #[serde(default)]
+    pub created_after: Option<NaiveDateTime>,
+    /// Stories created before this time
+    #[serde(default)]
+    pub created_before: Option<NaiveDateTime>,
+    /// Stories last updated at or after this time
+    #[serde(default)]
+    pub updated_after: Option<NaiveDateTime>,
+    /// Stories last updated before this time
+    #[serde(default)]
+    pub updated_before: Option<NaiveDateTime>,
+    /// true for stories with acceptance criteria, false for stories without any
+    #[serde(default)]
+    pub has_criteria: Option<bool>,
+    /// Stories with at least this many acceptance criteria
+    #[serde(default)]
+    pub min_criteria: Option<i64>,
+    /// Stories with at most this many acceptance criteria
+    #[serde(default)]
+    pub max_criteria: Option<i64>,
+    /// Column to sort by (default created_at)
+    #[serde(default)]
+    pub sort_by: StorySortField,
+    /// Sort direction (default desc)
+    #[serde(default)]
+    pub direction: SortDirection,
+    /// Most stories to return; limits above 1000 are cut down to it and continue through
+    /// `cursor`. All matching stories when absent
+    #[serde(default)]
+    pub limit: Option<i64>,
+    /// next_cursor of the previous page, to continue right after its last story
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+/// Position in the results of a `StoryQuery`: the sort key and id of the last story on a
+/// page. The next page starts after that key whatever was added or removed before it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoryCursor {
+    pub sort_by: StorySortField,
+    pub direction: SortDirection,
+    pub key: SortKey,
+    pub id: String,
+}
+
+/// Value of the column a story is sorted by
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    /// None for a story without assignee or reviewer
+    Text(Option<String>),
+    Time(NaiveDateTime),
+    Number(i64),
+}
+
+impl StoryCursor {
+    /// Cursor after `story` in the order of `query`; `criteria_count` is the number of live
+    /// acceptance criteria of the story, read only when the query sorts by it
+    pub fn new(query: &StoryQuery, story: &UserStory, criteria_count: i64) -> Self {
+        let key = match query.sort_by {
+            StorySortField::Id => SortKey::Text(Some(story.id.clone())),
+            StorySortField::Title => SortKey::Text(Some(story.title.clone())),
+            StorySortField::Persona => SortKey::Text(Some(story.persona.clone())),
+            StorySortField::Assignee => SortKey::Text(story.assignee.clone()),
+            StorySortField::Reviewer => SortKey::Text(story.reviewer.clone()),
+            StorySortField::CreatedAt => SortKey::Time(story.created_at),
+            StorySortField::UpdatedAt => SortKey::Time(story.updated_at),
+            StorySortField::Version => SortKey::Number(story.version),
+            StorySortField::CriteriaCount => SortKey::Number(criteria_count),
+        };
+        Self {
+            sort_by: query.sort_by,
+            direction: query.direction,
+            key,
+            id: story.id.clone(),
+        }
+    }
+
+    /// Opaque text form handed to clients as `next_cursor`
+    pub fn encode(&self) -> String {
+        hex::encode(serde_json::to_vec(self).expect("a cursor serializes"))
+    }
+
+    /// Read back a cursor produced by `encode`; `None` for anything else
+    pub fn decode(cursor: &str) -> Option<Self> {
+        serde_json::from_slice(&hex::decode(cursor).ok()?).ok()
+    }
+}
+
+/// What a `StoryQuery` orders stories by; ties are broken by id
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StorySortField {
+    Id,
+    Title,
+    Persona,
+    Assignee,
+    Reviewer,
+    #[default]
+    CreatedAt,
+    UpdatedAt,
+    Version,
+    /// Number of live acceptance criteria
+    CriteriaCount,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    Asc,
+    #[default]
+    Desc,
+}
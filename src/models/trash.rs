@@ -0,0 +1,46 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{AcceptanceCriteria, UserStory, UserStoryWithCriteria};
+
+/// Days deleted stories and criteria are kept before `purge_trash` removes them
+pub const DEFAULT_TRASH_RETENTION_DAYS: i64 = 30;
+
+/// A deleted user story with the acceptance criteria deleted along with it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashedUserStory {
+    #[serde(flatten)]
+    pub user_story: UserStory,
+    pub acceptance_criteria: Vec<AcceptanceCriteria>,
+    pub deleted_at: NaiveDateTime,
+}
+
+/// Acceptance criteria deleted on their own from a story that still exists
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashedAcceptanceCriteria {
+    #[serde(flatten)]
+    pub acceptance_criteria: AcceptanceCriteria,
+    pub deleted_at: NaiveDateTime,
+}
+
+/// Everything that can be restored, most recently deleted first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Trash {
+    pub user_stories: Vec<TrashedUserStory>,
+    pub acceptance_criteria: Vec<TrashedAcceptanceCriteria>,
+}
+
+/// What `restore` brought back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Restored {
+    UserStory(UserStoryWithCriteria),
+    AcceptanceCriteria(AcceptanceCriteria),
+}
+
+/// Number of rows removed for good by `purge_trash`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PurgeReport {
+    pub user_stories: u64,
+    pub acceptance_criteria: u64,
+}
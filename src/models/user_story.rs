@@ -1,35 +1,72 @@
 use chrono::NaiveDateTime;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, JsonSchema)]
 pub struct UserStory {
     pub id: String,
     pub title: String,
     pub description: String,
     pub persona: String,
+    /// Name of the member who owns the story
+    #[serde(default)]
+    pub assignee: Option<String>,
+    /// Name of the member who reviews the story
+    #[serde(default)]
+    pub reviewer: Option<String>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    /// Incremented on every write; pass it back as `expected_version` to detect lost updates
+    #[serde(default = "first_version")]
+    pub version: i64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Version of a row that has never been updated, also assumed for documents without versions
+pub(crate) fn first_version() -> i64 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CreateUserStoryRequest {
-    pub id: String,
+    /// Generated from the id scheme when absent
+    #[serde(default)]
+    pub id: Option<String>,
     pub title: String,
     pub description: String,
     pub persona: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct UpdateUserStoryRequest {
     pub title: Option<String>,
     pub description: Option<String>,
     pub persona: Option<String>,
+    /// Reject the update if the story is no longer at this version
+    #[serde(default)]
+    pub expected_version: Option<i64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct UserStoryWithCriteria {
     #[serde(flatten)]
     pub user_story: UserStory,
     pub acceptance_criteria: Vec<crate::models::AcceptanceCriteria>,
 }
+
+/// A user story with the full persona it is written for
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UserStoryWithPersona {
+    #[serde(flatten)]
+    pub user_story: UserStory,
+    pub persona_context: crate::models::Persona,
+}
+
+/// A newly created user story with the existing stories it resembles
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CreatedUserStory {
+    #[serde(flatten)]
+    pub user_story: UserStory,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub possible_duplicates: Vec<crate::models::SimilarStory>,
+}
@@ -0,0 +1,221 @@
+use crate::models::IdScheme;
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ValidationPolicyError {
+    #[error("Cannot read validation policy: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid validation policy: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid validation policy: {message}")]
+    Invalid { message: String },
+}
+
+/// A user story field that may be left empty when the policy does not require it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StoryField {
+    Title,
+    Description,
+}
+
+/// Regular expression an id must match, read and written as its source text
+#[derive(Debug, Clone)]
+pub struct IdPattern(Regex);
+
+impl IdPattern {
+    pub fn new(pattern: &str) -> Result<Self, ValidationPolicyError> {
+        Regex::new(pattern)
+            .map(Self)
+            .map_err(|e| ValidationPolicyError::Invalid {
+                message: format!("id pattern '{}': {}", pattern, e),
+            })
+    }
+
+    pub fn is_match(&self, id: &str) -> bool {
+        self.0.is_match(id)
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl fmt::Display for IdPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for IdPattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for IdPattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Self::new(&pattern).map_err(serde::de::Error::custom)
+    }
+}
+
+/// The rules user stories and acceptance criteria are validated against.
+///
+/// Loaded per project from a JSON file; fields left out keep their defaults. Without id
+/// patterns, ids must start with the prefixes of the id scheme.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ValidationPolicy {
+    pub story_title_max_length: usize,
+    pub story_description_max_length: usize,
+    pub criteria_description_max_length: usize,
+    pub max_criteria_per_story: i64,
+    /// Story fields that cannot be empty; the persona is always required
+    pub required_story_fields: Vec<StoryField>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub story_id_pattern: Option<IdPattern>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub criteria_id_pattern: Option<IdPattern>,
+}
+
+impl Default for ValidationPolicy {
+    fn default() -> Self {
+        Self {
+            story_title_max_length: 200,
+            story_description_max_length: 2000,
+            criteria_description_max_length: 1000,
+            max_criteria_per_story: 20,
+            required_story_fields: vec![StoryField::Title, StoryField::Description],
+            story_id_pattern: None,
+            criteria_id_pattern: None,
+        }
+    }
+}
+
+impl ValidationPolicy {
+    /// Read a policy from a JSON file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ValidationPolicyError> {
+        let policy: Self = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        policy.check()?;
+        Ok(policy)
+    }
+
+    /// Reject limits no story or criterion could satisfy
+    pub fn check(&self) -> Result<(), ValidationPolicyError> {
+        let limits = [
+            ("story_title_max_length", self.story_title_max_length as i64),
+            (
+                "story_description_max_length",
+                self.story_description_max_length as i64,
+            ),
+            (
+                "criteria_description_max_length",
+                self.criteria_description_max_length as i64,
+            ),
+            ("max_criteria_per_story", self.max_criteria_per_story),
+        ];
+        for (name, value) in limits {
+            if value < 1 {
+                return Err(ValidationPolicyError::Invalid {
+                    message: format!("{} must be at least 1", name),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn requires(&self, field: StoryField) -> bool {
+        self.required_story_fields.contains(&field)
+    }
+
+    /// Plain-language summary of the rules, for clients that cannot read the policy file
+    pub fn describe(&self, id_scheme: &IdScheme) -> String {
+        let id_rule = |pattern: &Option<IdPattern>, prefix: &str| match pattern {
+            Some(pattern) => format!("match '{}'", pattern),
+            None => format!("start with '{}'", prefix),
+        };
+        let required: Vec<&str> = [
+            (StoryField::Title, "title"),
+            (StoryField::Description, "description"),
+        ]
+        .into_iter()
+        .filter(|(field, _)| self.requires(*field))
+        .map(|(_, name)| name)
+        .chain(std::iter::once("persona"))
+        .collect();
+
+        format!(
+            "User story ids must {}, acceptance criteria ids must {}. \
+            Required story fields: {}. Story titles are limited to {} characters and \
+            descriptions to {}; acceptance criteria descriptions to {}. \
+            A story may have at most {} acceptance criteria.",
+            id_rule(&self.story_id_pattern, &id_scheme.story_prefix),
+            id_rule(&self.criteria_id_pattern, &id_scheme.criteria_prefix),
+            required.join(", "),
+            self.story_title_max_length,
+            self.story_description_max_length,
+            self.criteria_description_max_length,
+            self.max_criteria_per_story,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_policy_keeps_defaults() {
+        let policy: ValidationPolicy = serde_json::from_str(
+            r#"{ "story_title_max_length": 80, "story_id_pattern": "^US-[0-9]{3,}$" }"#,
+        )
+        .unwrap();
+
+        assert_eq!(policy.story_title_max_length, 80);
+        assert_eq!(policy.max_criteria_per_story, 20);
+        assert!(policy.requires(StoryField::Description));
+
+        let pattern = policy.story_id_pattern.unwrap();
+        assert!(pattern.is_match("US-106"));
+        assert!(!pattern.is_match("US-TEST"));
+    }
+
+    #[test]
+    fn test_invalid_policies() {
+        let unknown = serde_json::from_str::<ValidationPolicy>(r#"{ "max_title": 80 }"#);
+        assert!(unknown.is_err());
+
+        let pattern = serde_json::from_str::<ValidationPolicy>(r#"{ "story_id_pattern": "(" }"#);
+        assert!(pattern.unwrap_err().to_string().contains("id pattern '('"));
+
+        let policy = ValidationPolicy {
+            max_criteria_per_story: 0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            policy.check(),
+            Err(ValidationPolicyError::Invalid { .. })
+        ));
+    }
+
+    #[test]
+    fn test_describe() {
+        let policy = ValidationPolicy {
+            criteria_id_pattern: Some(IdPattern::new("^AC-[0-9]+$").unwrap()),
+            required_story_fields: vec![StoryField::Title],
+            max_criteria_per_story: 5,
+            ..Default::default()
+        };
+
+        let description = policy.describe(&IdScheme::default());
+        assert!(description.contains("User story ids must start with 'US-'"));
+        assert!(description.contains("acceptance criteria ids must match '^AC-[0-9]+$'"));
+        assert!(description.contains("Required story fields: title, persona."));
+        assert!(description.contains("at most 5 acceptance criteria"));
+    }
+}
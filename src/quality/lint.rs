@@ -0,0 +1,541 @@
+//! Heuristic INVEST and narrative checks for user stories.
+//!
+//! The description is expected to follow "As a <role>, I want <goal> so that <benefit>".
+//! Each finding counts against one INVEST quality; a quality starts at 100 and loses
+//! points per finding according to its severity.
+
+use crate::models::{
+    AcceptanceCriteria, BacklogLintReport, InvestPrinciple, InvestScores, LintFinding, LintRule,
+    LintSeverity, StoryLintReport, UserStoryWithCriteria,
+};
+use crate::quality::{normalize, VAGUE_WORDS};
+use std::collections::BTreeMap;
+
+/// Stories with more acceptance criteria than this should probably be split
+pub const RECOMMENDED_MAX_CRITERIA: usize = 8;
+
+/// Descriptions with fewer words than this are too thin to estimate
+const MIN_DESCRIPTION_WORDS: usize = 6;
+
+/// Descriptions with more words than this describe more than one story
+const MAX_DESCRIPTION_WORDS: usize = 120;
+
+/// Words that describe a solution rather than a need
+const IMPLEMENTATION_WORDS: &[&str] = &[
+    "database",
+    "sql",
+    "endpoint",
+    "api",
+    "json",
+    "xml",
+    "button",
+    "dropdown",
+    "checkbox",
+    "click",
+    "css",
+    "html",
+    "javascript",
+    "microservice",
+    "redis",
+];
+
+const DEPENDENCY_PHRASES: &[&str] = &["depends on", "dependent on", "blocked by", "once story"];
+
+const ROLE_MARKERS: &[&str] = &["as a", "as an", "as the"];
+const GOAL_MARKERS: &[&str] = &["i want", "i need", "i would like", "i d like", "i can"];
+const BENEFIT_MARKERS: &[&str] = &["so that", "so i can", "in order to"];
+
+/// Lint one user story with its acceptance criteria
+pub fn lint_story(story: &UserStoryWithCriteria) -> StoryLintReport {
+    let user_story = &story.user_story;
+    let mut findings = Vec::new();
+
+    check_narrative(&user_story.description, &mut findings);
+    check_size(&user_story.description, &mut findings);
+
+    for (field, text) in [
+        ("title", &user_story.title),
+        ("description", &user_story.description),
+    ] {
+        check_vague_words(field, text, InvestPrinciple::Estimable, &mut findings);
+        check_implementation_details(field, text, &mut findings);
+        check_dependencies(&user_story.id, field, text, &mut findings);
+    }
+
+    check_criteria(&story.acceptance_criteria, &mut findings);
+    for criteria in &story.acceptance_criteria {
+        check_vague_words(
+            &criteria.id,
+            &criteria.description,
+            InvestPrinciple::Testable,
+            &mut findings,
+        );
+        check_dependencies(
+            &user_story.id,
+            &criteria.id,
+            &criteria.description,
+            &mut findings,
+        );
+    }
+
+    let invest = InvestScores {
+        independent: principle_score(&findings, InvestPrinciple::Independent),
+        negotiable: principle_score(&findings, InvestPrinciple::Negotiable),
+        valuable: principle_score(&findings, InvestPrinciple::Valuable),
+        estimable: principle_score(&findings, InvestPrinciple::Estimable),
+        small: principle_score(&findings, InvestPrinciple::Small),
+        testable: principle_score(&findings, InvestPrinciple::Testable),
+    };
+    let total = invest.independent as u32
+        + invest.negotiable as u32
+        + invest.valuable as u32
+        + invest.estimable as u32
+        + invest.small as u32
+        + invest.testable as u32;
+
+    StoryLintReport {
+        user_story_id: user_story.id.clone(),
+        score: (total as f64 / 6.0).round() as u8,
+        invest,
+        findings,
+    }
+}
+
+/// Lint every story and summarise the findings across the backlog
+pub fn lint_backlog(stories: &[UserStoryWithCriteria]) -> BacklogLintReport {
+    let mut reports: Vec<StoryLintReport> = stories.iter().map(lint_story).collect();
+    reports.sort_by(|a, b| {
+        a.score
+            .cmp(&b.score)
+            .then_with(|| a.user_story_id.cmp(&b.user_story_id))
+    });
+
+    let mut rule_counts = BTreeMap::new();
+    for finding in reports.iter().flat_map(|report| &report.findings) {
+        *rule_counts.entry(finding.rule).or_insert(0) += 1;
+    }
+
+    let average_score = if reports.is_empty() {
+        0.0
+    } else {
+        reports
+            .iter()
+            .map(|report| report.score as f64)
+            .sum::<f64>()
+            / reports.len() as f64
+    };
+
+    BacklogLintReport {
+        story_count: reports.len(),
+        average_score,
+        rule_counts,
+        stories: reports,
+    }
+}
+
+fn check_narrative(description: &str, findings: &mut Vec<LintFinding>) {
+    let text = normalize(description);
+
+    if !ROLE_MARKERS
+        .iter()
+        .any(|marker| text.starts_with(&format!(" {} ", marker)))
+    {
+        findings.push(finding(
+            LintRule::MissingRole,
+            LintSeverity::Warning,
+            InvestPrinciple::Valuable,
+            "description",
+            "Start the description with who the story is for: \"As a <role>, ...\"",
+        ));
+    }
+
+    let goal = find_marker(&text, GOAL_MARKERS);
+    let benefit = find_marker(&text, BENEFIT_MARKERS);
+
+    match goal {
+        Some((_, goal_end)) => {
+            let clause_end = benefit.map_or(text.len(), |(start, _)| start.max(goal_end) + 1);
+            let clause = &text[goal_end..clause_end];
+            if clause.contains(" and ") {
+                findings.push(finding(
+                    LintRule::CompoundGoal,
+                    LintSeverity::Warning,
+                    InvestPrinciple::Small,
+                    "description",
+                    "The goal combines several things joined by \"and\"; consider splitting the story",
+                ));
+            }
+        }
+        None => findings.push(finding(
+            LintRule::MissingGoal,
+            LintSeverity::Warning,
+            InvestPrinciple::Estimable,
+            "description",
+            "State what the role wants: \"..., I want <goal> ...\"",
+        )),
+    }
+
+    if benefit.is_none() {
+        findings.push(finding(
+            LintRule::MissingBenefit,
+            LintSeverity::Warning,
+            InvestPrinciple::Valuable,
+            "description",
+            "State why it matters: \"... so that <benefit>\"",
+        ));
+    }
+}
+
+fn check_size(description: &str, findings: &mut Vec<LintFinding>) {
+    let words = description.split_whitespace().count();
+
+    if words < MIN_DESCRIPTION_WORDS {
+        findings.push(finding(
+            LintRule::TooShort,
+            LintSeverity::Warning,
+            InvestPrinciple::Estimable,
+            "description",
+            format!(
+                "The description has only {} words, too few to estimate the story",
+                words
+            ),
+        ));
+    } else if words > MAX_DESCRIPTION_WORDS {
+        findings.push(finding(
+            LintRule::TooLong,
+            LintSeverity::Warning,
+            InvestPrinciple::Small,
+            "description",
+            format!(
+                "The description has {} words; a story this large should be split",
+                words
+            ),
+        ));
+    }
+}
+
+fn check_vague_words(
+    field: &str,
+    text: &str,
+    principle: InvestPrinciple,
+    findings: &mut Vec<LintFinding>,
+) {
+    let normalized = normalize(text);
+
+    for word in VAGUE_WORDS {
+        if normalized.contains(&format!(" {} ", word)) {
+            findings.push(finding(
+                LintRule::VagueWording,
+                LintSeverity::Warning,
+                principle,
+                field,
+                format!(
+                    "\"{}\" cannot be verified; state a measurable expectation instead",
+                    word
+                ),
+            ));
+        }
+    }
+}
+
+fn check_implementation_details(field: &str, text: &str, findings: &mut Vec<LintFinding>) {
+    let normalized = normalize(text);
+
+    let words: Vec<&str> = IMPLEMENTATION_WORDS
+        .iter()
+        .copied()
+        .filter(|word| normalized.contains(&format!(" {} ", word)))
+        .collect();
+    if !words.is_empty() {
+        findings.push(finding(
+            LintRule::ImplementationDetail,
+            LintSeverity::Info,
+            InvestPrinciple::Negotiable,
+            field,
+            format!(
+                "Mentions {}; describe the need and leave the solution open",
+                words.join(", ")
+            ),
+        ));
+    }
+}
+
+fn check_dependencies(own_id: &str, field: &str, text: &str, findings: &mut Vec<LintFinding>) {
+    let mut referenced: Vec<String> = story_ids(text)
+        .into_iter()
+        .filter(|id| !id.eq_ignore_ascii_case(own_id))
+        .collect();
+    referenced.dedup();
+
+    let normalized = normalize(text);
+    let has_phrase = DEPENDENCY_PHRASES
+        .iter()
+        .any(|phrase| normalized.contains(&format!(" {} ", phrase)));
+
+    if !referenced.is_empty() {
+        findings.push(finding(
+            LintRule::Dependency,
+            LintSeverity::Warning,
+            InvestPrinciple::Independent,
+            field,
+            format!(
+                "Refers to {}; a story should be deliverable on its own",
+                referenced.join(", ")
+            ),
+        ));
+    } else if has_phrase {
+        findings.push(finding(
+            LintRule::Dependency,
+            LintSeverity::Warning,
+            InvestPrinciple::Independent,
+            field,
+            "Depends on other work; a story should be deliverable on its own",
+        ));
+    }
+}
+
+fn check_criteria(criteria: &[AcceptanceCriteria], findings: &mut Vec<LintFinding>) {
+    if criteria.is_empty() {
+        findings.push(finding(
+            LintRule::NoAcceptanceCriteria,
+            LintSeverity::Error,
+            InvestPrinciple::Testable,
+            "acceptance_criteria",
+            "The story has no acceptance criteria, so nobody can tell when it is done",
+        ));
+    } else if criteria.len() > RECOMMENDED_MAX_CRITERIA {
+        findings.push(finding(
+            LintRule::TooManyAcceptanceCriteria,
+            LintSeverity::Warning,
+            InvestPrinciple::Small,
+            "acceptance_criteria",
+            format!(
+                "The story has {} acceptance criteria (more than {}); consider splitting it",
+                criteria.len(),
+                RECOMMENDED_MAX_CRITERIA
+            ),
+        ));
+    }
+}
+
+fn principle_score(findings: &[LintFinding], principle: InvestPrinciple) -> u8 {
+    let penalty: u32 = findings
+        .iter()
+        .filter(|finding| finding.principle == principle)
+        .map(|finding| match finding.severity {
+            LintSeverity::Info => 10,
+            LintSeverity::Warning => 25,
+            LintSeverity::Error => 50,
+        })
+        .sum();
+
+    100u32.saturating_sub(penalty) as u8
+}
+
+fn finding(
+    rule: LintRule,
+    severity: LintSeverity,
+    principle: InvestPrinciple,
+    field: &str,
+    message: impl Into<String>,
+) -> LintFinding {
+    LintFinding {
+        rule,
+        severity,
+        principle,
+        field: field.to_string(),
+        message: message.into(),
+    }
+}
+
+/// Byte range of the first marker found in normalized text
+fn find_marker(text: &str, markers: &[&str]) -> Option<(usize, usize)> {
+    markers
+        .iter()
+        .filter_map(|marker| {
+            let pattern = format!(" {} ", marker);
+            text.find(&pattern)
+                .map(|start| (start, start + pattern.len() - 1))
+        })
+        .min()
+}
+
+/// Story ids such as `US-042` mentioned in the text, in order of appearance
+fn story_ids(text: &str) -> Vec<String> {
+    let upper = text.to_uppercase();
+    let mut ids = Vec::new();
+
+    for (start, _) in upper.match_indices("US-") {
+        let preceded_by_word = upper[..start]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric());
+        let suffix: String = upper[start + 3..]
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '-')
+            .collect();
+        let suffix = suffix.trim_end_matches('-');
+        if !preceded_by_word && !suffix.is_empty() {
+            ids.push(format!("US-{}", suffix));
+        }
+    }
+
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::UserStory;
+    use chrono::NaiveDateTime;
+
+    fn story(description: &str, criteria: &[&str]) -> UserStoryWithCriteria {
+        let timestamp = NaiveDateTime::default();
+        UserStoryWithCriteria {
+            user_story: UserStory {
+                id: "US-010".to_string(),
+                title: "Order history".to_string(),
+                description: description.to_string(),
+                persona: "Shopper".to_string(),
+                created_at: timestamp,
+                updated_at: timestamp,
+                version: 1,
+                assignee: None,
+                reviewer: None,
+            },
+            acceptance_criteria: criteria
+                .iter()
+                .enumerate()
+                .map(|(i, description)| AcceptanceCriteria {
+                    id: format!("AC-010-{}", i + 1),
+                    user_story_id: "US-010".to_string(),
+                    description: description.to_string(),
+                    created_at: timestamp,
+                    updated_at: timestamp,
+                    version: 1,
+                })
+                .collect(),
+        }
+    }
+
+    fn rules(report: &StoryLintReport) -> Vec<LintRule> {
+        report.findings.iter().map(|finding| finding.rule).collect()
+    }
+
+    #[test]
+    fn test_well_formed_story_scores_full_marks() {
+        let report = lint_story(&story(
+            "As a shopper, I want to see my past orders so that I can reorder items I liked",
+            &["Given I have placed orders, When I open my account, Then I see them newest first"],
+        ));
+
+        assert!(report.findings.is_empty(), "{:?}", report.findings);
+        assert_eq!(report.score, 100);
+    }
+
+    #[test]
+    fn test_narrative_template() {
+        let report = lint_story(&story(
+            "Show the past orders of the current shopper on the account page",
+            &["Given orders exist, When I open my account, Then I see them"],
+        ));
+
+        assert_eq!(
+            rules(&report),
+            vec![
+                LintRule::MissingRole,
+                LintRule::MissingGoal,
+                LintRule::MissingBenefit
+            ]
+        );
+        assert_eq!(report.invest.valuable, 50);
+        assert_eq!(report.invest.estimable, 75);
+
+        let report = lint_story(&story(
+            "As a shopper, I want to filter and export my orders",
+            &["Given orders exist, When I filter them, Then only matches are shown"],
+        ));
+        assert_eq!(
+            rules(&report),
+            vec![LintRule::CompoundGoal, LintRule::MissingBenefit]
+        );
+    }
+
+    #[test]
+    fn test_vague_words_in_story_and_criteria() {
+        let report = lint_story(&story(
+            "As a shopper, I want a fast and user-friendly order history so that I can reorder",
+            &["The page loads quickly, shows totals, etc."],
+        ));
+
+        let vague: Vec<(&str, &str)> = report
+            .findings
+            .iter()
+            .filter(|finding| finding.rule == LintRule::VagueWording)
+            .map(|finding| (finding.field.as_str(), finding.message.as_str()))
+            .collect();
+        assert_eq!(vague.len(), 4);
+        assert!(vague[0].1.contains("\"fast\""));
+        assert!(vague[1].1.contains("\"user friendly\""));
+        assert_eq!(vague[2].0, "AC-010-1");
+        assert!(vague[3].1.contains("\"etc\""));
+        assert!(rules(&report).contains(&LintRule::CompoundGoal));
+    }
+
+    #[test]
+    fn test_criteria_count() {
+        let report = lint_story(&story(
+            "As a shopper, I want to see my past orders so that I can reorder",
+            &[],
+        ));
+        assert_eq!(rules(&report), vec![LintRule::NoAcceptanceCriteria]);
+        assert_eq!(report.invest.testable, 50);
+
+        let criteria = vec!["Given orders, When I look, Then I see them"; 9];
+        let report = lint_story(&story(
+            "As a shopper, I want to see my past orders so that I can reorder",
+            &criteria,
+        ));
+        assert_eq!(rules(&report), vec![LintRule::TooManyAcceptanceCriteria]);
+    }
+
+    #[test]
+    fn test_dependencies_and_implementation_details() {
+        let report = lint_story(&story(
+            "As a shopper, I want the orders API to return JSON so that the page from US-009 can show it",
+            &["Given US-010 is done, When I click the button, Then orders are listed"],
+        ));
+
+        let findings: Vec<(LintRule, &str)> = report
+            .findings
+            .iter()
+            .map(|finding| (finding.rule, finding.field.as_str()))
+            .collect();
+        assert_eq!(
+            findings,
+            vec![
+                (LintRule::ImplementationDetail, "description"),
+                (LintRule::Dependency, "description"),
+            ]
+        );
+        assert!(report.findings[1].message.contains("US-009"));
+    }
+
+    #[test]
+    fn test_lint_backlog() {
+        let good = story(
+            "As a shopper, I want to see my past orders so that I can reorder",
+            &["Given orders, When I look, Then I see them"],
+        );
+        let mut bad = story("Order history", &[]);
+        bad.user_story.id = "US-011".to_string();
+
+        let report = lint_backlog(&[good, bad]);
+        assert_eq!(report.story_count, 2);
+        assert_eq!(report.stories[0].user_story_id, "US-011");
+        assert_eq!(report.stories[1].score, 100);
+        assert_eq!(report.rule_counts[&LintRule::NoAcceptanceCriteria], 1);
+        assert_eq!(report.rule_counts.get(&LintRule::VagueWording), None);
+        assert!(report.average_score < 100.0);
+    }
+}
@@ -0,0 +1,47 @@
+//! Opt-in quality analysis of user stories. Nothing here is enforced on create or
+//! update; the checks produce findings that help a team or an agent improve a story.
+
+pub mod lint;
+pub mod similarity;
+pub mod testability;
+
+pub use lint::{lint_backlog, lint_story};
+pub use similarity::{find_similar, SimilarityQuery, DEFAULT_SIMILARITY_THRESHOLD};
+pub use testability::{analyze_criteria, analyze_criterion, analyze_criterion_text};
+
+/// Words and phrases that sound like requirements but cannot be verified
+pub(crate) const VAGUE_WORDS: &[&str] = &[
+    "fast",
+    "quick",
+    "quickly",
+    "easy",
+    "easily",
+    "simple",
+    "simply",
+    "intuitive",
+    "user friendly",
+    "etc",
+    "and so on",
+    "flexible",
+    "efficient",
+    "robust",
+    "seamless",
+    "seamlessly",
+    "various",
+    "appropriate",
+    "as needed",
+    "adequate",
+    "optimal",
+    "better",
+];
+
+/// Lowercase words separated by single spaces, with a space at both ends so that
+/// `contains(" word ")` only matches whole words
+pub(crate) fn normalize(text: &str) -> String {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    format!(" {} ", words.join(" "))
+}
@@ -0,0 +1,285 @@
+//! Local near-duplicate detection for user stories.
+//!
+//! Each story becomes a bag of terms (content words with a light plural stem plus
+//! two-word shingles) taken from its title, description and acceptance criteria. Terms are
+//! weighted with TF-IDF over the whole backlog and stories are compared by cosine
+//! similarity, so shared rare words count for more than words every story uses.
+
+use crate::models::{SimilarStory, UserStoryWithCriteria};
+use crate::quality::normalize;
+use std::collections::{HashMap, HashSet};
+
+/// Similarity from which two stories are reported as likely duplicates
+pub const DEFAULT_SIMILARITY_THRESHOLD: f64 = 0.4;
+
+/// Words that carry no meaning on their own, including the narrative template
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "the", "as", "i", "want", "so", "that", "to", "of", "in", "on", "for", "and", "or",
+    "is", "are", "be", "can", "my", "me", "with", "it", "its", "this", "when", "then", "given",
+    "should", "will", "would", "like", "need", "from", "by", "at", "into", "am", "have", "has",
+    "their", "them", "they", "we", "our", "you", "your",
+];
+
+/// What to compare the backlog against
+#[derive(Debug, Clone, Copy)]
+pub enum SimilarityQuery<'a> {
+    /// A story in the corpus, which is left out of the results
+    Story(&'a str),
+    /// Free text such as a draft story
+    Text(&'a str),
+}
+
+/// Stories of the corpus at least `threshold` similar to the query, most similar first
+pub fn find_similar(
+    corpus: &[UserStoryWithCriteria],
+    query: SimilarityQuery<'_>,
+    threshold: f64,
+) -> Vec<SimilarStory> {
+    let mut documents: Vec<HashMap<String, usize>> = corpus
+        .iter()
+        .map(|story| {
+            let criteria = story
+                .acceptance_criteria
+                .iter()
+                .map(|criteria| criteria.description.as_str());
+            term_counts(&story_text(
+                &story.user_story.title,
+                &story.user_story.description,
+                criteria,
+            ))
+        })
+        .collect();
+
+    let (query_index, exclude) = match query {
+        SimilarityQuery::Story(id) => match corpus.iter().position(|s| s.user_story.id == id) {
+            Some(index) => (index, Some(id)),
+            None => return Vec::new(),
+        },
+        SimilarityQuery::Text(text) => {
+            documents.push(term_counts(text));
+            (documents.len() - 1, None)
+        }
+    };
+
+    let idf = inverse_document_frequency(&documents);
+    let vectors: Vec<HashMap<&str, f64>> =
+        documents.iter().map(|counts| weigh(counts, &idf)).collect();
+    let query_vector = &vectors[query_index];
+
+    let mut similar: Vec<SimilarStory> = corpus
+        .iter()
+        .zip(&vectors)
+        .filter(|(story, _)| Some(story.user_story.id.as_str()) != exclude)
+        .map(|(story, vector)| (story, cosine(query_vector, vector)))
+        .filter(|(_, score)| *score >= threshold)
+        .map(|(story, score)| SimilarStory {
+            user_story_id: story.user_story.id.clone(),
+            title: story.user_story.title.clone(),
+            score: (score * 1000.0).round() / 1000.0,
+        })
+        .collect();
+    similar.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.user_story_id.cmp(&b.user_story_id))
+    });
+
+    similar
+}
+
+/// Text compared for a story; the title is repeated because it summarises the story
+pub(crate) fn story_text<'a>(
+    title: &str,
+    description: &str,
+    criteria: impl Iterator<Item = &'a str>,
+) -> String {
+    let mut text = format!("{}. {}. {}.", title, title, description);
+    for criterion in criteria {
+        text.push(' ');
+        text.push_str(criterion);
+        text.push('.');
+    }
+    text
+}
+
+fn term_counts(text: &str) -> HashMap<String, usize> {
+    let words: Vec<String> = normalize(text)
+        .split_whitespace()
+        .filter(|word| !STOP_WORDS.contains(word))
+        .map(stem)
+        .collect();
+
+    let mut counts = HashMap::new();
+    for word in &words {
+        *counts.entry(word.clone()).or_insert(0) += 1;
+    }
+    for pair in words.windows(2) {
+        *counts
+            .entry(format!("{} {}", pair[0], pair[1]))
+            .or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Strip a plural `s` so that "orders" and "order" match
+fn stem(word: &str) -> String {
+    match word.strip_suffix('s') {
+        Some(stem) if stem.len() >= 3 && !stem.ends_with('s') => stem.to_string(),
+        _ => word.to_string(),
+    }
+}
+
+fn inverse_document_frequency(documents: &[HashMap<String, usize>]) -> HashMap<&str, f64> {
+    let mut frequency: HashMap<&str, usize> = HashMap::new();
+    for document in documents {
+        let terms: HashSet<&str> = document.keys().map(String::as_str).collect();
+        for term in terms {
+            *frequency.entry(term).or_insert(0) += 1;
+        }
+    }
+
+    let total = documents.len() as f64;
+    frequency
+        .into_iter()
+        .map(|(term, count)| (term, ((1.0 + total) / (1.0 + count as f64)).ln() + 1.0))
+        .collect()
+}
+
+fn weigh<'a>(
+    counts: &'a HashMap<String, usize>,
+    idf: &HashMap<&str, f64>,
+) -> HashMap<&'a str, f64> {
+    counts
+        .iter()
+        .map(|(term, count)| {
+            let tf = 1.0 + (*count as f64).ln();
+            (term.as_str(), tf * idf[term.as_str()])
+        })
+        .collect()
+}
+
+fn cosine(a: &HashMap<&str, f64>, b: &HashMap<&str, f64>) -> f64 {
+    let dot: f64 = a
+        .iter()
+        .filter_map(|(term, weight)| b.get(term).map(|other| weight * other))
+        .sum();
+    let norm = |v: &HashMap<&str, f64>| v.values().map(|w| w * w).sum::<f64>().sqrt();
+    let norms = norm(a) * norm(b);
+
+    if dot == 0.0 || norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AcceptanceCriteria, UserStory};
+    use chrono::NaiveDateTime;
+
+    fn story(id: &str, title: &str, description: &str, criteria: &[&str]) -> UserStoryWithCriteria {
+        let timestamp = NaiveDateTime::default();
+        UserStoryWithCriteria {
+            user_story: UserStory {
+                id: id.to_string(),
+                title: title.to_string(),
+                description: description.to_string(),
+                persona: "Shopper".to_string(),
+                created_at: timestamp,
+                updated_at: timestamp,
+                version: 1,
+                assignee: None,
+                reviewer: None,
+            },
+            acceptance_criteria: criteria
+                .iter()
+                .enumerate()
+                .map(|(i, description)| AcceptanceCriteria {
+                    id: format!("AC-{}-{}", id, i),
+                    user_story_id: id.to_string(),
+                    description: description.to_string(),
+                    created_at: timestamp,
+                    updated_at: timestamp,
+                    version: 1,
+                })
+                .collect(),
+        }
+    }
+
+    fn corpus() -> Vec<UserStoryWithCriteria> {
+        vec![
+            story(
+                "US-001",
+                "Order history",
+                "As a shopper, I want to see my past orders so that I can reorder items",
+                &["Given I have orders, When I open my account, Then I see my past orders"],
+            ),
+            story(
+                "US-002",
+                "Past orders list",
+                "As a shopper, I want a list of my previous orders so that I can buy items again",
+                &["Given I placed an order, When I open my account, Then the order is listed"],
+            ),
+            story(
+                "US-003",
+                "Password reset",
+                "As a user, I want to reset my password so that I can regain access",
+                &["Given I forgot my password, When I request a reset, Then I receive an email"],
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_similar_stories_rank_first() {
+        let similar = find_similar(&corpus(), SimilarityQuery::Story("US-001"), 0.0);
+
+        let ids: Vec<&str> = similar.iter().map(|s| s.user_story_id.as_str()).collect();
+        assert_eq!(ids, vec!["US-002", "US-003"]);
+        assert!(similar[0].score > similar[1].score);
+    }
+
+    #[test]
+    fn test_threshold() {
+        let similar = find_similar(
+            &corpus(),
+            SimilarityQuery::Story("US-001"),
+            DEFAULT_SIMILARITY_THRESHOLD,
+        );
+        assert!(similar.is_empty() || similar.iter().all(|s| s.user_story_id == "US-002"));
+
+        assert!(find_similar(&corpus(), SimilarityQuery::Story("US-999"), 0.0).is_empty());
+    }
+
+    #[test]
+    fn test_text_query() {
+        let similar = find_similar(
+            &corpus(),
+            SimilarityQuery::Text("Reset password: as a user I want to reset a forgotten password"),
+            DEFAULT_SIMILARITY_THRESHOLD,
+        );
+
+        assert_eq!(similar.len(), 1);
+        assert_eq!(similar[0].user_story_id, "US-003");
+        assert_eq!(similar[0].title, "Password reset");
+    }
+
+    #[test]
+    fn test_identical_text_scores_one() {
+        let corpus = corpus();
+        let original = &corpus[2];
+        let text = story_text(
+            &original.user_story.title,
+            &original.user_story.description,
+            original
+                .acceptance_criteria
+                .iter()
+                .map(|c| c.description.as_str()),
+        );
+
+        let similar = find_similar(&corpus, SimilarityQuery::Text(&text), 0.99);
+        assert_eq!(similar.len(), 1);
+        assert_eq!(similar[0].score, 1.0);
+    }
+}
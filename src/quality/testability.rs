@@ -0,0 +1,449 @@
+//! Heuristic testability checks for acceptance criteria.
+//!
+//! A testable criterion names a context, an action and an outcome a tester can observe,
+//! ideally as "Given ..., When ..., Then ...". `Given`, `When` and `Then` only start a
+//! clause at the beginning of the text, after punctuation or when capitalized, so
+//! "notify me when it ships" is not mistaken for a step.
+
+use crate::models::{
+    AcceptanceCriteria, CriterionTestabilityReport, LintSeverity, StoryTestabilityReport,
+    TestabilityFinding, TestabilityRule,
+};
+use crate::quality::{normalize, VAGUE_WORDS};
+
+/// Quantities that leave the tester to guess the expected number
+const AMBIGUOUS_QUANTIFIERS: &[&str] = &[
+    "some",
+    "many",
+    "few",
+    "several",
+    "lots",
+    "a lot",
+    "numerous",
+    "various",
+    "often",
+    "usually",
+    "sometimes",
+    "rarely",
+    "frequently",
+    "occasionally",
+    "soon",
+    "as soon as possible",
+    "reasonable",
+    "reasonably",
+    "acceptable",
+    "sufficient",
+    "enough",
+    "almost",
+];
+
+/// Words describing something a tester can see or check
+const OBSERVABLE_WORDS: &[&str] = &[
+    "see",
+    "sees",
+    "seen",
+    "show",
+    "shows",
+    "shown",
+    "display",
+    "displays",
+    "displayed",
+    "appear",
+    "appears",
+    "visible",
+    "hidden",
+    "receive",
+    "receives",
+    "received",
+    "redirect",
+    "redirected",
+    "return",
+    "returns",
+    "returned",
+    "saved",
+    "stored",
+    "persisted",
+    "sent",
+    "created",
+    "deleted",
+    "removed",
+    "updated",
+    "changed",
+    "contain",
+    "contains",
+    "listed",
+    "lists",
+    "message",
+    "error",
+    "errors",
+    "status",
+    "email",
+    "notification",
+    "notified",
+    "prompted",
+    "logged",
+    "enabled",
+    "disabled",
+    "rejected",
+    "accepted",
+    "blocked",
+    "locked",
+    "downloaded",
+    "exported",
+    "imported",
+    "charged",
+    "count",
+    "total",
+    "equal",
+    "equals",
+    "matches",
+    "highlighted",
+    "selected",
+    "sorted",
+];
+
+/// Phrases that claim success without saying how to recognise it
+const UNOBSERVABLE_PHRASES: &[&str] = &[
+    "works",
+    "work",
+    "working",
+    "properly",
+    "correctly",
+    "as expected",
+    "is handled",
+    "are handled",
+    "is supported",
+    "are supported",
+    "user friendly",
+    "good",
+    "nice",
+    "fine",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Given,
+    When,
+    Then,
+}
+
+struct Clause {
+    step: Option<Step>,
+    words: Vec<String>,
+}
+
+/// Analyze the description of a criterion that may not be stored yet
+pub fn analyze_criterion_text(description: &str) -> Vec<TestabilityFinding> {
+    let clauses = clauses(description);
+    let count = |step: Step| clauses.iter().filter(|c| c.step == Some(step)).count();
+    let (givens, whens, thens) = (count(Step::Given), count(Step::When), count(Step::Then));
+    let mut findings = Vec::new();
+
+    if givens + whens + thens == 0 {
+        findings.push(finding(
+            TestabilityRule::MissingStructure,
+            LintSeverity::Warning,
+            "The criterion is not written as Given/When/Then",
+            "Rewrite as \"Given <context>, When <action>, Then <observable outcome>\"",
+        ));
+    } else {
+        let missing: Vec<&str> = [("When", whens), ("Then", thens)]
+            .iter()
+            .filter(|(_, count)| *count == 0)
+            .map(|(keyword, _)| *keyword)
+            .collect();
+        if !missing.is_empty() {
+            findings.push(finding(
+                TestabilityRule::MissingStructure,
+                LintSeverity::Info,
+                format!("The criterion has no {} clause", missing.join(" or ")),
+                "Complete it as \"Given <context>, When <action>, Then <observable outcome>\"",
+            ));
+        }
+    }
+
+    check_outcome(&clauses, thens > 0, &mut findings);
+
+    let normalized = normalize(description);
+    for quantifier in AMBIGUOUS_QUANTIFIERS {
+        if normalized.contains(&format!(" {} ", quantifier)) {
+            findings.push(finding(
+                TestabilityRule::AmbiguousQuantifier,
+                LintSeverity::Warning,
+                format!("\"{}\" does not say how many or how often", quantifier),
+                format!(
+                    "Replace \"{}\" with an exact number, limit or range",
+                    quantifier
+                ),
+            ));
+        }
+    }
+    for word in VAGUE_WORDS {
+        if !AMBIGUOUS_QUANTIFIERS.contains(word) && normalized.contains(&format!(" {} ", word)) {
+            findings.push(finding(
+                TestabilityRule::VagueWording,
+                LintSeverity::Warning,
+                format!("\"{}\" cannot be verified", word),
+                format!(
+                    "Replace \"{}\" with a measurable expectation such as a time limit or a count",
+                    word
+                ),
+            ));
+        }
+    }
+
+    let obligations = ["should", "must", "shall"]
+        .iter()
+        .map(|word| normalized.matches(&format!(" {} ", word)).count())
+        .sum::<usize>();
+    if whens > 1 || thens > 1 || (whens + thens == 0 && obligations > 1) {
+        findings.push(finding(
+            TestabilityRule::CompoundCriterion,
+            LintSeverity::Warning,
+            "The criterion describes more than one scenario",
+            "Split it into one criterion per When/Then pair",
+        ));
+    }
+
+    findings
+}
+
+/// Analyze a stored acceptance criterion
+pub fn analyze_criterion(criteria: &AcceptanceCriteria) -> CriterionTestabilityReport {
+    let findings = analyze_criterion_text(&criteria.description);
+
+    CriterionTestabilityReport {
+        acceptance_criteria_id: Some(criteria.id.clone()),
+        user_story_id: Some(criteria.user_story_id.clone()),
+        description: criteria.description.clone(),
+        testable: is_testable(&findings),
+        findings,
+    }
+}
+
+/// Analyze every acceptance criterion of a user story
+pub fn analyze_criteria(
+    user_story_id: &str,
+    criteria: &[AcceptanceCriteria],
+) -> StoryTestabilityReport {
+    let reports: Vec<CriterionTestabilityReport> = criteria.iter().map(analyze_criterion).collect();
+
+    StoryTestabilityReport {
+        user_story_id: user_story_id.to_string(),
+        criteria_count: reports.len(),
+        testable_count: reports.iter().filter(|report| report.testable).count(),
+        criteria: reports,
+    }
+}
+
+/// A criterion is testable when nothing worse than an info finding was raised
+pub(crate) fn is_testable(findings: &[TestabilityFinding]) -> bool {
+    findings
+        .iter()
+        .all(|finding| finding.severity == LintSeverity::Info)
+}
+
+fn check_outcome(clauses: &[Clause], has_then: bool, findings: &mut Vec<TestabilityFinding>) {
+    // Without a Then clause the whole criterion has to carry the outcome
+    let outcome: Vec<&str> = clauses
+        .iter()
+        .filter(|clause| !has_then || clause.step == Some(Step::Then))
+        .flat_map(|clause| clause.words.iter().map(String::as_str))
+        .collect();
+
+    let observable = outcome
+        .iter()
+        .any(|word| OBSERVABLE_WORDS.contains(word) || word.chars().any(|c| c.is_ascii_digit()));
+    if observable {
+        return;
+    }
+
+    let text = format!(" {} ", outcome.join(" "));
+    let claims: Vec<&str> = UNOBSERVABLE_PHRASES
+        .iter()
+        .copied()
+        .filter(|phrase| text.contains(&format!(" {} ", phrase)))
+        .collect();
+    let message = if claims.is_empty() {
+        "The criterion has no outcome a tester can observe".to_string()
+    } else {
+        format!(
+            "\"{}\" is not something a tester can observe",
+            claims.join("\", \"")
+        )
+    };
+
+    findings.push(finding(
+        TestabilityRule::MissingOutcome,
+        LintSeverity::Error,
+        message,
+        "State what can be checked, e.g. \"Then I see ...\", \"Then an email is sent to ...\" or an exact number",
+    ));
+}
+
+/// Split a criterion into clauses at `Given`, `When` and `Then`
+fn clauses(text: &str) -> Vec<Clause> {
+    let mut clauses = vec![Clause {
+        step: None,
+        words: Vec::new(),
+    }];
+
+    for (word, starts_clause) in words(text) {
+        let step = match word.as_str() {
+            "given" => Some(Step::Given),
+            "when" => Some(Step::When),
+            "then" => Some(Step::Then),
+            _ => None,
+        };
+
+        match step {
+            Some(step) if starts_clause => clauses.push(Clause {
+                step: Some(step),
+                words: Vec::new(),
+            }),
+            _ => clauses.last_mut().unwrap().words.push(word),
+        }
+    }
+
+    clauses
+}
+
+/// Lowercase words, each flagged when it may start a clause: at the start of the text,
+/// after punctuation or when capitalized
+fn words(text: &str) -> Vec<(String, bool)> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut capitalized = false;
+    let mut after_boundary = true;
+
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            if current.is_empty() {
+                capitalized = c.is_uppercase();
+            }
+            current.extend(c.to_lowercase());
+            continue;
+        }
+
+        if !current.is_empty() {
+            words.push((std::mem::take(&mut current), after_boundary || capitalized));
+            after_boundary = false;
+        }
+        if matches!(c, ',' | '.' | ';' | ':' | '\n') {
+            after_boundary = true;
+        }
+    }
+    if !current.is_empty() {
+        words.push((current, after_boundary || capitalized));
+    }
+
+    words
+}
+
+fn finding(
+    rule: TestabilityRule,
+    severity: LintSeverity,
+    message: impl Into<String>,
+    suggestion: impl Into<String>,
+) -> TestabilityFinding {
+    TestabilityFinding {
+        rule,
+        severity,
+        message: message.into(),
+        suggestion: suggestion.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(description: &str) -> Vec<TestabilityRule> {
+        analyze_criterion_text(description)
+            .iter()
+            .map(|finding| finding.rule)
+            .collect()
+    }
+
+    #[test]
+    fn test_testable_criterion() {
+        let findings = analyze_criterion_text(
+            "Given I am on the login page, When I enter invalid credentials, Then I see an error message",
+        );
+        assert!(findings.is_empty(), "{:?}", findings);
+    }
+
+    #[test]
+    fn test_untestable_criterion() {
+        let findings = analyze_criterion_text("The page should be user friendly");
+        let rules: Vec<TestabilityRule> = findings.iter().map(|f| f.rule).collect();
+        assert_eq!(
+            rules,
+            vec![
+                TestabilityRule::MissingStructure,
+                TestabilityRule::MissingOutcome,
+                TestabilityRule::VagueWording,
+            ]
+        );
+        assert_eq!(findings[1].severity, LintSeverity::Error);
+        assert!(findings[1].message.contains("user friendly"));
+        assert!(!is_testable(&findings));
+    }
+
+    #[test]
+    fn test_outcome_must_be_observable() {
+        assert_eq!(
+            rules("Given a cart, When I check out, Then the payment works correctly"),
+            vec![TestabilityRule::MissingOutcome]
+        );
+        // A number is observable even without an observable verb
+        assert!(rules("Given a cart, When I check out, Then the fee is 5 EUR").is_empty());
+    }
+
+    #[test]
+    fn test_ambiguous_quantifiers() {
+        let findings = analyze_criterion_text(
+            "Given several orders, When I open my account, Then I see some of them",
+        );
+        let suggestions: Vec<&str> = findings.iter().map(|f| f.suggestion.as_str()).collect();
+        assert_eq!(
+            suggestions,
+            vec![
+                "Replace \"some\" with an exact number, limit or range",
+                "Replace \"several\" with an exact number, limit or range",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compound_criteria() {
+        assert_eq!(
+            rules("Given a cart, When I pay, Then I see a receipt, When I refund, Then I see a credit note"),
+            vec![TestabilityRule::CompoundCriterion]
+        );
+        assert_eq!(
+            rules("The order must be saved and the customer must receive an email"),
+            vec![
+                TestabilityRule::MissingStructure,
+                TestabilityRule::CompoundCriterion
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keywords_only_start_clauses_at_boundaries() {
+        // "when" inside the outcome is an ordinary word, not a second When clause
+        let findings = analyze_criterion_text(
+            "Given an order, When it ships, Then I receive an email when the parcel leaves",
+        );
+        assert!(findings.is_empty(), "{:?}", findings);
+
+        assert_eq!(
+            rules("Given an order, Then I see its status"),
+            vec![TestabilityRule::MissingStructure]
+        );
+        assert!(is_testable(&analyze_criterion_text(
+            "Given an order, Then I see its status"
+        )));
+    }
+}
@@ -1,8 +1,12 @@
-use crate::database::DbPool;
+use crate::database::{begin_write, DbPool};
 use crate::models::{
-    AcceptanceCriteria, CreateAcceptanceCriteriaRequest, UpdateAcceptanceCriteriaRequest,
+    AcceptanceCriteria, CreateAcceptanceCriteriaRequest, PageCursor,
+    UpdateAcceptanceCriteriaRequest,
 };
+use crate::repositories::claim_repository::{blocking_claim, blocking_criteria_claim};
+use crate::repositories::{ClaimGuardedWrite, VersionedWrite};
 use chrono::Utc;
+use std::collections::HashMap;
 
 #[derive(Clone)]
 pub struct AcceptanceCriteriaRepository {
@@ -14,19 +18,26 @@ impl AcceptanceCriteriaRepository {
         Self { pool }
     }
 
-    /// Create a new acceptance criteria
+    /// Create a new acceptance criteria as `agent_id`, unless another agent holds a live claim
+    /// on their story
     pub async fn create(
         &self,
         request: CreateAcceptanceCriteriaRequest,
-    ) -> Result<AcceptanceCriteria, sqlx::Error> {
+        agent_id: Option<&str>,
+    ) -> Result<ClaimGuardedWrite<AcceptanceCriteria>, sqlx::Error> {
         let now = Utc::now().naive_utc();
+        let mut tx = begin_write(&self.pool).await?;
+
+        if let Some(claim) = blocking_claim(&mut tx, &request.user_story_id, agent_id).await? {
+            return Ok(ClaimGuardedWrite::Claimed(claim));
+        }
 
         let criteria = sqlx::query_as!(
             AcceptanceCriteria,
             r#"
             INSERT INTO acceptance_criteria (id, user_story_id, description, created_at, updated_at)
             VALUES ($1, $2, $3, $4, $5)
-            RETURNING id, user_story_id, description, created_at, updated_at
+            RETURNING id, user_story_id, description, created_at, updated_at, version
             "#,
             request.id,
             request.user_story_id,
@@ -34,10 +45,11 @@ impl AcceptanceCriteriaRepository {
             now,
             now
         )
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await?;
 
-        Ok(criteria)
+        tx.commit().await?;
+        Ok(ClaimGuardedWrite::Applied(criteria))
     }
 
     /// Get acceptance criteria by ID
@@ -45,9 +57,9 @@ impl AcceptanceCriteriaRepository {
         let criteria = sqlx::query_as!(
             AcceptanceCriteria,
             r#"
-            SELECT id, user_story_id, description, created_at, updated_at
+            SELECT id, user_story_id, description, created_at, updated_at, version
             FROM acceptance_criteria
-            WHERE id = $1
+            WHERE id = $1 AND deleted_at IS NULL
             "#,
             id
         )
@@ -57,6 +69,25 @@ impl AcceptanceCriteriaRepository {
         Ok(criteria)
     }
 
+    /// Get the live acceptance criteria among the given ids with a single query
+    pub async fn get_by_ids(&self, ids: &[String]) -> Result<Vec<AcceptanceCriteria>, sqlx::Error> {
+        let ids = serde_json::to_string(ids).expect("string slices serialize");
+
+        let criteria = sqlx::query_as!(
+            AcceptanceCriteria,
+            r#"
+            SELECT id, user_story_id, description, created_at, updated_at, version
+            FROM acceptance_criteria
+            WHERE id IN (SELECT value FROM json_each($1)) AND deleted_at IS NULL
+            "#,
+            ids
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(criteria)
+    }
+
     /// Get all acceptance criteria for a user story
     pub async fn get_by_user_story_id(
         &self,
@@ -65,9 +96,9 @@ impl AcceptanceCriteriaRepository {
         let criteria = sqlx::query_as!(
             AcceptanceCriteria,
             r#"
-            SELECT id, user_story_id, description, created_at, updated_at
+            SELECT id, user_story_id, description, created_at, updated_at, version
             FROM acceptance_criteria
-            WHERE user_story_id = $1
+            WHERE user_story_id = $1 AND deleted_at IS NULL
             ORDER BY created_at ASC
             "#,
             user_story_id
@@ -78,13 +109,70 @@ impl AcceptanceCriteriaRepository {
         Ok(criteria)
     }
 
+    /// Get the acceptance criteria of several user stories in one query
+    pub async fn get_by_user_story_ids(
+        &self,
+        user_story_ids: &[String],
+    ) -> Result<Vec<AcceptanceCriteria>, sqlx::Error> {
+        let ids = serde_json::to_string(user_story_ids).expect("string slices serialize");
+
+        let criteria = sqlx::query_as!(
+            AcceptanceCriteria,
+            r#"
+            SELECT id, user_story_id, description, created_at, updated_at, version
+            FROM acceptance_criteria
+            WHERE user_story_id IN (SELECT value FROM json_each($1)) AND deleted_at IS NULL
+            ORDER BY created_at ASC
+            "#,
+            ids
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(criteria)
+    }
+
+    /// Get up to `limit` acceptance criteria after a cursor, of one user story or of all,
+    /// oldest first
+    pub async fn get_page(
+        &self,
+        user_story_id: Option<&str>,
+        after: Option<&PageCursor>,
+        limit: i64,
+    ) -> Result<Vec<AcceptanceCriteria>, sqlx::Error> {
+        let created_at = after.map(|cursor| cursor.created_at);
+        let id = after.map(|cursor| cursor.id.as_str());
+
+        let criteria = sqlx::query_as!(
+            AcceptanceCriteria,
+            r#"
+            SELECT id, user_story_id, description, created_at, updated_at, version
+            FROM acceptance_criteria
+            WHERE deleted_at IS NULL
+                AND ($1 IS NULL OR user_story_id = $1)
+                AND ($2 IS NULL OR created_at > $2 OR (created_at = $2 AND id > $3))
+            ORDER BY created_at ASC, id ASC
+            LIMIT $4
+            "#,
+            user_story_id,
+            created_at,
+            id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(criteria)
+    }
+
     /// Get all acceptance criteria
     pub async fn get_all(&self) -> Result<Vec<AcceptanceCriteria>, sqlx::Error> {
         let criteria = sqlx::query_as!(
             AcceptanceCriteria,
             r#"
-            SELECT id, user_story_id, description, created_at, updated_at
+            SELECT id, user_story_id, description, created_at, updated_at, version
             FROM acceptance_criteria
+            WHERE deleted_at IS NULL
             ORDER BY created_at DESC
             "#
         )
@@ -94,19 +182,20 @@ impl AcceptanceCriteriaRepository {
         Ok(criteria)
     }
 
-    /// Update acceptance criteria
+    /// Update acceptance criteria as `agent_id`, unless they have moved past the expected
+    /// version of the request or another agent holds a live claim on their story
     pub async fn update(
         &self,
         id: &str,
         request: UpdateAcceptanceCriteriaRequest,
-    ) -> Result<Option<AcceptanceCriteria>, sqlx::Error> {
-        // First, check if the acceptance criteria exists
-        let existing = self.get_by_id(id).await?;
-        if existing.is_none() {
-            return Ok(None);
-        }
-
+        agent_id: Option<&str>,
+    ) -> Result<VersionedWrite<AcceptanceCriteria>, sqlx::Error> {
         let now = Utc::now().naive_utc();
+        let mut tx = begin_write(&self.pool).await?;
+
+        if let Some(claim) = blocking_criteria_claim(&mut tx, id, agent_id).await? {
+            return Ok(VersionedWrite::Claimed(claim));
+        }
 
         let criteria = sqlx::query_as!(
             AcceptanceCriteria,
@@ -114,48 +203,91 @@ impl AcceptanceCriteriaRepository {
             UPDATE acceptance_criteria
             SET
                 description = COALESCE($2, description),
-                updated_at = $3
-            WHERE id = $1
-            RETURNING id, user_story_id, description, created_at, updated_at
+                updated_at = $3,
+                version = version + 1
+            WHERE id = $1 AND deleted_at IS NULL AND ($4 IS NULL OR version = $4)
+            RETURNING id, user_story_id, description, created_at, updated_at, version
             "#,
             id,
             request.description,
-            now
+            now,
+            request.expected_version
         )
-        .fetch_optional(&self.pool)
+        .fetch_optional(&mut *tx)
         .await?;
+        tx.commit().await?;
 
-        Ok(criteria)
+        match criteria {
+            Some(criteria) => Ok(VersionedWrite::Applied(criteria)),
+            None => Ok(VersionedWrite::rejected(self.get_by_id(id).await?)),
+        }
     }
 
-    /// Delete acceptance criteria
-    pub async fn delete(&self, id: &str) -> Result<bool, sqlx::Error> {
-        let result = sqlx::query!(
+    /// Move acceptance criteria to the trash as `agent_id`, unless they have moved past the
+    /// expected version or another agent holds a live claim on their story
+    pub async fn delete(
+        &self,
+        id: &str,
+        expected_version: Option<i64>,
+        agent_id: Option<&str>,
+    ) -> Result<VersionedWrite<AcceptanceCriteria>, sqlx::Error> {
+        let now = Utc::now().naive_utc();
+        let mut tx = begin_write(&self.pool).await?;
+
+        if let Some(claim) = blocking_criteria_claim(&mut tx, id, agent_id).await? {
+            return Ok(VersionedWrite::Claimed(claim));
+        }
+
+        let criteria = sqlx::query_as!(
+            AcceptanceCriteria,
             r#"
-            DELETE FROM acceptance_criteria
-            WHERE id = $1
+            UPDATE acceptance_criteria
+            SET deleted_at = $2, version = version + 1
+            WHERE id = $1 AND deleted_at IS NULL AND ($3 IS NULL OR version = $3)
+            RETURNING id, user_story_id, description, created_at, updated_at, version
             "#,
-            id
+            id,
+            now,
+            expected_version
         )
-        .execute(&self.pool)
+        .fetch_optional(&mut *tx)
         .await?;
+        tx.commit().await?;
 
-        Ok(result.rows_affected() > 0)
+        match criteria {
+            Some(criteria) => Ok(VersionedWrite::Applied(criteria)),
+            None => Ok(VersionedWrite::rejected(self.get_by_id(id).await?)),
+        }
     }
 
-    /// Delete all acceptance criteria for a user story
-    pub async fn delete_by_user_story_id(&self, user_story_id: &str) -> Result<u64, sqlx::Error> {
+    /// Move all acceptance criteria of a user story to the trash as `agent_id`, unless another
+    /// agent holds a live claim on the story
+    pub async fn delete_by_user_story_id(
+        &self,
+        user_story_id: &str,
+        agent_id: Option<&str>,
+    ) -> Result<ClaimGuardedWrite<u64>, sqlx::Error> {
+        let now = Utc::now().naive_utc();
+        let mut tx = begin_write(&self.pool).await?;
+
+        if let Some(claim) = blocking_claim(&mut tx, user_story_id, agent_id).await? {
+            return Ok(ClaimGuardedWrite::Claimed(claim));
+        }
+
         let result = sqlx::query!(
             r#"
-            DELETE FROM acceptance_criteria
-            WHERE user_story_id = $1
+            UPDATE acceptance_criteria
+            SET deleted_at = $2, version = version + 1
+            WHERE user_story_id = $1 AND deleted_at IS NULL
             "#,
-            user_story_id
+            user_story_id,
+            now
         )
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await?;
 
-        Ok(result.rows_affected())
+        tx.commit().await?;
+        Ok(ClaimGuardedWrite::Applied(result.rows_affected()))
     }
 
     /// Search acceptance criteria by description
@@ -165,9 +297,9 @@ impl AcceptanceCriteriaRepository {
         let criteria = sqlx::query_as!(
             AcceptanceCriteria,
             r#"
-            SELECT id, user_story_id, description, created_at, updated_at
+            SELECT id, user_story_id, description, created_at, updated_at, version
             FROM acceptance_criteria
-            WHERE description LIKE $1
+            WHERE description LIKE $1 AND deleted_at IS NULL
             ORDER BY created_at DESC
             "#,
             search_pattern
@@ -184,7 +316,7 @@ impl AcceptanceCriteriaRepository {
             r#"
             SELECT COUNT(*) as count
             FROM acceptance_criteria
-            WHERE user_story_id = $1
+            WHERE user_story_id = $1 AND deleted_at IS NULL
             "#,
             user_story_id
         )
@@ -194,12 +326,30 @@ impl AcceptanceCriteriaRepository {
         Ok(count.count)
     }
 
+    /// Count the acceptance criteria of every user story, including stories without any
+    pub async fn count_by_user_story(&self) -> Result<HashMap<String, i64>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT s.id, COUNT(c.id) as "count!: i64"
+            FROM user_stories s
+            LEFT JOIN acceptance_criteria c ON c.user_story_id = s.id AND c.deleted_at IS NULL
+            WHERE s.deleted_at IS NULL
+            GROUP BY s.id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| (row.id, row.count)).collect())
+    }
+
     /// Get total count of all acceptance criteria
     pub async fn count(&self) -> Result<i64, sqlx::Error> {
         let count = sqlx::query!(
             r#"
             SELECT COUNT(*) as count
             FROM acceptance_criteria
+            WHERE deleted_at IS NULL
             "#
         )
         .fetch_one(&self.pool)
@@ -208,14 +358,32 @@ impl AcceptanceCriteriaRepository {
         Ok(count.count)
     }
 
-    /// Create multiple acceptance criteria in a transaction
+    /// Whether acceptance criteria with this id exist, counting criteria in the trash
+    pub async fn exists(&self, id: &str) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM acceptance_criteria WHERE id = $1) as "exists!: bool""#,
+            id
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Create multiple acceptance criteria in a transaction as `agent_id`, unless another agent
+    /// holds a live claim on one of their stories
     pub async fn create_batch(
         &self,
         requests: Vec<CreateAcceptanceCriteriaRequest>,
-    ) -> Result<Vec<AcceptanceCriteria>, sqlx::Error> {
-        let mut tx = self.pool.begin().await?;
+        agent_id: Option<&str>,
+    ) -> Result<ClaimGuardedWrite<Vec<AcceptanceCriteria>>, sqlx::Error> {
+        let mut tx = begin_write(&self.pool).await?;
         let mut created_criteria = Vec::new();
 
+        for request in &requests {
+            if let Some(claim) = blocking_claim(&mut tx, &request.user_story_id, agent_id).await? {
+                return Ok(ClaimGuardedWrite::Claimed(claim));
+            }
+        }
+
         for request in requests {
             let now = Utc::now().naive_utc();
 
@@ -224,7 +392,7 @@ impl AcceptanceCriteriaRepository {
                 r#"
                 INSERT INTO acceptance_criteria (id, user_story_id, description, created_at, updated_at)
                 VALUES ($1, $2, $3, $4, $5)
-                RETURNING id, user_story_id, description, created_at, updated_at
+                RETURNING id, user_story_id, description, created_at, updated_at, version
                 "#,
                 request.id,
                 request.user_story_id,
@@ -239,7 +407,7 @@ impl AcceptanceCriteriaRepository {
         }
 
         tx.commit().await?;
-        Ok(created_criteria)
+        Ok(ClaimGuardedWrite::Applied(created_criteria))
     }
 }
 
@@ -251,19 +419,19 @@ mod tests {
 
     async fn create_test_user_story(user_story_repo: &UserStoryRepository) -> String {
         let request = CreateUserStoryRequest {
-            id: "US-TEST-001".to_string(),
+            id: Some("US-TEST-001".to_string()),
             title: "Test User Story".to_string(),
             description: "As a user, I want to test this functionality".to_string(),
             persona: "Test User".to_string(),
         };
 
         user_story_repo.create(request.clone()).await.unwrap();
-        request.id
+        request.id.unwrap()
     }
 
     fn create_test_criteria_request(user_story_id: String) -> CreateAcceptanceCriteriaRequest {
         CreateAcceptanceCriteriaRequest {
-            id: "AC-TEST-001".to_string(),
+            id: Some("AC-TEST-001".to_string()),
             user_story_id,
             description: "Given I am on the page, When I click the button, Then something happens"
                 .to_string(),
@@ -278,11 +446,13 @@ mod tests {
         let user_story_id = create_test_user_story(&user_story_repo).await;
         let request = create_test_criteria_request(user_story_id);
 
-        let result = criteria_repo.create(request.clone()).await;
+        let result = criteria_repo.create(request.clone(), None).await;
         assert!(result.is_ok());
 
-        let criteria = result.unwrap();
-        assert_eq!(criteria.id, request.id);
+        let ClaimGuardedWrite::Applied(criteria) = result.unwrap() else {
+            panic!("unclaimed create should be applied");
+        };
+        assert_eq!(Some(criteria.id), request.id);
         assert_eq!(criteria.user_story_id, request.user_story_id);
         assert_eq!(criteria.description, request.description);
     }
@@ -322,6 +492,28 @@ mod tests {
         assert_eq!(criteria_list.len(), 3); // AC-001, AC-002, AC-003
     }
 
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_get_criteria_by_user_story_ids(pool: sqlx::SqlitePool) {
+        let criteria_repo = AcceptanceCriteriaRepository::new(pool);
+
+        let ids = vec![
+            "US-001".to_string(),
+            "US-005".to_string(),
+            "US-999".to_string(),
+        ];
+        let criteria_list = criteria_repo.get_by_user_story_ids(&ids).await.unwrap();
+        assert_eq!(criteria_list.len(), 5); // AC-001..003 and AC-009..010
+        assert!(criteria_list.iter().all(
+            |criteria| criteria.user_story_id == "US-001" || criteria.user_story_id == "US-005"
+        ));
+
+        let none = criteria_repo.get_by_user_story_ids(&[]).await.unwrap();
+        assert!(none.is_empty());
+    }
+
     #[sqlx::test(fixtures(
         "../../fixtures/user_stories.sql",
         "../../fixtures/acceptance_criteria.sql"
@@ -336,6 +528,37 @@ mod tests {
         assert_eq!(criteria_list.len(), 10); // Should match fixture count
     }
 
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_get_page(pool: sqlx::SqlitePool) {
+        let criteria_repo = AcceptanceCriteriaRepository::new(pool);
+        let ids = |criteria: &[AcceptanceCriteria]| {
+            criteria
+                .iter()
+                .map(|criteria| criteria.id.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let first = criteria_repo.get_page(None, None, 4).await.unwrap();
+        assert_eq!(ids(&first), ["AC-001", "AC-002", "AC-003", "AC-004"]);
+
+        let cursor = PageCursor::new(first[3].created_at, first[3].id.clone());
+        let next = criteria_repo
+            .get_page(None, Some(&cursor), 100)
+            .await
+            .unwrap();
+        assert_eq!(next.len(), 6);
+        assert_eq!(next[0].id, "AC-005");
+
+        let of_story = criteria_repo
+            .get_page(Some("US-001"), None, 2)
+            .await
+            .unwrap();
+        assert_eq!(ids(&of_story), ["AC-001", "AC-002"]);
+    }
+
     #[sqlx::test(fixtures(
         "../../fixtures/user_stories.sql",
         "../../fixtures/acceptance_criteria.sql"
@@ -346,16 +569,17 @@ mod tests {
         // Update an existing acceptance criteria from fixtures
         let update_request = UpdateAcceptanceCriteriaRequest {
             description: Some("Given I am on the updated login page, When I enter valid credentials, Then I should be logged in successfully".to_string()),
+            expected_version: None,
         };
 
-        let result = criteria_repo.update("AC-001", update_request).await;
+        let result = criteria_repo.update("AC-001", update_request, None).await;
         assert!(result.is_ok());
 
-        let criteria = result.unwrap();
-        assert!(criteria.is_some());
-
-        let criteria = criteria.unwrap();
+        let VersionedWrite::Applied(criteria) = result.unwrap() else {
+            panic!("unguarded update should be applied");
+        };
         assert!(criteria.description.contains("updated login page"));
+        assert_eq!(criteria.version, 2);
     }
 
     #[sqlx::test(fixtures(
@@ -366,9 +590,9 @@ mod tests {
         let criteria_repo = AcceptanceCriteriaRepository::new(pool);
 
         // Delete an existing acceptance criteria from fixtures
-        let result = criteria_repo.delete("AC-001").await;
+        let result = criteria_repo.delete("AC-001", None, None).await;
         assert!(result.is_ok());
-        assert!(result.unwrap());
+        assert!(matches!(result.unwrap(), VersionedWrite::Applied(_)));
 
         // Verify it's deleted
         let get_result = criteria_repo.get_by_id("AC-001").await;
@@ -384,9 +608,10 @@ mod tests {
         let criteria_repo = AcceptanceCriteriaRepository::new(pool);
 
         // Delete all acceptance criteria for US-001
-        let result = criteria_repo.delete_by_user_story_id("US-001").await;
+        let result = criteria_repo.delete_by_user_story_id("US-001", None).await;
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), 3); // Should delete 3 criteria
+        // Should delete 3 criteria
+        assert!(matches!(result.unwrap(), ClaimGuardedWrite::Applied(3)));
 
         // Verify they're deleted
         let get_result = criteria_repo.get_by_user_story_id("US-001").await;
@@ -429,6 +654,23 @@ mod tests {
         assert_eq!(count, 3); // Should match fixture count for US-001
     }
 
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_count_by_user_story(pool: sqlx::SqlitePool) {
+        let criteria_repo = AcceptanceCriteriaRepository::new(pool);
+        criteria_repo.delete("AC-009", None, None).await.unwrap();
+        criteria_repo.delete("AC-010", None, None).await.unwrap();
+
+        let counts = criteria_repo.count_by_user_story().await.unwrap();
+        assert_eq!(counts.len(), 5);
+        assert_eq!(counts["US-001"], 3);
+        assert_eq!(counts["US-004"], 1);
+        // Stories whose criteria are all deleted are still counted
+        assert_eq!(counts["US-005"], 0);
+    }
+
     #[sqlx::test(fixtures(
         "../../fixtures/user_stories.sql",
         "../../fixtures/acceptance_criteria.sql"
@@ -452,21 +694,23 @@ mod tests {
 
         let requests = vec![
             CreateAcceptanceCriteriaRequest {
-                id: "AC-BATCH-001".to_string(),
+                id: Some("AC-BATCH-001".to_string()),
                 user_story_id: user_story_id.clone(),
                 description: "First batch criteria".to_string(),
             },
             CreateAcceptanceCriteriaRequest {
-                id: "AC-BATCH-002".to_string(),
+                id: Some("AC-BATCH-002".to_string()),
                 user_story_id: user_story_id.clone(),
                 description: "Second batch criteria".to_string(),
             },
         ];
 
-        let result = criteria_repo.create_batch(requests).await;
+        let result = criteria_repo.create_batch(requests, None).await;
         assert!(result.is_ok());
 
-        let created_criteria = result.unwrap();
+        let ClaimGuardedWrite::Applied(created_criteria) = result.unwrap() else {
+            panic!("unclaimed batch should be applied");
+        };
         assert_eq!(created_criteria.len(), 2);
 
         // Verify they were actually created
@@ -0,0 +1,333 @@
+use crate::database::{begin_write, DbPool};
+use crate::models::{ConflictPolicy, UserStoryWithCriteria};
+use crate::repositories::member_repository::resolve_member_id;
+use crate::repositories::persona_repository::resolve_persona_id;
+use chrono::Utc;
+use sqlx::SqliteConnection;
+
+/// Outcome of `BacklogRepository::import`
+#[derive(Debug, PartialEq, Eq)]
+pub enum ImportWrite {
+    /// The stories were written, apart from those skipped because an id was taken
+    Applied { skipped: Vec<String> },
+    /// An id was taken and the policy does not allow replacing it; nothing was written
+    Conflict { id: String },
+}
+
+#[derive(Clone)]
+pub struct BacklogRepository {
+    pool: DbPool,
+}
+
+impl BacklogRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Write user stories and their acceptance criteria in a single transaction,
+    /// keeping their timestamps. Stories are linked to the persona and members with the same
+    /// names, ignoring case; members not known yet are added as humans.
+    ///
+    /// Ids are checked against the database inside the transaction, counting rows in the
+    /// trash. Under `ConflictPolicy::Overwrite` existing rows with the same ids are replaced
+    /// and a replaced story moves any criteria not present in the imported version to the
+    /// trash. Under `Skip` a story whose id or criteria ids are taken is left out; under the
+    /// other policies a taken id aborts the import.
+    pub async fn import(
+        &self,
+        user_stories: &[UserStoryWithCriteria],
+        policy: ConflictPolicy,
+    ) -> Result<ImportWrite, sqlx::Error> {
+        let now = Utc::now().naive_utc();
+        let mut tx = begin_write(&self.pool).await?;
+        let mut skipped = Vec::new();
+
+        for entry in user_stories {
+            let story = &entry.user_story;
+            if policy != ConflictPolicy::Overwrite {
+                if let Some(id) = taken_id(&mut tx, entry).await? {
+                    if policy == ConflictPolicy::Skip {
+                        skipped.push(story.id.clone());
+                        continue;
+                    }
+                    // Dropping the transaction rolls back what was written so far
+                    return Ok(ImportWrite::Conflict { id });
+                }
+            }
+
+            let persona_id = resolve_persona_id(&mut tx, &story.persona, story.created_at).await?;
+            let assignee_id = match story.assignee {
+                Some(ref name) => Some(resolve_member_id(&mut tx, name, story.created_at).await?),
+                None => None,
+            };
+            let reviewer_id = match story.reviewer {
+                Some(ref name) => Some(resolve_member_id(&mut tx, name, story.created_at).await?),
+                None => None,
+            };
+
+            let kept_ids = serde_json::to_string(
+                &entry
+                    .acceptance_criteria
+                    .iter()
+                    .map(|criteria| criteria.id.as_str())
+                    .collect::<Vec<_>>(),
+            )
+            .map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+            sqlx::query!(
+                r#"
+                UPDATE acceptance_criteria
+                SET deleted_at = $2, version = version + 1
+                WHERE user_story_id = $1 AND deleted_at IS NULL
+                    AND id NOT IN (SELECT value FROM json_each($3))
+                "#,
+                story.id,
+                now,
+                kept_ids
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query!(
+                r#"
+                INSERT INTO user_stories (id, title, description, persona_id, assignee_id,
+                    reviewer_id, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                ON CONFLICT(id) DO UPDATE SET
+                    title = excluded.title,
+                    description = excluded.description,
+                    persona_id = excluded.persona_id,
+                    assignee_id = excluded.assignee_id,
+                    reviewer_id = excluded.reviewer_id,
+                    created_at = excluded.created_at,
+                    updated_at = excluded.updated_at,
+                    version = user_stories.version + 1,
+                    deleted_at = NULL
+                "#,
+                story.id,
+                story.title,
+                story.description,
+                persona_id,
+                assignee_id,
+                reviewer_id,
+                story.created_at,
+                story.updated_at
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            for criteria in &entry.acceptance_criteria {
+                sqlx::query!(
+                    r#"
+                    INSERT INTO acceptance_criteria (id, user_story_id, description, created_at, updated_at)
+                    VALUES ($1, $2, $3, $4, $5)
+                    ON CONFLICT(id) DO UPDATE SET
+                        user_story_id = excluded.user_story_id,
+                        description = excluded.description,
+                        created_at = excluded.created_at,
+                        updated_at = excluded.updated_at,
+                        version = acceptance_criteria.version + 1,
+                        deleted_at = NULL
+                    "#,
+                    criteria.id,
+                    criteria.user_story_id,
+                    criteria.description,
+                    criteria.created_at,
+                    criteria.updated_at
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(ImportWrite::Applied { skipped })
+    }
+}
+
+/// The first id of a story or its criteria that is already in use, including in the trash
+async fn taken_id(
+    conn: &mut SqliteConnection,
+    entry: &UserStoryWithCriteria,
+) -> Result<Option<String>, sqlx::Error> {
+    let story_id = sqlx::query_scalar!(
+        "SELECT id FROM user_stories WHERE id = $1",
+        entry.user_story.id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    if story_id.is_some() {
+        return Ok(story_id);
+    }
+
+    for criteria in &entry.acceptance_criteria {
+        let criteria_id = sqlx::query_scalar!(
+            "SELECT id FROM acceptance_criteria WHERE id = $1",
+            criteria.id
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+        if criteria_id.is_some() {
+            return Ok(criteria_id);
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AcceptanceCriteria, UserStory};
+    use crate::repositories::{AcceptanceCriteriaRepository, UserStoryRepository};
+    use chrono::NaiveDate;
+
+    fn create_test_entry(id: &str, criteria_ids: &[&str]) -> UserStoryWithCriteria {
+        let timestamp = NaiveDate::from_ymd_opt(2023, 6, 1)
+            .unwrap()
+            .and_hms_opt(9, 30, 0)
+            .unwrap();
+
+        UserStoryWithCriteria {
+            user_story: UserStory {
+                id: id.to_string(),
+                title: "Imported Story".to_string(),
+                description: "As an importer, I want my backlog preserved".to_string(),
+                persona: "Importer".to_string(),
+                created_at: timestamp,
+                updated_at: timestamp,
+                version: 1,
+                assignee: None,
+                reviewer: None,
+            },
+            acceptance_criteria: criteria_ids
+                .iter()
+                .map(|criteria_id| AcceptanceCriteria {
+                    id: criteria_id.to_string(),
+                    user_story_id: id.to_string(),
+                    description: "Given an export, When I import it, Then nothing is lost"
+                        .to_string(),
+                    created_at: timestamp,
+                    updated_at: timestamp,
+                    version: 1,
+                })
+                .collect(),
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_import_preserves_timestamps(pool: sqlx::SqlitePool) {
+        let repo = BacklogRepository::new(pool.clone());
+        let entry = create_test_entry("US-IMP-001", &["AC-IMP-001", "AC-IMP-002"]);
+
+        repo.import(std::slice::from_ref(&entry), ConflictPolicy::Fail)
+            .await
+            .unwrap();
+
+        let story = UserStoryRepository::new(pool.clone())
+            .get_by_id("US-IMP-001")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(story.created_at, entry.user_story.created_at);
+        assert_eq!(story.updated_at, entry.user_story.updated_at);
+
+        let criteria = AcceptanceCriteriaRepository::new(pool)
+            .get_by_user_story_id("US-IMP-001")
+            .await
+            .unwrap();
+        assert_eq!(criteria.len(), 2);
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_import_replaces_existing_story(pool: sqlx::SqlitePool) {
+        let repo = BacklogRepository::new(pool.clone());
+        let entry = create_test_entry("US-001", &["AC-001"]);
+
+        repo.import(&[entry], ConflictPolicy::Overwrite)
+            .await
+            .unwrap();
+
+        let story = UserStoryRepository::new(pool.clone())
+            .get_by_id("US-001")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(story.title, "Imported Story");
+
+        // AC-002 and AC-003 are not part of the imported story any more
+        let criteria_repo = AcceptanceCriteriaRepository::new(pool);
+        let criteria = criteria_repo.get_by_user_story_id("US-001").await.unwrap();
+        assert_eq!(criteria.len(), 1);
+        assert_eq!(criteria[0].id, "AC-001");
+        // They are in the trash rather than gone
+        assert!(criteria_repo.get_by_id("AC-002").await.unwrap().is_none());
+        assert!(criteria_repo.exists("AC-002").await.unwrap());
+    }
+
+    #[sqlx::test]
+    async fn test_import_assigns_members(pool: sqlx::SqlitePool) {
+        let repo = BacklogRepository::new(pool.clone());
+        let mut entry = create_test_entry("US-IMP-001", &[]);
+        entry.user_story.assignee = Some("Alice".to_string());
+        entry.user_story.reviewer = Some("alice".to_string());
+
+        repo.import(&[entry], ConflictPolicy::Fail).await.unwrap();
+
+        let story = UserStoryRepository::new(pool.clone())
+            .get_by_id("US-IMP-001")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(story.assignee.as_deref(), Some("Alice"));
+        assert_eq!(story.reviewer.as_deref(), Some("Alice"));
+
+        let members = crate::repositories::MemberRepository::new(pool)
+            .get_all_with_workload()
+            .await
+            .unwrap();
+        assert_eq!(members.len(), 1);
+        assert_eq!((members[0].assigned, members[0].reviewing), (1, 1));
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_import_taken_ids(pool: sqlx::SqlitePool) {
+        let repo = BacklogRepository::new(pool.clone());
+        let entries = [
+            create_test_entry("US-IMP-001", &["AC-IMP-001"]),
+            // AC-001 belongs to US-001
+            create_test_entry("US-IMP-002", &["AC-001"]),
+        ];
+
+        let write = repo.import(&entries, ConflictPolicy::Fail).await.unwrap();
+        assert_eq!(
+            write,
+            ImportWrite::Conflict {
+                id: "AC-001".to_string()
+            }
+        );
+        let stories = UserStoryRepository::new(pool.clone());
+        assert!(!stories.exists("US-IMP-001").await.unwrap());
+
+        let write = repo.import(&entries, ConflictPolicy::Skip).await.unwrap();
+        assert_eq!(
+            write,
+            ImportWrite::Applied {
+                skipped: vec!["US-IMP-002".to_string()]
+            }
+        );
+        assert!(stories.exists("US-IMP-001").await.unwrap());
+        assert!(!stories.exists("US-IMP-002").await.unwrap());
+        let ac_001 = AcceptanceCriteriaRepository::new(pool)
+            .get_by_id("AC-001")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ac_001.user_story_id, "US-001");
+    }
+}
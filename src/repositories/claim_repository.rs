@@ -0,0 +1,245 @@
+use crate::database::DbPool;
+use crate::models::{StoryClaim, UserStory, WorkQueueFilter};
+use chrono::{Duration, NaiveDateTime, Utc};
+use sqlx::SqliteConnection;
+
+#[derive(Clone)]
+pub struct ClaimRepository {
+    pool: DbPool,
+}
+
+impl ClaimRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Claim a user story for `ttl`, taking over an expired claim or renewing the agent's own.
+    /// Returns `None` while another agent holds a live claim.
+    pub async fn claim(
+        &self,
+        user_story_id: &str,
+        agent_id: &str,
+        ttl: Duration,
+    ) -> Result<Option<StoryClaim>, sqlx::Error> {
+        let now = Utc::now().naive_utc();
+        let expires_at = now + ttl;
+
+        sqlx::query_as!(
+            StoryClaim,
+            r#"
+            INSERT INTO story_claims (user_story_id, agent_id, claimed_at, heartbeat_at, expires_at)
+            VALUES ($1, $2, $3, $3, $4)
+            ON CONFLICT (user_story_id) DO UPDATE SET
+                claimed_at = CASE
+                    WHEN story_claims.agent_id = excluded.agent_id
+                        AND story_claims.expires_at > excluded.claimed_at
+                    THEN story_claims.claimed_at
+                    ELSE excluded.claimed_at
+                END,
+                agent_id = excluded.agent_id,
+                heartbeat_at = excluded.heartbeat_at,
+                expires_at = excluded.expires_at
+            WHERE story_claims.agent_id = excluded.agent_id
+                OR story_claims.expires_at <= excluded.claimed_at
+            RETURNING user_story_id, agent_id, claimed_at as "claimed_at: NaiveDateTime",
+                heartbeat_at as "heartbeat_at: NaiveDateTime",
+                expires_at as "expires_at: NaiveDateTime"
+            "#,
+            user_story_id,
+            agent_id,
+            now,
+            expires_at
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Extend a live claim held by the agent to `ttl` from now
+    pub async fn heartbeat(
+        &self,
+        user_story_id: &str,
+        agent_id: &str,
+        ttl: Duration,
+    ) -> Result<Option<StoryClaim>, sqlx::Error> {
+        let now = Utc::now().naive_utc();
+        let expires_at = now + ttl;
+
+        sqlx::query_as!(
+            StoryClaim,
+            r#"
+            UPDATE story_claims
+            SET heartbeat_at = $3, expires_at = $4
+            WHERE user_story_id = $1 AND agent_id = $2 AND expires_at > $3
+            RETURNING user_story_id, agent_id, claimed_at as "claimed_at: NaiveDateTime",
+                heartbeat_at as "heartbeat_at: NaiveDateTime",
+                expires_at as "expires_at: NaiveDateTime"
+            "#,
+            user_story_id,
+            agent_id,
+            now,
+            expires_at
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Drop the agent's claim on a user story, live or expired
+    pub async fn release(&self, user_story_id: &str, agent_id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            "DELETE FROM story_claims WHERE user_story_id = $1 AND agent_id = $2",
+            user_story_id,
+            agent_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get the live claim on a user story
+    pub async fn get_active(&self, user_story_id: &str) -> Result<Option<StoryClaim>, sqlx::Error> {
+        let now = Utc::now().naive_utc();
+
+        sqlx::query_as!(
+            StoryClaim,
+            r#"
+            SELECT user_story_id, agent_id, claimed_at, heartbeat_at, expires_at
+            FROM story_claims
+            WHERE user_story_id = $1 AND expires_at > $2
+            "#,
+            user_story_id,
+            now
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Get the live claim on the user story an acceptance criterion belongs to
+    pub async fn get_active_for_criteria(
+        &self,
+        acceptance_criteria_id: &str,
+    ) -> Result<Option<StoryClaim>, sqlx::Error> {
+        let now = Utc::now().naive_utc();
+
+        sqlx::query_as!(
+            StoryClaim,
+            r#"
+            SELECT c.user_story_id, c.agent_id, c.claimed_at, c.heartbeat_at, c.expires_at
+            FROM story_claims c
+            JOIN acceptance_criteria a ON a.user_story_id = c.user_story_id
+            WHERE a.id = $1 AND c.expires_at > $2
+            "#,
+            acceptance_criteria_id,
+            now
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Get all live claims on live user stories, soonest to expire first
+    pub async fn get_all_active(&self) -> Result<Vec<StoryClaim>, sqlx::Error> {
+        let now = Utc::now().naive_utc();
+
+        sqlx::query_as!(
+            StoryClaim,
+            r#"
+            SELECT c.user_story_id, c.agent_id, c.claimed_at, c.heartbeat_at, c.expires_at
+            FROM story_claims c
+            JOIN user_stories s ON s.id = c.user_story_id
+            WHERE c.expires_at > $1 AND s.deleted_at IS NULL
+            ORDER BY c.expires_at, c.user_story_id
+            "#,
+            now
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Get the oldest live user story matching the filter that nobody holds a live claim on
+    pub async fn next_unclaimed(
+        &self,
+        filter: &WorkQueueFilter,
+    ) -> Result<Option<UserStory>, sqlx::Error> {
+        let now = Utc::now().naive_utc();
+        let persona = filter.persona.as_deref().map(str::trim);
+        let assignee = filter.assignee.as_deref().map(str::trim);
+
+        sqlx::query_as!(
+            UserStory,
+            r#"
+            SELECT s.id, s.title, s.description, p.name as persona, ma.name as "assignee?",
+                mr.name as "reviewer?", s.created_at, s.updated_at, s.version
+            FROM user_stories s
+            JOIN personas p ON p.id = s.persona_id
+            LEFT JOIN members ma ON ma.id = s.assignee_id
+            LEFT JOIN members mr ON mr.id = s.reviewer_id
+            WHERE s.deleted_at IS NULL
+                AND ($1 IS NULL OR p.name = $1)
+                AND ($2 IS NULL OR ma.name = $2)
+                AND (NOT $3 OR s.assignee_id IS NULL)
+                AND NOT EXISTS (
+                    SELECT 1 FROM story_claims c
+                    WHERE c.user_story_id = s.id AND c.expires_at > $4
+                )
+            ORDER BY s.created_at, s.id
+            LIMIT 1
+            "#,
+            persona,
+            assignee,
+            filter.unassigned,
+            now
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+}
+
+/// Get the live claim on a user story unless `agent_id` holds it. Writes check this inside
+/// their `begin_write` transaction, which holds the database write lock, so no agent can take
+/// the claim between the check and the write.
+pub(crate) async fn blocking_claim(
+    conn: &mut SqliteConnection,
+    user_story_id: &str,
+    agent_id: Option<&str>,
+) -> Result<Option<StoryClaim>, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+
+    sqlx::query_as!(
+        StoryClaim,
+        r#"
+        SELECT user_story_id, agent_id, claimed_at, heartbeat_at, expires_at
+        FROM story_claims
+        WHERE user_story_id = $1 AND expires_at > $2 AND ($3 IS NULL OR agent_id <> $3)
+        "#,
+        user_story_id,
+        now,
+        agent_id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+}
+
+/// Get the live claim on the user story acceptance criteria belong to unless `agent_id`
+/// holds it; see [`blocking_claim`]
+pub(crate) async fn blocking_criteria_claim(
+    conn: &mut SqliteConnection,
+    acceptance_criteria_id: &str,
+    agent_id: Option<&str>,
+) -> Result<Option<StoryClaim>, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+
+    sqlx::query_as!(
+        StoryClaim,
+        r#"
+        SELECT c.user_story_id, c.agent_id, c.claimed_at, c.heartbeat_at, c.expires_at
+        FROM story_claims c
+        JOIN acceptance_criteria a ON a.user_story_id = c.user_story_id
+        WHERE a.id = $1 AND c.expires_at > $2 AND ($3 IS NULL OR c.agent_id <> $3)
+        "#,
+        acceptance_criteria_id,
+        now,
+        agent_id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+}
@@ -0,0 +1,235 @@
+use crate::database::DbPool;
+use crate::models::Comment;
+use chrono::{NaiveDateTime, Utc};
+use sqlx::types::Json;
+
+/// Database row of a comment, with its mentions still wrapped as JSON
+struct CommentRow {
+    id: i64,
+    user_story_id: String,
+    acceptance_criteria_id: Option<String>,
+    parent_id: Option<i64>,
+    author: String,
+    body: String,
+    mentions: Json<Vec<String>>,
+    resolved_by: Option<String>,
+    resolved_at: Option<NaiveDateTime>,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+}
+
+impl From<CommentRow> for Comment {
+    fn from(row: CommentRow) -> Self {
+        Self {
+            id: row.id,
+            user_story_id: row.user_story_id,
+            acceptance_criteria_id: row.acceptance_criteria_id,
+            parent_id: row.parent_id,
+            author: row.author,
+            body: row.body,
+            mentions: row.mentions.0,
+            resolved_by: row.resolved_by,
+            resolved_at: row.resolved_at,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+/// Where a new comment goes
+pub struct CommentPlacement<'a> {
+    pub user_story_id: &'a str,
+    pub acceptance_criteria_id: Option<&'a str>,
+    pub parent_id: Option<i64>,
+}
+
+#[derive(Clone)]
+pub struct CommentRepository {
+    pool: DbPool,
+}
+
+impl CommentRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create a new comment
+    pub async fn create(
+        &self,
+        placement: CommentPlacement<'_>,
+        author: &str,
+        body: &str,
+        mentions: Vec<String>,
+    ) -> Result<Comment, sqlx::Error> {
+        let now = Utc::now().naive_utc();
+        let mentions = Json(mentions);
+
+        let row = sqlx::query_as!(
+            CommentRow,
+            r#"
+            INSERT INTO comments (user_story_id, acceptance_criteria_id, parent_id, author, body,
+                mentions, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id, user_story_id, acceptance_criteria_id, parent_id, author, body,
+                mentions as "mentions: Json<Vec<String>>",
+                resolved_by, resolved_at as "resolved_at: NaiveDateTime", created_at, updated_at
+            "#,
+            placement.user_story_id,
+            placement.acceptance_criteria_id,
+            placement.parent_id,
+            author,
+            body,
+            mentions,
+            now,
+            now
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.into())
+    }
+
+    /// Get a comment by ID
+    pub async fn get_by_id(&self, id: i64) -> Result<Option<Comment>, sqlx::Error> {
+        let row = sqlx::query_as!(
+            CommentRow,
+            r#"
+            SELECT id, user_story_id, acceptance_criteria_id, parent_id, author, body,
+                mentions as "mentions: Json<Vec<String>>",
+                resolved_by, resolved_at as "resolved_at: NaiveDateTime", created_at, updated_at
+            FROM comments
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(Comment::from))
+    }
+
+    /// Get the comments on a user story and its live acceptance criteria, oldest first
+    pub async fn get_by_user_story_id(
+        &self,
+        user_story_id: &str,
+    ) -> Result<Vec<Comment>, sqlx::Error> {
+        let rows = sqlx::query_as!(
+            CommentRow,
+            r#"
+            SELECT c.id as "id!", c.user_story_id, c.acceptance_criteria_id, c.parent_id,
+                c.author, c.body, c.mentions as "mentions: Json<Vec<String>>",
+                c.resolved_by, c.resolved_at as "resolved_at: NaiveDateTime",
+                c.created_at, c.updated_at
+            FROM comments c
+            LEFT JOIN acceptance_criteria a ON a.id = c.acceptance_criteria_id
+            WHERE c.user_story_id = $1 AND a.deleted_at IS NULL
+            ORDER BY c.created_at ASC, c.id ASC
+            "#,
+            user_story_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(Comment::from).collect())
+    }
+
+    /// Get the comments mentioning a name, ignoring case, on live stories and criteria
+    pub async fn get_mentioning(&self, name: &str) -> Result<Vec<Comment>, sqlx::Error> {
+        let rows = sqlx::query_as!(
+            CommentRow,
+            r#"
+            SELECT c.id as "id!", c.user_story_id, c.acceptance_criteria_id, c.parent_id,
+                c.author, c.body, c.mentions as "mentions: Json<Vec<String>>",
+                c.resolved_by, c.resolved_at as "resolved_at: NaiveDateTime",
+                c.created_at, c.updated_at
+            FROM comments c
+            JOIN user_stories s ON s.id = c.user_story_id
+            LEFT JOIN acceptance_criteria a ON a.id = c.acceptance_criteria_id
+            WHERE s.deleted_at IS NULL AND a.deleted_at IS NULL
+                AND EXISTS (
+                    SELECT 1 FROM json_each(c.mentions) m WHERE m.value = $1 COLLATE NOCASE
+                )
+            ORDER BY c.created_at DESC, c.id DESC
+            "#,
+            name
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(Comment::from).collect())
+    }
+
+    /// Replace the body of a comment and the names it mentions
+    pub async fn update_body(
+        &self,
+        id: i64,
+        body: &str,
+        mentions: Vec<String>,
+    ) -> Result<Option<Comment>, sqlx::Error> {
+        let now = Utc::now().naive_utc();
+        let mentions = Json(mentions);
+
+        let row = sqlx::query_as!(
+            CommentRow,
+            r#"
+            UPDATE comments
+            SET body = $2, mentions = $3, updated_at = $4
+            WHERE id = $1
+            RETURNING id, user_story_id, acceptance_criteria_id, parent_id, author, body,
+                mentions as "mentions: Json<Vec<String>>",
+                resolved_by, resolved_at as "resolved_at: NaiveDateTime", created_at, updated_at
+            "#,
+            id,
+            body,
+            mentions,
+            now
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(Comment::from))
+    }
+
+    /// Mark a thread resolved by someone, or open it again with `None`
+    pub async fn set_resolved(
+        &self,
+        id: i64,
+        resolved_by: Option<&str>,
+    ) -> Result<Option<Comment>, sqlx::Error> {
+        let resolved_at = resolved_by.map(|_| Utc::now().naive_utc());
+
+        let row = sqlx::query_as!(
+            CommentRow,
+            r#"
+            UPDATE comments
+            SET resolved_by = $2, resolved_at = $3
+            WHERE id = $1
+            RETURNING id, user_story_id, acceptance_criteria_id, parent_id, author, body,
+                mentions as "mentions: Json<Vec<String>>",
+                resolved_by, resolved_at as "resolved_at: NaiveDateTime", created_at, updated_at
+            "#,
+            id,
+            resolved_by,
+            resolved_at
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(Comment::from))
+    }
+
+    /// Delete a comment; deleting the first comment of a thread deletes its replies
+    pub async fn delete(&self, id: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM comments
+            WHERE id = $1
+            "#,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,400 @@
+use crate::database::{begin_write, DbPool};
+use crate::models::{DeliveryStatus, DomainEvent, DomainEventKind, Webhook, WebhookDelivery};
+use chrono::{NaiveDateTime, Utc};
+use sqlx::types::Json;
+
+/// Database row of an outbox event, with its kind still as text
+struct DomainEventRow {
+    id: i64,
+    kind: String,
+    user_story_id: String,
+    acceptance_criteria_id: Option<String>,
+    payload: Json<serde_json::Value>,
+    occurred_at: NaiveDateTime,
+}
+
+impl From<DomainEventRow> for DomainEvent {
+    fn from(row: DomainEventRow) -> Self {
+        Self {
+            id: row.id,
+            // The triggers only record known kinds
+            kind: row.kind.parse().unwrap_or(DomainEventKind::StoryUpdated),
+            user_story_id: row.user_story_id,
+            acceptance_criteria_id: row.acceptance_criteria_id,
+            payload: row.payload.0,
+            occurred_at: row.occurred_at,
+        }
+    }
+}
+
+/// Database row of a webhook, with its event kinds as JSON
+struct WebhookRow {
+    id: i64,
+    url: String,
+    secret: String,
+    event_kinds: Json<Vec<DomainEventKind>>,
+    created_at: NaiveDateTime,
+}
+
+impl From<WebhookRow> for Webhook {
+    fn from(row: WebhookRow) -> Self {
+        Self {
+            id: row.id,
+            url: row.url,
+            secret: row.secret,
+            event_kinds: row.event_kinds.0,
+            created_at: row.created_at,
+        }
+    }
+}
+
+/// Database row of a delivery, with its event kind and status still as text
+struct WebhookDeliveryRow {
+    id: i64,
+    webhook_id: i64,
+    url: String,
+    event_id: i64,
+    event_kind: String,
+    user_story_id: String,
+    status: String,
+    attempts: i64,
+    next_attempt_at: NaiveDateTime,
+    last_error: Option<String>,
+    delivered_at: Option<NaiveDateTime>,
+}
+
+impl From<WebhookDeliveryRow> for WebhookDelivery {
+    fn from(row: WebhookDeliveryRow) -> Self {
+        Self {
+            id: row.id,
+            webhook_id: row.webhook_id,
+            url: row.url,
+            event_id: row.event_id,
+            event_kind: row
+                .event_kind
+                .parse()
+                .unwrap_or(DomainEventKind::StoryUpdated),
+            user_story_id: row.user_story_id,
+            // The table only admits known statuses
+            status: row.status.parse().unwrap_or(DeliveryStatus::Pending),
+            attempts: row.attempts,
+            next_attempt_at: row.next_attempt_at,
+            last_error: row.last_error,
+            delivered_at: row.delivered_at,
+        }
+    }
+}
+
+/// A delivery that is due, with what is needed to send it
+#[derive(Debug, Clone)]
+pub struct DueDelivery {
+    pub id: i64,
+    pub attempts: i64,
+    pub url: String,
+    pub secret: String,
+    pub event: DomainEvent,
+}
+
+/// A story, or a criterion when `acceptance_criteria_id` is set, with its last change
+#[derive(Debug, Clone)]
+pub struct ChangedEntity {
+    pub sequence: i64,
+    pub user_story_id: String,
+    pub acceptance_criteria_id: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct EventRepository {
+    pool: DbPool,
+}
+
+impl EventRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Get up to `limit` outbox events recorded after the event `after_id`, oldest first
+    pub async fn get_after(
+        &self,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<DomainEvent>, sqlx::Error> {
+        let rows = sqlx::query_as!(
+            DomainEventRow,
+            r#"
+            SELECT id, kind, user_story_id, acceptance_criteria_id,
+                payload as "payload: Json<serde_json::Value>", occurred_at
+            FROM domain_events
+            WHERE id > $1
+            ORDER BY id
+            LIMIT $2
+            "#,
+            after_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(DomainEvent::from).collect())
+    }
+
+    /// Get up to `limit` stories and criteria changed after the event `after_id`, each with the
+    /// id of its last event, in the order of those events
+    pub async fn get_changed_since(
+        &self,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<ChangedEntity>, sqlx::Error> {
+        sqlx::query_as!(
+            ChangedEntity,
+            r#"
+            SELECT MAX(id) as "sequence!: i64", user_story_id as "user_story_id!",
+                acceptance_criteria_id
+            FROM domain_events
+            WHERE id > $1
+            GROUP BY user_story_id, acceptance_criteria_id
+            ORDER BY 1
+            LIMIT $2
+            "#,
+            after_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Id of the last event in the outbox, 0 while it is empty
+    pub async fn latest_id(&self) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar!(r#"SELECT COALESCE(MAX(id), 0) as "id!: i64" FROM domain_events"#)
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    /// Register a webhook; it receives the events recorded from now on
+    pub async fn create_webhook(
+        &self,
+        url: &str,
+        secret: &str,
+        event_kinds: &[DomainEventKind],
+    ) -> Result<Webhook, sqlx::Error> {
+        let now = Utc::now().naive_utc();
+        let event_kinds = Json(event_kinds);
+
+        let row = sqlx::query_as!(
+            WebhookRow,
+            r#"
+            INSERT INTO webhooks (url, secret, event_kinds, last_event_id, created_at)
+            VALUES ($1, $2, $3, (SELECT COALESCE(MAX(id), 0) FROM domain_events), $4)
+            RETURNING id as "id!", url, secret,
+                event_kinds as "event_kinds: Json<Vec<DomainEventKind>>",
+                created_at as "created_at: NaiveDateTime"
+            "#,
+            url,
+            secret,
+            event_kinds,
+            now
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.into())
+    }
+
+    /// Get all webhooks, oldest first
+    pub async fn get_webhooks(&self) -> Result<Vec<Webhook>, sqlx::Error> {
+        let rows = sqlx::query_as!(
+            WebhookRow,
+            r#"
+            SELECT id, url, secret, event_kinds as "event_kinds: Json<Vec<DomainEventKind>>",
+                created_at
+            FROM webhooks
+            ORDER BY id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(Webhook::from).collect())
+    }
+
+    /// Delete a webhook along with its deliveries
+    pub async fn delete_webhook(&self, id: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!("DELETE FROM webhooks WHERE id = $1", id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Queue a delivery of every event recorded since the last call to each webhook that
+    /// wants its kind
+    pub async fn enqueue_deliveries(&self) -> Result<u64, sqlx::Error> {
+        let now = Utc::now().naive_utc();
+        let mut tx = begin_write(&self.pool).await?;
+
+        // Events recorded while this runs are left for the next call
+        let latest =
+            sqlx::query_scalar!(r#"SELECT COALESCE(MAX(id), 0) as "id!: i64" FROM domain_events"#)
+                .fetch_one(&mut *tx)
+                .await?;
+
+        let result = sqlx::query!(
+            r#"
+            INSERT OR IGNORE INTO webhook_deliveries (webhook_id, event_id, next_attempt_at)
+            SELECT w.id, e.id, $2
+            FROM webhooks w
+            JOIN domain_events e ON e.id > w.last_event_id AND e.id <= $1
+            WHERE w.event_kinds = '[]'
+                OR e.kind IN (SELECT value FROM json_each(w.event_kinds))
+            "#,
+            latest,
+            now
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "UPDATE webhooks SET last_event_id = $1 WHERE last_event_id < $1",
+            latest
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Get up to `limit` pending deliveries whose next attempt is due, oldest event first
+    pub async fn get_due(&self, limit: i64) -> Result<Vec<DueDelivery>, sqlx::Error> {
+        let now = Utc::now().naive_utc();
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT d.id, d.attempts, w.url, w.secret, e.id as event_id, e.kind,
+                e.user_story_id, e.acceptance_criteria_id,
+                e.payload as "payload: Json<serde_json::Value>", e.occurred_at
+            FROM webhook_deliveries d
+            JOIN webhooks w ON w.id = d.webhook_id
+            JOIN domain_events e ON e.id = d.event_id
+            WHERE d.status = 'pending' AND d.next_attempt_at <= $1
+            ORDER BY e.id, d.id
+            LIMIT $2
+            "#,
+            now,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| DueDelivery {
+                id: row.id,
+                attempts: row.attempts,
+                url: row.url,
+                secret: row.secret,
+                event: DomainEventRow {
+                    id: row.event_id,
+                    kind: row.kind,
+                    user_story_id: row.user_story_id,
+                    acceptance_criteria_id: row.acceptance_criteria_id,
+                    payload: row.payload,
+                    occurred_at: row.occurred_at,
+                }
+                .into(),
+            })
+            .collect())
+    }
+
+    /// Record a successful attempt
+    pub async fn mark_delivered(&self, id: i64) -> Result<(), sqlx::Error> {
+        let now = Utc::now().naive_utc();
+
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = 'delivered', attempts = attempts + 1, delivered_at = $2,
+                last_error = NULL
+            WHERE id = $1
+            "#,
+            id,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record a failed attempt, to be tried again at `retry_at` or, without one, moved to the
+    /// dead letters
+    pub async fn mark_failed(
+        &self,
+        id: i64,
+        error: &str,
+        retry_at: Option<NaiveDateTime>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = CASE WHEN $3 IS NULL THEN 'dead' ELSE 'pending' END,
+                attempts = attempts + 1,
+                next_attempt_at = COALESCE($3, next_attempt_at),
+                last_error = $2
+            WHERE id = $1
+            "#,
+            id,
+            error,
+            retry_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get the deliveries in a status, oldest event first
+    pub async fn get_deliveries(
+        &self,
+        status: DeliveryStatus,
+    ) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+        let status = status.as_str();
+
+        let rows = sqlx::query_as!(
+            WebhookDeliveryRow,
+            r#"
+            SELECT d.id, d.webhook_id, w.url, d.event_id, e.kind as event_kind, e.user_story_id,
+                d.status, d.attempts, d.next_attempt_at, d.last_error, d.delivered_at
+            FROM webhook_deliveries d
+            JOIN webhooks w ON w.id = d.webhook_id
+            JOIN domain_events e ON e.id = d.event_id
+            WHERE d.status = $1
+            ORDER BY d.event_id, d.id
+            "#,
+            status
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(WebhookDelivery::from).collect())
+    }
+
+    /// Give a dead delivery a fresh set of attempts, starting now
+    pub async fn retry_dead(&self, id: i64) -> Result<bool, sqlx::Error> {
+        let now = Utc::now().naive_utc();
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = 'pending', attempts = 0, next_attempt_at = $2
+            WHERE id = $1 AND status = 'dead'
+            "#,
+            id,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
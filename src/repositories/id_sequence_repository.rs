@@ -0,0 +1,167 @@
+use crate::database::DbPool;
+use crate::models::IdScheme;
+
+/// Hands out sequential numbers per id prefix. Each allocation is a single statement, so
+/// concurrent callers never receive the same number, and numbering continues after the
+/// highest id already stored with the prefix.
+pub struct IdSequenceRepository {
+    pool: DbPool,
+}
+
+impl IdSequenceRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Allocate an unused user story id following the scheme
+    pub async fn next_story_id(&self, scheme: &IdScheme) -> Result<String, sqlx::Error> {
+        loop {
+            let id = scheme.story_id(self.next_story_number(&scheme.story_prefix).await?);
+            // Stored ids with other zero padding are not seen by the counter
+            let taken = sqlx::query_scalar!(
+                r#"SELECT EXISTS(SELECT 1 FROM user_stories WHERE id = $1) as "taken: bool""#,
+                id
+            )
+            .fetch_one(&self.pool)
+            .await?;
+            if !taken {
+                return Ok(id);
+            }
+        }
+    }
+
+    /// Allocate an unused acceptance criteria id for a user story following the scheme
+    pub async fn next_criteria_id(
+        &self,
+        scheme: &IdScheme,
+        user_story_id: &str,
+    ) -> Result<String, sqlx::Error> {
+        let prefix = scheme.criteria_prefix_for(user_story_id);
+        loop {
+            let id = format!("{}{}", prefix, self.next_criteria_number(&prefix).await?);
+            let taken = sqlx::query_scalar!(
+                r#"SELECT EXISTS(SELECT 1 FROM acceptance_criteria WHERE id = $1) as "taken: bool""#,
+                id
+            )
+            .fetch_one(&self.pool)
+            .await?;
+            if !taken {
+                return Ok(id);
+            }
+        }
+    }
+
+    /// Allocate the next user story number for a prefix such as `US-`
+    pub async fn next_story_number(&self, prefix: &str) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+            INSERT INTO id_sequences (prefix, last_value)
+            VALUES ($1, (
+                SELECT COALESCE(MAX(CAST(SUBSTR(id, LENGTH($1) + 1) AS INTEGER)), 0) + 1
+                FROM user_stories
+                WHERE SUBSTR(id, 1, LENGTH($1)) = $1
+            ))
+            ON CONFLICT(prefix) DO UPDATE SET last_value = MAX(last_value + 1, excluded.last_value)
+            RETURNING last_value
+            "#,
+            prefix
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Allocate the next acceptance criteria number for a prefix such as `AC-105-`
+    pub async fn next_criteria_number(&self, prefix: &str) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+            INSERT INTO id_sequences (prefix, last_value)
+            VALUES ($1, (
+                SELECT COALESCE(MAX(CAST(SUBSTR(id, LENGTH($1) + 1) AS INTEGER)), 0) + 1
+                FROM acceptance_criteria
+                WHERE SUBSTR(id, 1, LENGTH($1)) = $1
+            ))
+            ON CONFLICT(prefix) DO UPDATE SET last_value = MAX(last_value + 1, excluded.last_value)
+            RETURNING last_value
+            "#,
+            prefix
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_numbering_continues_after_stored_ids(pool: sqlx::SqlitePool) {
+        let repository = IdSequenceRepository::new(pool);
+
+        assert_eq!(repository.next_story_number("US-").await.unwrap(), 6);
+        assert_eq!(repository.next_story_number("US-").await.unwrap(), 7);
+        assert_eq!(repository.next_story_number("EPIC-").await.unwrap(), 1);
+
+        assert_eq!(repository.next_criteria_number("AC-").await.unwrap(), 11);
+        assert_eq!(repository.next_criteria_number("AC-001-").await.unwrap(), 1);
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_ids_stored_after_allocation_are_skipped(pool: sqlx::SqlitePool) {
+        let repository = IdSequenceRepository::new(pool.clone());
+        assert_eq!(repository.next_story_number("US-").await.unwrap(), 6);
+
+        sqlx::query(
+            "INSERT INTO user_stories (id, title, description, persona_id) VALUES ('US-010', 't', 'd', 1)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        assert_eq!(repository.next_story_number("US-").await.unwrap(), 11);
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_next_ids(pool: sqlx::SqlitePool) {
+        let repository = IdSequenceRepository::new(pool);
+        let scheme = IdScheme::default();
+
+        assert_eq!(repository.next_story_id(&scheme).await.unwrap(), "US-006");
+        assert_eq!(
+            repository
+                .next_criteria_id(&scheme, "US-105")
+                .await
+                .unwrap(),
+            "AC-105-1"
+        );
+        assert_eq!(
+            repository
+                .next_criteria_id(&scheme, "US-105")
+                .await
+                .unwrap(),
+            "AC-105-2"
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_concurrent_allocation(pool: sqlx::SqlitePool) {
+        let repository = Arc::new(IdSequenceRepository::new(pool));
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for _ in 0..20 {
+            let repository = repository.clone();
+            tasks.spawn(async move { repository.next_story_number("US-").await.unwrap() });
+        }
+
+        let mut numbers = HashSet::new();
+        while let Some(number) = tasks.join_next().await {
+            assert!(numbers.insert(number.unwrap()));
+        }
+        assert_eq!(numbers, (1..=20).collect());
+    }
+}
@@ -0,0 +1,202 @@
+use crate::database::{begin_write, DbPool};
+use crate::models::{CreateMemberRequest, Member, MemberWithWorkload};
+use chrono::{NaiveDateTime, Utc};
+use sqlx::SqliteConnection;
+
+/// Database row of a member, with its kind still as text
+struct MemberRow {
+    id: i64,
+    name: String,
+    kind: String,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+}
+
+impl From<MemberRow> for Member {
+    fn from(row: MemberRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            // The table only admits known kinds
+            kind: row.kind.parse().unwrap_or_default(),
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct MemberRepository {
+    pool: DbPool,
+}
+
+impl MemberRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create a new member
+    pub async fn create(&self, request: CreateMemberRequest) -> Result<Member, sqlx::Error> {
+        let now = Utc::now().naive_utc();
+        let kind = request.kind.as_str();
+
+        let row = sqlx::query_as!(
+            MemberRow,
+            r#"
+            INSERT INTO members (name, kind, created_at, updated_at)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, name, kind, created_at, updated_at
+            "#,
+            request.name,
+            kind,
+            now,
+            now
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.into())
+    }
+
+    /// Get a member by ID
+    pub async fn get_by_id(&self, id: i64) -> Result<Option<Member>, sqlx::Error> {
+        let row = sqlx::query_as!(
+            MemberRow,
+            r#"
+            SELECT id, name, kind, created_at, updated_at
+            FROM members
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(Member::from))
+    }
+
+    /// Get a member by name, ignoring case
+    pub async fn get_by_name(&self, name: &str) -> Result<Option<Member>, sqlx::Error> {
+        let row = sqlx::query_as!(
+            MemberRow,
+            r#"
+            SELECT id, name, kind, created_at, updated_at
+            FROM members
+            WHERE name = $1
+            "#,
+            name
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(Member::from))
+    }
+
+    /// Get all members with the number of live user stories assigned to and reviewed by each
+    pub async fn get_all_with_workload(&self) -> Result<Vec<MemberWithWorkload>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT m.id, m.name, m.kind, m.created_at, m.updated_at,
+                (SELECT COUNT(*) FROM user_stories s
+                    WHERE s.assignee_id = m.id AND s.deleted_at IS NULL) as "assigned!: i64",
+                (SELECT COUNT(*) FROM user_stories s
+                    WHERE s.reviewer_id = m.id AND s.deleted_at IS NULL) as "reviewing!: i64"
+            FROM members m
+            ORDER BY m.name
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| MemberWithWorkload {
+                member: MemberRow {
+                    id: row.id,
+                    name: row.name,
+                    kind: row.kind,
+                    created_at: row.created_at,
+                    updated_at: row.updated_at,
+                }
+                .into(),
+                assigned: row.assigned,
+                reviewing: row.reviewing,
+            })
+            .collect())
+    }
+
+    /// Count the live user stories a member is assigned to or reviews
+    pub async fn count_assignments(&self, id: i64) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!: i64"
+            FROM user_stories
+            WHERE (assignee_id = $1 OR reviewer_id = $1) AND deleted_at IS NULL
+            "#,
+            id
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Delete a member, unassigning them from stories in the trash
+    pub async fn delete(&self, id: i64) -> Result<bool, sqlx::Error> {
+        let mut tx = begin_write(&self.pool).await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE user_stories
+            SET assignee_id = CASE WHEN assignee_id = $1 THEN NULL ELSE assignee_id END,
+                reviewer_id = CASE WHEN reviewer_id = $1 THEN NULL ELSE reviewer_id END
+            WHERE (assignee_id = $1 OR reviewer_id = $1) AND deleted_at IS NOT NULL
+            "#,
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM members
+            WHERE id = $1
+            "#,
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// Id of the member with this name, ignoring case, creating a human member when none exists
+pub(crate) async fn resolve_member_id(
+    conn: &mut SqliteConnection,
+    name: &str,
+    now: NaiveDateTime,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO members (name, created_at, updated_at)
+        VALUES ($1, $2, $3)
+        ON CONFLICT(name) DO NOTHING
+        "#,
+        name,
+        now,
+        now
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query_scalar!(
+        r#"
+        SELECT id
+        FROM members
+        WHERE name = $1
+        "#,
+        name
+    )
+    .fetch_one(&mut *conn)
+    .await
+}
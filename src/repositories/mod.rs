@@ -1,23 +1,91 @@
 pub mod acceptance_criteria_repository;
+pub mod backlog_repository;
+pub mod claim_repository;
+pub mod comment_repository;
+pub mod event_repository;
+pub mod id_sequence_repository;
+pub mod member_repository;
+pub mod persona_repository;
+pub mod revision_repository;
+pub mod trash_repository;
 pub mod user_story_repository;
 
 pub use acceptance_criteria_repository::AcceptanceCriteriaRepository;
+pub use backlog_repository::BacklogRepository;
+pub use claim_repository::ClaimRepository;
+pub use comment_repository::CommentRepository;
+pub use event_repository::EventRepository;
+pub use id_sequence_repository::IdSequenceRepository;
+pub use member_repository::MemberRepository;
+pub use persona_repository::PersonaRepository;
+pub use revision_repository::RevisionRepository;
+pub use trash_repository::TrashRepository;
 pub use user_story_repository::UserStoryRepository;
 
 use crate::database::DbPool;
+use crate::models::StoryClaim;
 use std::sync::Arc;
 
+/// Outcome of a write that may be guarded by an expected version and by story claims
+#[derive(Debug)]
+pub enum VersionedWrite<T> {
+    /// The write went through; holds the row as written (or as deleted)
+    Applied(T),
+    NotFound,
+    /// The row is at another version than expected and was left untouched
+    Conflict(T),
+    /// Another agent holds a live claim on the story and nothing was written
+    Claimed(StoryClaim),
+}
+
+impl<T> VersionedWrite<T> {
+    /// Outcome of a guarded write that changed no rows, given the row as it is now
+    pub(crate) fn rejected(current: Option<T>) -> Self {
+        match current {
+            Some(current) => Self::Conflict(current),
+            None => Self::NotFound,
+        }
+    }
+}
+
+/// Outcome of a write that only a live claim on the story can refuse
+#[derive(Debug)]
+pub enum ClaimGuardedWrite<T> {
+    /// The write went through; holds what was written
+    Applied(T),
+    /// Another agent holds a live claim on the story and nothing was written
+    Claimed(StoryClaim),
+}
+
 #[derive(Clone)]
 pub struct Repositories {
     pub user_stories: Arc<UserStoryRepository>,
     pub acceptance_criteria: Arc<AcceptanceCriteriaRepository>,
+    pub backlog: Arc<BacklogRepository>,
+    pub claims: Arc<ClaimRepository>,
+    pub comments: Arc<CommentRepository>,
+    pub events: Arc<EventRepository>,
+    pub id_sequences: Arc<IdSequenceRepository>,
+    pub members: Arc<MemberRepository>,
+    pub personas: Arc<PersonaRepository>,
+    pub revisions: Arc<RevisionRepository>,
+    pub trash: Arc<TrashRepository>,
 }
 
 impl Repositories {
     pub fn new(pool: DbPool) -> Self {
         Self {
             user_stories: Arc::new(UserStoryRepository::new(pool.clone())),
-            acceptance_criteria: Arc::new(AcceptanceCriteriaRepository::new(pool)),
+            acceptance_criteria: Arc::new(AcceptanceCriteriaRepository::new(pool.clone())),
+            backlog: Arc::new(BacklogRepository::new(pool.clone())),
+            claims: Arc::new(ClaimRepository::new(pool.clone())),
+            comments: Arc::new(CommentRepository::new(pool.clone())),
+            events: Arc::new(EventRepository::new(pool.clone())),
+            id_sequences: Arc::new(IdSequenceRepository::new(pool.clone())),
+            members: Arc::new(MemberRepository::new(pool.clone())),
+            personas: Arc::new(PersonaRepository::new(pool.clone())),
+            revisions: Arc::new(RevisionRepository::new(pool.clone())),
+            trash: Arc::new(TrashRepository::new(pool)),
         }
     }
 }
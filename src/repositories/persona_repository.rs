@@ -0,0 +1,334 @@
+use crate::database::DbPool;
+use crate::models::{CreatePersonaRequest, Persona, PersonaWithUsage, UpdatePersonaRequest};
+use chrono::{NaiveDateTime, Utc};
+use sqlx::types::Json;
+use sqlx::SqliteConnection;
+
+/// Database row of a persona, with its lists still wrapped as JSON
+struct PersonaRow {
+    id: i64,
+    name: String,
+    description: String,
+    goals: Json<Vec<String>>,
+    pain_points: Json<Vec<String>>,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+}
+
+impl From<PersonaRow> for Persona {
+    fn from(row: PersonaRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            description: row.description,
+            goals: row.goals.0,
+            pain_points: row.pain_points.0,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct PersonaRepository {
+    pool: DbPool,
+}
+
+impl PersonaRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create a new persona
+    pub async fn create(&self, request: CreatePersonaRequest) -> Result<Persona, sqlx::Error> {
+        let now = Utc::now().naive_utc();
+        let goals = Json(request.goals);
+        let pain_points = Json(request.pain_points);
+
+        let row = sqlx::query_as!(
+            PersonaRow,
+            r#"
+            INSERT INTO personas (name, description, goals, pain_points, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, name, description,
+                goals as "goals: Json<Vec<String>>",
+                pain_points as "pain_points: Json<Vec<String>>",
+                created_at, updated_at
+            "#,
+            request.name,
+            request.description,
+            goals,
+            pain_points,
+            now,
+            now
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.into())
+    }
+
+    /// Get a persona by ID
+    pub async fn get_by_id(&self, id: i64) -> Result<Option<Persona>, sqlx::Error> {
+        let row = sqlx::query_as!(
+            PersonaRow,
+            r#"
+            SELECT id, name, description,
+                goals as "goals: Json<Vec<String>>",
+                pain_points as "pain_points: Json<Vec<String>>",
+                created_at, updated_at
+            FROM personas
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(Persona::from))
+    }
+
+    /// Get a persona by name, ignoring case
+    pub async fn get_by_name(&self, name: &str) -> Result<Option<Persona>, sqlx::Error> {
+        let row = sqlx::query_as!(
+            PersonaRow,
+            r#"
+            SELECT id, name, description,
+                goals as "goals: Json<Vec<String>>",
+                pain_points as "pain_points: Json<Vec<String>>",
+                created_at, updated_at
+            FROM personas
+            WHERE name = $1
+            "#,
+            name
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(Persona::from))
+    }
+
+    /// Get all personas with the number of user stories written for each
+    pub async fn get_all_with_usage(&self) -> Result<Vec<PersonaWithUsage>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT p.id, p.name, p.description,
+                p.goals as "goals: Json<Vec<String>>",
+                p.pain_points as "pain_points: Json<Vec<String>>",
+                p.created_at, p.updated_at,
+                COUNT(s.id) as "story_count!: i64"
+            FROM personas p
+            LEFT JOIN user_stories s ON s.persona_id = p.id AND s.deleted_at IS NULL
+            GROUP BY p.id
+            ORDER BY p.name
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| PersonaWithUsage {
+                persona: Persona {
+                    id: row.id,
+                    name: row.name,
+                    description: row.description,
+                    goals: row.goals.0,
+                    pain_points: row.pain_points.0,
+                    created_at: row.created_at,
+                    updated_at: row.updated_at,
+                },
+                story_count: row.story_count,
+            })
+            .collect())
+    }
+
+    /// Update a persona
+    pub async fn update(
+        &self,
+        id: i64,
+        request: UpdatePersonaRequest,
+    ) -> Result<Option<Persona>, sqlx::Error> {
+        let now = Utc::now().naive_utc();
+        let goals = request.goals.map(Json);
+        let pain_points = request.pain_points.map(Json);
+
+        let row = sqlx::query_as!(
+            PersonaRow,
+            r#"
+            UPDATE personas
+            SET
+                name = COALESCE($2, name),
+                description = COALESCE($3, description),
+                goals = COALESCE($4, goals),
+                pain_points = COALESCE($5, pain_points),
+                updated_at = $6
+            WHERE id = $1
+            RETURNING id, name, description,
+                goals as "goals: Json<Vec<String>>",
+                pain_points as "pain_points: Json<Vec<String>>",
+                created_at, updated_at
+            "#,
+            id,
+            request.name,
+            request.description,
+            goals,
+            pain_points,
+            now
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(Persona::from))
+    }
+
+    /// Delete a persona
+    pub async fn delete(&self, id: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM personas
+            WHERE id = $1
+            "#,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Count the user stories written for a persona, including those in the trash
+    pub async fn count_stories(&self, id: i64) -> Result<i64, sqlx::Error> {
+        let count = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as count
+            FROM user_stories
+            WHERE persona_id = $1
+            "#,
+            id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count.count)
+    }
+}
+
+/// Id of the persona with this name, ignoring case and surrounding whitespace, creating a
+/// bare persona when none exists
+pub(crate) async fn resolve_persona_id(
+    conn: &mut SqliteConnection,
+    name: &str,
+    now: NaiveDateTime,
+) -> Result<i64, sqlx::Error> {
+    let name = name.trim();
+    sqlx::query!(
+        r#"
+        INSERT INTO personas (name, created_at, updated_at)
+        VALUES ($1, $2, $3)
+        ON CONFLICT(name) DO NOTHING
+        "#,
+        name,
+        now,
+        now
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let persona = sqlx::query!(
+        r#"
+        SELECT id
+        FROM personas
+        WHERE name = $1
+        "#,
+        name
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(persona.id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_request() -> CreatePersonaRequest {
+        CreatePersonaRequest {
+            name: "Shopper".to_string(),
+            description: "Buys things online".to_string(),
+            goals: vec!["Check out quickly".to_string()],
+            pain_points: vec!["Hidden shipping costs".to_string()],
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_create_persona(pool: sqlx::SqlitePool) {
+        let repo = PersonaRepository::new(pool);
+
+        let persona = repo.create(create_test_request()).await.unwrap();
+        assert_eq!(persona.name, "Shopper");
+        assert_eq!(persona.goals, vec!["Check out quickly"]);
+        assert_eq!(persona.pain_points, vec!["Hidden shipping costs"]);
+
+        let found = repo.get_by_name("SHOPPER").await.unwrap().unwrap();
+        assert_eq!(found, persona);
+    }
+
+    #[sqlx::test]
+    async fn test_names_are_unique_ignoring_case(pool: sqlx::SqlitePool) {
+        let repo = PersonaRepository::new(pool);
+        repo.create(create_test_request()).await.unwrap();
+
+        let mut duplicate = create_test_request();
+        duplicate.name = "shopper".to_string();
+        assert!(repo.create(duplicate).await.is_err());
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_get_all_with_usage(pool: sqlx::SqlitePool) {
+        let repo = PersonaRepository::new(pool);
+
+        let personas = repo.get_all_with_usage().await.unwrap();
+        let counts: Vec<(&str, i64)> = personas
+            .iter()
+            .map(|p| (p.persona.name.as_str(), p.story_count))
+            .collect();
+        assert_eq!(
+            counts,
+            vec![("End User", 1), ("New User", 1), ("Registered User", 3)]
+        );
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_update_persona(pool: sqlx::SqlitePool) {
+        let repo = PersonaRepository::new(pool);
+
+        let request = UpdatePersonaRequest {
+            goals: Some(vec!["Find content fast".to_string()]),
+            ..Default::default()
+        };
+        let persona = repo.update(3, request).await.unwrap().unwrap();
+        assert_eq!(persona.name, "End User");
+        assert_eq!(persona.goals, vec!["Find content fast"]);
+
+        let missing = repo.update(99, UpdatePersonaRequest::default()).await;
+        assert!(missing.unwrap().is_none());
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_resolve_persona_id(pool: sqlx::SqlitePool) {
+        let mut conn = pool.acquire().await.unwrap();
+        let now = Utc::now().naive_utc();
+
+        let existing = resolve_persona_id(&mut conn, "registered user", now)
+            .await
+            .unwrap();
+        assert_eq!(existing, 1);
+
+        let created = resolve_persona_id(&mut conn, "Admin", now).await.unwrap();
+        let again = resolve_persona_id(&mut conn, "ADMIN", now).await.unwrap();
+        assert_eq!(created, again);
+        assert_ne!(created, existing);
+    }
+}
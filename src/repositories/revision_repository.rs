@@ -0,0 +1,295 @@
+use crate::database::{begin_write, DbPool};
+use crate::models::{StoryRevision, UserStory, UserStoryWithCriteria};
+use crate::repositories::claim_repository::blocking_claim;
+use crate::repositories::persona_repository::resolve_persona_id;
+use crate::repositories::VersionedWrite;
+use chrono::{NaiveDateTime, Utc};
+use sqlx::types::Json;
+use sqlx::SqliteConnection;
+use std::collections::HashSet;
+
+/// Outcome of `RevisionRepository::revert`
+#[derive(Debug)]
+pub enum RevertWrite {
+    /// The story was reverted, or left untouched because it moved on, is claimed or is gone
+    Story(VersionedWrite<UserStory>),
+    /// A criteria id of the snapshot belongs to another story by now; nothing was written
+    CriteriaIdTaken { id: String, owner: String },
+}
+
+/// Database row of a revision, with its snapshot still wrapped as JSON
+struct RevisionRow {
+    user_story_id: String,
+    revision: i64,
+    note: String,
+    created_at: NaiveDateTime,
+    snapshot: Json<UserStoryWithCriteria>,
+}
+
+impl From<RevisionRow> for StoryRevision {
+    fn from(row: RevisionRow) -> Self {
+        Self {
+            user_story_id: row.user_story_id,
+            revision: row.revision,
+            note: row.note,
+            created_at: row.created_at,
+            snapshot: row.snapshot.0,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RevisionRepository {
+    pool: DbPool,
+}
+
+impl RevisionRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Store a snapshot as the next revision of its story
+    pub async fn create(
+        &self,
+        snapshot: &UserStoryWithCriteria,
+        note: &str,
+    ) -> Result<StoryRevision, sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+        insert_revision(&mut conn, snapshot, note, Utc::now().naive_utc()).await
+    }
+
+    /// Get a revision of a user story by its number
+    pub async fn get(
+        &self,
+        user_story_id: &str,
+        revision: i64,
+    ) -> Result<Option<StoryRevision>, sqlx::Error> {
+        let row = sqlx::query_as!(
+            RevisionRow,
+            r#"
+            SELECT user_story_id, revision, note, created_at,
+                snapshot as "snapshot: Json<UserStoryWithCriteria>"
+            FROM story_revisions
+            WHERE user_story_id = $1 AND revision = $2
+            "#,
+            user_story_id,
+            revision
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(StoryRevision::from))
+    }
+
+    /// List the revisions of a user story, newest first
+    pub async fn list(&self, user_story_id: &str) -> Result<Vec<StoryRevision>, sqlx::Error> {
+        let rows = sqlx::query_as!(
+            RevisionRow,
+            r#"
+            SELECT user_story_id, revision, note, created_at,
+                snapshot as "snapshot: Json<UserStoryWithCriteria>"
+            FROM story_revisions
+            WHERE user_story_id = $1
+            ORDER BY revision DESC
+            "#,
+            user_story_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(StoryRevision::from).collect())
+    }
+
+    /// Story an acceptance criterion id belongs to, counting criteria in the trash
+    pub async fn criteria_owner(&self, id: &str) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT user_story_id
+            FROM acceptance_criteria
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Put a story and its criteria back the way a snapshot has them, in one transaction.
+    ///
+    /// `current` is saved first as a new revision with `note`, so the revert can itself be
+    /// reverted. Criteria missing from the snapshot move to the trash and criteria in the
+    /// trash or purged since are brought back. Nothing is written when the story is no
+    /// longer at the version of `current`, when an agent other than `agent_id` holds a live
+    /// claim on it, or when another story took one of the criteria ids of the snapshot.
+    pub async fn revert(
+        &self,
+        current: &UserStoryWithCriteria,
+        snapshot: &UserStoryWithCriteria,
+        note: &str,
+        agent_id: Option<&str>,
+    ) -> Result<RevertWrite, sqlx::Error> {
+        let id = &current.user_story.id;
+        let target = &snapshot.user_story;
+        let now = Utc::now().naive_utc();
+        let mut tx = begin_write(&self.pool).await?;
+
+        if let Some(claim) = blocking_claim(&mut tx, id, agent_id).await? {
+            return Ok(RevertWrite::Story(VersionedWrite::Claimed(claim)));
+        }
+
+        let persona_id = resolve_persona_id(&mut tx, &target.persona, now).await?;
+        let result = sqlx::query!(
+            r#"
+            UPDATE user_stories
+            SET title = $2, description = $3, persona_id = $4, updated_at = $5,
+                version = version + 1
+            WHERE id = $1 AND deleted_at IS NULL AND version = $6
+            "#,
+            id,
+            target.title,
+            target.description,
+            persona_id,
+            now,
+            current.user_story.version
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            drop(tx);
+            let current = sqlx::query_as!(
+                UserStory,
+                r#"
+                SELECT s.id, s.title, s.description, p.name as persona, ma.name as "assignee?",
+                    mr.name as "reviewer?", s.created_at, s.updated_at, s.version
+                FROM user_stories s
+                JOIN personas p ON p.id = s.persona_id
+                LEFT JOIN members ma ON ma.id = s.assignee_id
+                LEFT JOIN members mr ON mr.id = s.reviewer_id
+                WHERE s.id = $1 AND s.deleted_at IS NULL
+                "#,
+                id
+            )
+            .fetch_optional(&self.pool)
+            .await?;
+            return Ok(RevertWrite::Story(VersionedWrite::rejected(current)));
+        }
+
+        insert_revision(&mut tx, current, note, now).await?;
+
+        let kept: HashSet<&str> = snapshot
+            .acceptance_criteria
+            .iter()
+            .map(|c| c.id.as_str())
+            .collect();
+        for criteria in &current.acceptance_criteria {
+            if !kept.contains(criteria.id.as_str()) {
+                sqlx::query!(
+                    r#"
+                    UPDATE acceptance_criteria
+                    SET deleted_at = $2, version = version + 1
+                    WHERE id = $1 AND deleted_at IS NULL
+                    "#,
+                    criteria.id,
+                    now
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        for criteria in &snapshot.acceptance_criteria {
+            let result = sqlx::query!(
+                r#"
+                INSERT INTO acceptance_criteria (id, user_story_id, description, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT(id) DO UPDATE SET
+                    description = excluded.description,
+                    updated_at = $5,
+                    version = acceptance_criteria.version + 1,
+                    deleted_at = NULL
+                WHERE acceptance_criteria.user_story_id = excluded.user_story_id
+                    AND (acceptance_criteria.description != excluded.description
+                        OR acceptance_criteria.deleted_at IS NOT NULL)
+                "#,
+                criteria.id,
+                id,
+                criteria.description,
+                criteria.created_at,
+                now
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            // Unchanged criteria are left alone; an id now used by another story is not
+            if result.rows_affected() == 0 {
+                let owner = sqlx::query_scalar!(
+                    "SELECT user_story_id FROM acceptance_criteria WHERE id = $1",
+                    criteria.id
+                )
+                .fetch_one(&mut *tx)
+                .await?;
+                if &owner != id {
+                    // Dropping the transaction rolls back what was written so far
+                    return Ok(RevertWrite::CriteriaIdTaken {
+                        id: criteria.id.clone(),
+                        owner,
+                    });
+                }
+            }
+        }
+
+        let story = sqlx::query_as!(
+            UserStory,
+            r#"
+            SELECT s.id, s.title, s.description, p.name as persona, ma.name as "assignee?",
+                mr.name as "reviewer?", s.created_at, s.updated_at, s.version
+            FROM user_stories s
+            JOIN personas p ON p.id = s.persona_id
+            LEFT JOIN members ma ON ma.id = s.assignee_id
+            LEFT JOIN members mr ON mr.id = s.reviewer_id
+            WHERE s.id = $1
+            "#,
+            id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(RevertWrite::Story(VersionedWrite::Applied(story)))
+    }
+}
+
+/// Insert a snapshot under the next revision number of its story
+async fn insert_revision(
+    conn: &mut SqliteConnection,
+    snapshot: &UserStoryWithCriteria,
+    note: &str,
+    now: NaiveDateTime,
+) -> Result<StoryRevision, sqlx::Error> {
+    let user_story_id = &snapshot.user_story.id;
+    let json = Json(snapshot);
+    let revision = sqlx::query_scalar!(
+        r#"
+        INSERT INTO story_revisions (user_story_id, revision, note, snapshot, created_at)
+        SELECT $1, COALESCE(MAX(revision), 0) + 1, $2, $3, $4
+        FROM story_revisions
+        WHERE user_story_id = $1
+        RETURNING revision
+        "#,
+        user_story_id,
+        note,
+        json,
+        now
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(StoryRevision {
+        user_story_id: user_story_id.clone(),
+        revision,
+        note: note.to_string(),
+        created_at: now,
+        snapshot: snapshot.clone(),
+    })
+}
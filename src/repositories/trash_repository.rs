@@ -0,0 +1,219 @@
+use crate::database::{begin_write, DbPool};
+use crate::models::{
+    AcceptanceCriteria, PurgeReport, Trash, TrashedAcceptanceCriteria, TrashedUserStory, UserStory,
+};
+use chrono::NaiveDateTime;
+use std::collections::HashMap;
+
+#[derive(Clone)]
+pub struct TrashRepository {
+    pool: DbPool,
+}
+
+impl TrashRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// List deleted user stories with the criteria deleted along with them, and criteria
+    /// deleted on their own from live stories. Criteria deleted on their own before their
+    /// story was deleted are left out until the story is restored.
+    pub async fn list(&self) -> Result<Trash, sqlx::Error> {
+        let stories = sqlx::query!(
+            r#"
+            SELECT s.id, s.title, s.description, p.name as persona, ma.name as "assignee?",
+                mr.name as "reviewer?", s.created_at, s.updated_at, s.version,
+                s.deleted_at as "deleted_at!: NaiveDateTime"
+            FROM user_stories s
+            JOIN personas p ON p.id = s.persona_id
+            LEFT JOIN members ma ON ma.id = s.assignee_id
+            LEFT JOIN members mr ON mr.id = s.reviewer_id
+            WHERE s.deleted_at IS NOT NULL
+            ORDER BY s.deleted_at DESC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let criteria = sqlx::query!(
+            r#"
+            SELECT c.id, c.user_story_id, c.description, c.created_at, c.updated_at, c.version,
+                c.deleted_at as "deleted_at!: NaiveDateTime",
+                s.deleted_at as "story_deleted_at: NaiveDateTime"
+            FROM acceptance_criteria c
+            JOIN user_stories s ON s.id = c.user_story_id
+            WHERE c.deleted_at IS NOT NULL
+            ORDER BY c.created_at ASC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut trash = Trash::default();
+        let mut story_index = HashMap::new();
+        for row in stories {
+            story_index.insert(row.id.clone(), trash.user_stories.len());
+            trash.user_stories.push(TrashedUserStory {
+                user_story: UserStory {
+                    id: row.id,
+                    title: row.title,
+                    description: row.description,
+                    persona: row.persona,
+                    assignee: row.assignee,
+                    reviewer: row.reviewer,
+                    created_at: row.created_at,
+                    updated_at: row.updated_at,
+                    version: row.version,
+                },
+                acceptance_criteria: Vec::new(),
+                deleted_at: row.deleted_at,
+            });
+        }
+
+        for row in criteria {
+            let deleted_at = row.deleted_at;
+            let acceptance_criteria = AcceptanceCriteria {
+                id: row.id,
+                user_story_id: row.user_story_id,
+                description: row.description,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+                version: row.version,
+            };
+            match row.story_deleted_at {
+                None => trash.acceptance_criteria.push(TrashedAcceptanceCriteria {
+                    acceptance_criteria,
+                    deleted_at,
+                }),
+                Some(story_deleted_at) if story_deleted_at == deleted_at => {
+                    let index = story_index[&acceptance_criteria.user_story_id];
+                    trash.user_stories[index]
+                        .acceptance_criteria
+                        .push(acceptance_criteria);
+                }
+                Some(_) => {}
+            }
+        }
+        trash
+            .acceptance_criteria
+            .sort_by_key(|c| std::cmp::Reverse(c.deleted_at));
+
+        Ok(trash)
+    }
+
+    /// Bring a deleted user story back with the criteria deleted along with it. Returns
+    /// false when the story is not in the trash.
+    pub async fn restore_user_story(&self, id: &str) -> Result<bool, sqlx::Error> {
+        let mut tx = begin_write(&self.pool).await?;
+
+        let deleted_at = sqlx::query_scalar!(
+            r#"
+            SELECT deleted_at as "deleted_at!: NaiveDateTime"
+            FROM user_stories
+            WHERE id = $1 AND deleted_at IS NOT NULL
+            "#,
+            id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(deleted_at) = deleted_at else {
+            return Ok(false);
+        };
+
+        sqlx::query!(
+            r#"
+            UPDATE acceptance_criteria
+            SET deleted_at = NULL, version = version + 1
+            WHERE user_story_id = $1 AND deleted_at = $2
+            "#,
+            id,
+            deleted_at
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE user_stories
+            SET deleted_at = NULL, version = version + 1
+            WHERE id = $1
+            "#,
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Get acceptance criteria that are in the trash
+    pub async fn get_deleted_acceptance_criteria(
+        &self,
+        id: &str,
+    ) -> Result<Option<AcceptanceCriteria>, sqlx::Error> {
+        sqlx::query_as!(
+            AcceptanceCriteria,
+            r#"
+            SELECT id, user_story_id, description, created_at, updated_at, version
+            FROM acceptance_criteria
+            WHERE id = $1 AND deleted_at IS NOT NULL
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Bring deleted acceptance criteria back, provided their user story is not in the trash
+    pub async fn restore_acceptance_criteria(
+        &self,
+        id: &str,
+    ) -> Result<Option<AcceptanceCriteria>, sqlx::Error> {
+        sqlx::query_as!(
+            AcceptanceCriteria,
+            r#"
+            UPDATE acceptance_criteria
+            SET deleted_at = NULL, version = version + 1
+            WHERE id = $1 AND deleted_at IS NOT NULL
+                AND user_story_id IN (SELECT id FROM user_stories WHERE deleted_at IS NULL)
+            RETURNING id, user_story_id, description, created_at, updated_at, version
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Permanently delete everything that went to the trash before `cutoff`
+    pub async fn purge(&self, cutoff: NaiveDateTime) -> Result<PurgeReport, sqlx::Error> {
+        let mut tx = begin_write(&self.pool).await?;
+
+        // Criteria of a deleted story were deleted no later than the story, so they go first
+        let criteria = sqlx::query!(
+            r#"
+            DELETE FROM acceptance_criteria
+            WHERE deleted_at IS NOT NULL AND deleted_at < $1
+            "#,
+            cutoff
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let stories = sqlx::query!(
+            r#"
+            DELETE FROM user_stories
+            WHERE deleted_at IS NOT NULL AND deleted_at < $1
+            "#,
+            cutoff
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(PurgeReport {
+            user_stories: stories.rows_affected(),
+            acceptance_criteria: criteria.rows_affected(),
+        })
+    }
+}
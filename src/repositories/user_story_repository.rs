@@ -1,8 +1,43 @@
-use crate::database::DbPool;
-use crate::models::{CreateUserStoryRequest, UpdateUserStoryRequest, UserStory};
+use crate::database::{begin_write, DbPool};
+use crate::models::{
+    AcceptanceCriteria, AssignmentRole, CreateAcceptanceCriteriaRequest, CreateUserStoryRequest,
+    PageCursor, SortDirection, SortKey, StoryCursor, StoryQuery, StorySortField,
+    UpdateAcceptanceCriteriaRequest, UpdateUserStoryRequest, UserStory, UserStoryWithCriteria,
+};
+use crate::repositories::claim_repository::blocking_claim;
+use crate::repositories::persona_repository::resolve_persona_id;
+use crate::repositories::VersionedWrite;
 use chrono::Utc;
+use sqlx::{QueryBuilder, Sqlite};
 use std::collections::HashMap;
 
+/// Changes to a story and its criteria that `UserStoryRepository::apply_edit` writes together
+#[derive(Debug, Clone)]
+pub struct StoryEdit {
+    /// Story fields to change, if any; its expected version guards the whole edit
+    pub story: UpdateUserStoryRequest,
+    /// Criteria of the story to move to the trash, by id and expected version
+    pub deleted: Vec<(String, Option<i64>)>,
+    /// Criteria of the story to update, by id
+    pub updated: Vec<(String, UpdateAcceptanceCriteriaRequest)>,
+    /// New criteria of the story, each with its id already chosen
+    pub created: Vec<CreateAcceptanceCriteriaRequest>,
+}
+
+/// Outcome of `UserStoryRepository::apply_edit`
+#[derive(Debug)]
+pub enum StoryEditWrite {
+    /// The edit was written, or nothing was because the story moved on, is claimed or is gone
+    Story(VersionedWrite<UserStory>),
+    /// Criterion `id` moved on or is gone; nothing was written
+    Criteria {
+        id: String,
+        write: VersionedWrite<AcceptanceCriteria>,
+    },
+    /// The id of a new criterion is taken; nothing was written
+    CriteriaIdTaken { id: String },
+}
+
 #[derive(Clone)]
 pub struct UserStoryRepository {
     pool: DbPool,
@@ -13,28 +48,31 @@ impl UserStoryRepository {
         Self { pool }
     }
 
-    /// Create a new user story
+    /// Create a new user story, linking it to the persona with that name (created if missing)
     pub async fn create(&self, request: CreateUserStoryRequest) -> Result<UserStory, sqlx::Error> {
         let now = Utc::now().naive_utc();
+        let mut tx = begin_write(&self.pool).await?;
 
-        let user_story = sqlx::query_as!(
-            UserStory,
+        let persona_id = resolve_persona_id(&mut tx, &request.persona, now).await?;
+        let id = sqlx::query_scalar!(
             r#"
-            INSERT INTO user_stories (id, title, description, persona, created_at, updated_at)
+            INSERT INTO user_stories (id, title, description, persona_id, created_at, updated_at)
             VALUES ($1, $2, $3, $4, $5, $6)
-            RETURNING id, title, description, persona, created_at, updated_at
+            RETURNING id
             "#,
             request.id,
             request.title,
             request.description,
-            request.persona,
+            persona_id,
             now,
             now
         )
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await?;
 
-        Ok(user_story)
+        tx.commit().await?;
+
+        self.get_by_id(&id).await?.ok_or(sqlx::Error::RowNotFound)
     }
 
     /// Get a user story by ID
@@ -42,9 +80,13 @@ impl UserStoryRepository {
         let user_story = sqlx::query_as!(
             UserStory,
             r#"
-            SELECT id, title, description, persona, created_at, updated_at
-            FROM user_stories
-            WHERE id = $1
+            SELECT s.id, s.title, s.description, p.name as persona, ma.name as "assignee?",
+                mr.name as "reviewer?", s.created_at, s.updated_at, s.version
+            FROM user_stories s
+            JOIN personas p ON p.id = s.persona_id
+            LEFT JOIN members ma ON ma.id = s.assignee_id
+            LEFT JOIN members mr ON mr.id = s.reviewer_id
+            WHERE s.id = $1 AND s.deleted_at IS NULL
             "#,
             id
         )
@@ -54,14 +96,58 @@ impl UserStoryRepository {
         Ok(user_story)
     }
 
+    /// Get the live user stories among the given ids with a single query
+    pub async fn get_by_ids(&self, ids: &[String]) -> Result<Vec<UserStory>, sqlx::Error> {
+        let ids = serde_json::to_string(ids).expect("string slices serialize");
+
+        let user_stories = sqlx::query_as!(
+            UserStory,
+            r#"
+            SELECT s.id, s.title, s.description, p.name as persona, ma.name as "assignee?",
+                mr.name as "reviewer?", s.created_at, s.updated_at, s.version
+            FROM user_stories s
+            JOIN personas p ON p.id = s.persona_id
+            LEFT JOIN members ma ON ma.id = s.assignee_id
+            LEFT JOIN members mr ON mr.id = s.reviewer_id
+            WHERE s.id IN (SELECT value FROM json_each($1)) AND s.deleted_at IS NULL
+            "#,
+            ids
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(user_stories)
+    }
+
+    /// Get the id of the persona a user story is written for
+    pub async fn get_persona_id(&self, id: &str) -> Result<Option<i64>, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+            SELECT persona_id as "persona_id!: i64"
+            FROM user_stories
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| row.persona_id))
+    }
+
     /// Get all user stories
     pub async fn get_all(&self) -> Result<Vec<UserStory>, sqlx::Error> {
         let user_stories = sqlx::query_as!(
             UserStory,
             r#"
-            SELECT id, title, description, persona, created_at, updated_at
-            FROM user_stories
-            ORDER BY created_at DESC
+            SELECT s.id, s.title, s.description, p.name as persona, ma.name as "assignee?",
+                mr.name as "reviewer?", s.created_at, s.updated_at, s.version
+            FROM user_stories s
+            JOIN personas p ON p.id = s.persona_id
+            LEFT JOIN members ma ON ma.id = s.assignee_id
+            LEFT JOIN members mr ON mr.id = s.reviewer_id
+            WHERE s.deleted_at IS NULL
+            ORDER BY s.created_at DESC
             "#
         )
         .fetch_all(&self.pool)
@@ -70,22 +156,67 @@ impl UserStoryRepository {
         Ok(user_stories)
     }
 
-    /// Get user stories with pagination
-    pub async fn get_paginated(
+    /// Get all user stories with their acceptance criteria in two queries, however many
+    /// stories there are
+    pub async fn get_all_with_criteria(&self) -> Result<Vec<UserStoryWithCriteria>, sqlx::Error> {
+        let user_stories = self.get_all().await?;
+        let criteria = sqlx::query_as!(
+            AcceptanceCriteria,
+            r#"
+            SELECT c.id, c.user_story_id, c.description, c.created_at, c.updated_at, c.version
+            FROM acceptance_criteria c
+            JOIN user_stories s ON s.id = c.user_story_id
+            WHERE c.deleted_at IS NULL AND s.deleted_at IS NULL
+            ORDER BY c.created_at ASC, c.id ASC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut criteria_by_story: HashMap<String, Vec<AcceptanceCriteria>> = HashMap::new();
+        for criteria in criteria {
+            criteria_by_story
+                .entry(criteria.user_story_id.clone())
+                .or_default()
+                .push(criteria);
+        }
+
+        Ok(user_stories
+            .into_iter()
+            .map(|user_story| UserStoryWithCriteria {
+                acceptance_criteria: criteria_by_story.remove(&user_story.id).unwrap_or_default(),
+                user_story,
+            })
+            .collect())
+    }
+
+    /// Get up to `limit` user stories after a cursor, newest first. Stories created meanwhile
+    /// sort before any cursor, so pages never skip or repeat a story.
+    pub async fn get_page(
         &self,
+        after: Option<&PageCursor>,
         limit: i64,
-        offset: i64,
     ) -> Result<Vec<UserStory>, sqlx::Error> {
+        let created_at = after.map(|cursor| cursor.created_at);
+        let id = after.map(|cursor| cursor.id.as_str());
+
         let user_stories = sqlx::query_as!(
             UserStory,
             r#"
-            SELECT id, title, description, persona, created_at, updated_at
-            FROM user_stories
-            ORDER BY created_at DESC
-            LIMIT $1 OFFSET $2
+            SELECT s.id, s.title, s.description, p.name as persona, ma.name as "assignee?",
+                mr.name as "reviewer?", s.created_at, s.updated_at, s.version
+            FROM user_stories s
+            JOIN personas p ON p.id = s.persona_id
+            LEFT JOIN members ma ON ma.id = s.assignee_id
+            LEFT JOIN members mr ON mr.id = s.reviewer_id
+            WHERE s.deleted_at IS NULL
+                AND ($1 IS NULL OR s.created_at < $1 OR (s.created_at = $1 AND s.id < $2))
+            ORDER BY s.created_at DESC, s.id DESC
+            LIMIT $3
             "#,
-            limit,
-            offset
+            created_at,
+            id,
+            limit
         )
         .fetch_all(&self.pool)
         .await?;
@@ -93,70 +224,396 @@ impl UserStoryRepository {
         Ok(user_stories)
     }
 
-    /// Update a user story
+    /// Update a user story as `agent_id`, unless it has moved past the expected version of the
+    /// request or another agent holds a live claim on it
     pub async fn update(
         &self,
         id: &str,
         request: UpdateUserStoryRequest,
-    ) -> Result<Option<UserStory>, sqlx::Error> {
-        // First, check if the user story exists
-        let existing = self.get_by_id(id).await?;
-        if existing.is_none() {
-            return Ok(None);
-        }
-
+        agent_id: Option<&str>,
+    ) -> Result<VersionedWrite<UserStory>, sqlx::Error> {
         let now = Utc::now().naive_utc();
+        let mut tx = begin_write(&self.pool).await?;
 
-        let user_story = sqlx::query_as!(
-            UserStory,
+        if let Some(claim) = blocking_claim(&mut tx, id, agent_id).await? {
+            return Ok(VersionedWrite::Claimed(claim));
+        }
+        let persona_id = match request.persona {
+            Some(ref persona) => Some(resolve_persona_id(&mut tx, persona, now).await?),
+            None => None,
+        };
+        let result = sqlx::query!(
             r#"
             UPDATE user_stories
             SET
                 title = COALESCE($2, title),
                 description = COALESCE($3, description),
-                persona = COALESCE($4, persona),
-                updated_at = $5
-            WHERE id = $1
-            RETURNING id, title, description, persona, created_at, updated_at
+                persona_id = COALESCE($4, persona_id),
+                updated_at = $5,
+                version = version + 1
+            WHERE id = $1 AND deleted_at IS NULL AND ($6 IS NULL OR version = $6)
             "#,
             id,
             request.title,
             request.description,
-            request.persona,
-            now
+            persona_id,
+            now,
+            request.expected_version
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            // Dropping the transaction rolls back a persona created for this update
+            drop(tx);
+            return Ok(VersionedWrite::rejected(self.get_by_id(id).await?));
+        }
+        tx.commit().await?;
+
+        let story = self.get_by_id(id).await?.ok_or(sqlx::Error::RowNotFound)?;
+        Ok(VersionedWrite::Applied(story))
+    }
+
+    /// Write an edit of a story and its criteria as `agent_id`, in one transaction.
+    ///
+    /// Nothing is written when another agent holds a live claim on the story, when the story
+    /// or one of the edited criteria is gone or has moved past its expected version, or when
+    /// the id of a new criterion is taken, including by a criterion in the trash.
+    pub async fn apply_edit(
+        &self,
+        id: &str,
+        edit: StoryEdit,
+        agent_id: Option<&str>,
+    ) -> Result<StoryEditWrite, sqlx::Error> {
+        let now = Utc::now().naive_utc();
+        let mut tx = begin_write(&self.pool).await?;
+
+        if let Some(claim) = blocking_claim(&mut tx, id, agent_id).await? {
+            return Ok(StoryEditWrite::Story(VersionedWrite::Claimed(claim)));
+        }
+
+        let request = &edit.story;
+        let story_written = if request.title.is_some()
+            || request.description.is_some()
+            || request.persona.is_some()
+        {
+            let persona_id = match request.persona {
+                Some(ref persona) => Some(resolve_persona_id(&mut tx, persona, now).await?),
+                None => None,
+            };
+            sqlx::query!(
+                r#"
+                UPDATE user_stories
+                SET
+                    title = COALESCE($2, title),
+                    description = COALESCE($3, description),
+                    persona_id = COALESCE($4, persona_id),
+                    updated_at = $5,
+                    version = version + 1
+                WHERE id = $1 AND deleted_at IS NULL AND ($6 IS NULL OR version = $6)
+                "#,
+                id,
+                request.title,
+                request.description,
+                persona_id,
+                now,
+                request.expected_version
+            )
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+                > 0
+        } else {
+            sqlx::query_scalar!(
+                r#"
+                SELECT version
+                FROM user_stories
+                WHERE id = $1 AND deleted_at IS NULL AND ($2 IS NULL OR version = $2)
+                "#,
+                id,
+                request.expected_version
+            )
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_some()
+        };
+        if !story_written {
+            drop(tx);
+            return Ok(StoryEditWrite::Story(VersionedWrite::rejected(
+                self.get_by_id(id).await?,
+            )));
+        }
+
+        for (criteria_id, expected_version) in &edit.deleted {
+            let result = sqlx::query!(
+                r#"
+                UPDATE acceptance_criteria
+                SET deleted_at = $3, version = version + 1
+                WHERE id = $1 AND user_story_id = $2 AND deleted_at IS NULL
+                    AND ($4 IS NULL OR version = $4)
+                "#,
+                criteria_id,
+                id,
+                now,
+                expected_version
+            )
+            .execute(&mut *tx)
+            .await?;
+            if result.rows_affected() == 0 {
+                // Dropping the transaction rolls back what was written so far
+                drop(tx);
+                return self.criteria_rejected(criteria_id).await;
+            }
+        }
+
+        for (criteria_id, request) in &edit.updated {
+            let result = sqlx::query!(
+                r#"
+                UPDATE acceptance_criteria
+                SET
+                    description = COALESCE($3, description),
+                    updated_at = $4,
+                    version = version + 1
+                WHERE id = $1 AND user_story_id = $2 AND deleted_at IS NULL
+                    AND ($5 IS NULL OR version = $5)
+                "#,
+                criteria_id,
+                id,
+                request.description,
+                now,
+                request.expected_version
+            )
+            .execute(&mut *tx)
+            .await?;
+            if result.rows_affected() == 0 {
+                drop(tx);
+                return self.criteria_rejected(criteria_id).await;
+            }
+        }
+
+        for request in &edit.created {
+            let result = sqlx::query!(
+                r#"
+                INSERT INTO acceptance_criteria (id, user_story_id, description, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT(id) DO NOTHING
+                "#,
+                request.id,
+                id,
+                request.description,
+                now,
+                now
+            )
+            .execute(&mut *tx)
+            .await?;
+            if result.rows_affected() == 0 {
+                drop(tx);
+                return Ok(StoryEditWrite::CriteriaIdTaken {
+                    id: request.id.clone().unwrap_or_default(),
+                });
+            }
+        }
+
+        tx.commit().await?;
+
+        let story = self.get_by_id(id).await?.ok_or(sqlx::Error::RowNotFound)?;
+        Ok(StoryEditWrite::Story(VersionedWrite::Applied(story)))
+    }
+
+    /// Outcome of an edit that stopped at criterion `id`, given as it is now
+    async fn criteria_rejected(&self, id: &str) -> Result<StoryEditWrite, sqlx::Error> {
+        let current = sqlx::query_as!(
+            AcceptanceCriteria,
+            r#"
+            SELECT id, user_story_id, description, created_at, updated_at, version
+            FROM acceptance_criteria
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+            id
         )
         .fetch_optional(&self.pool)
         .await?;
 
-        Ok(user_story)
+        Ok(StoryEditWrite::Criteria {
+            id: id.to_string(),
+            write: VersionedWrite::rejected(current),
+        })
+    }
+
+    /// Set or clear the member in a role on a user story as `agent_id`, unless it has moved
+    /// past the expected version or another agent holds a live claim on it
+    pub async fn set_member(
+        &self,
+        id: &str,
+        role: AssignmentRole,
+        member_id: Option<i64>,
+        expected_version: Option<i64>,
+        agent_id: Option<&str>,
+    ) -> Result<VersionedWrite<UserStory>, sqlx::Error> {
+        let now = Utc::now().naive_utc();
+        let mut tx = begin_write(&self.pool).await?;
+
+        if let Some(claim) = blocking_claim(&mut tx, id, agent_id).await? {
+            return Ok(VersionedWrite::Claimed(claim));
+        }
+
+        let result = match role {
+            AssignmentRole::Assignee => {
+                sqlx::query!(
+                    r#"
+                    UPDATE user_stories
+                    SET assignee_id = $2, updated_at = $3, version = version + 1
+                    WHERE id = $1 AND deleted_at IS NULL AND ($4 IS NULL OR version = $4)
+                    "#,
+                    id,
+                    member_id,
+                    now,
+                    expected_version
+                )
+                .execute(&mut *tx)
+                .await?
+            }
+            AssignmentRole::Reviewer => {
+                sqlx::query!(
+                    r#"
+                    UPDATE user_stories
+                    SET reviewer_id = $2, updated_at = $3, version = version + 1
+                    WHERE id = $1 AND deleted_at IS NULL AND ($4 IS NULL OR version = $4)
+                    "#,
+                    id,
+                    member_id,
+                    now,
+                    expected_version
+                )
+                .execute(&mut *tx)
+                .await?
+            }
+        };
+
+        tx.commit().await?;
+
+        let story = self.get_by_id(id).await?;
+        if result.rows_affected() == 0 {
+            return Ok(VersionedWrite::rejected(story));
+        }
+        Ok(VersionedWrite::Applied(
+            story.ok_or(sqlx::Error::RowNotFound)?,
+        ))
     }
 
-    /// Delete a user story
-    pub async fn delete(&self, id: &str) -> Result<bool, sqlx::Error> {
+    /// Get the user stories a member is assigned to, reviews, or either when `role` is `None`
+    pub async fn get_by_member(
+        &self,
+        member_id: i64,
+        role: Option<AssignmentRole>,
+    ) -> Result<Vec<UserStory>, sqlx::Error> {
+        let as_assignee = role != Some(AssignmentRole::Reviewer);
+        let as_reviewer = role != Some(AssignmentRole::Assignee);
+
+        let user_stories = sqlx::query_as!(
+            UserStory,
+            r#"
+            SELECT s.id, s.title, s.description, p.name as persona, ma.name as "assignee?",
+                mr.name as "reviewer?", s.created_at, s.updated_at, s.version
+            FROM user_stories s
+            JOIN personas p ON p.id = s.persona_id
+            LEFT JOIN members ma ON ma.id = s.assignee_id
+            LEFT JOIN members mr ON mr.id = s.reviewer_id
+            WHERE s.deleted_at IS NULL
+                AND ((s.assignee_id = $1 AND $2) OR (s.reviewer_id = $1 AND $3))
+            ORDER BY s.created_at DESC
+            "#,
+            member_id,
+            as_assignee,
+            as_reviewer
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(user_stories)
+    }
+
+    /// Move a user story and its acceptance criteria to the trash as `agent_id`, unless the
+    /// story has moved past the expected version or another agent holds a live claim on it
+    pub async fn delete(
+        &self,
+        id: &str,
+        expected_version: Option<i64>,
+        agent_id: Option<&str>,
+    ) -> Result<VersionedWrite<UserStory>, sqlx::Error> {
+        let Some(story) = self.get_by_id(id).await? else {
+            return Ok(VersionedWrite::NotFound);
+        };
+
+        let now = Utc::now().naive_utc();
+        let mut tx = begin_write(&self.pool).await?;
+
+        if let Some(claim) = blocking_claim(&mut tx, id, agent_id).await? {
+            return Ok(VersionedWrite::Claimed(claim));
+        }
+
         let result = sqlx::query!(
             r#"
-            DELETE FROM user_stories
-            WHERE id = $1
+            UPDATE user_stories
+            SET deleted_at = $2, version = version + 1
+            WHERE id = $1 AND deleted_at IS NULL AND ($3 IS NULL OR version = $3)
             "#,
-            id
+            id,
+            now,
+            expected_version
         )
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await?;
 
-        Ok(result.rows_affected() > 0)
+        if result.rows_affected() == 0 {
+            drop(tx);
+            return Ok(VersionedWrite::rejected(self.get_by_id(id).await?));
+        }
+
+        // Criteria share the deletion time of their story so that restoring the story
+        // brings back exactly these
+        sqlx::query!(
+            r#"
+            UPDATE acceptance_criteria
+            SET deleted_at = $2, version = version + 1
+            WHERE user_story_id = $1 AND deleted_at IS NULL
+            "#,
+            id,
+            now
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(VersionedWrite::Applied(story))
     }
 
-    /// Search user stories by title or description
+    /// Whether a user story with this id exists, counting stories in the trash
+    pub async fn exists(&self, id: &str) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM user_stories WHERE id = $1) as "exists!: bool""#,
+            id
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Search user stories by title, description or persona name
     pub async fn search(&self, query: &str) -> Result<Vec<UserStory>, sqlx::Error> {
         let search_pattern = format!("%{}%", query);
 
         let user_stories = sqlx::query_as!(
             UserStory,
             r#"
-            SELECT id, title, description, persona, created_at, updated_at
-            FROM user_stories
-            WHERE title LIKE $1 OR description LIKE $1 OR persona LIKE $1
-            ORDER BY created_at DESC
+            SELECT s.id, s.title, s.description, p.name as persona, ma.name as "assignee?",
+                mr.name as "reviewer?", s.created_at, s.updated_at, s.version
+            FROM user_stories s
+            JOIN personas p ON p.id = s.persona_id
+            LEFT JOIN members ma ON ma.id = s.assignee_id
+            LEFT JOIN members mr ON mr.id = s.reviewer_id
+            WHERE (s.title LIKE $1 OR s.description LIKE $1 OR p.name LIKE $1)
+                AND s.deleted_at IS NULL
+            ORDER BY s.created_at DESC
             "#,
             search_pattern
         )
@@ -166,15 +623,19 @@ impl UserStoryRepository {
         Ok(user_stories)
     }
 
-    /// Get user stories by persona
+    /// Get user stories by persona name, ignoring case
     pub async fn get_by_persona(&self, persona: &str) -> Result<Vec<UserStory>, sqlx::Error> {
         let user_stories = sqlx::query_as!(
             UserStory,
             r#"
-            SELECT id, title, description, persona, created_at, updated_at
-            FROM user_stories
-            WHERE persona = $1
-            ORDER BY created_at DESC
+            SELECT s.id, s.title, s.description, p.name as persona, ma.name as "assignee?",
+                mr.name as "reviewer?", s.created_at, s.updated_at, s.version
+            FROM user_stories s
+            JOIN personas p ON p.id = s.persona_id
+            LEFT JOIN members ma ON ma.id = s.assignee_id
+            LEFT JOIN members mr ON mr.id = s.reviewer_id
+            WHERE p.name = $1 AND s.deleted_at IS NULL
+            ORDER BY s.created_at DESC
             "#,
             persona
         )
@@ -184,12 +645,113 @@ impl UserStoryRepository {
         Ok(user_stories)
     }
 
+    /// Get the user stories matching every filter set on the query, in its sort order,
+    /// starting after a cursor in that order
+    pub async fn query(
+        &self,
+        query: &StoryQuery,
+        after: Option<&StoryCursor>,
+    ) -> Result<Vec<UserStory>, sqlx::Error> {
+        let mut builder = QueryBuilder::<Sqlite>::new(
+            r#"
+            SELECT s.id, s.title, s.description, p.name as persona, ma.name as assignee,
+                mr.name as reviewer, s.created_at, s.updated_at, s.version
+            FROM user_stories s
+            JOIN personas p ON p.id = s.persona_id
+            LEFT JOIN members ma ON ma.id = s.assignee_id
+            LEFT JOIN members mr ON mr.id = s.reviewer_id
+            LEFT JOIN (
+                SELECT user_story_id, COUNT(*) as criteria_count
+                FROM acceptance_criteria
+                WHERE deleted_at IS NULL
+                GROUP BY user_story_id
+            ) c ON c.user_story_id = s.id
+            WHERE s.deleted_at IS NULL
+            "#,
+        );
+
+        push_filters(&mut builder, query);
+
+        // Column and direction come from enums, never from the caller's text
+        let column = sort_column(query.sort_by);
+        let (direction, beyond) = match query.direction {
+            SortDirection::Asc => ("ASC", ">"),
+            SortDirection::Desc => ("DESC", "<"),
+        };
+        if let Some(after) = after {
+            // SQLite sorts missing assignees and reviewers before every name
+            builder.push(" AND (");
+            match &after.key {
+                SortKey::Text(None) => {
+                    builder
+                        .push(format_args!("({column} IS NULL AND s.id {beyond} "))
+                        .push_bind(after.id.clone())
+                        .push(")");
+                    if query.direction == SortDirection::Asc {
+                        builder.push(format_args!(" OR {column} IS NOT NULL"));
+                    }
+                }
+                key => {
+                    builder.push(format_args!("{column} {beyond} "));
+                    push_sort_key(&mut builder, key);
+                    builder.push(format_args!(" OR ({column} = "));
+                    push_sort_key(&mut builder, key);
+                    builder
+                        .push(format_args!(" AND s.id {beyond} "))
+                        .push_bind(after.id.clone())
+                        .push(")");
+                    if query.direction == SortDirection::Desc {
+                        builder.push(format_args!(" OR {column} IS NULL"));
+                    }
+                }
+            }
+            builder.push(")");
+        }
+        builder.push(format_args!(
+            " ORDER BY {column} {direction}, s.id {direction}"
+        ));
+
+        if let Some(limit) = query.limit {
+            builder.push(" LIMIT ").push_bind(limit);
+        }
+
+        builder
+            .build_query_as::<UserStory>()
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Count the user stories matching every filter set on the query, ignoring its page
+    pub async fn count_query(&self, query: &StoryQuery) -> Result<i64, sqlx::Error> {
+        let mut builder = QueryBuilder::<Sqlite>::new(
+            r#"
+            SELECT COUNT(*)
+            FROM user_stories s
+            JOIN personas p ON p.id = s.persona_id
+            LEFT JOIN (
+                SELECT user_story_id, COUNT(*) as criteria_count
+                FROM acceptance_criteria
+                WHERE deleted_at IS NULL
+                GROUP BY user_story_id
+            ) c ON c.user_story_id = s.id
+            WHERE s.deleted_at IS NULL
+            "#,
+        );
+        push_filters(&mut builder, query);
+
+        builder
+            .build_query_scalar::<i64>()
+            .fetch_one(&self.pool)
+            .await
+    }
+
     /// Get count of all user stories
     pub async fn count(&self) -> Result<i64, sqlx::Error> {
         let count = sqlx::query!(
             r#"
             SELECT COUNT(*) as count
             FROM user_stories
+            WHERE deleted_at IS NULL
             "#
         )
         .fetch_one(&self.pool)
@@ -198,6 +760,78 @@ impl UserStoryRepository {
         Ok(count.count)
     }
 
+    /// Count the user stories written for each persona that has any
+    pub async fn count_by_persona(&self) -> Result<HashMap<String, i64>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT p.name as persona, COUNT(*) as "count!: i64"
+            FROM user_stories s
+            JOIN personas p ON p.id = s.persona_id
+            WHERE s.deleted_at IS NULL
+            GROUP BY p.id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.persona, row.count))
+            .collect())
+    }
+
+    /// Count the user stories of each member in a role, leaving out members with none
+    pub async fn count_by_member(
+        &self,
+        role: AssignmentRole,
+    ) -> Result<HashMap<String, i64>, sqlx::Error> {
+        let counts = match role {
+            AssignmentRole::Assignee => sqlx::query!(
+                r#"
+                SELECT m.name, COUNT(*) as "count!: i64"
+                FROM user_stories s
+                JOIN members m ON m.id = s.assignee_id
+                WHERE s.deleted_at IS NULL
+                GROUP BY m.id
+                "#
+            )
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|row| (row.name, row.count))
+            .collect(),
+            AssignmentRole::Reviewer => sqlx::query!(
+                r#"
+                SELECT m.name, COUNT(*) as "count!: i64"
+                FROM user_stories s
+                JOIN members m ON m.id = s.reviewer_id
+                WHERE s.deleted_at IS NULL
+                GROUP BY m.id
+                "#
+            )
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|row| (row.name, row.count))
+            .collect(),
+        };
+
+        Ok(counts)
+    }
+
+    /// Count the user stories nobody is assigned to
+    pub async fn count_unassigned(&self) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!: i64"
+            FROM user_stories
+            WHERE deleted_at IS NULL AND assignee_id IS NULL
+            "#
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+
     /// Get user stories grouped by persona
     pub async fn get_grouped_by_persona(
         &self,
@@ -216,13 +850,109 @@ impl UserStoryRepository {
     }
 }
 
+/// Append the filters of a query to a statement over stories `s`, personas `p` and
+/// criteria counts `c`
+fn push_filters<'q>(builder: &mut QueryBuilder<'q, Sqlite>, query: &'q StoryQuery) {
+    if !query.personas.is_empty() {
+        // The NOCASE collation of the name column makes IN ignore case
+        builder.push(" AND p.name IN (");
+        let mut personas = builder.separated(", ");
+        for persona in &query.personas {
+            personas.push_bind(persona.as_str());
+        }
+        builder.push(")");
+    }
+    if let Some(text) = &query.text {
+        let pattern = format!("%{}%", escape_like(text));
+        builder
+            .push(" AND (s.title LIKE ")
+            .push_bind(pattern.clone())
+            .push(" ESCAPE '\\' OR s.description LIKE ")
+            .push_bind(pattern.clone())
+            .push(" ESCAPE '\\' OR p.name LIKE ")
+            .push_bind(pattern)
+            .push(" ESCAPE '\\')");
+    }
+    if let Some(created_after) = query.created_after {
+        builder
+            .push(" AND s.created_at >= ")
+            .push_bind(created_after);
+    }
+    if let Some(created_before) = query.created_before {
+        builder
+            .push(" AND s.created_at < ")
+            .push_bind(created_before);
+    }
+    if let Some(updated_after) = query.updated_after {
+        builder
+            .push(" AND s.updated_at >= ")
+            .push_bind(updated_after);
+    }
+    if let Some(updated_before) = query.updated_before {
+        builder
+            .push(" AND s.updated_at < ")
+            .push_bind(updated_before);
+    }
+    match query.has_criteria {
+        Some(true) => {
+            builder.push(" AND COALESCE(c.criteria_count, 0) > 0");
+        }
+        Some(false) => {
+            builder.push(" AND COALESCE(c.criteria_count, 0) = 0");
+        }
+        None => {}
+    }
+    if let Some(min_criteria) = query.min_criteria {
+        builder
+            .push(" AND COALESCE(c.criteria_count, 0) >= ")
+            .push_bind(min_criteria);
+    }
+    if let Some(max_criteria) = query.max_criteria {
+        builder
+            .push(" AND COALESCE(c.criteria_count, 0) <= ")
+            .push_bind(max_criteria);
+    }
+}
+
+/// SQL expression a `StorySortField` orders by
+fn sort_column(field: StorySortField) -> &'static str {
+    match field {
+        StorySortField::Id => "s.id",
+        StorySortField::Title => "s.title",
+        StorySortField::Persona => "p.name",
+        StorySortField::Assignee => "ma.name",
+        StorySortField::Reviewer => "mr.name",
+        StorySortField::CreatedAt => "s.created_at",
+        StorySortField::UpdatedAt => "s.updated_at",
+        StorySortField::Version => "s.version",
+        StorySortField::CriteriaCount => "COALESCE(c.criteria_count, 0)",
+    }
+}
+
+/// Bind the sort key of a cursor
+fn push_sort_key(builder: &mut QueryBuilder<'_, Sqlite>, key: &SortKey) {
+    match key {
+        SortKey::Text(text) => builder.push_bind(text.clone()),
+        SortKey::Time(time) => builder.push_bind(*time),
+        SortKey::Number(number) => builder.push_bind(*number),
+    };
+}
+
+/// Escape the LIKE wildcards in text to be matched literally, with `\` as escape character
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::repositories::{AcceptanceCriteriaRepository, ClaimRepository};
 
     fn create_test_request() -> CreateUserStoryRequest {
         CreateUserStoryRequest {
-            id: "US-TEST-001".to_string(),
+            id: Some("US-TEST-001".to_string()),
             title: "Test User Story".to_string(),
             description: "As a user, I want to test this functionality".to_string(),
             persona: "Test User".to_string(),
@@ -238,7 +968,7 @@ mod tests {
         assert!(result.is_ok());
 
         let user_story = result.unwrap();
-        assert_eq!(user_story.id, request.id);
+        assert_eq!(Some(user_story.id), request.id);
         assert_eq!(user_story.title, request.title);
         assert_eq!(user_story.description, request.description);
         assert_eq!(user_story.persona, request.persona);
@@ -281,29 +1011,139 @@ mod tests {
             title: Some("Updated User Login Feature".to_string()),
             description: None,
             persona: Some("Updated Persona".to_string()),
+            expected_version: None,
         };
 
-        let result = repo.update("US-001", update_request).await;
+        let result = repo.update("US-001", update_request, None).await;
         assert!(result.is_ok());
 
-        let user_story = result.unwrap();
-        assert!(user_story.is_some());
-
-        let user_story = user_story.unwrap();
+        let VersionedWrite::Applied(user_story) = result.unwrap() else {
+            panic!("unguarded update should be applied");
+        };
         assert_eq!(user_story.title, "Updated User Login Feature");
+        assert_eq!(user_story.version, 2);
         assert_eq!(user_story.persona, "Updated Persona");
         // Description should remain unchanged from fixture
         assert!(user_story.description.contains("registered user"));
     }
 
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_update_with_stale_version(pool: sqlx::SqlitePool) {
+        let repo = UserStoryRepository::new(pool);
+        let update = |title: &str| UpdateUserStoryRequest {
+            title: Some(title.to_string()),
+            description: None,
+            persona: Some("Auditor".to_string()),
+            expected_version: Some(1),
+        };
+
+        let first = repo.update("US-001", update("First"), None).await.unwrap();
+        assert!(matches!(first, VersionedWrite::Applied(ref story) if story.version == 2));
+
+        // The second writer read version 1 too and must not overwrite the first
+        let VersionedWrite::Conflict(current) =
+            repo.update("US-001", update("Second"), None).await.unwrap()
+        else {
+            panic!("stale update should conflict");
+        };
+        assert_eq!(current.title, "First");
+        assert_eq!(current.version, 2);
+
+        assert!(matches!(
+            repo.delete("US-001", Some(1), None).await.unwrap(),
+            VersionedWrite::Conflict(_)
+        ));
+        assert!(matches!(
+            repo.update("US-999", update("Missing"), None)
+                .await
+                .unwrap(),
+            VersionedWrite::NotFound
+        ));
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_update_claimed_story(pool: sqlx::SqlitePool) {
+        let repo = UserStoryRepository::new(pool.clone());
+        ClaimRepository::new(pool)
+            .claim("US-001", "agent-a", chrono::Duration::minutes(5))
+            .await
+            .unwrap();
+        let update = || UpdateUserStoryRequest {
+            title: Some("Claimed".to_string()),
+            description: None,
+            persona: None,
+            expected_version: None,
+        };
+
+        for agent_id in [None, Some("agent-b")] {
+            assert!(matches!(
+                repo.update("US-001", update(), agent_id).await.unwrap(),
+                VersionedWrite::Claimed(ref claim) if claim.agent_id == "agent-a"
+            ));
+            assert!(matches!(
+                repo.delete("US-001", None, agent_id).await.unwrap(),
+                VersionedWrite::Claimed(_)
+            ));
+        }
+        assert!(matches!(
+            repo.update("US-001", update(), Some("agent-a")).await.unwrap(),
+            VersionedWrite::Applied(ref story) if story.title == "Claimed"
+        ));
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_apply_edit_rolls_back(pool: sqlx::SqlitePool) {
+        let repo = UserStoryRepository::new(pool.clone());
+        let criteria_repo = AcceptanceCriteriaRepository::new(pool);
+        let edit = StoryEdit {
+            story: UpdateUserStoryRequest {
+                title: Some("Sign In".to_string()),
+                description: None,
+                persona: None,
+                expected_version: None,
+            },
+            deleted: vec![("AC-003".to_string(), None)],
+            updated: vec![(
+                "AC-002".to_string(),
+                UpdateAcceptanceCriteriaRequest {
+                    description: Some(
+                        "Given a typo, When I log in, Then I see an error".to_string(),
+                    ),
+                    expected_version: None,
+                },
+            )],
+            // AC-004 belongs to US-002
+            created: vec![CreateAcceptanceCriteriaRequest {
+                id: Some("AC-004".to_string()),
+                user_story_id: "US-001".to_string(),
+                description: "Given a taken id, When I add it, Then nothing changes".to_string(),
+            }],
+        };
+
+        let version = repo.get_by_id("US-001").await.unwrap().unwrap().version;
+        let write = repo.apply_edit("US-001", edit, None).await.unwrap();
+        assert!(matches!(write, StoryEditWrite::CriteriaIdTaken { ref id } if id == "AC-004"));
+
+        let story = repo.get_by_id("US-001").await.unwrap().unwrap();
+        assert_eq!(story.title, "User Login Feature");
+        assert_eq!(story.version, version);
+        let criteria = criteria_repo.get_by_user_story_id("US-001").await.unwrap();
+        assert_eq!(criteria.len(), 3);
+        let ac_002 = criteria.iter().find(|c| c.id == "AC-002").unwrap();
+        assert!(ac_002.description.ends_with("an error message"));
+    }
+
     #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
     async fn test_delete_user_story(pool: sqlx::SqlitePool) {
         let repo = UserStoryRepository::new(pool);
 
         // Delete an existing user story from fixtures
-        let result = repo.delete("US-001").await;
+        let result = repo.delete("US-001", None, None).await;
         assert!(result.is_ok());
-        assert!(result.unwrap());
+        assert!(matches!(result.unwrap(), VersionedWrite::Applied(_)));
 
         // Verify it's deleted
         let get_result = repo.get_by_id("US-001").await;
@@ -336,6 +1176,70 @@ mod tests {
         assert_eq!(stories.len(), 3); // US-001, US-003, US-004 from fixtures
     }
 
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_query_user_stories(pool: sqlx::SqlitePool) {
+        let repo = UserStoryRepository::new(pool);
+        let ids = |stories: Vec<UserStory>| {
+            stories
+                .into_iter()
+                .map(|story| story.id)
+                .collect::<Vec<_>>()
+        };
+
+        // Nothing set matches every story, newest first
+        let all = repo.query(&StoryQuery::default(), None).await.unwrap();
+        assert_eq!(ids(all), ["US-005", "US-004", "US-003", "US-002", "US-001"]);
+
+        let query = StoryQuery {
+            personas: vec!["registered user".to_string(), "End User".to_string()],
+            min_criteria: Some(2),
+            sort_by: StorySortField::CriteriaCount,
+            direction: SortDirection::Asc,
+            ..Default::default()
+        };
+        assert_eq!(
+            ids(repo.query(&query, None).await.unwrap()),
+            ["US-003", "US-005", "US-001"]
+        );
+        assert_eq!(repo.count_query(&query).await.unwrap(), 3);
+
+        let query = StoryQuery {
+            text: Some("PASSWORD".to_string()),
+            created_after: "2024-01-01T11:00:00".parse().ok(),
+            created_before: "2024-01-01T13:00:00".parse().ok(),
+            has_criteria: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(ids(repo.query(&query, None).await.unwrap()), ["US-003"]);
+
+        // Wildcards in the text are matched literally
+        let query = StoryQuery {
+            text: Some("%".to_string()),
+            ..Default::default()
+        };
+        assert!(repo.query(&query, None).await.unwrap().is_empty());
+
+        let query = StoryQuery {
+            has_criteria: Some(false),
+            ..Default::default()
+        };
+        assert!(repo.query(&query, None).await.unwrap().is_empty());
+
+        let query = StoryQuery {
+            sort_by: StorySortField::Title,
+            direction: SortDirection::Asc,
+            limit: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            ids(repo.query(&query, None).await.unwrap()),
+            ["US-003", "US-004"]
+        );
+    }
+
     #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
     async fn test_count_user_stories(pool: sqlx::SqlitePool) {
         let repo = UserStoryRepository::new(pool);
@@ -348,22 +1252,61 @@ mod tests {
     }
 
     #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
-    async fn test_get_paginated(pool: sqlx::SqlitePool) {
+    async fn test_get_page(pool: sqlx::SqlitePool) {
         let repo = UserStoryRepository::new(pool);
 
         // Get first 2 user stories
-        let result = repo.get_paginated(2, 0).await;
-        assert!(result.is_ok());
+        let first = repo.get_page(None, 2).await.unwrap();
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[1].id, "US-004");
 
-        let stories = result.unwrap();
-        assert_eq!(stories.len(), 2);
+        // A story created after the first page was read does not shift the next one
+        repo.create(create_test_request()).await.unwrap();
 
-        // Get next 2 user stories
-        let result = repo.get_paginated(2, 2).await;
-        assert!(result.is_ok());
+        let cursor = PageCursor::new(first[1].created_at, first[1].id.clone());
+        let next = repo.get_page(Some(&cursor), 2).await.unwrap();
+        let ids: Vec<_> = next.iter().map(|story| story.id.as_str()).collect();
+        assert_eq!(ids, ["US-003", "US-002"]);
+    }
 
-        let stories = result.unwrap();
-        assert_eq!(stories.len(), 2);
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_get_all_with_criteria(pool: sqlx::SqlitePool) {
+        let repo = UserStoryRepository::new(pool.clone());
+        AcceptanceCriteriaRepository::new(pool)
+            .delete("AC-002", None, None)
+            .await
+            .unwrap();
+
+        let stories = repo.get_all_with_criteria().await.unwrap();
+        assert_eq!(stories.len(), 5);
+
+        let login = stories
+            .iter()
+            .find(|story| story.user_story.id == "US-001")
+            .unwrap();
+        let ids: Vec<_> = login
+            .acceptance_criteria
+            .iter()
+            .map(|c| c.id.as_str())
+            .collect();
+        assert_eq!(ids, ["AC-001", "AC-003"]);
+
+        let total: usize = stories.iter().map(|s| s.acceptance_criteria.len()).sum();
+        assert_eq!(total, 9);
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_count_by_persona(pool: sqlx::SqlitePool) {
+        let repo = UserStoryRepository::new(pool);
+        repo.delete("US-002", None, None).await.unwrap();
+
+        let counts = repo.count_by_persona().await.unwrap();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts["Registered User"], 3);
+        assert_eq!(repo.count_unassigned().await.unwrap(), 4);
     }
 
     #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
@@ -1,7 +1,10 @@
+use crate::events::EventBus;
 use crate::models::{
-    AcceptanceCriteria, CreateAcceptanceCriteriaRequest, UpdateAcceptanceCriteriaRequest,
+    AcceptanceCriteria, CreateAcceptanceCriteriaRequest, IdScheme, Page, PageCursor, StoryClaim,
+    UpdateAcceptanceCriteriaRequest, ValidationPolicy, MAX_PAGE_SIZE,
 };
-use crate::repositories::Repositories;
+use crate::repositories::{ClaimGuardedWrite, Repositories, VersionedWrite};
+use std::collections::HashMap;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -18,6 +21,22 @@ pub enum AcceptanceCriteriaServiceError {
     Validation { message: String },
     #[error("Business rule violation: {message}")]
     BusinessRule { message: String },
+    #[error(
+        "Acceptance criteria {} were modified concurrently: expected version {expected_version}, current version {}",
+        .current.id,
+        .current.version
+    )]
+    Conflict {
+        expected_version: i64,
+        current: Box<AcceptanceCriteria>,
+    },
+    #[error(
+        "User story {} is claimed by {} until {}",
+        .claim.user_story_id,
+        .claim.agent_id,
+        .claim.expires_at
+    )]
+    Claimed { claim: StoryClaim },
 }
 
 pub type Result<T> = std::result::Result<T, AcceptanceCriteriaServiceError>;
@@ -25,32 +44,62 @@ pub type Result<T> = std::result::Result<T, AcceptanceCriteriaServiceError>;
 #[derive(Clone)]
 pub struct AcceptanceCriteriaService {
     repositories: Repositories,
+    id_scheme: IdScheme,
+    policy: ValidationPolicy,
+    agent_id: Option<String>,
+    events: EventBus,
 }
 
 impl AcceptanceCriteriaService {
     pub fn new(repositories: Repositories) -> Self {
-        Self { repositories }
+        Self {
+            repositories,
+            id_scheme: IdScheme::default(),
+            policy: ValidationPolicy::default(),
+            agent_id: None,
+            events: EventBus::new(),
+        }
+    }
+
+    /// Write as the given agent, which may change criteria of stories it holds the claim on
+    pub fn with_agent(mut self, agent_id: impl Into<String>) -> Self {
+        self.agent_id = Some(agent_id.into().trim().to_string());
+        self
+    }
+
+    /// Ring the given bus after each write so the webhook dispatcher picks up its events
+    pub fn with_event_bus(mut self, events: EventBus) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Generate and validate ids with the given scheme instead of `US-`/`AC-`
+    pub fn with_id_scheme(mut self, id_scheme: IdScheme) -> Self {
+        self.id_scheme = id_scheme;
+        self
+    }
+
+    /// Validate criteria against the given policy instead of the default limits
+    pub fn with_validation_policy(mut self, policy: ValidationPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub(crate) fn policy(&self) -> &ValidationPolicy {
+        &self.policy
     }
 
     /// Create a new acceptance criteria with validation
     pub async fn create(
         &self,
-        request: CreateAcceptanceCriteriaRequest,
+        mut request: CreateAcceptanceCriteriaRequest,
     ) -> Result<AcceptanceCriteria> {
         // Validate the request
         self.validate_create_request(&request).await?;
 
         // Check if acceptance criteria already exists
-        if self
-            .repositories
-            .acceptance_criteria
-            .get_by_id(&request.id)
-            .await?
-            .is_some()
-        {
-            return Err(AcceptanceCriteriaServiceError::AlreadyExists {
-                id: request.id.clone(),
-            });
+        if let Some(ref id) = request.id {
+            self.ensure_available(id).await?;
         }
 
         // Verify that the user story exists
@@ -66,20 +115,23 @@ impl AcceptanceCriteriaService {
             });
         }
 
+        if request.id.is_none() {
+            request.id = Some(self.next_id(&request.user_story_id).await?);
+        }
+
         // Create the acceptance criteria
-        let criteria = self
+        let write = self
             .repositories
             .acceptance_criteria
-            .create(request)
+            .create(request, self.agent_id.as_deref())
             .await?;
-
-        Ok(criteria)
+        self.claim_guarded(write)
     }
 
     /// Create multiple acceptance criteria for a user story
     pub async fn create_batch(
         &self,
-        requests: Vec<CreateAcceptanceCriteriaRequest>,
+        mut requests: Vec<CreateAcceptanceCriteriaRequest>,
     ) -> Result<Vec<AcceptanceCriteria>> {
         if requests.is_empty() {
             return Err(AcceptanceCriteriaServiceError::Validation {
@@ -93,18 +145,8 @@ impl AcceptanceCriteriaService {
         }
 
         // Check if any criteria already exist
-        for request in &requests {
-            if self
-                .repositories
-                .acceptance_criteria
-                .get_by_id(&request.id)
-                .await?
-                .is_some()
-            {
-                return Err(AcceptanceCriteriaServiceError::AlreadyExists {
-                    id: request.id.clone(),
-                });
-            }
+        for id in requests.iter().filter_map(|request| request.id.as_deref()) {
+            self.ensure_available(id).await?;
         }
 
         // Verify that all user stories exist
@@ -126,14 +168,19 @@ impl AcceptanceCriteriaService {
             }
         }
 
+        for request in &mut requests {
+            if request.id.is_none() {
+                request.id = Some(self.next_id(&request.user_story_id).await?);
+            }
+        }
+
         // Create all criteria in batch
-        let criteria = self
+        let write = self
             .repositories
             .acceptance_criteria
-            .create_batch(requests)
+            .create_batch(requests, self.agent_id.as_deref())
             .await?;
-
-        Ok(criteria)
+        self.claim_guarded(write)
     }
 
     /// Get acceptance criteria by ID
@@ -170,6 +217,81 @@ impl AcceptanceCriteriaService {
             .await?)
     }
 
+    /// Get the acceptance criteria of several user stories with a single query, keyed by
+    /// user story id. Every requested id is present, with no criteria for unknown stories.
+    pub async fn get_by_user_story_ids(
+        &self,
+        user_story_ids: &[String],
+    ) -> Result<HashMap<String, Vec<AcceptanceCriteria>>> {
+        let mut grouped: HashMap<String, Vec<AcceptanceCriteria>> = user_story_ids
+            .iter()
+            .map(|id| (id.clone(), Vec::new()))
+            .collect();
+
+        for criteria in self
+            .repositories
+            .acceptance_criteria
+            .get_by_user_story_ids(user_story_ids)
+            .await?
+        {
+            grouped
+                .entry(criteria.user_story_id.clone())
+                .or_default()
+                .push(criteria);
+        }
+
+        Ok(grouped)
+    }
+
+    /// Get a page of acceptance criteria, of one user story or of all, oldest first. Pass the
+    /// `next_cursor` of a page back as `cursor` for the next one; limits above
+    /// `MAX_PAGE_SIZE` are cut down to it.
+    pub async fn get_page(
+        &self,
+        user_story_id: Option<&str>,
+        cursor: Option<&str>,
+        limit: i64,
+    ) -> Result<Page<AcceptanceCriteria>> {
+        if limit <= 0 {
+            return Err(AcceptanceCriteriaServiceError::Validation {
+                message: "Limit must be positive".to_string(),
+            });
+        }
+        let after = cursor
+            .map(|cursor| {
+                PageCursor::decode(cursor).ok_or_else(|| {
+                    AcceptanceCriteriaServiceError::Validation {
+                        message: format!("Invalid cursor: {}", cursor),
+                    }
+                })
+            })
+            .transpose()?;
+
+        if let Some(user_story_id) = user_story_id {
+            if self
+                .repositories
+                .user_stories
+                .get_by_id(user_story_id)
+                .await?
+                .is_none()
+            {
+                return Err(AcceptanceCriteriaServiceError::UserStoryNotFound {
+                    user_story_id: user_story_id.to_string(),
+                });
+            }
+        }
+
+        let limit = limit.min(MAX_PAGE_SIZE);
+        let rows = self
+            .repositories
+            .acceptance_criteria
+            .get_page(user_story_id, after.as_ref(), limit + 1)
+            .await?;
+        Ok(Page::from_rows(rows, limit, |criteria| {
+            PageCursor::new(criteria.created_at, criteria.id.clone())
+        }))
+    }
+
     /// Get all acceptance criteria
     pub async fn get_all(&self) -> Result<Vec<AcceptanceCriteria>> {
         Ok(self.repositories.acceptance_criteria.get_all().await?)
@@ -184,22 +306,66 @@ impl AcceptanceCriteriaService {
         // Validate the update request
         self.validate_update_request(&request)?;
 
-        self.repositories
+        let expected_version = request.expected_version;
+        let write = self
+            .repositories
             .acceptance_criteria
-            .update(id, request)
-            .await?
-            .ok_or_else(|| AcceptanceCriteriaServiceError::NotFound { id: id.to_string() })
+            .update(id, request, self.agent_id.as_deref())
+            .await?;
+        self.versioned(id, expected_version, write)
     }
 
     /// Delete acceptance criteria
     pub async fn delete(&self, id: &str) -> Result<()> {
-        let deleted = self.repositories.acceptance_criteria.delete(id).await?;
+        self.delete_at_version(id, None).await
+    }
 
-        if !deleted {
-            return Err(AcceptanceCriteriaServiceError::NotFound { id: id.to_string() });
+    /// Delete acceptance criteria, failing with a conflict if they are no longer at
+    /// `expected_version`
+    pub async fn delete_at_version(&self, id: &str, expected_version: Option<i64>) -> Result<()> {
+        let write = self
+            .repositories
+            .acceptance_criteria
+            .delete(id, expected_version, self.agent_id.as_deref())
+            .await?;
+        self.versioned(id, expected_version, write).map(|_| ())
+    }
+
+    pub(crate) fn versioned(
+        &self,
+        id: &str,
+        expected_version: Option<i64>,
+        write: VersionedWrite<AcceptanceCriteria>,
+    ) -> Result<AcceptanceCriteria> {
+        match write {
+            VersionedWrite::Applied(criteria) => {
+                self.events.notify();
+                Ok(criteria)
+            }
+            VersionedWrite::NotFound => {
+                Err(AcceptanceCriteriaServiceError::NotFound { id: id.to_string() })
+            }
+            VersionedWrite::Conflict(current) => Err(AcceptanceCriteriaServiceError::Conflict {
+                expected_version: expected_version.unwrap_or_default(),
+                current: Box::new(current),
+            }),
+            VersionedWrite::Claimed(claim) => {
+                Err(AcceptanceCriteriaServiceError::Claimed { claim })
+            }
         }
+    }
 
-        Ok(())
+    /// Outcome of a write that only a claim on the story can refuse
+    fn claim_guarded<T>(&self, write: ClaimGuardedWrite<T>) -> Result<T> {
+        match write {
+            ClaimGuardedWrite::Applied(written) => {
+                self.events.notify();
+                Ok(written)
+            }
+            ClaimGuardedWrite::Claimed(claim) => {
+                Err(AcceptanceCriteriaServiceError::Claimed { claim })
+            }
+        }
     }
 
     /// Delete all acceptance criteria for a user story
@@ -217,13 +383,12 @@ impl AcceptanceCriteriaService {
             });
         }
 
-        let deleted_count = self
+        let write = self
             .repositories
             .acceptance_criteria
-            .delete_by_user_story_id(user_story_id)
+            .delete_by_user_story_id(user_story_id, self.agent_id.as_deref())
             .await?;
-
-        Ok(deleted_count)
+        self.claim_guarded(write)
     }
 
     /// Search acceptance criteria by description
@@ -270,18 +435,11 @@ impl AcceptanceCriteriaService {
             0.0
         };
 
-        // Get criteria distribution by user story
-        let all_stories = self.repositories.user_stories.get_all().await?;
-        let mut criteria_distribution = std::collections::HashMap::new();
-
-        for story in all_stories {
-            let count = self
-                .repositories
-                .acceptance_criteria
-                .count_by_user_story_id(&story.id)
-                .await?;
-            criteria_distribution.insert(story.id, count);
-        }
+        let criteria_distribution = self
+            .repositories
+            .acceptance_criteria
+            .count_by_user_story()
+            .await?;
 
         Ok(AcceptanceCriteriaStatistics {
             total_criteria,
@@ -291,17 +449,78 @@ impl AcceptanceCriteriaService {
         })
     }
 
+    async fn ensure_available(&self, id: &str) -> Result<()> {
+        // Ids of criteria in the trash stay taken until they are purged
+        if self.repositories.acceptance_criteria.exists(id).await? {
+            return Err(AcceptanceCriteriaServiceError::AlreadyExists { id: id.to_string() });
+        }
+        Ok(())
+    }
+
+    /// Generate the next criteria id of a user story from the id scheme
+    async fn next_id(&self, user_story_id: &str) -> Result<String> {
+        Ok(self
+            .repositories
+            .id_sequences
+            .next_criteria_id(&self.id_scheme, user_story_id)
+            .await?)
+    }
+
     /// Validate create request
     async fn validate_create_request(
         &self,
         request: &CreateAcceptanceCriteriaRequest,
     ) -> Result<()> {
-        if request.id.trim().is_empty() {
-            return Err(AcceptanceCriteriaServiceError::Validation {
-                message: "Acceptance criteria ID cannot be empty".to_string(),
+        self.validate_fields(request)?;
+
+        // Business rule: Check if the user story already has too many acceptance criteria
+        let existing_count = self
+            .repositories
+            .acceptance_criteria
+            .count_by_user_story_id(&request.user_story_id)
+            .await?;
+
+        let max_criteria = self.policy.max_criteria_per_story;
+        if existing_count >= max_criteria {
+            return Err(AcceptanceCriteriaServiceError::BusinessRule {
+                message: format!(
+                    "User story {} already has {} acceptance criteria. Maximum allowed is {}.",
+                    request.user_story_id, existing_count, max_criteria
+                ),
             });
         }
 
+        Ok(())
+    }
+
+    /// Validate the fields of a create request without consulting the database
+    pub(crate) fn validate_fields(&self, request: &CreateAcceptanceCriteriaRequest) -> Result<()> {
+        if let Some(ref id) = request.id {
+            if id.trim().is_empty() {
+                return Err(AcceptanceCriteriaServiceError::Validation {
+                    message: "Acceptance criteria ID cannot be empty".to_string(),
+                });
+            }
+
+            // Validate ID format against the policy, or the criteria prefix of the id scheme
+            match self.policy.criteria_id_pattern {
+                Some(ref pattern) if !pattern.is_match(id) => {
+                    return Err(AcceptanceCriteriaServiceError::Validation {
+                        message: format!("Acceptance criteria ID should match '{}'", pattern),
+                    });
+                }
+                None if !id.starts_with(&self.id_scheme.criteria_prefix) => {
+                    return Err(AcceptanceCriteriaServiceError::Validation {
+                        message: format!(
+                            "Acceptance criteria ID should start with '{}'",
+                            self.id_scheme.criteria_prefix
+                        ),
+                    });
+                }
+                _ => {}
+            }
+        }
+
         if request.user_story_id.trim().is_empty() {
             return Err(AcceptanceCriteriaServiceError::Validation {
                 message: "User story ID cannot be empty".to_string(),
@@ -314,69 +533,63 @@ impl AcceptanceCriteriaService {
             });
         }
 
-        // Validate ID format (should follow AC-XXX pattern)
-        if !request.id.starts_with("AC-") {
-            return Err(AcceptanceCriteriaServiceError::Validation {
-                message: "Acceptance criteria ID should start with 'AC-'".to_string(),
-            });
-        }
-
         // Validate user story ID format
-        if !request.user_story_id.starts_with("US-") {
-            return Err(AcceptanceCriteriaServiceError::Validation {
-                message: "User story ID should start with 'US-'".to_string(),
-            });
-        }
-
-        // Validate description length
-        if request.description.len() > 1000 {
-            return Err(AcceptanceCriteriaServiceError::Validation {
-                message: "Acceptance criteria description cannot exceed 1000 characters"
-                    .to_string(),
-            });
-        }
-
-        // Business rule: Check if the user story already has too many acceptance criteria
-        let existing_count = self
-            .repositories
-            .acceptance_criteria
-            .count_by_user_story_id(&request.user_story_id)
-            .await?;
-
-        const MAX_CRITERIA_PER_STORY: i64 = 20;
-        if existing_count >= MAX_CRITERIA_PER_STORY {
-            return Err(AcceptanceCriteriaServiceError::BusinessRule {
-                message: format!(
-                    "User story {} already has {} acceptance criteria. Maximum allowed is {}.",
-                    request.user_story_id, existing_count, MAX_CRITERIA_PER_STORY
-                ),
-            });
+        match self.policy.story_id_pattern {
+            Some(ref pattern) if !pattern.is_match(&request.user_story_id) => {
+                return Err(AcceptanceCriteriaServiceError::Validation {
+                    message: format!("User story ID should match '{}'", pattern),
+                });
+            }
+            None if !request
+                .user_story_id
+                .starts_with(&self.id_scheme.story_prefix) =>
+            {
+                return Err(AcceptanceCriteriaServiceError::Validation {
+                    message: format!(
+                        "User story ID should start with '{}'",
+                        self.id_scheme.story_prefix
+                    ),
+                });
+            }
+            _ => {}
         }
 
-        Ok(())
+        self.validate_description_length(&request.description)
     }
 
     /// Validate update request
-    fn validate_update_request(&self, request: &UpdateAcceptanceCriteriaRequest) -> Result<()> {
+    pub(crate) fn validate_update_request(
+        &self,
+        request: &UpdateAcceptanceCriteriaRequest,
+    ) -> Result<()> {
         if let Some(ref description) = request.description {
             if description.trim().is_empty() {
                 return Err(AcceptanceCriteriaServiceError::Validation {
                     message: "Acceptance criteria description cannot be empty".to_string(),
                 });
             }
-            if description.len() > 1000 {
-                return Err(AcceptanceCriteriaServiceError::Validation {
-                    message: "Acceptance criteria description cannot exceed 1000 characters"
-                        .to_string(),
-                });
-            }
+            self.validate_description_length(description)?;
+        }
+
+        Ok(())
+    }
+
+    fn validate_description_length(&self, description: &str) -> Result<()> {
+        let max_length = self.policy.criteria_description_max_length;
+        if description.len() > max_length {
+            return Err(AcceptanceCriteriaServiceError::Validation {
+                message: format!(
+                    "Acceptance criteria description cannot exceed {} characters",
+                    max_length
+                ),
+            });
         }
 
         Ok(())
     }
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, schemars::JsonSchema)]
 pub struct AcceptanceCriteriaStatistics {
     pub total_criteria: i64,
     pub total_stories: i64,
@@ -387,12 +600,12 @@ pub struct AcceptanceCriteriaStatistics {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::CreateUserStoryRequest;
+    use crate::models::{CreateUserStoryRequest, IdPattern};
     use crate::repositories::Repositories;
 
     async fn create_test_user_story(service: &AcceptanceCriteriaService) -> String {
         let user_story_request = CreateUserStoryRequest {
-            id: "US-TEST-001".to_string(),
+            id: Some("US-TEST-001".to_string()),
             title: "Test User Story".to_string(),
             description: "As a user, I want to test this functionality".to_string(),
             persona: "Test User".to_string(),
@@ -405,12 +618,12 @@ mod tests {
             .await
             .unwrap();
 
-        user_story_request.id
+        user_story_request.id.unwrap()
     }
 
     fn create_test_criteria_request(user_story_id: String) -> CreateAcceptanceCriteriaRequest {
         CreateAcceptanceCriteriaRequest {
-            id: "AC-TEST-001".to_string(),
+            id: Some("AC-TEST-001".to_string()),
             user_story_id,
             description: "Given I am on the page, When I click the button, Then something happens"
                 .to_string(),
@@ -428,7 +641,7 @@ mod tests {
         assert!(result.is_ok());
 
         let criteria = result.unwrap();
-        assert_eq!(criteria.id, request.id);
+        assert_eq!(Some(criteria.id), request.id);
         assert_eq!(criteria.description, request.description);
     }
 
@@ -471,7 +684,7 @@ mod tests {
         let service = AcceptanceCriteriaService::new(repositories);
         let user_story_id = create_test_user_story(&service).await;
         let mut request = create_test_criteria_request(user_story_id);
-        request.id = "".to_string();
+        request.id = Some("".to_string());
 
         let result = service.create(request).await;
         assert!(result.is_err());
@@ -487,7 +700,7 @@ mod tests {
         let service = AcceptanceCriteriaService::new(repositories);
         let user_story_id = create_test_user_story(&service).await;
         let mut request = create_test_criteria_request(user_story_id);
-        request.id = "INVALID-001".to_string();
+        request.id = Some("INVALID-001".to_string());
 
         let result = service.create(request).await;
         assert!(result.is_err());
@@ -505,12 +718,12 @@ mod tests {
 
         let requests = vec![
             CreateAcceptanceCriteriaRequest {
-                id: "AC-BATCH-001".to_string(),
+                id: Some("AC-BATCH-001".to_string()),
                 user_story_id: user_story_id.clone(),
                 description: "First criteria".to_string(),
             },
             CreateAcceptanceCriteriaRequest {
-                id: "AC-BATCH-002".to_string(),
+                id: Some("AC-BATCH-002".to_string()),
                 user_story_id: user_story_id.clone(),
                 description: "Second criteria".to_string(),
             },
@@ -556,6 +769,34 @@ mod tests {
         assert_eq!(criteria_list.len(), 3); // AC-001, AC-002, AC-003
     }
 
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_get_page(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let service = AcceptanceCriteriaService::new(repositories);
+
+        let first = service.get_page(Some("US-001"), None, 2).await.unwrap();
+        assert_eq!(first.items.len(), 2);
+        let rest = service
+            .get_page(Some("US-001"), first.next_cursor.as_deref(), 2)
+            .await
+            .unwrap();
+        assert_eq!(rest.items.len(), 1);
+        assert_eq!(rest.items[0].id, "AC-003");
+        assert!(rest.next_cursor.is_none());
+
+        assert!(matches!(
+            service.get_page(Some("US-999"), None, 2).await.unwrap_err(),
+            AcceptanceCriteriaServiceError::UserStoryNotFound { .. }
+        ));
+        assert!(matches!(
+            service.get_page(None, Some("AC-001"), 2).await.unwrap_err(),
+            AcceptanceCriteriaServiceError::Validation { .. }
+        ));
+    }
+
     #[sqlx::test(fixtures(
         "../../fixtures/user_stories.sql",
         "../../fixtures/acceptance_criteria.sql"
@@ -567,6 +808,7 @@ mod tests {
         // Update an existing acceptance criteria from fixtures
         let update_request = UpdateAcceptanceCriteriaRequest {
             description: Some("Updated description".to_string()),
+            expected_version: None,
         };
 
         let result = service.update("AC-001", update_request).await;
@@ -648,4 +890,119 @@ mod tests {
         assert_eq!(stats.avg_criteria_per_story, 2.0);
         assert_eq!(stats.criteria_distribution.get("US-001"), Some(&3));
     }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_create_generates_id(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let service = AcceptanceCriteriaService::new(repositories);
+
+        for expected in ["AC-001-1", "AC-001-2"] {
+            let criteria = service
+                .create(CreateAcceptanceCriteriaRequest {
+                    id: None,
+                    user_story_id: "US-001".to_string(),
+                    description: "Given a user, When they log in, Then they see the dashboard"
+                        .to_string(),
+                })
+                .await
+                .unwrap();
+            assert_eq!(criteria.id, expected);
+        }
+
+        let batch = service
+            .create_batch(vec![CreateAcceptanceCriteriaRequest {
+                id: None,
+                user_story_id: "US-002".to_string(),
+                description: "Given a visitor, When they register, Then they see a welcome"
+                    .to_string(),
+            }])
+            .await
+            .unwrap();
+        assert_eq!(batch[0].id, "AC-002-1");
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_validation_policy(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let service =
+            AcceptanceCriteriaService::new(repositories).with_validation_policy(ValidationPolicy {
+                criteria_description_max_length: 40,
+                max_criteria_per_story: 3,
+                criteria_id_pattern: Some(IdPattern::new("^AC-[0-9]+-[0-9]+$").unwrap()),
+                ..Default::default()
+            });
+        let request =
+            |id: &str, user_story_id: &str, description: &str| CreateAcceptanceCriteriaRequest {
+                id: Some(id.to_string()),
+                user_story_id: user_story_id.to_string(),
+                description: description.to_string(),
+            };
+
+        // US-002 has two criteria in the fixtures, so one more reaches the limit
+        service
+            .create(request("AC-002-3", "US-002", "Given a, When b, Then c"))
+            .await
+            .unwrap();
+        assert!(matches!(
+            service
+                .create(request("AC-002-4", "US-002", "Given a, When b, Then c"))
+                .await,
+            Err(AcceptanceCriteriaServiceError::BusinessRule { message })
+                if message.contains("Maximum allowed is 3")
+        ));
+
+        assert!(matches!(
+            service
+                .create(request("AC-X", "US-004", "Given a, When b, Then c"))
+                .await,
+            Err(AcceptanceCriteriaServiceError::Validation { message })
+                if message.contains("should match")
+        ));
+        assert!(matches!(
+            service
+                .create(request("AC-004-9", "US-004", &"x".repeat(41)))
+                .await,
+            Err(AcceptanceCriteriaServiceError::Validation { message })
+                if message.contains("40 characters")
+        ));
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_update_with_stale_version(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let service = AcceptanceCriteriaService::new(repositories);
+        let update = |description: &str| UpdateAcceptanceCriteriaRequest {
+            description: Some(description.to_string()),
+            expected_version: Some(1),
+        };
+
+        let updated = service
+            .update("AC-003", update("Given a, When b, Then c"))
+            .await
+            .unwrap();
+        assert_eq!(updated.version, 2);
+
+        assert!(matches!(
+            service.update("AC-003", update("Given x, When y, Then z")).await,
+            Err(AcceptanceCriteriaServiceError::Conflict { current, .. })
+                if current.description == "Given a, When b, Then c"
+        ));
+        assert!(matches!(
+            service.delete_at_version("AC-003", Some(1)).await,
+            Err(AcceptanceCriteriaServiceError::Conflict { .. })
+        ));
+        assert!(matches!(
+            service.delete_at_version("AC-999", Some(1)).await,
+            Err(AcceptanceCriteriaServiceError::NotFound { .. })
+        ));
+    }
 }
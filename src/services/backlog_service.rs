@@ -0,0 +1,929 @@
+use crate::formats::csv::{self, CsvError, CsvMapping};
+use crate::formats::gherkin;
+use crate::models::{
+    BacklogDocument, ConflictPolicy, CreateAcceptanceCriteriaRequest, CreateUserStoryRequest,
+    CsvImportReport, CsvRowError, FeatureFile, IdScheme, ImportOptions, ImportReport, RenamedId,
+    StoryClaim, UserStoryWithCriteria, ValidationPolicy, BACKLOG_FORMAT_VERSION,
+};
+use crate::repositories::backlog_repository::ImportWrite;
+use crate::repositories::Repositories;
+use crate::services::{AcceptanceCriteriaService, UserStoryService};
+use chrono::Utc;
+use std::collections::HashSet;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum BacklogServiceError {
+    #[error("User story not found: {id}")]
+    NotFound { id: String },
+    #[error(
+        "Unsupported backlog format version {version}, this build reads up to version {supported}"
+    )]
+    UnsupportedVersion { version: u32, supported: u32 },
+    #[error("Import conflict: {id} already exists")]
+    Conflict { id: String },
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Validation error: {message}")]
+    Validation { message: String },
+    #[error("Invalid CSV: {0}")]
+    Csv(#[from] CsvError),
+    #[error(
+        "User story {} is claimed by {} until {}",
+        .claim.user_story_id,
+        .claim.agent_id,
+        .claim.expires_at
+    )]
+    Claimed { claim: StoryClaim },
+}
+
+pub type Result<T> = std::result::Result<T, BacklogServiceError>;
+
+#[derive(Clone)]
+pub struct BacklogService {
+    repositories: Repositories,
+    user_stories: UserStoryService,
+    acceptance_criteria: AcceptanceCriteriaService,
+    agent_id: Option<String>,
+}
+
+impl BacklogService {
+    pub fn new(repositories: Repositories) -> Self {
+        Self {
+            user_stories: UserStoryService::new(repositories.clone()),
+            acceptance_criteria: AcceptanceCriteriaService::new(repositories.clone()),
+            repositories,
+            agent_id: None,
+        }
+    }
+
+    /// Import as the given agent, which may overwrite stories it holds the claim on
+    pub fn with_agent(mut self, agent_id: impl Into<String>) -> Self {
+        self.agent_id = Some(agent_id.into().trim().to_string());
+        self
+    }
+
+    /// Refuse the write if another agent holds a live claim on the story
+    fn check_claim(&self, claim: Option<StoryClaim>) -> Result<()> {
+        match claim {
+            Some(claim) if !claim.is_held_by(self.agent_id.as_deref()) => {
+                Err(BacklogServiceError::Claimed { claim })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Validate imported ids with the given scheme instead of `US-`/`AC-`
+    pub fn with_id_scheme(mut self, id_scheme: IdScheme) -> Self {
+        self.user_stories = self.user_stories.with_id_scheme(id_scheme.clone());
+        self.acceptance_criteria = self.acceptance_criteria.with_id_scheme(id_scheme);
+        self
+    }
+
+    /// Validate imported stories and criteria against the given policy
+    pub fn with_validation_policy(mut self, policy: ValidationPolicy) -> Self {
+        self.user_stories = self.user_stories.with_validation_policy(policy.clone());
+        self.acceptance_criteria = self.acceptance_criteria.with_validation_policy(policy);
+        self
+    }
+
+    /// Export every user story with its acceptance criteria as a versioned document
+    pub async fn export_backlog(&self) -> Result<BacklogDocument> {
+        let user_stories = self
+            .repositories
+            .user_stories
+            .get_all_with_criteria()
+            .await?;
+
+        Ok(BacklogDocument {
+            format_version: BACKLOG_FORMAT_VERSION,
+            exported_at: Utc::now().naive_utc(),
+            user_stories,
+        })
+    }
+
+    /// Import a backlog document, resolving id conflicts according to `options.policy`.
+    /// Nothing is written when `options.dry_run` is set or when any entry fails.
+    pub async fn import_backlog(
+        &self,
+        document: BacklogDocument,
+        options: ImportOptions,
+    ) -> Result<ImportReport> {
+        if document.format_version > BACKLOG_FORMAT_VERSION {
+            return Err(BacklogServiceError::UnsupportedVersion {
+                version: document.format_version,
+                supported: BACKLOG_FORMAT_VERSION,
+            });
+        }
+
+        self.validate_document(&document)?;
+
+        let mut report = ImportReport {
+            dry_run: options.dry_run,
+            ..Default::default()
+        };
+
+        // Ids appearing anywhere in the document; renamed ids must avoid these as well
+        let mut reserved: HashSet<String> = HashSet::new();
+        for entry in &document.user_stories {
+            reserved.insert(entry.user_story.id.clone());
+            for criteria in &entry.acceptance_criteria {
+                reserved.insert(criteria.id.clone());
+            }
+        }
+
+        let mut to_write = Vec::new();
+
+        for mut entry in document.user_stories {
+            // Stories and criteria in the trash conflict too; overwriting restores them
+            let story_exists = self
+                .repositories
+                .user_stories
+                .exists(&entry.user_story.id)
+                .await?;
+
+            let mut conflicting_criteria = Vec::new();
+            for criteria in &entry.acceptance_criteria {
+                if self
+                    .repositories
+                    .acceptance_criteria
+                    .exists(&criteria.id)
+                    .await?
+                {
+                    conflicting_criteria.push(criteria.id.clone());
+                }
+            }
+
+            let has_conflict = story_exists || !conflicting_criteria.is_empty();
+
+            match options.policy {
+                _ if !has_conflict => report.created.push(entry.user_story.id.clone()),
+                ConflictPolicy::Fail => {
+                    let id = if story_exists {
+                        entry.user_story.id
+                    } else {
+                        conflicting_criteria.remove(0)
+                    };
+                    return Err(BacklogServiceError::Conflict { id });
+                }
+                ConflictPolicy::Skip => {
+                    report.skipped.push(entry.user_story.id);
+                    continue;
+                }
+                ConflictPolicy::Overwrite => {
+                    // Overwriting changes the story and the stories its criteria are taken from
+                    if story_exists {
+                        let claim = self.repositories.claims.get_active(&entry.user_story.id);
+                        self.check_claim(claim.await?)?;
+                    }
+                    for id in &conflicting_criteria {
+                        let claim = self.repositories.claims.get_active_for_criteria(id);
+                        self.check_claim(claim.await?)?;
+                    }
+                    if story_exists {
+                        report.overwritten.push(entry.user_story.id.clone());
+                    } else {
+                        report.created.push(entry.user_story.id.clone());
+                    }
+                }
+                ConflictPolicy::RenameIds => {
+                    if story_exists {
+                        let new_id = self
+                            .allocate_story_id(&entry.user_story.id, &mut reserved)
+                            .await?;
+                        report.renamed.push(RenamedId {
+                            from: entry.user_story.id.clone(),
+                            to: new_id.clone(),
+                        });
+                        entry.user_story.id = new_id;
+                    }
+
+                    for criteria in &mut entry.acceptance_criteria {
+                        criteria.user_story_id = entry.user_story.id.clone();
+                        if conflicting_criteria.contains(&criteria.id) {
+                            let new_id = self
+                                .allocate_criteria_id(&criteria.id, &mut reserved)
+                                .await?;
+                            report.renamed.push(RenamedId {
+                                from: criteria.id.clone(),
+                                to: new_id.clone(),
+                            });
+                            criteria.id = new_id;
+                        }
+                    }
+
+                    report.created.push(entry.user_story.id.clone());
+                }
+            }
+
+            report.criteria_imported += entry.acceptance_criteria.len();
+            to_write.push(entry);
+        }
+
+        // The repository checks the ids again under the write lock, as stories may have
+        // been created since they were looked up above
+        if !options.dry_run && !to_write.is_empty() {
+            match self
+                .repositories
+                .backlog
+                .import(&to_write, options.policy)
+                .await?
+            {
+                ImportWrite::Applied { skipped } => {
+                    for id in skipped {
+                        report.created.retain(|created| created != &id);
+                        if let Some(entry) = to_write.iter().find(|e| e.user_story.id == id) {
+                            report.criteria_imported -= entry.acceptance_criteria.len();
+                        }
+                        report.skipped.push(id);
+                    }
+                }
+                ImportWrite::Conflict { id } => return Err(BacklogServiceError::Conflict { id }),
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Render a single user story and its acceptance criteria as a Gherkin `.feature` file
+    pub async fn export_feature(&self, id: &str) -> Result<FeatureFile> {
+        let user_story = self
+            .repositories
+            .user_stories
+            .get_by_id(id)
+            .await?
+            .ok_or_else(|| BacklogServiceError::NotFound { id: id.to_string() })?;
+        let acceptance_criteria = self
+            .repositories
+            .acceptance_criteria
+            .get_by_user_story_id(id)
+            .await?;
+
+        Ok(feature_file(&UserStoryWithCriteria {
+            user_story,
+            acceptance_criteria,
+        }))
+    }
+
+    /// Render every user story as a Gherkin `.feature` file
+    pub async fn export_features(&self) -> Result<Vec<FeatureFile>> {
+        let document = self.export_backlog().await?;
+        Ok(document.user_stories.iter().map(feature_file).collect())
+    }
+
+    /// Import Gherkin `.feature` documents, one user story per document,
+    /// with the same conflict handling as `import_backlog`
+    pub async fn import_features(
+        &self,
+        features: &[String],
+        options: ImportOptions,
+    ) -> Result<ImportReport> {
+        let now = Utc::now().naive_utc();
+        let user_stories = features
+            .iter()
+            .enumerate()
+            .map(|(index, content)| {
+                gherkin::parse_feature(content, now, self.user_stories.id_scheme()).map_err(|e| {
+                    BacklogServiceError::Validation {
+                        message: format!("feature {}: {}", index + 1, e),
+                    }
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let document = BacklogDocument {
+            format_version: BACKLOG_FORMAT_VERSION,
+            exported_at: now,
+            user_stories,
+        };
+
+        self.import_backlog(document, options).await
+    }
+
+    /// Export every user story as CSV laid out according to `mapping`
+    pub async fn export_csv(&self, mapping: &CsvMapping) -> Result<String> {
+        let document = self.export_backlog().await?;
+        Ok(csv::write_csv(&document.user_stories, mapping)?)
+    }
+
+    /// Import user stories from CSV laid out according to `mapping`.
+    ///
+    /// Every row is checked against the create rules first. If any row is rejected the
+    /// import runs as a dry run over the remaining stories, so nothing is written and the
+    /// report shows both the row errors and what the accepted rows would do.
+    pub async fn import_csv(
+        &self,
+        input: &str,
+        mapping: &CsvMapping,
+        options: ImportOptions,
+    ) -> Result<CsvImportReport> {
+        let now = Utc::now().naive_utc();
+        let (stories, mut row_errors) = csv::read_csv(input, mapping, now)?;
+
+        let mut seen_ids = HashSet::new();
+        let mut user_stories = Vec::new();
+        for entry in stories {
+            match self.validate_entry(&entry.story, &mut seen_ids) {
+                Ok(()) => user_stories.push(entry.story),
+                Err(BacklogServiceError::Validation { message }) => row_errors.push(CsvRowError {
+                    row: entry.row,
+                    message,
+                }),
+                Err(e) => return Err(e),
+            }
+        }
+        row_errors.sort_by_key(|error| error.row);
+
+        let options = ImportOptions {
+            dry_run: options.dry_run || !row_errors.is_empty(),
+            ..options
+        };
+        let document = BacklogDocument {
+            format_version: BACKLOG_FORMAT_VERSION,
+            exported_at: now,
+            user_stories,
+        };
+        let import = self.import_backlog(document, options).await?;
+
+        Ok(CsvImportReport { import, row_errors })
+    }
+
+    /// Apply the regular create rules to every story and criterion in the document
+    fn validate_document(&self, document: &BacklogDocument) -> Result<()> {
+        let mut seen_ids = HashSet::new();
+        for entry in &document.user_stories {
+            self.validate_entry(entry, &mut seen_ids)?;
+        }
+        Ok(())
+    }
+
+    /// Apply the regular create rules to one story and its criteria, recording their
+    /// ids in `seen_ids` to catch duplicates within the same import
+    fn validate_entry(
+        &self,
+        entry: &UserStoryWithCriteria,
+        seen_ids: &mut HashSet<String>,
+    ) -> Result<()> {
+        let story = &entry.user_story;
+
+        self.user_stories
+            .validate_create_request(&CreateUserStoryRequest {
+                id: Some(story.id.clone()),
+                title: story.title.clone(),
+                description: story.description.clone(),
+                persona: story.persona.clone(),
+            })
+            .map_err(|e| BacklogServiceError::Validation {
+                message: format!("{}: {}", story.id, e),
+            })?;
+
+        if !seen_ids.insert(story.id.clone()) {
+            return Err(BacklogServiceError::Validation {
+                message: format!("Duplicate id {} in backlog document", story.id),
+            });
+        }
+
+        let max_criteria = self.acceptance_criteria.policy().max_criteria_per_story;
+        if entry.acceptance_criteria.len() as i64 > max_criteria {
+            return Err(BacklogServiceError::Validation {
+                message: format!(
+                    "User story {} has {} acceptance criteria. Maximum allowed is {}.",
+                    story.id,
+                    entry.acceptance_criteria.len(),
+                    max_criteria
+                ),
+            });
+        }
+
+        for criteria in &entry.acceptance_criteria {
+            if criteria.user_story_id != story.id {
+                return Err(BacklogServiceError::Validation {
+                    message: format!(
+                        "Acceptance criteria {} does not belong to user story {}",
+                        criteria.id, story.id
+                    ),
+                });
+            }
+
+            self.acceptance_criteria
+                .validate_fields(&CreateAcceptanceCriteriaRequest {
+                    id: Some(criteria.id.clone()),
+                    user_story_id: criteria.user_story_id.clone(),
+                    description: criteria.description.clone(),
+                })
+                .map_err(|e| BacklogServiceError::Validation {
+                    message: format!("{}: {}", criteria.id, e),
+                })?;
+
+            if !seen_ids.insert(criteria.id.clone()) {
+                return Err(BacklogServiceError::Validation {
+                    message: format!("Duplicate id {} in backlog document", criteria.id),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Find the first `<id>-<n>` user story id that is neither stored nor reserved
+    async fn allocate_story_id(&self, id: &str, reserved: &mut HashSet<String>) -> Result<String> {
+        for n in 1.. {
+            let candidate = format!("{}-{}", id, n);
+            if !reserved.contains(&candidate)
+                && !self.repositories.user_stories.exists(&candidate).await?
+            {
+                reserved.insert(candidate.clone());
+                return Ok(candidate);
+            }
+        }
+        unreachable!("id space exhausted")
+    }
+
+    /// Find the first `<id>-<n>` acceptance criteria id that is neither stored nor reserved
+    async fn allocate_criteria_id(
+        &self,
+        id: &str,
+        reserved: &mut HashSet<String>,
+    ) -> Result<String> {
+        for n in 1.. {
+            let candidate = format!("{}-{}", id, n);
+            if !reserved.contains(&candidate)
+                && !self
+                    .repositories
+                    .acceptance_criteria
+                    .exists(&candidate)
+                    .await?
+            {
+                reserved.insert(candidate.clone());
+                return Ok(candidate);
+            }
+        }
+        unreachable!("id space exhausted")
+    }
+}
+
+fn feature_file(story: &UserStoryWithCriteria) -> FeatureFile {
+    FeatureFile {
+        file_name: format!("{}.feature", story.user_story.id),
+        content: gherkin::render_feature(story),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::CsvPreset;
+    use crate::models::Change;
+    use crate::repositories::Repositories;
+
+    async fn export_fixture_backlog(pool: sqlx::SqlitePool) -> BacklogDocument {
+        let service = BacklogService::new(Repositories::new(pool));
+        service.export_backlog().await.unwrap()
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_export_backlog(pool: sqlx::SqlitePool) {
+        let document = export_fixture_backlog(pool).await;
+
+        assert_eq!(document.format_version, BACKLOG_FORMAT_VERSION);
+        assert_eq!(document.user_stories.len(), 5);
+
+        let us_001 = document
+            .user_stories
+            .iter()
+            .find(|entry| entry.user_story.id == "US-001")
+            .unwrap();
+        let criteria_ids: Vec<&str> = us_001
+            .acceptance_criteria
+            .iter()
+            .map(|c| c.id.as_str())
+            .collect();
+        assert_eq!(criteria_ids, vec!["AC-001", "AC-002", "AC-003"]);
+        assert_eq!(
+            us_001.user_story.created_at.to_string(),
+            "2024-01-01 10:00:00"
+        );
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_round_trip_into_empty_database(pool: sqlx::SqlitePool) {
+        let document = export_fixture_backlog(pool).await;
+
+        let target = crate::database::create_test_db().await.unwrap();
+        let service = BacklogService::new(Repositories::new(target));
+
+        let report = service
+            .import_backlog(document.clone(), ImportOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(report.created.len(), 5);
+        assert_eq!(report.criteria_imported, 10);
+
+        let reexported = service.export_backlog().await.unwrap();
+        assert_eq!(
+            serde_json::to_value(&reexported.user_stories).unwrap(),
+            serde_json::to_value(&document.user_stories).unwrap()
+        );
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_import_fail_policy(pool: sqlx::SqlitePool) {
+        let service = BacklogService::new(Repositories::new(pool.clone()));
+        let document = service.export_backlog().await.unwrap();
+
+        let result = service
+            .import_backlog(document, ImportOptions::default())
+            .await;
+        assert!(matches!(
+            result.unwrap_err(),
+            BacklogServiceError::Conflict { .. }
+        ));
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_import_skip_policy(pool: sqlx::SqlitePool) {
+        let service = BacklogService::new(Repositories::new(pool.clone()));
+        let document = service.export_backlog().await.unwrap();
+
+        let report = service
+            .import_backlog(
+                document,
+                ImportOptions {
+                    policy: ConflictPolicy::Skip,
+                    dry_run: false,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(report.skipped.len(), 5);
+        assert!(report.created.is_empty());
+        assert_eq!(service.repositories.user_stories.count().await.unwrap(), 5);
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_import_overwrite_policy(pool: sqlx::SqlitePool) {
+        let service = BacklogService::new(Repositories::new(pool.clone()));
+        let mut document = service.export_backlog().await.unwrap();
+        for entry in &mut document.user_stories {
+            entry.user_story.title = format!("{} (imported)", entry.user_story.title);
+        }
+
+        let report = service
+            .import_backlog(
+                document,
+                ImportOptions {
+                    policy: ConflictPolicy::Overwrite,
+                    dry_run: false,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(report.overwritten.len(), 5);
+
+        let story = service
+            .repositories
+            .user_stories
+            .get_by_id("US-001")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(story.title, "User Login Feature (imported)");
+        assert_eq!(
+            service
+                .repositories
+                .acceptance_criteria
+                .count()
+                .await
+                .unwrap(),
+            10
+        );
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_import_overwrite_leaves_tombstones(pool: sqlx::SqlitePool) {
+        let services = crate::services::Services::new(Repositories::new(pool));
+        let cursor = services
+            .events
+            .changes_since(0, 100)
+            .await
+            .unwrap()
+            .next_cursor;
+
+        let mut document = services.backlog.export_backlog().await.unwrap();
+        document
+            .user_stories
+            .retain(|entry| entry.user_story.id == "US-001");
+        document.user_stories[0]
+            .acceptance_criteria
+            .retain(|criteria| criteria.id != "AC-002");
+        services
+            .backlog
+            .import_backlog(
+                document,
+                ImportOptions {
+                    policy: ConflictPolicy::Overwrite,
+                    dry_run: false,
+                },
+            )
+            .await
+            .unwrap();
+
+        // Sync clients learn that the dropped criterion is gone
+        let feed = services.events.changes_since(cursor, 100).await.unwrap();
+        assert!(feed.changes.iter().any(|change| matches!(
+            change,
+            Change::CriteriaDeleted { id, .. } if id == "AC-002"
+        )));
+        assert_eq!(
+            services
+                .trash
+                .list_trash()
+                .await
+                .unwrap()
+                .acceptance_criteria
+                .len(),
+            1
+        );
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_import_rename_policy(pool: sqlx::SqlitePool) {
+        let service = BacklogService::new(Repositories::new(pool.clone()));
+        let mut document = service.export_backlog().await.unwrap();
+        document
+            .user_stories
+            .retain(|entry| entry.user_story.id == "US-001");
+
+        let report = service
+            .import_backlog(
+                document,
+                ImportOptions {
+                    policy: ConflictPolicy::RenameIds,
+                    dry_run: false,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(report.created, vec!["US-001-1".to_string()]);
+        assert_eq!(report.renamed.len(), 4); // the story and its three criteria
+
+        let criteria = service
+            .repositories
+            .acceptance_criteria
+            .get_by_user_story_id("US-001-1")
+            .await
+            .unwrap();
+        assert_eq!(criteria.len(), 3);
+        assert!(criteria.iter().all(|c| c.id.ends_with("-1")));
+        assert_eq!(service.repositories.user_stories.count().await.unwrap(), 6);
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_import_dry_run(pool: sqlx::SqlitePool) {
+        let service = BacklogService::new(Repositories::new(pool.clone()));
+        let mut document = service.export_backlog().await.unwrap();
+        for entry in &mut document.user_stories {
+            entry.user_story.title = "Changed".to_string();
+        }
+
+        let report = service
+            .import_backlog(
+                document,
+                ImportOptions {
+                    policy: ConflictPolicy::Overwrite,
+                    dry_run: true,
+                },
+            )
+            .await
+            .unwrap();
+        assert!(report.dry_run);
+        assert_eq!(report.overwritten.len(), 5);
+
+        let story = service
+            .repositories
+            .user_stories
+            .get_by_id("US-001")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(story.title, "User Login Feature");
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_export_feature(pool: sqlx::SqlitePool) {
+        let service = BacklogService::new(Repositories::new(pool));
+
+        let feature = service.export_feature("US-001").await.unwrap();
+        assert_eq!(feature.file_name, "US-001.feature");
+        assert!(feature.content.contains("Feature: User Login Feature"));
+        assert_eq!(feature.content.matches("Scenario:").count(), 3);
+
+        let missing = service.export_feature("US-999").await;
+        assert!(matches!(
+            missing.unwrap_err(),
+            BacklogServiceError::NotFound { .. }
+        ));
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_feature_round_trip(pool: sqlx::SqlitePool) {
+        let source = BacklogService::new(Repositories::new(pool));
+        let features: Vec<String> = source
+            .export_features()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|feature| feature.content)
+            .collect();
+
+        let target = crate::database::create_test_db().await.unwrap();
+        let service = BacklogService::new(Repositories::new(target));
+        let report = service
+            .import_features(&features, ImportOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(report.created.len(), 5);
+        assert_eq!(report.criteria_imported, 10);
+
+        let criteria = service
+            .repositories
+            .acceptance_criteria
+            .get_by_id("AC-002")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            criteria.description,
+            "Given I am on the login page, When I enter invalid credentials, Then I should see an error message"
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_feature_round_trip_custom_prefixes(pool: sqlx::SqlitePool) {
+        let scheme = IdScheme {
+            story_prefix: "SHOP-".to_string(),
+            criteria_prefix: "SHOP-AC-".to_string(),
+            story_number_width: 1,
+        };
+        let source = BacklogService::new(Repositories::new(pool)).with_id_scheme(scheme.clone());
+        let story = source
+            .user_stories
+            .create(CreateUserStoryRequest {
+                id: None,
+                title: "Wishlist".to_string(),
+                description: "As a shopper, I want to save items so that I can buy them later"
+                    .to_string(),
+                persona: "Shopper".to_string(),
+            })
+            .await
+            .unwrap();
+        let criteria = source
+            .acceptance_criteria
+            .create(CreateAcceptanceCriteriaRequest {
+                id: None,
+                user_story_id: story.id.clone(),
+                description: "Given an item, When I save it, Then it is on my wishlist".to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(criteria.id, "SHOP-AC-1-1");
+        let features: Vec<String> = source
+            .export_features()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|feature| feature.content)
+            .collect();
+
+        let target = crate::database::create_test_db().await.unwrap();
+        let service = BacklogService::new(Repositories::new(target)).with_id_scheme(scheme);
+        let report = service
+            .import_features(&features, ImportOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(report.created, vec![story.id]);
+        assert!(service
+            .repositories
+            .acceptance_criteria
+            .exists("SHOP-AC-1-1")
+            .await
+            .unwrap());
+    }
+
+    #[sqlx::test]
+    async fn test_import_invalid_feature(pool: sqlx::SqlitePool) {
+        let service = BacklogService::new(Repositories::new(pool));
+
+        let result = service
+            .import_features(
+                &["Feature: Missing id\n".to_string()],
+                ImportOptions::default(),
+            )
+            .await;
+        assert!(matches!(
+            result.unwrap_err(),
+            BacklogServiceError::Validation { .. }
+        ));
+    }
+
+    #[sqlx::test]
+    async fn test_import_rejects_newer_format(pool: sqlx::SqlitePool) {
+        let service = BacklogService::new(Repositories::new(pool));
+        let document = BacklogDocument {
+            format_version: BACKLOG_FORMAT_VERSION + 1,
+            exported_at: Utc::now().naive_utc(),
+            user_stories: Vec::new(),
+        };
+
+        let result = service
+            .import_backlog(document, ImportOptions::default())
+            .await;
+        assert!(matches!(
+            result.unwrap_err(),
+            BacklogServiceError::UnsupportedVersion { .. }
+        ));
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_csv_round_trip_into_empty_database(pool: sqlx::SqlitePool) {
+        let source = BacklogService::new(Repositories::new(pool));
+        let csv = source.export_csv(&CsvMapping::default()).await.unwrap();
+
+        let target = crate::database::create_test_db().await.unwrap();
+        let service = BacklogService::new(Repositories::new(target));
+
+        let report = service
+            .import_csv(&csv, &CsvMapping::default(), ImportOptions::default())
+            .await
+            .unwrap();
+        assert!(report.row_errors.is_empty());
+        assert_eq!(report.import.created.len(), 5);
+        assert_eq!(report.import.criteria_imported, 10);
+
+        let stories = service.export_backlog().await.unwrap().user_stories;
+        let ids: Vec<&str> = stories[0]
+            .acceptance_criteria
+            .iter()
+            .map(|c| c.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["AC-001", "AC-002", "AC-003"]);
+    }
+
+    #[sqlx::test]
+    async fn test_import_csv_row_errors(pool: sqlx::SqlitePool) {
+        let service = BacklogService::new(Repositories::new(pool));
+        let input = format!(
+            "ID,Title,Description,Acceptance Criteria\n\
+             7,Checkout,<div>As a shopper, I want to pay</div>,<ul><li>Card payments work</li></ul>\n\
+             8,{},<div>Too long</div>,\n",
+            "x".repeat(201)
+        );
+        let mut mapping = CsvMapping::preset(CsvPreset::AzureDevops);
+        mapping.default_persona = Some("Shopper".to_string());
+
+        let report = service
+            .import_csv(&input, &mapping, ImportOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(report.row_errors.len(), 1);
+        assert_eq!(report.row_errors[0].row, 3);
+        assert!(report.import.dry_run);
+        assert_eq!(report.import.created, vec!["US-7"]);
+
+        let document = service.export_backlog().await.unwrap();
+        assert!(document.user_stories.is_empty());
+    }
+}
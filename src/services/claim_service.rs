@@ -0,0 +1,355 @@
+use crate::models::{StoryClaim, UserStory, WorkQueueFilter};
+use crate::repositories::Repositories;
+use chrono::Duration;
+use thiserror::Error;
+
+/// How long a claim lasts when the agent does not say
+pub const DEFAULT_CLAIM_TTL_SECONDS: i64 = 900;
+
+/// Longest lease an agent can take at once; longer work is kept alive with heartbeats
+pub const MAX_CLAIM_TTL_SECONDS: i64 = 86_400;
+
+#[derive(Error, Debug)]
+pub enum ClaimServiceError {
+    #[error("User story not found: {id}")]
+    UserStoryNotFound { id: String },
+    #[error(
+        "User story {} is claimed by {} until {}",
+        .claim.user_story_id,
+        .claim.agent_id,
+        .claim.expires_at
+    )]
+    Claimed { claim: StoryClaim },
+    #[error("Agent {agent_id} holds no live claim on user story {user_story_id}")]
+    NotHeld {
+        user_story_id: String,
+        agent_id: String,
+    },
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Validation error: {message}")]
+    Validation { message: String },
+}
+
+pub type Result<T> = std::result::Result<T, ClaimServiceError>;
+
+/// Leases that keep agents from working on the same user story at once
+#[derive(Clone)]
+pub struct ClaimService {
+    repositories: Repositories,
+}
+
+impl ClaimService {
+    pub fn new(repositories: Repositories) -> Self {
+        Self { repositories }
+    }
+
+    /// Claim a user story for `ttl_seconds`. Claiming a story the agent already holds renews
+    /// the claim; a story claimed by another agent is refused until that claim expires.
+    pub async fn claim_story(
+        &self,
+        id: &str,
+        agent_id: &str,
+        ttl_seconds: i64,
+    ) -> Result<StoryClaim> {
+        let agent_id = Self::validate_agent_id(agent_id)?;
+        let ttl = Self::validate_ttl(ttl_seconds)?;
+
+        if self
+            .repositories
+            .user_stories
+            .get_by_id(id)
+            .await?
+            .is_none()
+        {
+            return Err(ClaimServiceError::UserStoryNotFound { id: id.to_string() });
+        }
+
+        match self.repositories.claims.claim(id, agent_id, ttl).await? {
+            Some(claim) => Ok(claim),
+            None => Err(self.claimed_or_retry(id).await?),
+        }
+    }
+
+    /// Extend the agent's live claim to `ttl_seconds` from now
+    pub async fn heartbeat(
+        &self,
+        id: &str,
+        agent_id: &str,
+        ttl_seconds: i64,
+    ) -> Result<StoryClaim> {
+        let agent_id = Self::validate_agent_id(agent_id)?;
+        let ttl = Self::validate_ttl(ttl_seconds)?;
+
+        self.repositories
+            .claims
+            .heartbeat(id, agent_id, ttl)
+            .await?
+            .ok_or_else(|| ClaimServiceError::NotHeld {
+                user_story_id: id.to_string(),
+                agent_id: agent_id.to_string(),
+            })
+    }
+
+    /// Give up the agent's claim so other agents can pick the story up
+    pub async fn release_story(&self, id: &str, agent_id: &str) -> Result<()> {
+        let agent_id = Self::validate_agent_id(agent_id)?;
+
+        if !self.repositories.claims.release(id, agent_id).await? {
+            return Err(ClaimServiceError::NotHeld {
+                user_story_id: id.to_string(),
+                agent_id: agent_id.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Get the live claim on a user story, if any
+    pub async fn get_claim(&self, id: &str) -> Result<Option<StoryClaim>> {
+        Ok(self.repositories.claims.get_active(id).await?)
+    }
+
+    /// Get all live claims, soonest to expire first
+    pub async fn list_claims(&self) -> Result<Vec<StoryClaim>> {
+        Ok(self.repositories.claims.get_all_active().await?)
+    }
+
+    /// The oldest user story matching the filter that no agent has claimed. Claim it next;
+    /// if another agent got there first, the claim fails and the queue can be asked again.
+    pub async fn next_unclaimed_story(
+        &self,
+        filter: &WorkQueueFilter,
+    ) -> Result<Option<UserStory>> {
+        Ok(self.repositories.claims.next_unclaimed(filter).await?)
+    }
+
+    /// The claim that refused a write; if it expired in the meantime, ask to try again
+    async fn claimed_or_retry(&self, id: &str) -> Result<ClaimServiceError> {
+        Ok(match self.repositories.claims.get_active(id).await? {
+            Some(claim) => ClaimServiceError::Claimed { claim },
+            None => ClaimServiceError::Validation {
+                message: format!("The claim on user story {} just changed; try again", id),
+            },
+        })
+    }
+
+    fn validate_agent_id(agent_id: &str) -> Result<&str> {
+        let agent_id = agent_id.trim();
+        if agent_id.is_empty() {
+            return Err(ClaimServiceError::Validation {
+                message: "Agent id cannot be empty".to_string(),
+            });
+        }
+        Ok(agent_id)
+    }
+
+    fn validate_ttl(ttl_seconds: i64) -> Result<Duration> {
+        if !(1..=MAX_CLAIM_TTL_SECONDS).contains(&ttl_seconds) {
+            return Err(ClaimServiceError::Validation {
+                message: format!(
+                    "Claim ttl must be between 1 and {} seconds",
+                    MAX_CLAIM_TTL_SECONDS
+                ),
+            });
+        }
+        Ok(Duration::seconds(ttl_seconds))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConflictPolicy, ImportOptions, UpdateUserStoryRequest};
+    use crate::services::backlog_service::BacklogServiceError;
+    use crate::services::trash_service::TrashServiceError;
+    use crate::services::user_story_service::UserStoryServiceError;
+    use crate::services::{Services, UserStoryService};
+    use chrono::Utc;
+
+    async fn expire_claims(pool: &sqlx::SqlitePool) {
+        sqlx::query("UPDATE story_claims SET expires_at = $1")
+            .bind(Utc::now().naive_utc() - Duration::minutes(1))
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_claim_lifecycle(pool: sqlx::SqlitePool) {
+        let service = ClaimService::new(Repositories::new(pool.clone()));
+
+        let claim = service.claim_story("US-001", "agent-a", 60).await.unwrap();
+        assert_eq!(claim.agent_id, "agent-a");
+
+        let taken = service.claim_story("US-001", "agent-b", 60).await;
+        assert!(matches!(
+            taken,
+            Err(ClaimServiceError::Claimed { claim }) if claim.agent_id == "agent-a"
+        ));
+
+        // Claiming again renews without resetting when the claim was taken
+        let renewed = service.claim_story("US-001", "agent-a", 600).await.unwrap();
+        assert_eq!(renewed.claimed_at, claim.claimed_at);
+        assert!(renewed.expires_at > claim.expires_at);
+
+        let beat = service.heartbeat("US-001", "agent-a", 60).await.unwrap();
+        assert!(beat.heartbeat_at >= renewed.heartbeat_at);
+        assert!(matches!(
+            service.heartbeat("US-001", "agent-b", 60).await,
+            Err(ClaimServiceError::NotHeld { .. })
+        ));
+
+        // An expired claim can no longer be renewed and is free to take
+        expire_claims(&pool).await;
+        assert!(service.get_claim("US-001").await.unwrap().is_none());
+        assert!(matches!(
+            service.heartbeat("US-001", "agent-a", 60).await,
+            Err(ClaimServiceError::NotHeld { .. })
+        ));
+        let taken = service.claim_story("US-001", "agent-b", 60).await.unwrap();
+        assert_eq!(taken.agent_id, "agent-b");
+        assert!(taken.claimed_at > claim.claimed_at);
+
+        assert!(matches!(
+            service.release_story("US-001", "agent-a").await,
+            Err(ClaimServiceError::NotHeld { .. })
+        ));
+        service.release_story("US-001", "agent-b").await.unwrap();
+        assert!(service.list_claims().await.unwrap().is_empty());
+
+        assert!(matches!(
+            service.claim_story("US-999", "agent-a", 60).await,
+            Err(ClaimServiceError::UserStoryNotFound { .. })
+        ));
+        assert!(matches!(
+            service.claim_story("US-001", "agent-a", 0).await,
+            Err(ClaimServiceError::Validation { .. })
+        ));
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_writes_need_the_claim(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool.clone());
+        let service = ClaimService::new(repositories.clone());
+        let stories = UserStoryService::new(repositories);
+        let update = |title: &str| UpdateUserStoryRequest {
+            title: Some(title.to_string()),
+            description: None,
+            persona: None,
+            expected_version: None,
+        };
+
+        service.claim_story("US-001", "agent-a", 60).await.unwrap();
+
+        for writer in [stories.clone(), stories.clone().with_agent("agent-b")] {
+            assert!(matches!(
+                writer.update("US-001", update("Taken")).await,
+                Err(UserStoryServiceError::Claimed { .. })
+            ));
+            assert!(matches!(
+                writer.delete("US-001").await,
+                Err(UserStoryServiceError::Claimed { .. })
+            ));
+        }
+
+        let holder = stories.clone().with_agent("agent-a");
+        let story = holder.update("US-001", update("Mine")).await.unwrap();
+        assert_eq!(story.title, "Mine");
+
+        // Other stories and expired claims do not get in the way
+        stories.update("US-002", update("Free")).await.unwrap();
+        expire_claims(&pool).await;
+        stories
+            .update("US-001", update("Free again"))
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_import_and_restore_need_the_claim(pool: sqlx::SqlitePool) {
+        let services = Services::new(Repositories::new(pool));
+        let holder = services.as_agent("agent-a");
+        let other = services.as_agent("agent-b");
+        services
+            .claims
+            .claim_story("US-001", "agent-a", 60)
+            .await
+            .unwrap();
+
+        let overwrite = ImportOptions {
+            policy: ConflictPolicy::Overwrite,
+            dry_run: false,
+        };
+        let mut document = services.backlog.export_backlog().await.unwrap();
+        for entry in &mut document.user_stories {
+            entry.user_story.title = "Overwritten".to_string();
+        }
+        for writer in [&services, &other] {
+            assert!(matches!(
+                writer
+                    .backlog
+                    .import_backlog(document.clone(), overwrite.clone())
+                    .await,
+                Err(BacklogServiceError::Claimed { .. })
+            ));
+        }
+        // Nothing was written by the rejected imports
+        let story = services.user_stories.get_by_id("US-002").await.unwrap();
+        assert_ne!(story.title, "Overwritten");
+        holder
+            .backlog
+            .import_backlog(document, overwrite)
+            .await
+            .unwrap();
+
+        holder.user_stories.delete("US-001").await.unwrap();
+        holder.acceptance_criteria.delete("AC-004").await.unwrap();
+        services
+            .claims
+            .claim_story("US-002", "agent-a", 60)
+            .await
+            .unwrap();
+        for id in ["US-001", "AC-004"] {
+            assert!(matches!(
+                other.trash.restore(id).await,
+                Err(TrashServiceError::Claimed { .. })
+            ));
+            holder.trash.restore(id).await.unwrap();
+        }
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_next_unclaimed_story(pool: sqlx::SqlitePool) {
+        let service = ClaimService::new(Repositories::new(pool));
+        let all = WorkQueueFilter::default();
+
+        let first = service.next_unclaimed_story(&all).await.unwrap().unwrap();
+        service.claim_story(&first.id, "agent-a", 60).await.unwrap();
+        let second = service.next_unclaimed_story(&all).await.unwrap().unwrap();
+        assert_ne!(second.id, first.id);
+
+        let filtered = WorkQueueFilter {
+            persona: Some(second.persona.to_uppercase()),
+            ..Default::default()
+        };
+        let next = service
+            .next_unclaimed_story(&filtered)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(next.persona, second.persona);
+
+        let nobody = WorkQueueFilter {
+            assignee: Some("nobody".to_string()),
+            ..Default::default()
+        };
+        assert!(service
+            .next_unclaimed_story(&nobody)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
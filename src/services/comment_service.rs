@@ -0,0 +1,504 @@
+use crate::models::{parse_mentions, Comment, CommentThread, CreateCommentRequest};
+use crate::repositories::comment_repository::CommentPlacement;
+use crate::repositories::Repositories;
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// Longest comment body accepted
+pub const MAX_COMMENT_LENGTH: usize = 5000;
+
+#[derive(Error, Debug)]
+pub enum CommentServiceError {
+    #[error("Comment not found: {id}")]
+    NotFound { id: i64 },
+    #[error("User story not found: {user_story_id}")]
+    UserStoryNotFound { user_story_id: String },
+    #[error("Acceptance criteria not found: {id}")]
+    AcceptanceCriteriaNotFound { id: String },
+    #[error("Comment {id} was written by {author}; only they can change it")]
+    NotAuthor { id: i64, author: String },
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Validation error: {message}")]
+    Validation { message: String },
+}
+
+pub type Result<T> = std::result::Result<T, CommentServiceError>;
+
+/// Discussion threads on user stories and acceptance criteria
+#[derive(Clone)]
+pub struct CommentService {
+    repositories: Repositories,
+}
+
+impl CommentService {
+    pub fn new(repositories: Repositories) -> Self {
+        Self { repositories }
+    }
+
+    /// Start a thread on a story or criterion, or reply to a comment. Replying to a reply
+    /// adds to the same thread.
+    pub async fn create(&self, request: CreateCommentRequest) -> Result<Comment> {
+        self.validate_author(&request.author)?;
+        self.validate_body(&request.body)?;
+        let mentions = parse_mentions(&request.body);
+
+        let (user_story_id, acceptance_criteria_id, parent_id) = match (
+            request.user_story_id,
+            request.acceptance_criteria_id,
+            request.parent_id,
+        ) {
+            (Some(user_story_id), None, None) => {
+                self.ensure_user_story(&user_story_id).await?;
+                (user_story_id, None, None)
+            }
+            (None, Some(criteria_id), None) => {
+                let criteria = self
+                    .repositories
+                    .acceptance_criteria
+                    .get_by_id(&criteria_id)
+                    .await?
+                    .ok_or(CommentServiceError::AcceptanceCriteriaNotFound { id: criteria_id })?;
+                (criteria.user_story_id, Some(criteria.id), None)
+            }
+            (None, None, Some(parent_id)) => {
+                let parent = self.get_by_id(parent_id).await?;
+                let thread_id = parent.parent_id.unwrap_or(parent.id);
+                (
+                    parent.user_story_id,
+                    parent.acceptance_criteria_id,
+                    Some(thread_id),
+                )
+            }
+            _ => {
+                return Err(CommentServiceError::Validation {
+                    message: "Give exactly one of user_story_id, acceptance_criteria_id or \
+                        parent_id"
+                        .to_string(),
+                })
+            }
+        };
+
+        let placement = CommentPlacement {
+            user_story_id: &user_story_id,
+            acceptance_criteria_id: acceptance_criteria_id.as_deref(),
+            parent_id,
+        };
+        Ok(self
+            .repositories
+            .comments
+            .create(placement, request.author.trim(), &request.body, mentions)
+            .await?)
+    }
+
+    /// Get comment by ID
+    pub async fn get_by_id(&self, id: i64) -> Result<Comment> {
+        self.repositories
+            .comments
+            .get_by_id(id)
+            .await?
+            .ok_or(CommentServiceError::NotFound { id })
+    }
+
+    /// Threads on a story and its acceptance criteria, oldest first
+    pub async fn threads_for_story(
+        &self,
+        user_story_id: &str,
+        include_resolved: bool,
+    ) -> Result<Vec<CommentThread>> {
+        self.ensure_user_story(user_story_id).await?;
+        let comments = self
+            .repositories
+            .comments
+            .get_by_user_story_id(user_story_id)
+            .await?;
+        Ok(threads(comments, include_resolved))
+    }
+
+    /// Threads on one acceptance criterion, oldest first
+    pub async fn threads_for_criteria(
+        &self,
+        criteria_id: &str,
+        include_resolved: bool,
+    ) -> Result<Vec<CommentThread>> {
+        let criteria = self
+            .repositories
+            .acceptance_criteria
+            .get_by_id(criteria_id)
+            .await?
+            .ok_or_else(|| CommentServiceError::AcceptanceCriteriaNotFound {
+                id: criteria_id.to_string(),
+            })?;
+        let comments = self
+            .repositories
+            .comments
+            .get_by_user_story_id(&criteria.user_story_id)
+            .await?
+            .into_iter()
+            .filter(|c| c.acceptance_criteria_id.as_deref() == Some(criteria_id))
+            .collect();
+        Ok(threads(comments, include_resolved))
+    }
+
+    /// Comments mentioning `@name`, newest first; comments in resolved threads are left out
+    /// unless asked for
+    pub async fn mentions(&self, name: &str, include_resolved: bool) -> Result<Vec<Comment>> {
+        let name = name.trim().trim_start_matches('@');
+        if name.is_empty() {
+            return Err(CommentServiceError::Validation {
+                message: "Name cannot be empty".to_string(),
+            });
+        }
+
+        let comments = self.repositories.comments.get_mentioning(name).await?;
+        if include_resolved {
+            return Ok(comments);
+        }
+
+        let mut open = Vec::new();
+        let mut thread_resolved: HashMap<i64, bool> = HashMap::new();
+        for comment in comments {
+            let resolved = match comment.parent_id {
+                None => comment.is_resolved(),
+                Some(thread_id) => match thread_resolved.get(&thread_id) {
+                    Some(resolved) => *resolved,
+                    None => {
+                        let resolved = self
+                            .repositories
+                            .comments
+                            .get_by_id(thread_id)
+                            .await?
+                            .is_some_and(|thread| thread.is_resolved());
+                        thread_resolved.insert(thread_id, resolved);
+                        resolved
+                    }
+                },
+            };
+            if !resolved {
+                open.push(comment);
+            }
+        }
+        Ok(open)
+    }
+
+    /// Change the body of a comment; only its author can
+    pub async fn edit(&self, id: i64, author: &str, body: &str) -> Result<Comment> {
+        self.validate_body(body)?;
+        self.ensure_author(id, author).await?;
+
+        self.repositories
+            .comments
+            .update_body(id, body, parse_mentions(body))
+            .await?
+            .ok_or(CommentServiceError::NotFound { id })
+    }
+
+    /// Delete a comment, with its replies when it starts a thread; only its author can
+    pub async fn delete(&self, id: i64, author: &str) -> Result<()> {
+        self.ensure_author(id, author).await?;
+
+        if !self.repositories.comments.delete(id).await? {
+            return Err(CommentServiceError::NotFound { id });
+        }
+        Ok(())
+    }
+
+    /// Mark the thread a comment starts as resolved by someone, or open it again
+    pub async fn set_resolved(&self, id: i64, resolved: bool, by: &str) -> Result<Comment> {
+        self.validate_author(by)?;
+        let comment = self.get_by_id(id).await?;
+        if let Some(thread_id) = comment.parent_id {
+            return Err(CommentServiceError::Validation {
+                message: format!(
+                    "Comment {} is a reply; resolve the thread it belongs to ({}) instead",
+                    id, thread_id
+                ),
+            });
+        }
+
+        self.repositories
+            .comments
+            .set_resolved(id, resolved.then_some(by.trim()))
+            .await?
+            .ok_or(CommentServiceError::NotFound { id })
+    }
+
+    async fn ensure_user_story(&self, user_story_id: &str) -> Result<()> {
+        if self
+            .repositories
+            .user_stories
+            .get_by_id(user_story_id)
+            .await?
+            .is_none()
+        {
+            return Err(CommentServiceError::UserStoryNotFound {
+                user_story_id: user_story_id.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    async fn ensure_author(&self, id: i64, author: &str) -> Result<()> {
+        let comment = self.get_by_id(id).await?;
+        if !comment.author.eq_ignore_ascii_case(author.trim()) {
+            return Err(CommentServiceError::NotAuthor {
+                id,
+                author: comment.author,
+            });
+        }
+        Ok(())
+    }
+
+    fn validate_author(&self, author: &str) -> Result<()> {
+        if author.trim().is_empty() {
+            return Err(CommentServiceError::Validation {
+                message: "Comment author cannot be empty".to_string(),
+            });
+        }
+        if author.len() > 100 {
+            return Err(CommentServiceError::Validation {
+                message: "Comment author cannot exceed 100 characters".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn validate_body(&self, body: &str) -> Result<()> {
+        if body.trim().is_empty() {
+            return Err(CommentServiceError::Validation {
+                message: "Comment cannot be empty".to_string(),
+            });
+        }
+        if body.len() > MAX_COMMENT_LENGTH {
+            return Err(CommentServiceError::Validation {
+                message: format!("Comment cannot exceed {} characters", MAX_COMMENT_LENGTH),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Group comments, oldest first, into threads under their top-level comment
+fn threads(comments: Vec<Comment>, include_resolved: bool) -> Vec<CommentThread> {
+    let mut threads: Vec<CommentThread> = Vec::new();
+    let mut index = HashMap::new();
+    let mut replies = Vec::new();
+
+    for comment in comments {
+        if comment.parent_id.is_some() {
+            replies.push(comment);
+        } else if include_resolved || !comment.is_resolved() {
+            index.insert(comment.id, threads.len());
+            threads.push(CommentThread {
+                comment,
+                replies: Vec::new(),
+            });
+        }
+    }
+    for reply in replies {
+        if let Some(&i) = reply.parent_id.and_then(|id| index.get(&id)) {
+            threads[i].replies.push(reply);
+        }
+    }
+
+    threads
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{AcceptanceCriteriaService, UserStoryService};
+
+    fn request(author: &str, body: &str) -> CreateCommentRequest {
+        CreateCommentRequest {
+            author: author.to_string(),
+            body: body.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_threads(pool: sqlx::SqlitePool) {
+        let service = CommentService::new(Repositories::new(pool));
+
+        let story_thread = service
+            .create(CreateCommentRequest {
+                user_story_id: Some("US-001".to_string()),
+                ..request("reviewer", "Is remember-me in scope? @po")
+            })
+            .await
+            .unwrap();
+        assert_eq!(story_thread.mentions, vec!["po"]);
+
+        let criteria_thread = service
+            .create(CreateCommentRequest {
+                acceptance_criteria_id: Some("AC-002".to_string()),
+                ..request("qa-agent", "Which error message exactly?")
+            })
+            .await
+            .unwrap();
+        assert_eq!(criteria_thread.user_story_id, "US-001");
+
+        let reply = service
+            .create(CreateCommentRequest {
+                parent_id: Some(story_thread.id),
+                ..request("po", "Not yet, @reviewer")
+            })
+            .await
+            .unwrap();
+        // A reply to a reply joins the same thread
+        let nested = service
+            .create(CreateCommentRequest {
+                parent_id: Some(reply.id),
+                ..request("reviewer", "Thanks")
+            })
+            .await
+            .unwrap();
+        assert_eq!(nested.parent_id, Some(story_thread.id));
+
+        let threads = service.threads_for_story("US-001", false).await.unwrap();
+        assert_eq!(threads.len(), 2);
+        assert_eq!(threads[0].comment.id, story_thread.id);
+        assert_eq!(threads[0].replies.len(), 2);
+        assert_eq!(
+            threads[1].comment.acceptance_criteria_id.as_deref(),
+            Some("AC-002")
+        );
+
+        let threads = service.threads_for_criteria("AC-002", false).await.unwrap();
+        assert_eq!(threads.len(), 1);
+        assert!(threads[0].replies.is_empty());
+
+        assert!(matches!(
+            service
+                .create(CreateCommentRequest {
+                    user_story_id: Some("US-001".to_string()),
+                    parent_id: Some(story_thread.id),
+                    ..request("reviewer", "Ambiguous")
+                })
+                .await,
+            Err(CommentServiceError::Validation { .. })
+        ));
+        assert!(matches!(
+            service
+                .create(CreateCommentRequest {
+                    user_story_id: Some("US-999".to_string()),
+                    ..request("reviewer", "Lost")
+                })
+                .await,
+            Err(CommentServiceError::UserStoryNotFound { .. })
+        ));
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_edit_delete_and_resolve(pool: sqlx::SqlitePool) {
+        let service = CommentService::new(Repositories::new(pool));
+        let thread = service
+            .create(CreateCommentRequest {
+                user_story_id: Some("US-002".to_string()),
+                ..request("reviewer", "Needs a persona")
+            })
+            .await
+            .unwrap();
+        let reply = service
+            .create(CreateCommentRequest {
+                parent_id: Some(thread.id),
+                ..request("writer", "Done @reviewer")
+            })
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            service.edit(thread.id, "writer", "Hijacked").await,
+            Err(CommentServiceError::NotAuthor { .. })
+        ));
+        let edited = service
+            .edit(thread.id, "Reviewer", "Needs a persona, @writer")
+            .await
+            .unwrap();
+        assert_eq!(edited.mentions, vec!["writer"]);
+
+        assert_eq!(service.mentions("@reviewer", false).await.unwrap().len(), 1);
+        assert!(matches!(
+            service.set_resolved(reply.id, true, "reviewer").await,
+            Err(CommentServiceError::Validation { .. })
+        ));
+        let resolved = service
+            .set_resolved(thread.id, true, "reviewer")
+            .await
+            .unwrap();
+        assert_eq!(resolved.resolved_by.as_deref(), Some("reviewer"));
+
+        assert!(service
+            .threads_for_story("US-002", false)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            service
+                .threads_for_story("US-002", true)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(service
+            .mentions("reviewer", false)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(service.mentions("reviewer", true).await.unwrap().len(), 1);
+
+        let reopened = service.set_resolved(thread.id, false, "po").await.unwrap();
+        assert!(!reopened.is_resolved());
+
+        // Deleting the first comment of a thread deletes the whole thread
+        service.delete(thread.id, "reviewer").await.unwrap();
+        assert!(matches!(
+            service.get_by_id(reply.id).await,
+            Err(CommentServiceError::NotFound { .. })
+        ));
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_comments_follow_the_trash(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let service = CommentService::new(repositories.clone());
+        service
+            .create(CreateCommentRequest {
+                acceptance_criteria_id: Some("AC-004".to_string()),
+                ..request("qa-agent", "@writer is this testable?")
+            })
+            .await
+            .unwrap();
+
+        AcceptanceCriteriaService::new(repositories.clone())
+            .delete("AC-004")
+            .await
+            .unwrap();
+        assert!(service
+            .threads_for_story("US-002", false)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(service.mentions("writer", false).await.unwrap().is_empty());
+
+        UserStoryService::new(repositories)
+            .delete("US-002")
+            .await
+            .unwrap();
+        assert!(matches!(
+            service.threads_for_story("US-002", false).await,
+            Err(CommentServiceError::UserStoryNotFound { .. })
+        ));
+    }
+}
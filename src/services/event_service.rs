@@ -0,0 +1,400 @@
+use crate::events::{DispatcherConfig, EventBus, WebhookDispatcher};
+use crate::models::{
+    Change, ChangeFeed, CreateWebhookRequest, DeliveryStatus, DomainEvent, Webhook, WebhookDelivery,
+};
+use crate::repositories::Repositories;
+use std::collections::HashMap;
+use thiserror::Error;
+use tokio::sync::broadcast;
+
+/// Most events returned by one `list_events` call, and most changes by one `changes_since` call
+pub const MAX_EVENTS_PER_PAGE: i64 = 1000;
+
+#[derive(Error, Debug)]
+pub enum EventServiceError {
+    #[error("Webhook not found: {id}")]
+    WebhookNotFound { id: i64 },
+    #[error("Dead letter not found: {id}")]
+    DeadLetterNotFound { id: i64 },
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Validation error: {message}")]
+    Validation { message: String },
+}
+
+pub type Result<T> = std::result::Result<T, EventServiceError>;
+
+/// The change event outbox, the webhooks it is delivered to and deliveries that gave up
+#[derive(Clone)]
+pub struct EventService {
+    repositories: Repositories,
+    bus: EventBus,
+}
+
+impl EventService {
+    pub fn new(repositories: Repositories, bus: EventBus) -> Self {
+        Self { repositories, bus }
+    }
+
+    /// Events relayed by the dispatcher from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<DomainEvent> {
+        self.bus.subscribe()
+    }
+
+    /// A dispatcher delivering this service's outbox, woken by writes through the services
+    /// sharing its bus
+    pub fn dispatcher(&self, config: DispatcherConfig) -> WebhookDispatcher {
+        WebhookDispatcher::with_config(self.repositories.clone(), self.bus.clone(), config)
+    }
+
+    /// Events recorded after the event `after_id`, oldest first
+    pub async fn list_events(&self, after_id: i64, limit: i64) -> Result<Vec<DomainEvent>> {
+        Self::validate_limit(limit)?;
+        Ok(self.repositories.events.get_after(after_id, limit).await?)
+    }
+
+    /// The current state of every story and criterion changed after `cursor`, oldest change
+    /// first, with tombstones for those deleted since. Start from cursor 0 for a full copy.
+    pub async fn changes_since(&self, cursor: i64, limit: i64) -> Result<ChangeFeed> {
+        Self::validate_limit(limit)?;
+
+        // One extra row tells whether another page follows
+        let mut changed = self
+            .repositories
+            .events
+            .get_changed_since(cursor, limit + 1)
+            .await?;
+        let has_more = changed.len() as i64 > limit;
+        changed.truncate(limit as usize);
+
+        let (criteria_ids, story_ids): (Vec<_>, Vec<_>) = changed
+            .iter()
+            .partition(|entity| entity.acceptance_criteria_id.is_some());
+        let story_ids: Vec<String> = story_ids
+            .into_iter()
+            .map(|entity| entity.user_story_id.clone())
+            .collect();
+        let criteria_ids: Vec<String> = criteria_ids
+            .into_iter()
+            .filter_map(|entity| entity.acceptance_criteria_id.clone())
+            .collect();
+
+        let mut stories: HashMap<_, _> = self
+            .repositories
+            .user_stories
+            .get_by_ids(&story_ids)
+            .await?
+            .into_iter()
+            .map(|story| (story.id.clone(), story))
+            .collect();
+        let mut criteria: HashMap<_, _> = self
+            .repositories
+            .acceptance_criteria
+            .get_by_ids(&criteria_ids)
+            .await?
+            .into_iter()
+            .map(|criteria| (criteria.id.clone(), criteria))
+            .collect();
+
+        let next_cursor = changed.last().map_or(cursor, |entity| entity.sequence);
+        let changes = changed
+            .into_iter()
+            .map(|entity| {
+                let sequence = entity.sequence;
+                match entity.acceptance_criteria_id {
+                    None => match stories.remove(&entity.user_story_id) {
+                        Some(user_story) => Change::StoryUpserted {
+                            sequence,
+                            user_story,
+                        },
+                        None => Change::StoryDeleted {
+                            sequence,
+                            id: entity.user_story_id,
+                        },
+                    },
+                    Some(id) => match criteria.remove(&id) {
+                        Some(acceptance_criteria) => Change::CriteriaUpserted {
+                            sequence,
+                            acceptance_criteria,
+                        },
+                        None => Change::CriteriaDeleted {
+                            sequence,
+                            id,
+                            user_story_id: entity.user_story_id,
+                        },
+                    },
+                }
+            })
+            .collect();
+
+        Ok(ChangeFeed {
+            changes,
+            next_cursor,
+            has_more,
+        })
+    }
+
+    /// Register an endpoint for the events recorded from now on
+    pub async fn register_webhook(&self, request: CreateWebhookRequest) -> Result<Webhook> {
+        let url = request.url.trim();
+        match reqwest::Url::parse(url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {}
+            _ => {
+                return Err(EventServiceError::Validation {
+                    message: format!("Webhook URL must be an http or https URL: '{}'", url),
+                })
+            }
+        }
+
+        if request.secret.is_empty() {
+            return Err(EventServiceError::Validation {
+                message: "Webhook secret cannot be empty".to_string(),
+            });
+        }
+
+        let mut event_kinds = request.event_kinds;
+        event_kinds.sort_by_key(|kind| kind.as_str());
+        event_kinds.dedup();
+
+        Ok(self
+            .repositories
+            .events
+            .create_webhook(url, &request.secret, &event_kinds)
+            .await?)
+    }
+
+    /// Get all webhooks, without their secrets
+    pub async fn list_webhooks(&self) -> Result<Vec<Webhook>> {
+        Ok(self.repositories.events.get_webhooks().await?)
+    }
+
+    /// Delete a webhook; its pending deliveries are dropped
+    pub async fn delete_webhook(&self, id: i64) -> Result<()> {
+        if !self.repositories.events.delete_webhook(id).await? {
+            return Err(EventServiceError::WebhookNotFound { id });
+        }
+        Ok(())
+    }
+
+    /// Deliveries that failed on every attempt
+    pub async fn dead_letters(&self) -> Result<Vec<WebhookDelivery>> {
+        Ok(self
+            .repositories
+            .events
+            .get_deliveries(DeliveryStatus::Dead)
+            .await?)
+    }
+
+    /// Send a dead letter again, with a fresh set of attempts
+    pub async fn retry_dead_letter(&self, id: i64) -> Result<()> {
+        if !self.repositories.events.retry_dead(id).await? {
+            return Err(EventServiceError::DeadLetterNotFound { id });
+        }
+        self.bus.notify();
+        Ok(())
+    }
+
+    fn validate_limit(limit: i64) -> Result<()> {
+        if !(1..=MAX_EVENTS_PER_PAGE).contains(&limit) {
+            return Err(EventServiceError::Validation {
+                message: format!("Limit must be between 1 and {}", MAX_EVENTS_PER_PAGE),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        CreateAcceptanceCriteriaRequest, CreateUserStoryRequest, DomainEventKind,
+        UpdateUserStoryRequest,
+    };
+    use crate::services::{AcceptanceCriteriaService, TrashService, UserStoryService};
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_outbox_records_every_change(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let service = EventService::new(repositories.clone(), EventBus::new());
+        let stories = UserStoryService::new(repositories.clone());
+        let criteria = AcceptanceCriteriaService::new(repositories.clone());
+        let start = repositories.events.latest_id().await.unwrap();
+
+        stories
+            .create(CreateUserStoryRequest {
+                id: Some("US-100".to_string()),
+                title: "Export reports".to_string(),
+                description: "As a manager, I want to export reports".to_string(),
+                persona: "End User".to_string(),
+            })
+            .await
+            .unwrap();
+        criteria
+            .create(CreateAcceptanceCriteriaRequest {
+                id: Some("AC-100".to_string()),
+                user_story_id: "US-100".to_string(),
+                description: "Given a report, when I export it, then I get a CSV".to_string(),
+            })
+            .await
+            .unwrap();
+        stories
+            .update(
+                "US-100",
+                UpdateUserStoryRequest {
+                    title: Some("Export reports as CSV".to_string()),
+                    description: None,
+                    persona: None,
+                    expected_version: None,
+                },
+            )
+            .await
+            .unwrap();
+        stories.delete("US-100").await.unwrap();
+        TrashService::new(repositories)
+            .restore("US-100")
+            .await
+            .unwrap();
+
+        let events = service.list_events(start, 100).await.unwrap();
+        let kinds: Vec<_> = events.iter().map(|event| event.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                DomainEventKind::StoryCreated,
+                DomainEventKind::CriteriaAdded,
+                DomainEventKind::StoryUpdated,
+                DomainEventKind::StoryDeleted,
+                DomainEventKind::CriteriaDeleted,
+                DomainEventKind::CriteriaRestored,
+                DomainEventKind::StoryRestored,
+            ]
+        );
+        assert!(events.iter().all(|event| event.user_story_id == "US-100"));
+        assert_eq!(events[1].acceptance_criteria_id.as_deref(), Some("AC-100"));
+        assert_eq!(events[2].payload["title"], "Export reports as CSV");
+        assert!(events.windows(2).all(|pair| pair[0].id < pair[1].id));
+
+        assert_eq!(
+            service.list_events(events[5].id, 100).await.unwrap().len(),
+            1
+        );
+        assert!(service.list_events(start, 0).await.is_err());
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_changes_since(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let service = EventService::new(repositories.clone(), EventBus::new());
+        let stories = UserStoryService::new(repositories.clone());
+        let criteria = AcceptanceCriteriaService::new(repositories);
+
+        // Paging from the start copies the whole backlog
+        let mut cursor = 0;
+        let mut copied = Vec::new();
+        loop {
+            let page = service.changes_since(cursor, 2).await.unwrap();
+            assert!(page.changes.len() <= 2);
+            copied.extend(page.changes);
+            cursor = page.next_cursor;
+            if !page.has_more {
+                break;
+            }
+        }
+        assert_eq!(copied.len(), 5); // US-001..005
+        assert!(copied
+            .windows(2)
+            .all(|pair| pair[0].sequence() < pair[1].sequence()));
+        assert!(!copied.iter().any(|change| matches!(
+            change,
+            Change::StoryDeleted { .. } | Change::CriteriaDeleted { .. }
+        )));
+
+        let retitle = UpdateUserStoryRequest {
+            title: Some("Renamed".to_string()),
+            description: None,
+            persona: None,
+            expected_version: None,
+        };
+        stories.update("US-002", retitle.clone()).await.unwrap();
+        stories.update("US-002", retitle).await.unwrap();
+        // A criterion created and deleted since the cursor only leaves its tombstone
+        criteria
+            .create(CreateAcceptanceCriteriaRequest {
+                id: Some("AC-100".to_string()),
+                user_story_id: "US-003".to_string(),
+                description: "Given a draft, when I discard it, then it is gone".to_string(),
+            })
+            .await
+            .unwrap();
+        criteria.delete("AC-100").await.unwrap();
+
+        let feed = service.changes_since(cursor, 100).await.unwrap();
+        assert!(!feed.has_more);
+        assert_eq!(feed.changes.len(), 2);
+        match &feed.changes[0] {
+            Change::StoryUpserted { user_story, .. } => {
+                assert_eq!(user_story.id, "US-002");
+                assert_eq!(user_story.title, "Renamed");
+            }
+            other => panic!("expected an upsert, got {:?}", other),
+        }
+        match &feed.changes[1] {
+            Change::CriteriaDeleted {
+                id, user_story_id, ..
+            } => assert_eq!((id.as_str(), user_story_id.as_str()), ("AC-100", "US-003")),
+            other => panic!("expected a tombstone, got {:?}", other),
+        }
+        assert_eq!(feed.next_cursor, feed.changes[1].sequence());
+
+        let caught_up = service.changes_since(feed.next_cursor, 100).await.unwrap();
+        assert!(caught_up.changes.is_empty());
+        assert_eq!(caught_up.next_cursor, feed.next_cursor);
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_webhooks(pool: sqlx::SqlitePool) {
+        let service = EventService::new(Repositories::new(pool), EventBus::new());
+
+        for (url, secret) in [
+            ("ftp://example.com", "s"),
+            ("not a url", "s"),
+            ("http://example.com", ""),
+        ] {
+            let result = service
+                .register_webhook(CreateWebhookRequest {
+                    url: url.to_string(),
+                    secret: secret.to_string(),
+                    event_kinds: Vec::new(),
+                })
+                .await;
+            assert!(matches!(result, Err(EventServiceError::Validation { .. })));
+        }
+
+        let webhook = service
+            .register_webhook(CreateWebhookRequest {
+                url: " https://example.com/hooks ".to_string(),
+                secret: "s3cret".to_string(),
+                event_kinds: vec![DomainEventKind::StoryUpdated, DomainEventKind::StoryUpdated],
+            })
+            .await
+            .unwrap();
+        assert_eq!(webhook.url, "https://example.com/hooks");
+        assert_eq!(webhook.event_kinds, [DomainEventKind::StoryUpdated]);
+        assert!(serde_json::to_string(&webhook)
+            .unwrap()
+            .find("s3cret")
+            .is_none());
+
+        assert_eq!(service.list_webhooks().await.unwrap().len(), 1);
+        service.delete_webhook(webhook.id).await.unwrap();
+        assert!(matches!(
+            service.delete_webhook(webhook.id).await,
+            Err(EventServiceError::WebhookNotFound { .. })
+        ));
+        assert!(matches!(
+            service.retry_dead_letter(1).await,
+            Err(EventServiceError::DeadLetterNotFound { .. })
+        ));
+    }
+}
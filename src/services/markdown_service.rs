@@ -0,0 +1,665 @@
+use crate::formats::markdown::{self, MarkdownError};
+use crate::models::{
+    CreateAcceptanceCriteriaRequest, IdScheme, UpdateAcceptanceCriteriaRequest,
+    UpdateUserStoryRequest, ValidationPolicy,
+};
+use crate::repositories::user_story_repository::{StoryEdit, StoryEditWrite};
+use crate::repositories::Repositories;
+use crate::services::acceptance_criteria_service::AcceptanceCriteriaServiceError;
+use crate::services::user_story_service::UserStoryServiceError;
+use crate::services::{AcceptanceCriteriaService, UserStoryService};
+use serde::Serialize;
+use std::collections::HashSet;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum MarkdownServiceError {
+    #[error("Invalid Markdown document: {0}")]
+    Parse(#[from] MarkdownError),
+    #[error(transparent)]
+    UserStory(#[from] UserStoryServiceError),
+    #[error(transparent)]
+    AcceptanceCriteria(#[from] AcceptanceCriteriaServiceError),
+}
+
+pub type Result<T> = std::result::Result<T, MarkdownServiceError>;
+
+/// Changes made (or, for a dry run, that would be made) by applying a Markdown document
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MarkdownApplyReport {
+    pub user_story_id: String,
+    pub dry_run: bool,
+    /// Names of the story fields whose value changed
+    pub updated_fields: Vec<String>,
+    pub criteria_created: Vec<String>,
+    pub criteria_updated: Vec<String>,
+    pub criteria_deleted: Vec<String>,
+}
+
+impl MarkdownApplyReport {
+    pub fn is_empty(&self) -> bool {
+        self.updated_fields.is_empty()
+            && self.criteria_created.is_empty()
+            && self.criteria_updated.is_empty()
+            && self.criteria_deleted.is_empty()
+    }
+}
+
+#[derive(Clone)]
+pub struct MarkdownService {
+    repositories: Repositories,
+    user_stories: UserStoryService,
+    acceptance_criteria: AcceptanceCriteriaService,
+}
+
+impl MarkdownService {
+    pub fn new(repositories: Repositories) -> Self {
+        Self {
+            user_stories: UserStoryService::new(repositories.clone()),
+            acceptance_criteria: AcceptanceCriteriaService::new(repositories.clone()),
+            repositories,
+        }
+    }
+
+    /// Validate imported ids with the given scheme instead of `US-`/`AC-`
+    pub fn with_id_scheme(mut self, id_scheme: IdScheme) -> Self {
+        self.user_stories = self.user_stories.with_id_scheme(id_scheme.clone());
+        self.acceptance_criteria = self.acceptance_criteria.with_id_scheme(id_scheme);
+        self
+    }
+
+    /// Validate applied documents against the given policy
+    pub fn with_validation_policy(mut self, policy: ValidationPolicy) -> Self {
+        self.user_stories = self.user_stories.with_validation_policy(policy.clone());
+        self.acceptance_criteria = self.acceptance_criteria.with_validation_policy(policy);
+        self
+    }
+
+    /// Apply documents as the given agent, which may edit stories it holds the claim on
+    pub fn with_agent(mut self, agent_id: impl Into<String>) -> Self {
+        let agent_id = agent_id.into();
+        self.user_stories = self.user_stories.with_agent(agent_id.clone());
+        self.acceptance_criteria = self.acceptance_criteria.with_agent(agent_id);
+        self
+    }
+
+    /// Render a user story and its acceptance criteria as a Markdown document
+    pub async fn render(&self, id: &str) -> Result<String> {
+        let story = self.user_stories.get_with_criteria(id).await?;
+        Ok(markdown::render_markdown(&story))
+    }
+
+    /// Apply an edited Markdown document to the stored story it refers to.
+    ///
+    /// Changed story fields are updated; criteria missing from the document are deleted,
+    /// changed ones updated and new ones created, all in one transaction so a failing edit
+    /// leaves the story as it was. Criteria added without an id are numbered `AC-<story>-<n>`.
+    /// Fails with a conflict when the story has moved past the `version` the document has.
+    /// A dry run makes the same checks, so it fails exactly when the apply would.
+    pub async fn apply(&self, document: &str, dry_run: bool) -> Result<MarkdownApplyReport> {
+        let edited = markdown::parse_markdown(document, self.user_stories.id_scheme())?;
+        let current = self.user_stories.get_with_criteria(&edited.id).await?;
+
+        let mut report = MarkdownApplyReport {
+            user_story_id: edited.id.clone(),
+            dry_run,
+            ..Default::default()
+        };
+
+        let story = &current.user_story;
+        let update_request = UpdateUserStoryRequest {
+            title: (edited.title != story.title).then(|| edited.title.clone()),
+            description: (edited.description != story.description)
+                .then(|| edited.description.clone()),
+            persona: (edited.persona != story.persona).then(|| edited.persona.clone()),
+            expected_version: edited.version,
+        };
+        for (field, changed) in [
+            ("title", update_request.title.is_some()),
+            ("description", update_request.description.is_some()),
+            ("persona", update_request.persona.is_some()),
+        ] {
+            if changed {
+                report.updated_fields.push(field.to_string());
+            }
+        }
+
+        let kept_ids: HashSet<&str> = edited
+            .acceptance_criteria
+            .iter()
+            .filter_map(|criterion| criterion.id.as_deref())
+            .collect();
+        let deleted: Vec<String> = current
+            .acceptance_criteria
+            .iter()
+            .filter(|criteria| !kept_ids.contains(criteria.id.as_str()))
+            .map(|criteria| criteria.id.clone())
+            .collect();
+
+        let mut updates = Vec::new();
+        let mut creates = Vec::new();
+        let mut taken_ids: HashSet<String> = kept_ids.iter().map(|id| id.to_string()).collect();
+        let criteria_prefix = self
+            .user_stories
+            .id_scheme()
+            .criteria_prefix_for(&edited.id);
+
+        for criterion in &edited.acceptance_criteria {
+            let existing = criterion.id.as_ref().and_then(|id| {
+                current
+                    .acceptance_criteria
+                    .iter()
+                    .find(|criteria| &criteria.id == id)
+            });
+
+            match existing {
+                Some(existing) => {
+                    if existing.description != criterion.description {
+                        updates.push((existing.id.clone(), criterion.description.clone()));
+                    }
+                }
+                None => {
+                    let id = match &criterion.id {
+                        Some(id) => id.clone(),
+                        None => {
+                            self.allocate_criteria_id(&criteria_prefix, &mut taken_ids)
+                                .await?
+                        }
+                    };
+                    creates.push(CreateAcceptanceCriteriaRequest {
+                        id: Some(id),
+                        user_story_id: edited.id.clone(),
+                        description: criterion.description.clone(),
+                    });
+                }
+            }
+        }
+
+        report.criteria_deleted = deleted.clone();
+        report.criteria_updated = updates.iter().map(|(id, _)| id.clone()).collect();
+        report.criteria_created = creates
+            .iter()
+            .filter_map(|request| request.id.clone())
+            .collect();
+
+        if report.is_empty() {
+            return Ok(report);
+        }
+
+        // Check the whole edit up front, for dry runs too; the transaction below only guards
+        // against changes made meanwhile
+        if let Some(expected_version) = edited.version {
+            if expected_version != story.version {
+                return Err(UserStoryServiceError::Conflict {
+                    expected_version,
+                    current: Box::new(story.clone()),
+                }
+                .into());
+            }
+        }
+        if let Some(claim) = self
+            .repositories
+            .claims
+            .get_active(&edited.id)
+            .await
+            .map_err(UserStoryServiceError::from)?
+        {
+            if !claim.is_held_by(self.user_stories.agent_id()) {
+                return Err(UserStoryServiceError::Claimed { claim }.into());
+            }
+        }
+        self.user_stories.validate_update_request(&update_request)?;
+        for (_, description) in &updates {
+            self.acceptance_criteria
+                .validate_update_request(&UpdateAcceptanceCriteriaRequest {
+                    description: Some(description.clone()),
+                    expected_version: None,
+                })?;
+        }
+        for request in &creates {
+            self.acceptance_criteria.validate_fields(request)?;
+        }
+        self.check_criteria_count(
+            &edited.id,
+            current.acceptance_criteria.len(),
+            &deleted,
+            &creates,
+        )?;
+        for id in &report.criteria_created {
+            if self
+                .repositories
+                .acceptance_criteria
+                .exists(id)
+                .await
+                .map_err(AcceptanceCriteriaServiceError::from)?
+            {
+                return Err(
+                    AcceptanceCriteriaServiceError::AlreadyExists { id: id.clone() }.into(),
+                );
+            }
+        }
+        if dry_run {
+            return Ok(report);
+        }
+
+        // The document carries the story version only, so criteria are expected at the
+        // versions read above
+        let criteria_version = |id: &str| {
+            current
+                .acceptance_criteria
+                .iter()
+                .find(|criteria| criteria.id == id)
+                .map(|criteria| criteria.version)
+        };
+        let edit = StoryEdit {
+            story: update_request,
+            deleted: deleted
+                .into_iter()
+                .map(|id| {
+                    let version = criteria_version(&id);
+                    (id, version)
+                })
+                .collect(),
+            updated: updates
+                .into_iter()
+                .map(|(id, description)| {
+                    let request = UpdateAcceptanceCriteriaRequest {
+                        description: Some(description),
+                        expected_version: criteria_version(&id),
+                    };
+                    (id, request)
+                })
+                .collect(),
+            created: creates,
+        };
+        let write = self
+            .repositories
+            .user_stories
+            .apply_edit(&edited.id, edit, self.user_stories.agent_id())
+            .await
+            .map_err(UserStoryServiceError::from)?;
+        match write {
+            StoryEditWrite::Story(write) => {
+                self.user_stories
+                    .versioned(&edited.id, edited.version, write)?;
+            }
+            StoryEditWrite::Criteria { id, write } => {
+                let expected_version = criteria_version(&id);
+                self.acceptance_criteria
+                    .versioned(&id, expected_version, write)?;
+            }
+            // Another story or criterion took the id after the check above
+            StoryEditWrite::CriteriaIdTaken { id } => {
+                return Err(AcceptanceCriteriaServiceError::AlreadyExists { id }.into())
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Refuse an edit that would leave the story with more criteria than the policy allows
+    fn check_criteria_count(
+        &self,
+        user_story_id: &str,
+        current: usize,
+        deleted: &[String],
+        created: &[CreateAcceptanceCriteriaRequest],
+    ) -> Result<()> {
+        let count = (current - deleted.len() + created.len()) as i64;
+        let max_criteria = self.acceptance_criteria.policy().max_criteria_per_story;
+        if count > max_criteria {
+            return Err(AcceptanceCriteriaServiceError::BusinessRule {
+                message: format!(
+                    "User story {} would have {} acceptance criteria. Maximum allowed is {}.",
+                    user_story_id, count, max_criteria
+                ),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Find the first unused `AC-<story>-<n>` id; unlike the id sequences this has no side
+    /// effects, so dry runs report the ids an apply would create
+    async fn allocate_criteria_id(
+        &self,
+        criteria_prefix: &str,
+        taken: &mut HashSet<String>,
+    ) -> Result<String> {
+        for n in 1.. {
+            let candidate = format!("{}{}", criteria_prefix, n);
+            // Criteria in the trash keep their id until purged
+            if !taken.contains(&candidate)
+                && !self
+                    .repositories
+                    .acceptance_criteria
+                    .exists(&candidate)
+                    .await
+                    .map_err(AcceptanceCriteriaServiceError::from)?
+            {
+                taken.insert(candidate.clone());
+                return Ok(candidate);
+            }
+        }
+        unreachable!("id space exhausted")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::Repositories;
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_render(pool: sqlx::SqlitePool) {
+        let service = MarkdownService::new(Repositories::new(pool));
+
+        let document = service.render("US-001").await.unwrap();
+        assert!(document.contains("id: US-001"));
+        assert!(document.contains("# User Login Feature"));
+        assert_eq!(document.matches("- [ ] AC-").count(), 3);
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_apply_unchanged_document(pool: sqlx::SqlitePool) {
+        let service = MarkdownService::new(Repositories::new(pool));
+        let document = service.render("US-001").await.unwrap();
+
+        let report = service.apply(&document, false).await.unwrap();
+        assert!(report.is_empty());
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_apply_edits(pool: sqlx::SqlitePool) {
+        let service = MarkdownService::new(Repositories::new(pool));
+        let document = service
+            .render("US-001")
+            .await
+            .unwrap()
+            .replace("# User Login Feature", "# Sign In")
+            .replace(
+                "When I enter invalid credentials, Then I should see an error message",
+                "When I enter invalid credentials, Then I should see a friendly error",
+            )
+            .lines()
+            .filter(|line| !line.contains("AC-003"))
+            .collect::<Vec<_>>()
+            .join("\n")
+            + "\n- [ ] Given I forgot my password, When I click the reset link, Then I get an email\n";
+
+        let report = service.apply(&document, false).await.unwrap();
+        assert_eq!(report.updated_fields, vec!["title"]);
+        assert_eq!(report.criteria_updated, vec!["AC-002"]);
+        assert_eq!(report.criteria_deleted, vec!["AC-003"]);
+        assert_eq!(report.criteria_created, vec!["AC-001-1"]);
+
+        let story = service
+            .user_stories
+            .get_with_criteria("US-001")
+            .await
+            .unwrap();
+        assert_eq!(story.user_story.title, "Sign In");
+        let ids: Vec<&str> = story
+            .acceptance_criteria
+            .iter()
+            .map(|c| c.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["AC-001", "AC-002", "AC-001-1"]);
+        assert!(story.acceptance_criteria[1]
+            .description
+            .ends_with("a friendly error"));
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_apply_skips_trashed_ids(pool: sqlx::SqlitePool) {
+        let service = MarkdownService::new(Repositories::new(pool));
+        service
+            .acceptance_criteria
+            .create(CreateAcceptanceCriteriaRequest {
+                id: Some("AC-001-1".to_string()),
+                user_story_id: "US-001".to_string(),
+                description: "Given a trashed criterion, When I add another, Then ids differ"
+                    .to_string(),
+            })
+            .await
+            .unwrap();
+        service
+            .acceptance_criteria
+            .delete("AC-001-1")
+            .await
+            .unwrap();
+
+        let document = service.render("US-001").await.unwrap()
+            + "- [ ] Given I forgot my password, When I click the reset link, Then I get an email\n";
+        let report = service.apply(&document, false).await.unwrap();
+        assert_eq!(report.criteria_created, vec!["AC-001-2"]);
+        assert_eq!(
+            service
+                .user_stories
+                .get_with_criteria("US-001")
+                .await
+                .unwrap()
+                .acceptance_criteria
+                .len(),
+            4
+        );
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_apply_taken_id_changes_nothing(pool: sqlx::SqlitePool) {
+        let service = MarkdownService::new(Repositories::new(pool));
+        let before = service
+            .user_stories
+            .get_with_criteria("US-001")
+            .await
+            .unwrap();
+        // AC-004 belongs to US-002
+        let document = service
+            .render("US-001")
+            .await
+            .unwrap()
+            .replace("# User Login Feature", "# Sign In")
+            .lines()
+            .filter(|line| !line.contains("AC-003"))
+            .collect::<Vec<_>>()
+            .join("\n")
+            + "\n- [ ] AC-004: Given I forgot my password, When I reset it, Then I can log in\n";
+
+        let result = service.apply(&document, false).await;
+        assert!(matches!(
+            result.unwrap_err(),
+            MarkdownServiceError::AcceptanceCriteria(
+                AcceptanceCriteriaServiceError::AlreadyExists { id }
+            ) if id == "AC-004"
+        ));
+
+        let after = service
+            .user_stories
+            .get_with_criteria("US-001")
+            .await
+            .unwrap();
+        assert_eq!(after.user_story.title, "User Login Feature");
+        assert_eq!(after.user_story.version, before.user_story.version);
+        assert_eq!(after.acceptance_criteria.len(), 3);
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_apply_too_many_criteria(pool: sqlx::SqlitePool) {
+        let service = MarkdownService::new(Repositories::new(pool)).with_validation_policy(
+            ValidationPolicy {
+                max_criteria_per_story: 3,
+                ..ValidationPolicy::default()
+            },
+        );
+        let document = service.render("US-001").await.unwrap()
+            + "- [ ] Given I forgot my password, When I click the reset link, Then I get an email\n";
+
+        let result = service.apply(&document, false).await;
+        assert!(matches!(
+            result.unwrap_err(),
+            MarkdownServiceError::AcceptanceCriteria(
+                AcceptanceCriteriaServiceError::BusinessRule { .. }
+            )
+        ));
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_apply_stale_document(pool: sqlx::SqlitePool) {
+        let service = MarkdownService::new(Repositories::new(pool));
+        let document = service.render("US-001").await.unwrap();
+        let version = service
+            .user_stories
+            .get_by_id("US-001")
+            .await
+            .unwrap()
+            .version;
+        service
+            .user_stories
+            .update(
+                "US-001",
+                UpdateUserStoryRequest {
+                    title: Some("Log In".to_string()),
+                    description: None,
+                    persona: None,
+                    expected_version: None,
+                },
+            )
+            .await
+            .unwrap();
+
+        let retitled = document.replace("# User Login Feature", "# Sign In");
+        let result = service.apply(&retitled, false).await;
+        assert!(matches!(
+            result.unwrap_err(),
+            MarkdownServiceError::UserStory(UserStoryServiceError::Conflict { expected_version, .. })
+                if expected_version == version
+        ));
+
+        // Criteria edits are guarded by the story version as well
+        let trimmed = document
+            .lines()
+            .filter(|line| !line.contains("AC-003"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let result = service.apply(&trimmed, false).await;
+        assert!(matches!(
+            result.unwrap_err(),
+            MarkdownServiceError::UserStory(UserStoryServiceError::Conflict { .. })
+        ));
+
+        let story = service
+            .user_stories
+            .get_with_criteria("US-001")
+            .await
+            .unwrap();
+        assert_eq!(story.user_story.title, "Log In");
+        assert_eq!(story.acceptance_criteria.len(), 3);
+
+        // A fresh document applies
+        let document = service
+            .render("US-001")
+            .await
+            .unwrap()
+            .replace("# Log In", "# Sign In");
+        service.apply(&document, false).await.unwrap();
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_apply_dry_run(pool: sqlx::SqlitePool) {
+        let service = MarkdownService::new(Repositories::new(pool));
+        let document = service
+            .render("US-001")
+            .await
+            .unwrap()
+            .replace("persona: Registered User", "persona: Returning User");
+
+        let report = service.apply(&document, true).await.unwrap();
+        assert_eq!(report.updated_fields, vec!["persona"]);
+
+        let story = service.user_stories.get_by_id("US-001").await.unwrap();
+        assert_eq!(story.persona, "Registered User");
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_dry_run_of_invalid_document(pool: sqlx::SqlitePool) {
+        let service = MarkdownService::new(Repositories::new(pool));
+        let document = service.render("US-001").await.unwrap();
+
+        let too_long = document.replace("# User Login Feature", &format!("# {}", "x".repeat(201)));
+        assert!(matches!(
+            service.apply(&too_long, true).await.unwrap_err(),
+            MarkdownServiceError::UserStory(UserStoryServiceError::Validation { .. })
+        ));
+
+        // AC-004 belongs to US-002
+        let taken_id = document.clone() + "- [ ] AC-004: Given a taken id, Then the apply fails\n";
+        assert!(matches!(
+            service.apply(&taken_id, true).await.unwrap_err(),
+            MarkdownServiceError::AcceptanceCriteria(
+                AcceptanceCriteriaServiceError::AlreadyExists { .. }
+            )
+        ));
+
+        service
+            .user_stories
+            .update(
+                "US-001",
+                UpdateUserStoryRequest {
+                    title: Some("Log In".to_string()),
+                    description: None,
+                    persona: None,
+                    expected_version: None,
+                },
+            )
+            .await
+            .unwrap();
+        let stale = document.replace("# User Login Feature", "# Sign In");
+        assert!(matches!(
+            service.apply(&stale, true).await.unwrap_err(),
+            MarkdownServiceError::UserStory(UserStoryServiceError::Conflict { .. })
+        ));
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_apply_validation_error(pool: sqlx::SqlitePool) {
+        let service = MarkdownService::new(Repositories::new(pool));
+        let document = service
+            .render("US-002")
+            .await
+            .unwrap()
+            .replace("# User Registration", &format!("# {}", "x".repeat(201)));
+
+        let result = service.apply(&document, false).await;
+        assert!(matches!(
+            result.unwrap_err(),
+            MarkdownServiceError::UserStory(UserStoryServiceError::Validation { .. })
+        ));
+    }
+}
@@ -0,0 +1,152 @@
+use crate::models::{CreateMemberRequest, Member, MemberWithWorkload};
+use crate::repositories::Repositories;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum MemberServiceError {
+    #[error("Member not found: {member}")]
+    NotFound { member: String },
+    #[error("Member already exists: {name}")]
+    AlreadyExists { name: String },
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Validation error: {message}")]
+    Validation { message: String },
+    #[error("Business rule violation: {message}")]
+    BusinessRule { message: String },
+}
+
+pub type Result<T> = std::result::Result<T, MemberServiceError>;
+
+/// People and agents that user stories are assigned to and reviewed by
+#[derive(Clone)]
+pub struct MemberService {
+    repositories: Repositories,
+}
+
+impl MemberService {
+    pub fn new(repositories: Repositories) -> Self {
+        Self { repositories }
+    }
+
+    /// Register a new member
+    pub async fn create(&self, mut request: CreateMemberRequest) -> Result<Member> {
+        request.name = request.name.trim().to_string();
+        self.validate_name(&request.name)?;
+
+        // Names are unique regardless of case
+        if let Some(existing) = self.repositories.members.get_by_name(&request.name).await? {
+            return Err(MemberServiceError::AlreadyExists {
+                name: existing.name,
+            });
+        }
+
+        Ok(self.repositories.members.create(request).await?)
+    }
+
+    /// Get member by name, ignoring case
+    pub async fn get_by_name(&self, name: &str) -> Result<Member> {
+        self.repositories
+            .members
+            .get_by_name(name.trim())
+            .await?
+            .ok_or_else(|| MemberServiceError::NotFound {
+                member: name.to_string(),
+            })
+    }
+
+    /// Get all members with the number of stories each is assigned to and reviews
+    pub async fn get_all(&self) -> Result<Vec<MemberWithWorkload>> {
+        Ok(self.repositories.members.get_all_with_workload().await?)
+    }
+
+    /// Delete member; members still assigned to or reviewing live stories cannot be deleted
+    pub async fn delete(&self, name: &str) -> Result<()> {
+        let member = self.get_by_name(name).await?;
+
+        let assignments = self
+            .repositories
+            .members
+            .count_assignments(member.id)
+            .await?;
+        if assignments > 0 {
+            return Err(MemberServiceError::BusinessRule {
+                message: format!(
+                    "Member '{}' is assigned to or reviewing {} user stories",
+                    member.name, assignments
+                ),
+            });
+        }
+
+        self.repositories.members.delete(member.id).await?;
+        Ok(())
+    }
+
+    fn validate_name(&self, name: &str) -> Result<()> {
+        if name.is_empty() {
+            return Err(MemberServiceError::Validation {
+                message: "Member name cannot be empty".to_string(),
+            });
+        }
+
+        if name.len() > 100 {
+            return Err(MemberServiceError::Validation {
+                message: "Member name cannot exceed 100 characters".to_string(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AssignmentRole, MemberKind};
+    use crate::services::UserStoryService;
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_members(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let service = MemberService::new(repositories.clone());
+        let stories = UserStoryService::new(repositories);
+
+        let agent = service
+            .create(CreateMemberRequest {
+                name: " qa-agent ".to_string(),
+                kind: MemberKind::Agent,
+            })
+            .await
+            .unwrap();
+        assert_eq!(agent.name, "qa-agent");
+        assert_eq!(agent.kind, MemberKind::Agent);
+
+        let duplicate = service
+            .create(CreateMemberRequest {
+                name: "QA-Agent".to_string(),
+                kind: MemberKind::Human,
+            })
+            .await;
+        assert!(matches!(
+            duplicate,
+            Err(MemberServiceError::AlreadyExists { .. })
+        ));
+
+        stories
+            .assign("US-001", AssignmentRole::Reviewer, "QA-AGENT", None)
+            .await
+            .unwrap();
+        let members = service.get_all().await.unwrap();
+        assert_eq!((members[0].assigned, members[0].reviewing), (0, 1));
+
+        assert!(matches!(
+            service.delete("qa-agent").await,
+            Err(MemberServiceError::BusinessRule { .. })
+        ));
+
+        // Stories in the trash do not hold on to their members
+        stories.delete("US-001").await.unwrap();
+        service.delete("qa-agent").await.unwrap();
+        assert!(service.get_all().await.unwrap().is_empty());
+    }
+}
@@ -1,24 +1,131 @@
 pub mod acceptance_criteria_service;
+pub mod backlog_service;
+pub mod claim_service;
+pub mod comment_service;
+pub mod event_service;
+pub mod markdown_service;
+pub mod member_service;
+pub mod persona_service;
+pub mod quality_service;
+pub mod revision_service;
+pub mod trash_service;
 pub mod user_story_service;
 
 pub use acceptance_criteria_service::AcceptanceCriteriaService;
+pub use backlog_service::BacklogService;
+pub use claim_service::ClaimService;
+pub use comment_service::CommentService;
+pub use event_service::EventService;
+pub use markdown_service::MarkdownService;
+pub use member_service::MemberService;
+pub use persona_service::PersonaService;
+pub use quality_service::QualityService;
+pub use revision_service::RevisionService;
+pub use trash_service::TrashService;
 pub use user_story_service::UserStoryService;
 
+use crate::events::EventBus;
+use crate::models::{IdScheme, ValidationPolicy, ValidationPolicyError};
 use crate::repositories::Repositories;
 use std::sync::Arc;
 
+/// Project settings shared by every service
+#[derive(Debug, Clone, Default)]
+pub struct ServiceConfig {
+    pub id_scheme: IdScheme,
+    pub validation: ValidationPolicy,
+}
+
+impl ServiceConfig {
+    /// Id prefixes from the environment and the policy file named by `VALIDATION_POLICY`, if any
+    pub fn from_env() -> Result<Self, ValidationPolicyError> {
+        let validation = match std::env::var("VALIDATION_POLICY") {
+            Ok(path) if !path.is_empty() => ValidationPolicy::from_file(path)?,
+            _ => ValidationPolicy::default(),
+        };
+        Ok(Self {
+            id_scheme: IdScheme::from_env(),
+            validation,
+        })
+    }
+}
+
 #[derive(Clone)]
 pub struct Services {
     pub user_stories: Arc<UserStoryService>,
     #[allow(dead_code)]
     pub acceptance_criteria: Arc<AcceptanceCriteriaService>,
+    pub backlog: Arc<BacklogService>,
+    pub claims: Arc<ClaimService>,
+    pub comments: Arc<CommentService>,
+    pub events: Arc<EventService>,
+    pub markdown: Arc<MarkdownService>,
+    pub members: Arc<MemberService>,
+    pub personas: Arc<PersonaService>,
+    pub quality: Arc<QualityService>,
+    pub revisions: Arc<RevisionService>,
+    pub trash: Arc<TrashService>,
 }
 
 impl Services {
     pub fn new(repositories: Repositories) -> Self {
+        Self::with_config(repositories, ServiceConfig::default())
+    }
+
+    /// Services generating ids with the configured scheme and validating against its policy
+    pub fn with_config(repositories: Repositories, config: ServiceConfig) -> Self {
+        let ServiceConfig {
+            id_scheme,
+            validation,
+        } = config;
+        let bus = EventBus::new();
+        Self {
+            user_stories: Arc::new(
+                UserStoryService::new(repositories.clone())
+                    .with_id_scheme(id_scheme.clone())
+                    .with_validation_policy(validation.clone())
+                    .with_event_bus(bus.clone()),
+            ),
+            acceptance_criteria: Arc::new(
+                AcceptanceCriteriaService::new(repositories.clone())
+                    .with_id_scheme(id_scheme.clone())
+                    .with_validation_policy(validation.clone())
+                    .with_event_bus(bus.clone()),
+            ),
+            backlog: Arc::new(
+                BacklogService::new(repositories.clone())
+                    .with_id_scheme(id_scheme.clone())
+                    .with_validation_policy(validation.clone()),
+            ),
+            claims: Arc::new(ClaimService::new(repositories.clone())),
+            comments: Arc::new(CommentService::new(repositories.clone())),
+            events: Arc::new(EventService::new(repositories.clone(), bus)),
+            markdown: Arc::new(
+                MarkdownService::new(repositories.clone())
+                    .with_id_scheme(id_scheme)
+                    .with_validation_policy(validation.clone()),
+            ),
+            members: Arc::new(MemberService::new(repositories.clone())),
+            personas: Arc::new(PersonaService::new(repositories.clone())),
+            quality: Arc::new(QualityService::new(repositories.clone())),
+            revisions: Arc::new(
+                RevisionService::new(repositories.clone()).with_validation_policy(validation),
+            ),
+            trash: Arc::new(TrashService::new(repositories)),
+        }
+    }
+
+    /// The same services writing as the given agent, which may change the stories it holds
+    /// claims on. Services not scoped to an agent cannot change any claimed story.
+    pub fn as_agent(&self, agent_id: &str) -> Self {
         Self {
-            user_stories: Arc::new(UserStoryService::new(repositories.clone())),
-            acceptance_criteria: Arc::new(AcceptanceCriteriaService::new(repositories)),
+            user_stories: Arc::new((*self.user_stories).clone().with_agent(agent_id)),
+            acceptance_criteria: Arc::new((*self.acceptance_criteria).clone().with_agent(agent_id)),
+            markdown: Arc::new((*self.markdown).clone().with_agent(agent_id)),
+            revisions: Arc::new((*self.revisions).clone().with_agent(agent_id)),
+            backlog: Arc::new((*self.backlog).clone().with_agent(agent_id)),
+            trash: Arc::new((*self.trash).clone().with_agent(agent_id)),
+            ..self.clone()
         }
     }
 }
@@ -0,0 +1,278 @@
+use crate::models::{
+    CreatePersonaRequest, Persona, PersonaWithUsage, UpdatePersonaRequest, MAX_PERSONA_NAME_LENGTH,
+};
+use crate::repositories::Repositories;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum PersonaServiceError {
+    #[error("Persona not found: {persona}")]
+    NotFound { persona: String },
+    #[error("Persona already exists: {name}")]
+    AlreadyExists { name: String },
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Validation error: {message}")]
+    Validation { message: String },
+    #[error("Business rule violation: {message}")]
+    BusinessRule { message: String },
+}
+
+pub type Result<T> = std::result::Result<T, PersonaServiceError>;
+
+#[derive(Clone)]
+pub struct PersonaService {
+    repositories: Repositories,
+}
+
+impl PersonaService {
+    pub fn new(repositories: Repositories) -> Self {
+        Self { repositories }
+    }
+
+    /// Create a new persona with validation
+    pub async fn create(&self, mut request: CreatePersonaRequest) -> Result<Persona> {
+        request.name = request.name.trim().to_string();
+        self.validate_name(&request.name)?;
+        self.validate_details(
+            Some(&request.description),
+            Some(&request.goals),
+            Some(&request.pain_points),
+        )?;
+
+        // Names are unique regardless of case
+        if let Some(existing) = self
+            .repositories
+            .personas
+            .get_by_name(&request.name)
+            .await?
+        {
+            return Err(PersonaServiceError::AlreadyExists {
+                name: existing.name,
+            });
+        }
+
+        Ok(self.repositories.personas.create(request).await?)
+    }
+
+    /// Get persona by ID
+    pub async fn get_by_id(&self, id: i64) -> Result<Persona> {
+        self.repositories
+            .personas
+            .get_by_id(id)
+            .await?
+            .ok_or_else(|| PersonaServiceError::NotFound {
+                persona: id.to_string(),
+            })
+    }
+
+    /// Get persona by name, ignoring case
+    pub async fn get_by_name(&self, name: &str) -> Result<Persona> {
+        self.repositories
+            .personas
+            .get_by_name(name)
+            .await?
+            .ok_or_else(|| PersonaServiceError::NotFound {
+                persona: name.to_string(),
+            })
+    }
+
+    /// Get all personas with the number of user stories written for each
+    pub async fn get_all(&self) -> Result<Vec<PersonaWithUsage>> {
+        Ok(self.repositories.personas.get_all_with_usage().await?)
+    }
+
+    /// Update persona; renaming it renames it on every linked user story
+    pub async fn update(&self, id: i64, mut request: UpdatePersonaRequest) -> Result<Persona> {
+        if let Some(name) = request.name.as_mut() {
+            *name = name.trim().to_string();
+            self.validate_name(name)?;
+
+            if let Some(existing) = self.repositories.personas.get_by_name(name).await? {
+                if existing.id != id {
+                    return Err(PersonaServiceError::AlreadyExists {
+                        name: existing.name,
+                    });
+                }
+            }
+        }
+        self.validate_details(
+            request.description.as_ref(),
+            request.goals.as_ref(),
+            request.pain_points.as_ref(),
+        )?;
+
+        self.repositories
+            .personas
+            .update(id, request)
+            .await?
+            .ok_or_else(|| PersonaServiceError::NotFound {
+                persona: id.to_string(),
+            })
+    }
+
+    /// Delete persona; personas still used by user stories cannot be deleted
+    pub async fn delete(&self, id: i64) -> Result<()> {
+        let persona = self.get_by_id(id).await?;
+
+        let story_count = self.repositories.personas.count_stories(id).await?;
+        if story_count > 0 {
+            return Err(PersonaServiceError::BusinessRule {
+                message: format!(
+                    "Persona '{}' is used by {} user stories",
+                    persona.name, story_count
+                ),
+            });
+        }
+
+        self.repositories.personas.delete(id).await?;
+        Ok(())
+    }
+
+    fn validate_name(&self, name: &str) -> Result<()> {
+        if name.trim().is_empty() {
+            return Err(PersonaServiceError::Validation {
+                message: "Persona name cannot be empty".to_string(),
+            });
+        }
+
+        if name.len() > MAX_PERSONA_NAME_LENGTH {
+            return Err(PersonaServiceError::Validation {
+                message: format!(
+                    "Persona name cannot exceed {} characters",
+                    MAX_PERSONA_NAME_LENGTH
+                ),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn validate_details(
+        &self,
+        description: Option<&String>,
+        goals: Option<&Vec<String>>,
+        pain_points: Option<&Vec<String>>,
+    ) -> Result<()> {
+        if description.is_some_and(|description| description.len() > 2000) {
+            return Err(PersonaServiceError::Validation {
+                message: "Persona description cannot exceed 2000 characters".to_string(),
+            });
+        }
+
+        let items = goals.into_iter().chain(pain_points).flatten();
+        for item in items {
+            if item.trim().is_empty() {
+                return Err(PersonaServiceError::Validation {
+                    message: "Persona goals and pain points cannot be empty".to_string(),
+                });
+            }
+            if item.len() > 500 {
+                return Err(PersonaServiceError::Validation {
+                    message: "Persona goals and pain points cannot exceed 500 characters"
+                        .to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::UserStoryService;
+
+    fn create_test_request() -> CreatePersonaRequest {
+        CreatePersonaRequest {
+            name: "Shopper".to_string(),
+            description: "Buys things online".to_string(),
+            goals: vec!["Check out quickly".to_string()],
+            pain_points: vec![],
+        }
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_create_duplicate_ignoring_case(pool: sqlx::SqlitePool) {
+        let service = PersonaService::new(Repositories::new(pool));
+
+        let mut request = create_test_request();
+        request.name = "registered USER".to_string();
+        let result = service.create(request).await;
+        assert!(matches!(
+            result,
+            Err(PersonaServiceError::AlreadyExists { name }) if name == "Registered User"
+        ));
+
+        let mut request = create_test_request();
+        request.name = "Registered User ".to_string();
+        assert!(matches!(
+            service.create(request).await,
+            Err(PersonaServiceError::AlreadyExists { .. })
+        ));
+    }
+
+    #[sqlx::test]
+    async fn test_validation(pool: sqlx::SqlitePool) {
+        let service = PersonaService::new(Repositories::new(pool));
+
+        let mut request = create_test_request();
+        request.name = "  ".to_string();
+        assert!(matches!(
+            service.create(request).await,
+            Err(PersonaServiceError::Validation { .. })
+        ));
+
+        let mut request = create_test_request();
+        request.pain_points = vec!["".to_string()];
+        assert!(matches!(
+            service.create(request).await,
+            Err(PersonaServiceError::Validation { .. })
+        ));
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_rename_applies_to_stories(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let service = PersonaService::new(repositories.clone());
+
+        let request = UpdatePersonaRequest {
+            name: Some("Account Holder".to_string()),
+            ..Default::default()
+        };
+        service.update(1, request).await.unwrap();
+
+        let story = UserStoryService::new(repositories)
+            .get_by_id("US-001")
+            .await
+            .unwrap();
+        assert_eq!(story.persona, "Account Holder");
+
+        let taken = UpdatePersonaRequest {
+            name: Some("new user".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            service.update(1, taken).await,
+            Err(PersonaServiceError::AlreadyExists { .. })
+        ));
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_delete_persona_in_use(pool: sqlx::SqlitePool) {
+        let service = PersonaService::new(Repositories::new(pool));
+
+        assert!(matches!(
+            service.delete(1).await,
+            Err(PersonaServiceError::BusinessRule { .. })
+        ));
+
+        let unused = service.create(create_test_request()).await.unwrap();
+        service.delete(unused.id).await.unwrap();
+        assert!(matches!(
+            service.get_by_id(unused.id).await,
+            Err(PersonaServiceError::NotFound { .. })
+        ));
+    }
+}
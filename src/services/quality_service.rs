@@ -0,0 +1,176 @@
+use crate::models::{
+    BacklogLintReport, CriterionTestabilityReport, StoryLintReport, StoryTestabilityReport,
+    UserStoryWithCriteria,
+};
+use crate::quality;
+use crate::repositories::Repositories;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum QualityServiceError {
+    #[error("User story not found: {id}")]
+    NotFound { id: String },
+    #[error("Acceptance criteria not found: {id}")]
+    CriteriaNotFound { id: String },
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+pub type Result<T> = std::result::Result<T, QualityServiceError>;
+
+/// Opt-in quality analysis of stored user stories
+#[derive(Clone)]
+pub struct QualityService {
+    repositories: Repositories,
+}
+
+impl QualityService {
+    pub fn new(repositories: Repositories) -> Self {
+        Self { repositories }
+    }
+
+    /// Lint a user story and its acceptance criteria against INVEST and the narrative template
+    pub async fn lint_story(&self, id: &str) -> Result<StoryLintReport> {
+        let user_story = self
+            .repositories
+            .user_stories
+            .get_by_id(id)
+            .await?
+            .ok_or_else(|| QualityServiceError::NotFound { id: id.to_string() })?;
+        let acceptance_criteria = self
+            .repositories
+            .acceptance_criteria
+            .get_by_user_story_id(id)
+            .await?;
+
+        Ok(quality::lint_story(&UserStoryWithCriteria {
+            user_story,
+            acceptance_criteria,
+        }))
+    }
+
+    /// Lint every user story, lowest score first
+    pub async fn lint_backlog(&self) -> Result<BacklogLintReport> {
+        let stories = self
+            .repositories
+            .user_stories
+            .get_all_with_criteria()
+            .await?;
+        Ok(quality::lint_backlog(&stories))
+    }
+
+    /// Check every acceptance criterion of a user story for testability
+    pub async fn analyze_criteria(&self, user_story_id: &str) -> Result<StoryTestabilityReport> {
+        if self
+            .repositories
+            .user_stories
+            .get_by_id(user_story_id)
+            .await?
+            .is_none()
+        {
+            return Err(QualityServiceError::NotFound {
+                id: user_story_id.to_string(),
+            });
+        }
+        let criteria = self
+            .repositories
+            .acceptance_criteria
+            .get_by_user_story_id(user_story_id)
+            .await?;
+
+        Ok(quality::analyze_criteria(user_story_id, &criteria))
+    }
+
+    /// Check one acceptance criterion for testability
+    pub async fn analyze_criterion(&self, id: &str) -> Result<CriterionTestabilityReport> {
+        let criteria = self
+            .repositories
+            .acceptance_criteria
+            .get_by_id(id)
+            .await?
+            .ok_or_else(|| QualityServiceError::CriteriaNotFound { id: id.to_string() })?;
+
+        Ok(quality::analyze_criterion(&criteria))
+    }
+
+    /// Check a draft criterion description before it is stored
+    pub fn analyze_draft_criterion(&self, description: &str) -> CriterionTestabilityReport {
+        let findings = quality::analyze_criterion_text(description);
+
+        CriterionTestabilityReport {
+            acceptance_criteria_id: None,
+            user_story_id: None,
+            description: description.to_string(),
+            testable: quality::testability::is_testable(&findings),
+            findings,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::LintRule;
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_lint_story(pool: sqlx::SqlitePool) {
+        let service = QualityService::new(Repositories::new(pool));
+
+        let report = service.lint_story("US-001").await.unwrap();
+        assert_eq!(report.user_story_id, "US-001");
+        assert!(report
+            .findings
+            .iter()
+            .all(|finding| finding.rule != LintRule::NoAcceptanceCriteria));
+
+        assert!(matches!(
+            service.lint_story("US-999").await,
+            Err(QualityServiceError::NotFound { .. })
+        ));
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_lint_backlog(pool: sqlx::SqlitePool) {
+        let service = QualityService::new(Repositories::new(pool));
+
+        let report = service.lint_backlog().await.unwrap();
+        assert_eq!(report.story_count, 5);
+        let scores: Vec<u8> = report.stories.iter().map(|story| story.score).collect();
+        assert!(scores.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_analyze_criteria(pool: sqlx::SqlitePool) {
+        let service = QualityService::new(Repositories::new(pool));
+
+        let report = service.analyze_criteria("US-001").await.unwrap();
+        assert_eq!(report.criteria_count, 3);
+        assert_eq!(report.testable_count, 3);
+
+        let report = service.analyze_criterion("AC-010").await.unwrap();
+        assert_eq!(report.user_story_id.as_deref(), Some("US-005"));
+        assert!(report.testable);
+
+        assert!(matches!(
+            service.analyze_criterion("AC-999").await,
+            Err(QualityServiceError::CriteriaNotFound { .. })
+        ));
+        assert!(matches!(
+            service.analyze_criteria("US-999").await,
+            Err(QualityServiceError::NotFound { .. })
+        ));
+
+        let draft = service.analyze_draft_criterion("It should work fast");
+        assert!(!draft.testable);
+        assert!(draft.acceptance_criteria_id.is_none());
+    }
+}
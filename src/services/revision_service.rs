@@ -0,0 +1,386 @@
+use crate::models::{
+    RevisionDiff, StoryDiff, StoryRevision, StoryRevisionSummary, UpdateAcceptanceCriteriaRequest,
+    UpdateUserStoryRequest, UserStoryWithCriteria, ValidationPolicy,
+};
+use crate::repositories::revision_repository::RevertWrite;
+use crate::repositories::{Repositories, VersionedWrite};
+use crate::services::acceptance_criteria_service::AcceptanceCriteriaServiceError;
+use crate::services::user_story_service::UserStoryServiceError;
+use crate::services::{AcceptanceCriteriaService, UserStoryService};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum RevisionServiceError {
+    #[error("Revision {revision} of user story {user_story_id} not found")]
+    RevisionNotFound {
+        user_story_id: String,
+        revision: i64,
+    },
+    #[error("Acceptance criterion {id} now belongs to user story {owner}")]
+    CriteriaIdTaken { id: String, owner: String },
+    #[error(transparent)]
+    UserStory(#[from] UserStoryServiceError),
+    #[error(transparent)]
+    AcceptanceCriteria(#[from] AcceptanceCriteriaServiceError),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+pub type Result<T> = std::result::Result<T, RevisionServiceError>;
+
+/// Named snapshots of user stories with their acceptance criteria
+#[derive(Clone)]
+pub struct RevisionService {
+    repositories: Repositories,
+    user_stories: UserStoryService,
+    acceptance_criteria: AcceptanceCriteriaService,
+}
+
+impl RevisionService {
+    pub fn new(repositories: Repositories) -> Self {
+        Self {
+            user_stories: UserStoryService::new(repositories.clone()),
+            acceptance_criteria: AcceptanceCriteriaService::new(repositories.clone()),
+            repositories,
+        }
+    }
+
+    /// Validate reverted stories against the given policy
+    pub fn with_validation_policy(mut self, policy: ValidationPolicy) -> Self {
+        self.user_stories = self.user_stories.with_validation_policy(policy.clone());
+        self.acceptance_criteria = self.acceptance_criteria.with_validation_policy(policy);
+        self
+    }
+
+    /// Revert as the given agent, which may revert stories it holds the claim on
+    pub fn with_agent(mut self, agent_id: impl Into<String>) -> Self {
+        let agent_id = agent_id.into();
+        self.user_stories = self.user_stories.with_agent(agent_id.clone());
+        self.acceptance_criteria = self.acceptance_criteria.with_agent(agent_id);
+        self
+    }
+
+    /// Save the story with its current criteria as its next revision
+    pub async fn snapshot_story(&self, id: &str, note: &str) -> Result<StoryRevision> {
+        let current = self.user_stories.get_with_criteria(id).await?;
+        Ok(self
+            .repositories
+            .revisions
+            .create(&current, note.trim())
+            .await?)
+    }
+
+    /// Revisions of a story, newest first
+    pub async fn list_revisions(&self, id: &str) -> Result<Vec<StoryRevisionSummary>> {
+        self.user_stories.get_by_id(id).await?;
+        let revisions = self.repositories.revisions.list(id).await?;
+        Ok(revisions.iter().map(StoryRevisionSummary::from).collect())
+    }
+
+    /// Get a revision with its snapshot
+    pub async fn get_revision(&self, id: &str, revision: i64) -> Result<StoryRevision> {
+        self.repositories
+            .revisions
+            .get(id, revision)
+            .await?
+            .ok_or_else(|| RevisionServiceError::RevisionNotFound {
+                user_story_id: id.to_string(),
+                revision,
+            })
+    }
+
+    /// Compare revision `from` with revision `to`, or with the story as it is now when `to`
+    /// is `None`
+    pub async fn diff_revisions(
+        &self,
+        id: &str,
+        from: i64,
+        to: Option<i64>,
+    ) -> Result<RevisionDiff> {
+        let before = self.get_revision(id, from).await?.snapshot;
+        let after = match to {
+            Some(to) => self.get_revision(id, to).await?.snapshot,
+            None => self.user_stories.get_with_criteria(id).await?,
+        };
+
+        Ok(RevisionDiff {
+            user_story_id: id.to_string(),
+            from_revision: from,
+            to_revision: to,
+            diff: StoryDiff::between(&before, &after),
+        })
+    }
+
+    /// Put the story and its criteria back the way they were at `revision`.
+    ///
+    /// The current state is saved as a new revision first, in the same transaction, so a
+    /// revert can be undone. Fails with a conflict when the story changes in the meantime.
+    pub async fn revert_story(&self, id: &str, revision: i64) -> Result<UserStoryWithCriteria> {
+        let target = self.get_revision(id, revision).await?.snapshot;
+        let current = self.user_stories.get_with_criteria(id).await?;
+
+        self.validate_snapshot(&target).await?;
+
+        let note = format!("Before revert to revision {}", revision);
+        let write = self
+            .repositories
+            .revisions
+            .revert(&current, &target, &note, self.user_stories.agent_id())
+            .await?;
+        let write = match write {
+            RevertWrite::Story(write) => write,
+            // Another story took the id after validate_snapshot looked
+            RevertWrite::CriteriaIdTaken { id, owner } => {
+                return Err(RevisionServiceError::CriteriaIdTaken { id, owner })
+            }
+        };
+        match write {
+            VersionedWrite::Applied(_) => Ok(self.user_stories.get_with_criteria(id).await?),
+            VersionedWrite::NotFound => {
+                Err(UserStoryServiceError::NotFound { id: id.to_string() }.into())
+            }
+            VersionedWrite::Conflict(now) => Err(UserStoryServiceError::Conflict {
+                expected_version: current.user_story.version,
+                current: Box::new(now),
+            }
+            .into()),
+            VersionedWrite::Claimed(claim) => Err(UserStoryServiceError::Claimed { claim }.into()),
+        }
+    }
+
+    /// Check a snapshot against today's validation policy and that its criteria ids are
+    /// still the story's to take back
+    async fn validate_snapshot(&self, snapshot: &UserStoryWithCriteria) -> Result<()> {
+        let story = &snapshot.user_story;
+        self.user_stories
+            .validate_update_request(&UpdateUserStoryRequest {
+                title: Some(story.title.clone()),
+                description: Some(story.description.clone()),
+                persona: Some(story.persona.clone()),
+                expected_version: None,
+            })?;
+
+        let max_criteria = self.acceptance_criteria.policy().max_criteria_per_story;
+        if snapshot.acceptance_criteria.len() as i64 > max_criteria {
+            return Err(AcceptanceCriteriaServiceError::BusinessRule {
+                message: format!(
+                    "Revision has {} acceptance criteria. Maximum allowed is {}.",
+                    snapshot.acceptance_criteria.len(),
+                    max_criteria
+                ),
+            }
+            .into());
+        }
+
+        for criteria in &snapshot.acceptance_criteria {
+            self.acceptance_criteria
+                .validate_update_request(&UpdateAcceptanceCriteriaRequest {
+                    description: Some(criteria.description.clone()),
+                    expected_version: None,
+                })?;
+
+            let owner = self
+                .repositories
+                .revisions
+                .criteria_owner(&criteria.id)
+                .await?;
+            if let Some(owner) = owner.filter(|owner| owner != &story.id) {
+                return Err(RevisionServiceError::CriteriaIdTaken {
+                    id: criteria.id.clone(),
+                    owner,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CreateAcceptanceCriteriaRequest;
+
+    fn service(pool: sqlx::SqlitePool) -> (RevisionService, Repositories) {
+        let repositories = Repositories::new(pool);
+        (RevisionService::new(repositories.clone()), repositories)
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_snapshot_and_list(pool: sqlx::SqlitePool) {
+        let (service, _) = service(pool);
+
+        let first = service
+            .snapshot_story("US-001", " Baseline ")
+            .await
+            .unwrap();
+        let second = service.snapshot_story("US-001", "").await.unwrap();
+        assert_eq!((first.revision, second.revision), (1, 2));
+        assert_eq!(first.note, "Baseline");
+        assert_eq!(first.snapshot.acceptance_criteria.len(), 3);
+
+        let revisions = service.list_revisions("US-001").await.unwrap();
+        let numbers: Vec<i64> = revisions.iter().map(|r| r.revision).collect();
+        assert_eq!(numbers, vec![2, 1]);
+        assert_eq!(revisions[1].criteria_count, 3);
+
+        assert_eq!(
+            service.snapshot_story("US-002", "").await.unwrap().revision,
+            1
+        );
+        assert!(matches!(
+            service.snapshot_story("US-999", "").await,
+            Err(RevisionServiceError::UserStory(
+                UserStoryServiceError::NotFound { .. }
+            ))
+        ));
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_diff_and_revert(pool: sqlx::SqlitePool) {
+        let (service, repositories) = service(pool);
+        let stories = UserStoryService::new(repositories.clone());
+        let criteria = AcceptanceCriteriaService::new(repositories);
+
+        let original = service
+            .snapshot_story("US-001", "Before edits")
+            .await
+            .unwrap();
+
+        stories
+            .update(
+                "US-001",
+                UpdateUserStoryRequest {
+                    title: Some("Sign in".to_string()),
+                    description: None,
+                    persona: None,
+                    expected_version: None,
+                },
+            )
+            .await
+            .unwrap();
+        criteria.delete("AC-001").await.unwrap();
+        criteria
+            .update(
+                "AC-002",
+                UpdateAcceptanceCriteriaRequest {
+                    description: Some("Given a wrong password, Then I see an error".to_string()),
+                    expected_version: None,
+                },
+            )
+            .await
+            .unwrap();
+        criteria
+            .create(CreateAcceptanceCriteriaRequest {
+                id: Some("AC-011".to_string()),
+                user_story_id: "US-001".to_string(),
+                description: "Given a locked account, Then I cannot sign in".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let diff = service.diff_revisions("US-001", 1, None).await.unwrap();
+        assert_eq!(diff.diff.fields.len(), 1);
+        assert_eq!(diff.diff.fields[0].field, "title");
+        assert_eq!(diff.diff.criteria_added[0].id, "AC-011");
+        assert_eq!(diff.diff.criteria_removed[0].id, "AC-001");
+        assert_eq!(diff.diff.criteria_changed[0].id, "AC-002");
+
+        let reverted = service.revert_story("US-001", 1).await.unwrap();
+        assert_eq!(
+            reverted.user_story.title,
+            original.snapshot.user_story.title
+        );
+        let ids: Vec<&str> = reverted
+            .acceptance_criteria
+            .iter()
+            .map(|c| c.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["AC-001", "AC-002", "AC-003"]);
+        assert!(StoryDiff::between(&original.snapshot, &reverted).is_empty());
+
+        // The edited state was saved before the revert and can be compared against
+        let backup = service.get_revision("US-001", 2).await.unwrap();
+        assert_eq!(backup.note, "Before revert to revision 1");
+        assert_eq!(backup.snapshot.user_story.title, "Sign in");
+        let diff = service.diff_revisions("US-001", 1, Some(2)).await.unwrap();
+        assert_eq!(diff.diff.criteria_added.len(), 1);
+
+        assert!(matches!(
+            service.revert_story("US-001", 7).await,
+            Err(RevisionServiceError::RevisionNotFound { revision: 7, .. })
+        ));
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_revert_validates_snapshot(pool: sqlx::SqlitePool) {
+        let (service, repositories) = service(pool);
+        service.snapshot_story("US-001", "").await.unwrap();
+
+        let strict = RevisionService::new(repositories).with_validation_policy(ValidationPolicy {
+            max_criteria_per_story: 2,
+            ..Default::default()
+        });
+        assert!(matches!(
+            strict.revert_story("US-001", 1).await,
+            Err(RevisionServiceError::AcceptanceCriteria(
+                AcceptanceCriteriaServiceError::BusinessRule { .. }
+            ))
+        ));
+        // Nothing was written, not even the backup revision
+        assert_eq!(service.list_revisions("US-001").await.unwrap().len(), 1);
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_revert_criteria_id_taken_meanwhile(pool: sqlx::SqlitePool) {
+        let (service, repositories) = service(pool);
+        let current = UserStoryService::new(repositories.clone())
+            .get_with_criteria("US-001")
+            .await
+            .unwrap();
+
+        // A snapshot that validated while AC-004 was free, and another story has it now
+        let mut snapshot = current.clone();
+        snapshot.user_story.title = "Sign in".to_string();
+        let mut taken = snapshot.acceptance_criteria[0].clone();
+        taken.id = "AC-004".to_string();
+        snapshot.acceptance_criteria.push(taken);
+
+        let owner = repositories
+            .revisions
+            .criteria_owner("AC-004")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_ne!(owner, "US-001");
+        assert!(matches!(
+            repositories
+                .revisions
+                .revert(&current, &snapshot, "", None)
+                .await,
+            Ok(RevertWrite::CriteriaIdTaken { id, owner: taken_by })
+                if id == "AC-004" && taken_by == owner
+        ));
+
+        // The whole revert was rolled back
+        let after = UserStoryService::new(repositories)
+            .get_with_criteria("US-001")
+            .await
+            .unwrap();
+        assert_eq!(after.user_story.title, current.user_story.title);
+        assert_eq!(after.user_story.version, current.user_story.version);
+        assert!(service.list_revisions("US-001").await.unwrap().is_empty());
+    }
+}
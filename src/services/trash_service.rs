@@ -0,0 +1,232 @@
+use crate::models::{PurgeReport, Restored, StoryClaim, Trash, UserStoryWithCriteria};
+use crate::repositories::Repositories;
+use chrono::{Duration, Utc};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum TrashServiceError {
+    #[error("Not in the trash: {id}")]
+    NotFound { id: String },
+    #[error("User story {user_story_id} is in the trash; restore it first")]
+    UserStoryInTrash { user_story_id: String },
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Validation error: {message}")]
+    Validation { message: String },
+    #[error(
+        "User story {} is claimed by {} until {}",
+        .claim.user_story_id,
+        .claim.agent_id,
+        .claim.expires_at
+    )]
+    Claimed { claim: StoryClaim },
+}
+
+pub type Result<T> = std::result::Result<T, TrashServiceError>;
+
+/// Deleted user stories and acceptance criteria, which stay restorable until purged
+#[derive(Clone)]
+pub struct TrashService {
+    repositories: Repositories,
+    agent_id: Option<String>,
+}
+
+impl TrashService {
+    pub fn new(repositories: Repositories) -> Self {
+        Self {
+            repositories,
+            agent_id: None,
+        }
+    }
+
+    /// Restore as the given agent, which may restore stories it holds the claim on
+    pub fn with_agent(mut self, agent_id: impl Into<String>) -> Self {
+        self.agent_id = Some(agent_id.into().trim().to_string());
+        self
+    }
+
+    /// Refuse the write if another agent holds a live claim on the story
+    async fn check_claim(&self, user_story_id: &str) -> Result<()> {
+        match self.repositories.claims.get_active(user_story_id).await? {
+            Some(claim) if !claim.is_held_by(self.agent_id.as_deref()) => {
+                Err(TrashServiceError::Claimed { claim })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Everything that can be restored
+    pub async fn list_trash(&self) -> Result<Trash> {
+        Ok(self.repositories.trash.list().await?)
+    }
+
+    /// Restore a deleted user story with the criteria deleted along with it, or a single
+    /// deleted acceptance criterion
+    pub async fn restore(&self, id: &str) -> Result<Restored> {
+        self.check_claim(id).await?;
+        if self.repositories.trash.restore_user_story(id).await? {
+            let user_story = self
+                .repositories
+                .user_stories
+                .get_by_id(id)
+                .await?
+                .ok_or(sqlx::Error::RowNotFound)?;
+            let acceptance_criteria = self
+                .repositories
+                .acceptance_criteria
+                .get_by_user_story_id(id)
+                .await?;
+            return Ok(Restored::UserStory(UserStoryWithCriteria {
+                user_story,
+                acceptance_criteria,
+            }));
+        }
+
+        let Some(deleted) = self
+            .repositories
+            .trash
+            .get_deleted_acceptance_criteria(id)
+            .await?
+        else {
+            return Err(TrashServiceError::NotFound { id: id.to_string() });
+        };
+        self.check_claim(&deleted.user_story_id).await?;
+
+        self.repositories
+            .trash
+            .restore_acceptance_criteria(id)
+            .await?
+            .map(Restored::AcceptanceCriteria)
+            .ok_or(TrashServiceError::UserStoryInTrash {
+                user_story_id: deleted.user_story_id,
+            })
+    }
+
+    /// Permanently delete whatever has been in the trash for more than `retention_days`
+    pub async fn purge(&self, retention_days: i64) -> Result<PurgeReport> {
+        if retention_days < 0 {
+            return Err(TrashServiceError::Validation {
+                message: "Retention period cannot be negative".to_string(),
+            });
+        }
+
+        let cutoff = Utc::now().naive_utc() - Duration::days(retention_days);
+        Ok(self.repositories.trash.purge(cutoff).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CreateUserStoryRequest, DEFAULT_TRASH_RETENTION_DAYS};
+    use crate::services::{AcceptanceCriteriaService, UserStoryService};
+
+    struct Fixture {
+        stories: UserStoryService,
+        criteria: AcceptanceCriteriaService,
+        trash: TrashService,
+    }
+
+    fn fixture(pool: sqlx::SqlitePool) -> Fixture {
+        let repositories = Repositories::new(pool);
+        Fixture {
+            stories: UserStoryService::new(repositories.clone()),
+            criteria: AcceptanceCriteriaService::new(repositories.clone()),
+            trash: TrashService::new(repositories),
+        }
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_delete_and_restore_story(pool: sqlx::SqlitePool) {
+        let f = fixture(pool);
+
+        // AC-001 is deleted on its own first and stays in the trash when its story returns
+        f.criteria.delete("AC-001").await.unwrap();
+        f.stories.delete("US-001").await.unwrap();
+
+        assert!(f.stories.get_by_id("US-001").await.is_err());
+        assert!(f.criteria.get_by_id("AC-002").await.is_err());
+        assert!(f.stories.search("login").await.unwrap().is_empty());
+        assert_eq!(f.stories.get_statistics().await.unwrap().total_stories, 4);
+
+        let trash = f.trash.list_trash().await.unwrap();
+        assert_eq!(trash.user_stories.len(), 1);
+        let criteria_ids: Vec<&str> = trash.user_stories[0]
+            .acceptance_criteria
+            .iter()
+            .map(|c| c.id.as_str())
+            .collect();
+        assert_eq!(criteria_ids, vec!["AC-002", "AC-003"]);
+        assert!(trash.acceptance_criteria.is_empty());
+
+        // The id stays taken while the story is in the trash
+        let request = CreateUserStoryRequest {
+            id: Some("US-001".to_string()),
+            title: "Another login".to_string(),
+            description: "As a user, I want to log in again".to_string(),
+            persona: "End User".to_string(),
+        };
+        assert!(f.stories.create(request).await.is_err());
+
+        let Restored::UserStory(restored) = f.trash.restore("US-001").await.unwrap() else {
+            panic!("expected the story back");
+        };
+        assert_eq!(restored.acceptance_criteria.len(), 2);
+
+        let trash = f.trash.list_trash().await.unwrap();
+        assert!(trash.user_stories.is_empty());
+        assert_eq!(trash.acceptance_criteria.len(), 1);
+        assert!(matches!(
+            f.trash.restore("AC-001").await.unwrap(),
+            Restored::AcceptanceCriteria(ref c) if c.id == "AC-001"
+        ));
+        assert!(matches!(
+            f.trash.restore("AC-001").await,
+            Err(TrashServiceError::NotFound { .. })
+        ));
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_restore_criteria_of_deleted_story(pool: sqlx::SqlitePool) {
+        let f = fixture(pool);
+        f.criteria.delete("AC-004").await.unwrap();
+        f.stories.delete("US-002").await.unwrap();
+
+        assert!(matches!(
+            f.trash.restore("AC-004").await,
+            Err(TrashServiceError::UserStoryInTrash { user_story_id }) if user_story_id == "US-002"
+        ));
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_purge(pool: sqlx::SqlitePool) {
+        let f = fixture(pool);
+        f.stories.delete("US-003").await.unwrap();
+        f.criteria.delete("AC-001").await.unwrap();
+
+        let kept = f.trash.purge(DEFAULT_TRASH_RETENTION_DAYS).await.unwrap();
+        assert_eq!(kept, PurgeReport::default());
+
+        let purged = f.trash.purge(0).await.unwrap();
+        assert_eq!(purged.user_stories, 1);
+        assert_eq!(purged.acceptance_criteria, 3);
+        assert!(matches!(
+            f.trash.restore("US-003").await,
+            Err(TrashServiceError::NotFound { .. })
+        ));
+
+        assert!(matches!(
+            f.trash.purge(-1).await,
+            Err(TrashServiceError::Validation { .. })
+        ));
+    }
+}
@@ -1,8 +1,16 @@
+use crate::events::EventBus;
 use crate::models::{
-    CreateAcceptanceCriteriaRequest, CreateUserStoryRequest, UpdateUserStoryRequest, UserStory,
-    UserStoryWithCriteria,
+    AssignmentRole, CreateAcceptanceCriteriaRequest, CreateUserStoryRequest, CreatedUserStory,
+    DuplicateCheck, DuplicateMode, IdScheme, Page, PageCursor, SimilarStory, StoryClaim,
+    StoryCursor, StoryField, StoryQuery, StorySortField, UpdateUserStoryRequest, UserStory,
+    UserStoryWithCriteria, UserStoryWithPersona, ValidationPolicy, MAX_PAGE_SIZE,
+    MAX_PERSONA_NAME_LENGTH,
+};
+use crate::quality::similarity::{self, SimilarityQuery};
+use crate::repositories::{ClaimGuardedWrite, Repositories, VersionedWrite};
+use crate::services::acceptance_criteria_service::{
+    AcceptanceCriteriaService, AcceptanceCriteriaServiceError,
 };
-use crate::repositories::Repositories;
 use std::collections::HashMap;
 use thiserror::Error;
 
@@ -12,12 +20,43 @@ pub enum UserStoryServiceError {
     NotFound { id: String },
     #[error("User story already exists: {id}")]
     AlreadyExists { id: String },
+    #[error("Member not found: {name}")]
+    MemberNotFound { name: String },
     #[error("Database error: {0}")]
     Database(#[from] sqlx::Error),
     #[error("Validation error: {message}")]
     Validation { message: String },
     #[error("Business rule violation: {message}")]
     BusinessRule { message: String },
+    #[error("User story '{title}' looks like a duplicate of {}", similar_ids(.similar))]
+    PossibleDuplicate {
+        title: String,
+        similar: Vec<SimilarStory>,
+    },
+    #[error(
+        "User story {} was modified concurrently: expected version {expected_version}, current version {}",
+        .current.id,
+        .current.version
+    )]
+    Conflict {
+        expected_version: i64,
+        current: Box<UserStory>,
+    },
+    #[error(
+        "User story {} is claimed by {} until {}",
+        .claim.user_story_id,
+        .claim.agent_id,
+        .claim.expires_at
+    )]
+    Claimed { claim: StoryClaim },
+}
+
+fn similar_ids(similar: &[SimilarStory]) -> String {
+    similar
+        .iter()
+        .map(|story| format!("{} ({:.2})", story.user_story_id, story.score))
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 pub type Result<T> = std::result::Result<T, UserStoryServiceError>;
@@ -25,71 +64,185 @@ pub type Result<T> = std::result::Result<T, UserStoryServiceError>;
 #[derive(Clone)]
 pub struct UserStoryService {
     repositories: Repositories,
+    duplicate_check: DuplicateCheck,
+    id_scheme: IdScheme,
+    policy: ValidationPolicy,
+    agent_id: Option<String>,
+    events: EventBus,
 }
 
 impl UserStoryService {
     pub fn new(repositories: Repositories) -> Self {
-        Self { repositories }
+        Self {
+            repositories,
+            duplicate_check: DuplicateCheck::default(),
+            id_scheme: IdScheme::default(),
+            policy: ValidationPolicy::default(),
+            agent_id: None,
+            events: EventBus::new(),
+        }
+    }
+
+    /// Write as the given agent, which may change stories it holds the claim on
+    pub fn with_agent(mut self, agent_id: impl Into<String>) -> Self {
+        self.agent_id = Some(agent_id.into().trim().to_string());
+        self
+    }
+
+    /// Ring the given bus after each write so the webhook dispatcher picks up its events
+    pub fn with_event_bus(mut self, events: EventBus) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// The agent this service writes as
+    pub(crate) fn agent_id(&self) -> Option<&str> {
+        self.agent_id.as_deref()
+    }
+
+    /// Validate stories against the given policy instead of the default limits
+    pub fn with_validation_policy(mut self, policy: ValidationPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Generate and validate ids with the given scheme instead of `US-`/`AC-`
+    pub fn with_id_scheme(mut self, id_scheme: IdScheme) -> Self {
+        self.id_scheme = id_scheme;
+        self
+    }
+
+    /// The scheme ids are generated and validated with
+    pub fn id_scheme(&self) -> &IdScheme {
+        &self.id_scheme
+    }
+
+    /// Check new user stories against existing ones when they are created
+    pub fn with_duplicate_check(mut self, duplicate_check: DuplicateCheck) -> Self {
+        self.duplicate_check = duplicate_check;
+        self
     }
 
     /// Create a new user story with validation
     pub async fn create(&self, request: CreateUserStoryRequest) -> Result<UserStory> {
+        let created = self.create_checked(request, self.duplicate_check).await?;
+        Ok(created.user_story)
+    }
+
+    /// Create a new user story, reporting or rejecting likely duplicates as `check` says
+    pub async fn create_checked(
+        &self,
+        mut request: CreateUserStoryRequest,
+        check: DuplicateCheck,
+    ) -> Result<CreatedUserStory> {
         // Validate the request
         self.validate_create_request(&request)?;
 
         // Check if user story already exists
-        if self
-            .repositories
-            .user_stories
-            .get_by_id(&request.id)
-            .await?
-            .is_some()
-        {
-            return Err(UserStoryServiceError::AlreadyExists {
-                id: request.id.clone(),
-            });
+        if let Some(ref id) = request.id {
+            self.ensure_available(id).await?;
+        }
+
+        let text = similarity::story_text(&request.title, &request.description, std::iter::empty());
+        let possible_duplicates = self.check_duplicates(&request.title, &text, check).await?;
+
+        if request.id.is_none() {
+            request.id = Some(self.next_id().await?);
         }
 
         // Create the user story
         let user_story = self.repositories.user_stories.create(request).await?;
+        self.events.notify();
 
-        Ok(user_story)
+        Ok(CreatedUserStory {
+            user_story,
+            possible_duplicates,
+        })
     }
 
-    /// Create a user story with its acceptance criteria in a transaction-like manner
+    /// Create a user story with its acceptance criteria in a transaction-like manner.
+    /// Criteria with an empty `user_story_id` belong to the new story, which is how
+    /// criteria are attached to a story whose id is generated.
     pub async fn create_with_criteria(
         &self,
-        user_story_request: CreateUserStoryRequest,
-        criteria_requests: Vec<CreateAcceptanceCriteriaRequest>,
+        mut user_story_request: CreateUserStoryRequest,
+        mut criteria_requests: Vec<CreateAcceptanceCriteriaRequest>,
     ) -> Result<UserStoryWithCriteria> {
         // Validate user story request
         self.validate_create_request(&user_story_request)?;
 
         // Validate that all criteria belong to this user story
         for criteria in &criteria_requests {
-            if criteria.user_story_id != user_story_request.id {
+            if !criteria.user_story_id.is_empty()
+                && Some(&criteria.user_story_id) != user_story_request.id.as_ref()
+            {
                 return Err(UserStoryServiceError::Validation {
                     message: format!(
                         "Acceptance criteria {} does not belong to user story {}",
-                        criteria.id, user_story_request.id
+                        criteria.id.as_deref().unwrap_or(&criteria.description),
+                        user_story_request.id.as_deref().unwrap_or("being created")
                     ),
                 });
             }
         }
 
-        // Check if user story already exists
-        if self
-            .repositories
-            .user_stories
-            .get_by_id(&user_story_request.id)
-            .await?
-            .is_some()
-        {
-            return Err(UserStoryServiceError::AlreadyExists {
-                id: user_story_request.id.clone(),
+        let max_criteria = self.policy.max_criteria_per_story;
+        if criteria_requests.len() as i64 > max_criteria {
+            return Err(UserStoryServiceError::BusinessRule {
+                message: format!(
+                    "User story cannot have {} acceptance criteria. Maximum allowed is {}.",
+                    criteria_requests.len(),
+                    max_criteria
+                ),
             });
         }
 
+        // Check if user story already exists
+        if let Some(ref id) = user_story_request.id {
+            self.ensure_available(id).await?;
+        }
+
+        let text = similarity::story_text(
+            &user_story_request.title,
+            &user_story_request.description,
+            criteria_requests.iter().map(|c| c.description.as_str()),
+        );
+        self.check_duplicates(&user_story_request.title, &text, self.duplicate_check)
+            .await?;
+
+        let id = match user_story_request.id {
+            Some(ref id) => id.clone(),
+            None => self.next_id().await?,
+        };
+        user_story_request.id = Some(id.clone());
+        // The criteria face the same checks as those added through the criteria service
+        let criteria_service = AcceptanceCriteriaService::new(self.repositories.clone())
+            .with_validation_policy(self.policy.clone())
+            .with_id_scheme(self.id_scheme.clone());
+        for criteria in &mut criteria_requests {
+            criteria.user_story_id = id.clone();
+            criteria_service
+                .validate_fields(criteria)
+                .map_err(|e| match e {
+                    AcceptanceCriteriaServiceError::Validation { message } => {
+                        UserStoryServiceError::Validation { message }
+                    }
+                    e => UserStoryServiceError::Validation {
+                        message: e.to_string(),
+                    },
+                })?;
+        }
+        for criteria in &mut criteria_requests {
+            if criteria.id.is_none() {
+                criteria.id = Some(
+                    self.repositories
+                        .id_sequences
+                        .next_criteria_id(&self.id_scheme, &id)
+                        .await?,
+                );
+            }
+        }
+
         // Create user story first
         let user_story = self
             .repositories
@@ -99,13 +252,21 @@ impl UserStoryService {
 
         // Create acceptance criteria
         let acceptance_criteria = if !criteria_requests.is_empty() {
-            self.repositories
+            match self
+                .repositories
                 .acceptance_criteria
-                .create_batch(criteria_requests)
+                .create_batch(criteria_requests, self.agent_id.as_deref())
                 .await?
+            {
+                ClaimGuardedWrite::Applied(criteria) => criteria,
+                ClaimGuardedWrite::Claimed(claim) => {
+                    return Err(UserStoryServiceError::Claimed { claim })
+                }
+            }
         } else {
             Vec::new()
         };
+        self.events.notify();
 
         Ok(UserStoryWithCriteria {
             user_story,
@@ -137,6 +298,23 @@ impl UserStoryService {
         })
     }
 
+    /// Get user story with the full persona it is written for
+    pub async fn get_with_persona(&self, id: &str) -> Result<UserStoryWithPersona> {
+        let user_story = self.get_by_id(id).await?;
+        let persona = match self.repositories.user_stories.get_persona_id(id).await? {
+            Some(persona_id) => self.repositories.personas.get_by_id(persona_id).await?,
+            None => None,
+        };
+        // The story can be deleted between the two reads
+        let persona_context =
+            persona.ok_or_else(|| UserStoryServiceError::NotFound { id: id.to_string() })?;
+
+        Ok(UserStoryWithPersona {
+            user_story,
+            persona_context,
+        })
+    }
+
     /// Get all user stories
     pub async fn get_all(&self) -> Result<Vec<UserStory>> {
         Ok(self.repositories.user_stories.get_all().await?)
@@ -144,44 +322,43 @@ impl UserStoryService {
 
     /// Get all user stories with their acceptance criteria
     pub async fn get_all_with_criteria(&self) -> Result<Vec<UserStoryWithCriteria>> {
-        let user_stories = self.repositories.user_stories.get_all().await?;
-        let mut result = Vec::new();
-
-        for user_story in user_stories {
-            let acceptance_criteria = self
-                .repositories
-                .acceptance_criteria
-                .get_by_user_story_id(&user_story.id)
-                .await?;
-
-            result.push(UserStoryWithCriteria {
-                user_story,
-                acceptance_criteria,
-            });
-        }
-
-        Ok(result)
+        Ok(self
+            .repositories
+            .user_stories
+            .get_all_with_criteria()
+            .await?)
     }
 
-    /// Get user stories with pagination
-    pub async fn get_paginated(&self, limit: i64, offset: i64) -> Result<Vec<UserStory>> {
-        if limit <= 0 || limit > 100 {
+    /// Get a page of user stories, newest first. Pass the `next_cursor` of a page back as
+    /// `cursor` for the next one; limits above `MAX_PAGE_SIZE` are cut down to it.
+    pub async fn get_page(&self, cursor: Option<&str>, limit: i64) -> Result<Page<UserStory>> {
+        if limit <= 0 {
             return Err(UserStoryServiceError::Validation {
-                message: "Limit must be between 1 and 100".to_string(),
+                message: "Limit must be positive".to_string(),
             });
         }
-
-        if offset < 0 {
-            return Err(UserStoryServiceError::Validation {
-                message: "Offset must be non-negative".to_string(),
-            });
-        }
-
-        Ok(self
+        let after = cursor
+            .map(|cursor| {
+                PageCursor::decode(cursor).ok_or_else(|| UserStoryServiceError::Validation {
+                    message: format!("Invalid cursor: {}", cursor),
+                })
+            })
+            .transpose()?;
+
+        let limit = limit.min(MAX_PAGE_SIZE);
+        let rows = self
             .repositories
             .user_stories
-            .get_paginated(limit, offset)
-            .await?)
+            .get_page(after.as_ref(), limit + 1)
+            .await?;
+        Ok(Page::from_rows(rows, limit, |story| {
+            PageCursor::new(story.created_at, story.id.clone())
+        }))
+    }
+
+    /// Count all user stories
+    pub async fn count(&self) -> Result<i64> {
+        Ok(self.repositories.user_stories.count().await?)
     }
 
     /// Update user story
@@ -189,22 +366,113 @@ impl UserStoryService {
         // Validate the update request
         self.validate_update_request(&request)?;
 
-        self.repositories
+        let expected_version = request.expected_version;
+        let write = self
+            .repositories
             .user_stories
-            .update(id, request)
-            .await?
-            .ok_or_else(|| UserStoryServiceError::NotFound { id: id.to_string() })
+            .update(id, request, self.agent_id.as_deref())
+            .await?;
+        self.versioned(id, expected_version, write)
     }
 
     /// Delete user story (this will also delete associated acceptance criteria due to CASCADE)
     pub async fn delete(&self, id: &str) -> Result<()> {
-        let deleted = self.repositories.user_stories.delete(id).await?;
+        self.delete_at_version(id, None).await
+    }
+
+    /// Delete a user story, failing with a conflict if it is no longer at `expected_version`
+    pub async fn delete_at_version(&self, id: &str, expected_version: Option<i64>) -> Result<()> {
+        let write = self
+            .repositories
+            .user_stories
+            .delete(id, expected_version, self.agent_id.as_deref())
+            .await?;
+        self.versioned(id, expected_version, write).map(|_| ())
+    }
 
-        if !deleted {
-            return Err(UserStoryServiceError::NotFound { id: id.to_string() });
+    pub(crate) fn versioned(
+        &self,
+        id: &str,
+        expected_version: Option<i64>,
+        write: VersionedWrite<UserStory>,
+    ) -> Result<UserStory> {
+        match write {
+            VersionedWrite::Applied(story) => {
+                self.events.notify();
+                Ok(story)
+            }
+            VersionedWrite::NotFound => Err(UserStoryServiceError::NotFound { id: id.to_string() }),
+            VersionedWrite::Conflict(current) => Err(UserStoryServiceError::Conflict {
+                expected_version: expected_version.unwrap_or_default(),
+                current: Box::new(current),
+            }),
+            VersionedWrite::Claimed(claim) => Err(UserStoryServiceError::Claimed { claim }),
         }
+    }
 
-        Ok(())
+    /// Make a registered member the assignee or reviewer of a story, optionally only if the
+    /// story is still at `expected_version`
+    pub async fn assign(
+        &self,
+        id: &str,
+        role: AssignmentRole,
+        member: &str,
+        expected_version: Option<i64>,
+    ) -> Result<UserStory> {
+        let member = self.get_member_id(member).await?;
+        let write = self
+            .repositories
+            .user_stories
+            .set_member(
+                id,
+                role,
+                Some(member),
+                expected_version,
+                self.agent_id.as_deref(),
+            )
+            .await?;
+        self.versioned(id, expected_version, write)
+    }
+
+    /// Clear the assignee or reviewer of a story, optionally only if the story is still at
+    /// `expected_version`
+    pub async fn unassign(
+        &self,
+        id: &str,
+        role: AssignmentRole,
+        expected_version: Option<i64>,
+    ) -> Result<UserStory> {
+        let write = self
+            .repositories
+            .user_stories
+            .set_member(id, role, None, expected_version, self.agent_id.as_deref())
+            .await?;
+        self.versioned(id, expected_version, write)
+    }
+
+    /// Stories a member is assigned to, reviews, or either when `role` is `None`
+    pub async fn get_by_member(
+        &self,
+        member: &str,
+        role: Option<AssignmentRole>,
+    ) -> Result<Vec<UserStory>> {
+        let member = self.get_member_id(member).await?;
+        Ok(self
+            .repositories
+            .user_stories
+            .get_by_member(member, role)
+            .await?)
+    }
+
+    async fn get_member_id(&self, name: &str) -> Result<i64> {
+        self.repositories
+            .members
+            .get_by_name(name.trim())
+            .await?
+            .map(|member| member.id)
+            .ok_or_else(|| UserStoryServiceError::MemberNotFound {
+                name: name.to_string(),
+            })
     }
 
     /// Search user stories
@@ -218,7 +486,7 @@ impl UserStoryService {
         Ok(self.repositories.user_stories.search(query).await?)
     }
 
-    /// Get user stories by persona
+    /// Get user stories by persona name, ignoring case
     pub async fn get_by_persona(&self, persona: &str) -> Result<Vec<UserStory>> {
         if persona.trim().is_empty() {
             return Err(UserStoryServiceError::Validation {
@@ -233,6 +501,118 @@ impl UserStoryService {
             .await?)
     }
 
+    /// Get the user stories matching a query of composable filters, sorted on any column.
+    /// With a limit, pass the `next_cursor` of a page back as `cursor` for the next one.
+    pub async fn query(&self, mut query: StoryQuery) -> Result<Page<UserStory>> {
+        Self::validate_filters(&mut query)?;
+        if query.limit.is_some_and(|limit| limit <= 0) {
+            return Err(UserStoryServiceError::Validation {
+                message: "Limit must be positive".to_string(),
+            });
+        }
+
+        let after = query
+            .cursor
+            .as_deref()
+            .map(|cursor| {
+                StoryCursor::decode(cursor)
+                    .filter(|after| {
+                        after.sort_by == query.sort_by && after.direction == query.direction
+                    })
+                    .ok_or_else(|| UserStoryServiceError::Validation {
+                        message: format!("Invalid cursor for this sort order: {}", cursor),
+                    })
+            })
+            .transpose()?;
+
+        // One story past the limit only tells that another page follows
+        let limit = query.limit.map(|limit| limit.min(MAX_PAGE_SIZE));
+        let fetch = StoryQuery {
+            limit: limit.map(|limit| limit + 1),
+            ..query.clone()
+        };
+        let mut stories = self
+            .repositories
+            .user_stories
+            .query(&fetch, after.as_ref())
+            .await?;
+
+        let mut next_cursor = None;
+        if let Some(limit) = limit.filter(|&limit| stories.len() as i64 > limit) {
+            stories.truncate(limit as usize);
+            if let Some(last) = stories.last() {
+                let criteria_count = match query.sort_by {
+                    StorySortField::CriteriaCount => {
+                        self.repositories
+                            .acceptance_criteria
+                            .count_by_user_story_id(&last.id)
+                            .await?
+                    }
+                    _ => 0,
+                };
+                next_cursor = Some(StoryCursor::new(&query, last, criteria_count).encode());
+            }
+        }
+
+        Ok(Page {
+            items: stories,
+            next_cursor,
+        })
+    }
+
+    /// Count the user stories matching the filters of a query, ignoring its sort and page
+    pub async fn count_query(&self, mut query: StoryQuery) -> Result<i64> {
+        Self::validate_filters(&mut query)?;
+        Ok(self.repositories.user_stories.count_query(&query).await?)
+    }
+
+    /// Trim the persona and text filters of a query and reject contradicting ranges
+    fn validate_filters(query: &mut StoryQuery) -> Result<()> {
+        for persona in query.personas.iter_mut() {
+            *persona = persona.trim().to_string();
+            if persona.is_empty() {
+                return Err(UserStoryServiceError::Validation {
+                    message: "Persona cannot be empty".to_string(),
+                });
+            }
+        }
+        if let Some(text) = query.text.as_mut() {
+            *text = text.trim().to_string();
+            if text.is_empty() {
+                return Err(UserStoryServiceError::Validation {
+                    message: "Search text cannot be empty".to_string(),
+                });
+            }
+        }
+        if let (Some(after), Some(before)) = (query.created_after, query.created_before) {
+            if after >= before {
+                return Err(UserStoryServiceError::Validation {
+                    message: "created_after must be before created_before".to_string(),
+                });
+            }
+        }
+        if let (Some(after), Some(before)) = (query.updated_after, query.updated_before) {
+            if after >= before {
+                return Err(UserStoryServiceError::Validation {
+                    message: "updated_after must be before updated_before".to_string(),
+                });
+            }
+        }
+        if query.min_criteria.unwrap_or(0) < 0 || query.max_criteria.unwrap_or(0) < 0 {
+            return Err(UserStoryServiceError::Validation {
+                message: "Criteria counts must be non-negative".to_string(),
+            });
+        }
+        if let (Some(min), Some(max)) = (query.min_criteria, query.max_criteria) {
+            if min > max {
+                return Err(UserStoryServiceError::Validation {
+                    message: "min_criteria cannot exceed max_criteria".to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
     /// Get user stories grouped by persona
     pub async fn get_grouped_by_persona(&self) -> Result<HashMap<String, Vec<UserStory>>> {
         Ok(self
@@ -244,11 +624,15 @@ impl UserStoryService {
 
     /// Get statistics about user stories
     pub async fn get_statistics(&self) -> Result<UserStoryStatistics> {
-        let total_stories = self.repositories.user_stories.count().await?;
+        let stories = &self.repositories.user_stories;
+        let total_stories = stories.count().await?;
         let total_criteria = self.repositories.acceptance_criteria.count().await?;
-        let grouped_by_persona = self.get_grouped_by_persona().await?;
+        let stories_by_persona = stories.count_by_persona().await?;
+        let stories_by_assignee = stories.count_by_member(AssignmentRole::Assignee).await?;
+        let stories_by_reviewer = stories.count_by_member(AssignmentRole::Reviewer).await?;
+        let unassigned_stories = stories.count_unassigned().await?;
 
-        let personas_count = grouped_by_persona.len() as i64;
+        let personas_count = stories_by_persona.len() as i64;
         let avg_criteria_per_story = if total_stories > 0 {
             total_criteria as f64 / total_stories as f64
         } else {
@@ -260,120 +644,229 @@ impl UserStoryService {
             total_criteria,
             personas_count,
             avg_criteria_per_story,
-            stories_by_persona: grouped_by_persona
-                .into_iter()
-                .map(|(persona, stories)| (persona, stories.len() as i64))
-                .collect(),
+            stories_by_persona,
+            stories_by_assignee,
+            stories_by_reviewer,
+            unassigned_stories,
         })
     }
 
-    /// Validate create request
-    fn validate_create_request(&self, request: &CreateUserStoryRequest) -> Result<()> {
-        if request.id.trim().is_empty() {
+    async fn ensure_available(&self, id: &str) -> Result<()> {
+        // Ids of stories in the trash stay taken until they are purged
+        if self.repositories.user_stories.exists(id).await? {
+            return Err(UserStoryServiceError::AlreadyExists { id: id.to_string() });
+        }
+        Ok(())
+    }
+
+    /// Generate the next story id of the id scheme
+    async fn next_id(&self) -> Result<String> {
+        Ok(self
+            .repositories
+            .id_sequences
+            .next_story_id(&self.id_scheme)
+            .await?)
+    }
+
+    /// Find stories similar to a stored story, or to free text when no story has that ID
+    pub async fn find_similar(
+        &self,
+        id_or_text: &str,
+        threshold: f64,
+    ) -> Result<Vec<SimilarStory>> {
+        if id_or_text.trim().is_empty() {
             return Err(UserStoryServiceError::Validation {
-                message: "User story ID cannot be empty".to_string(),
+                message: "Story ID or text cannot be empty".to_string(),
+            });
+        }
+        Self::validate_threshold(threshold)?;
+
+        let corpus = self.get_all_with_criteria().await?;
+        let query = if corpus.iter().any(|s| s.user_story.id == id_or_text.trim()) {
+            SimilarityQuery::Story(id_or_text.trim())
+        } else {
+            SimilarityQuery::Text(id_or_text)
+        };
+
+        Ok(similarity::find_similar(&corpus, query, threshold))
+    }
+
+    /// Look for stories resembling a new story; rejects it when `check` says so
+    async fn check_duplicates(
+        &self,
+        title: &str,
+        text: &str,
+        check: DuplicateCheck,
+    ) -> Result<Vec<SimilarStory>> {
+        if check.mode == DuplicateMode::Off {
+            return Ok(Vec::new());
+        }
+        Self::validate_threshold(check.threshold)?;
+
+        let corpus = self.get_all_with_criteria().await?;
+        let similar =
+            similarity::find_similar(&corpus, SimilarityQuery::Text(text), check.threshold);
+
+        if check.mode == DuplicateMode::Reject && !similar.is_empty() {
+            return Err(UserStoryServiceError::PossibleDuplicate {
+                title: title.to_string(),
+                similar,
             });
         }
 
-        if request.title.trim().is_empty() {
+        Ok(similar)
+    }
+
+    fn validate_threshold(threshold: f64) -> Result<()> {
+        if !(threshold > 0.0 && threshold <= 1.0) {
             return Err(UserStoryServiceError::Validation {
-                message: "User story title cannot be empty".to_string(),
+                message: "Similarity threshold must be greater than 0 and at most 1".to_string(),
             });
         }
+        Ok(())
+    }
+
+    /// Validate create request
+    pub(crate) fn validate_create_request(&self, request: &CreateUserStoryRequest) -> Result<()> {
+        if let Some(ref id) = request.id {
+            self.validate_id(id)?;
+        }
+
+        self.validate_title(&request.title)?;
+        self.validate_description(&request.description)?;
+
+        self.validate_persona(&request.persona)?;
+
+        Ok(())
+    }
+
+    /// Validate update request
+    pub(crate) fn validate_update_request(&self, request: &UpdateUserStoryRequest) -> Result<()> {
+        if let Some(ref title) = request.title {
+            self.validate_title(title)?;
+        }
+
+        if let Some(ref description) = request.description {
+            self.validate_description(description)?;
+        }
 
-        if request.description.trim().is_empty() {
+        if let Some(ref persona) = request.persona {
+            self.validate_persona(persona)?;
+        }
+
+        Ok(())
+    }
+
+    fn validate_id(&self, id: &str) -> Result<()> {
+        if id.trim().is_empty() {
             return Err(UserStoryServiceError::Validation {
-                message: "User story description cannot be empty".to_string(),
+                message: "User story ID cannot be empty".to_string(),
             });
         }
 
-        if request.persona.trim().is_empty() {
+        // Validate ID format against the policy, or the story prefix of the id scheme
+        match self.policy.story_id_pattern {
+            Some(ref pattern) if !pattern.is_match(id) => Err(UserStoryServiceError::Validation {
+                message: format!("User story ID should match '{}'", pattern),
+            }),
+            None if !id.starts_with(&self.id_scheme.story_prefix) => {
+                Err(UserStoryServiceError::Validation {
+                    message: format!(
+                        "User story ID should start with '{}'",
+                        self.id_scheme.story_prefix
+                    ),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Personas are stored trimmed, so surrounding whitespace does not count
+    fn validate_persona(&self, persona: &str) -> Result<()> {
+        let persona = persona.trim();
+        if persona.is_empty() {
             return Err(UserStoryServiceError::Validation {
                 message: "User story persona cannot be empty".to_string(),
             });
         }
 
-        // Validate ID format (should follow US-XXX pattern)
-        if !request.id.starts_with("US-") {
+        if persona.len() > MAX_PERSONA_NAME_LENGTH {
             return Err(UserStoryServiceError::Validation {
-                message: "User story ID should start with 'US-'".to_string(),
+                message: format!(
+                    "User story persona cannot exceed {} characters",
+                    MAX_PERSONA_NAME_LENGTH
+                ),
             });
         }
 
-        // Validate title length
-        if request.title.len() > 200 {
+        Ok(())
+    }
+
+    fn validate_title(&self, title: &str) -> Result<()> {
+        if self.policy.requires(StoryField::Title) && title.trim().is_empty() {
             return Err(UserStoryServiceError::Validation {
-                message: "User story title cannot exceed 200 characters".to_string(),
+                message: "User story title cannot be empty".to_string(),
             });
         }
 
-        // Validate description length
-        if request.description.len() > 2000 {
+        let max_length = self.policy.story_title_max_length;
+        if title.len() > max_length {
             return Err(UserStoryServiceError::Validation {
-                message: "User story description cannot exceed 2000 characters".to_string(),
+                message: format!("User story title cannot exceed {} characters", max_length),
             });
         }
 
         Ok(())
     }
 
-    /// Validate update request
-    fn validate_update_request(&self, request: &UpdateUserStoryRequest) -> Result<()> {
-        if let Some(ref title) = request.title {
-            if title.trim().is_empty() {
-                return Err(UserStoryServiceError::Validation {
-                    message: "User story title cannot be empty".to_string(),
-                });
-            }
-            if title.len() > 200 {
-                return Err(UserStoryServiceError::Validation {
-                    message: "User story title cannot exceed 200 characters".to_string(),
-                });
-            }
-        }
-
-        if let Some(ref description) = request.description {
-            if description.trim().is_empty() {
-                return Err(UserStoryServiceError::Validation {
-                    message: "User story description cannot be empty".to_string(),
-                });
-            }
-            if description.len() > 2000 {
-                return Err(UserStoryServiceError::Validation {
-                    message: "User story description cannot exceed 2000 characters".to_string(),
-                });
-            }
+    fn validate_description(&self, description: &str) -> Result<()> {
+        if self.policy.requires(StoryField::Description) && description.trim().is_empty() {
+            return Err(UserStoryServiceError::Validation {
+                message: "User story description cannot be empty".to_string(),
+            });
         }
 
-        if let Some(ref persona) = request.persona {
-            if persona.trim().is_empty() {
-                return Err(UserStoryServiceError::Validation {
-                    message: "User story persona cannot be empty".to_string(),
-                });
-            }
+        let max_length = self.policy.story_description_max_length;
+        if description.len() > max_length {
+            return Err(UserStoryServiceError::Validation {
+                message: format!(
+                    "User story description cannot exceed {} characters",
+                    max_length
+                ),
+            });
         }
 
         Ok(())
     }
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, schemars::JsonSchema)]
 pub struct UserStoryStatistics {
     pub total_stories: i64,
     pub total_criteria: i64,
     pub personas_count: i64,
     pub avg_criteria_per_story: f64,
     pub stories_by_persona: HashMap<String, i64>,
+    /// Workload: the number of stories assigned to each member
+    pub stories_by_assignee: HashMap<String, i64>,
+    pub stories_by_reviewer: HashMap<String, i64>,
+    pub unassigned_stories: i64,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::{CreateMemberRequest, IdPattern, MemberKind, SortDirection};
     use crate::repositories::Repositories;
+    use std::sync::Arc;
+
+    fn ids(stories: &[UserStory]) -> Vec<&str> {
+        stories.iter().map(|story| story.id.as_str()).collect()
+    }
 
     fn create_test_request() -> CreateUserStoryRequest {
         CreateUserStoryRequest {
-            id: "US-TEST-001".to_string(),
+            id: Some("US-TEST-001".to_string()),
             title: "Test User Story".to_string(),
             description: "As a user, I want to test this functionality".to_string(),
             persona: "Test User".to_string(),
@@ -390,7 +883,7 @@ mod tests {
         assert!(result.is_ok());
 
         let user_story = result.unwrap();
-        assert_eq!(user_story.id, request.id);
+        assert_eq!(Some(user_story.id), request.id);
         assert_eq!(user_story.title, request.title);
         assert_eq!(user_story.description, request.description);
         assert_eq!(user_story.persona, request.persona);
@@ -419,7 +912,7 @@ mod tests {
         let repositories = Repositories::new(pool);
         let service = UserStoryService::new(repositories);
         let mut request = create_test_request();
-        request.id = "".to_string();
+        request.id = Some("".to_string());
 
         let result = service.create(request).await;
         assert!(result.is_err());
@@ -434,7 +927,7 @@ mod tests {
         let repositories = Repositories::new(pool);
         let service = UserStoryService::new(repositories);
         let mut request = create_test_request();
-        request.id = "INVALID-001".to_string();
+        request.id = Some("INVALID-001".to_string());
 
         let result = service.create(request).await;
         assert!(result.is_err());
@@ -452,13 +945,13 @@ mod tests {
 
         let criteria_requests = vec![
             CreateAcceptanceCriteriaRequest {
-                id: "AC-TEST-001".to_string(),
-                user_story_id: user_story_request.id.clone(),
+                id: Some("AC-TEST-001".to_string()),
+                user_story_id: user_story_request.id.clone().unwrap(),
                 description: "First criteria".to_string(),
             },
             CreateAcceptanceCriteriaRequest {
-                id: "AC-TEST-002".to_string(),
-                user_story_id: user_story_request.id.clone(),
+                id: Some("AC-TEST-002".to_string()),
+                user_story_id: user_story_request.id.clone().unwrap(),
                 description: "Second criteria".to_string(),
             },
         ];
@@ -469,10 +962,63 @@ mod tests {
 
         assert!(result.is_ok());
         let story_with_criteria = result.unwrap();
-        assert_eq!(story_with_criteria.user_story.id, user_story_request.id);
+        assert_eq!(
+            Some(story_with_criteria.user_story.id),
+            user_story_request.id
+        );
         assert_eq!(story_with_criteria.acceptance_criteria.len(), 2);
     }
 
+    #[sqlx::test]
+    async fn test_create_with_criteria_validation_policy(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let service =
+            UserStoryService::new(repositories.clone()).with_validation_policy(ValidationPolicy {
+                max_criteria_per_story: 2,
+                criteria_description_max_length: 20,
+                ..Default::default()
+            });
+        let criteria = |description: &str| CreateAcceptanceCriteriaRequest {
+            id: None,
+            user_story_id: String::new(),
+            description: description.to_string(),
+        };
+
+        let too_many = service
+            .create_with_criteria(
+                create_test_request(),
+                vec![criteria("One"), criteria("Two"), criteria("Three")],
+            )
+            .await;
+        assert!(matches!(
+            too_many,
+            Err(UserStoryServiceError::BusinessRule { .. })
+        ));
+
+        for description in ["  ", "A description far over the limit"] {
+            let result = service
+                .create_with_criteria(create_test_request(), vec![criteria(description)])
+                .await;
+            assert!(matches!(
+                result,
+                Err(UserStoryServiceError::Validation { .. })
+            ));
+        }
+
+        let mut bad_id = criteria("One");
+        bad_id.id = Some("CRIT-1".to_string());
+        let result = service
+            .create_with_criteria(create_test_request(), vec![bad_id])
+            .await;
+        assert!(matches!(
+            result,
+            Err(UserStoryServiceError::Validation { .. })
+        ));
+
+        // Nothing was written by the rejected requests
+        assert_eq!(repositories.user_stories.count().await.unwrap(), 0);
+    }
+
     #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
     async fn test_get_by_id(pool: sqlx::SqlitePool) {
         let repositories = Repositories::new(pool);
@@ -527,6 +1073,7 @@ mod tests {
             title: Some("Updated User Login Feature".to_string()),
             description: None,
             persona: Some("Updated Persona".to_string()),
+            expected_version: None,
         };
 
         let result = service.update("US-001", update_request).await;
@@ -569,6 +1116,165 @@ mod tests {
         assert_eq!(stories[0].id, "US-001");
     }
 
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_query_user_stories(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let service = UserStoryService::new(repositories);
+
+        let stories = service
+            .query(StoryQuery {
+                personas: vec!["  New User ".to_string()],
+                text: Some(" account ".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(stories.items.len(), 1);
+        assert_eq!(stories.items[0].id, "US-002");
+        assert!(stories.next_cursor.is_none());
+
+        // Limits above the page size are cut down rather than rejected
+        let stories = service
+            .query(StoryQuery {
+                limit: Some(MAX_PAGE_SIZE * 10),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(stories.items.len(), 5);
+        assert!(stories.next_cursor.is_none());
+
+        let invalid = [
+            StoryQuery {
+                personas: vec![" ".to_string()],
+                ..Default::default()
+            },
+            StoryQuery {
+                text: Some(String::new()),
+                ..Default::default()
+            },
+            StoryQuery {
+                created_after: "2024-01-02T00:00:00".parse().ok(),
+                created_before: "2024-01-01T00:00:00".parse().ok(),
+                ..Default::default()
+            },
+            StoryQuery {
+                min_criteria: Some(3),
+                max_criteria: Some(1),
+                ..Default::default()
+            },
+            StoryQuery {
+                limit: Some(0),
+                ..Default::default()
+            },
+        ];
+        for query in invalid {
+            assert!(matches!(
+                service.query(query).await.unwrap_err(),
+                UserStoryServiceError::Validation { .. }
+            ));
+        }
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_query_cursor(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        repositories
+            .members
+            .create(CreateMemberRequest {
+                name: "dana".to_string(),
+                kind: MemberKind::Human,
+            })
+            .await
+            .unwrap();
+        let service = UserStoryService::new(repositories);
+        for id in ["US-002", "US-004"] {
+            service
+                .assign(id, AssignmentRole::Assignee, "dana", None)
+                .await
+                .unwrap();
+        }
+
+        // Following the cursors yields every story once, in the order of the whole query,
+        // including sort keys that are missing or shared by several stories
+        for sort_by in [
+            StorySortField::Assignee,
+            StorySortField::Persona,
+            StorySortField::CreatedAt,
+            StorySortField::CriteriaCount,
+        ] {
+            for direction in [SortDirection::Asc, SortDirection::Desc] {
+                let query = StoryQuery {
+                    sort_by,
+                    direction,
+                    ..Default::default()
+                };
+                let all = service.query(query.clone()).await.unwrap().items;
+
+                let mut paged = Vec::new();
+                let mut cursor = None;
+                loop {
+                    let page = service
+                        .query(StoryQuery {
+                            limit: Some(2),
+                            cursor,
+                            ..query.clone()
+                        })
+                        .await
+                        .unwrap();
+                    assert!(page.items.len() <= 2);
+                    paged.extend(page.items);
+                    match page.next_cursor {
+                        Some(next) => cursor = Some(next),
+                        None => break,
+                    }
+                }
+                assert_eq!(ids(&paged), ids(&all), "{sort_by:?} {direction:?}");
+            }
+        }
+
+        // Deleting a story already read does not shift the next page
+        let first = service
+            .query(StoryQuery {
+                limit: Some(2),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(ids(&first.items), ["US-005", "US-004"]);
+        service.delete("US-005").await.unwrap();
+        let next = service
+            .query(StoryQuery {
+                limit: Some(2),
+                cursor: first.next_cursor.clone(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(ids(&next.items), ["US-003", "US-002"]);
+
+        let invalid = [
+            StoryQuery {
+                cursor: Some("not-a-cursor".to_string()),
+                ..Default::default()
+            },
+            StoryQuery {
+                cursor: first.next_cursor,
+                sort_by: StorySortField::Title,
+                ..Default::default()
+            },
+        ];
+        for query in invalid {
+            assert!(matches!(
+                service.query(query).await.unwrap_err(),
+                UserStoryServiceError::Validation { .. }
+            ));
+        }
+    }
+
     #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
     async fn test_get_by_persona(pool: sqlx::SqlitePool) {
         let repositories = Repositories::new(pool);
@@ -583,23 +1289,35 @@ mod tests {
     }
 
     #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
-    async fn test_get_paginated(pool: sqlx::SqlitePool) {
+    async fn test_get_page(pool: sqlx::SqlitePool) {
         let repositories = Repositories::new(pool);
         let service = UserStoryService::new(repositories);
 
-        // Get first 2 user stories
-        let result = service.get_paginated(2, 0).await;
-        assert!(result.is_ok());
-
-        let stories = result.unwrap();
-        assert_eq!(stories.len(), 2);
-
-        // Get next 2 user stories
-        let result = service.get_paginated(2, 2).await;
-        assert!(result.is_ok());
-
-        let stories = result.unwrap();
-        assert_eq!(stories.len(), 2);
+        // Follow the cursors through the whole backlog
+        let mut ids = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = service.get_page(cursor.as_deref(), 2).await.unwrap();
+            assert!(page.items.len() <= 2);
+            ids.extend(page.items.into_iter().map(|story| story.id));
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        assert_eq!(ids, ["US-005", "US-004", "US-003", "US-002", "US-001"]);
+
+        // Limits beyond the largest page are cut down rather than refused
+        let page = service.get_page(None, MAX_PAGE_SIZE + 1).await.unwrap();
+        assert_eq!(page.items.len(), 5);
+        assert!(page.next_cursor.is_none());
+
+        for (cursor, limit) in [(None, 0), (Some("not-a-cursor"), 10)] {
+            assert!(matches!(
+                service.get_page(cursor, limit).await.unwrap_err(),
+                UserStoryServiceError::Validation { .. }
+            ));
+        }
     }
 
     #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
@@ -632,4 +1350,373 @@ mod tests {
         assert_eq!(stats.personas_count, 3); // Should have 3 unique personas
         assert_eq!(stats.avg_criteria_per_story, 2.0);
     }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_persona_names_ignore_case(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let service = UserStoryService::new(repositories);
+
+        let mut request = create_test_request();
+        request.persona = "registered user".to_string();
+        let story = service.create(request).await.unwrap();
+        assert_eq!(story.persona, "Registered User");
+
+        let grouped = service.get_grouped_by_persona().await.unwrap();
+        assert_eq!(grouped.len(), 3);
+        assert_eq!(grouped["Registered User"].len(), 4);
+        assert_eq!(
+            service
+                .get_by_persona("REGISTERED USER")
+                .await
+                .unwrap()
+                .len(),
+            4
+        );
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_persona_names_are_trimmed_and_limited(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let service = UserStoryService::new(repositories);
+
+        let mut request = create_test_request();
+        request.persona = "  Registered User ".to_string();
+        let story = service.create(request).await.unwrap();
+        assert_eq!(story.persona, "Registered User");
+        assert_eq!(service.get_grouped_by_persona().await.unwrap().len(), 3);
+
+        let mut request = password_reset_request();
+        request.persona = "x".repeat(MAX_PERSONA_NAME_LENGTH + 1);
+        assert!(matches!(
+            service.create(request).await,
+            Err(UserStoryServiceError::Validation { .. })
+        ));
+        let update = UpdateUserStoryRequest {
+            title: None,
+            description: None,
+            persona: Some("x".repeat(MAX_PERSONA_NAME_LENGTH + 1)),
+            expected_version: None,
+        };
+        assert!(matches!(
+            service.update("US-001", update).await,
+            Err(UserStoryServiceError::Validation { .. })
+        ));
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_get_with_persona(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let service = UserStoryService::new(repositories);
+
+        let story = service.get_with_persona("US-001").await.unwrap();
+        assert_eq!(story.persona_context.name, "Registered User");
+        assert_eq!(
+            story.persona_context.pain_points,
+            vec!["Forgotten passwords"]
+        );
+
+        assert!(matches!(
+            service.get_with_persona("US-999").await,
+            Err(UserStoryServiceError::NotFound { .. })
+        ));
+    }
+
+    fn password_reset_request() -> CreateUserStoryRequest {
+        CreateUserStoryRequest {
+            id: Some("US-TEST-002".to_string()),
+            title: "Password Reset".to_string(),
+            description:
+                "As a registered user, I want to reset my password so that I can regain access"
+                    .to_string(),
+            persona: "Registered User".to_string(),
+        }
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_find_similar(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let service = UserStoryService::new(repositories);
+
+        let similar = service
+            .find_similar("Reset a forgotten password by email", 0.1)
+            .await
+            .unwrap();
+        assert_eq!(similar[0].user_story_id, "US-003");
+
+        // An existing ID compares that story with the others
+        let similar = service.find_similar("US-003", 0.01).await.unwrap();
+        assert!(similar.iter().all(|s| s.user_story_id != "US-003"));
+
+        assert!(matches!(
+            service.find_similar("US-003", 1.5).await,
+            Err(UserStoryServiceError::Validation { .. })
+        ));
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_create_checked_warns_about_duplicates(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let service = UserStoryService::new(repositories);
+        let check = DuplicateCheck {
+            mode: DuplicateMode::Warn,
+            ..Default::default()
+        };
+
+        let created = service
+            .create_checked(password_reset_request(), check)
+            .await
+            .unwrap();
+        assert_eq!(created.user_story.id, "US-TEST-002");
+        assert_eq!(created.possible_duplicates[0].user_story_id, "US-003");
+
+        // Unrelated stories are created without warnings
+        let created = service
+            .create_checked(create_test_request(), check)
+            .await
+            .unwrap();
+        assert!(created.possible_duplicates.is_empty());
+    }
+
+    #[sqlx::test(fixtures(
+        "../../fixtures/user_stories.sql",
+        "../../fixtures/acceptance_criteria.sql"
+    ))]
+    async fn test_create_rejects_duplicates(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let service = UserStoryService::new(repositories).with_duplicate_check(DuplicateCheck {
+            mode: DuplicateMode::Reject,
+            ..Default::default()
+        });
+
+        let result = service.create(password_reset_request()).await;
+        assert!(matches!(
+            result,
+            Err(UserStoryServiceError::PossibleDuplicate { ref similar, .. })
+                if similar[0].user_story_id == "US-003"
+        ));
+        assert!(matches!(
+            service.get_by_id("US-TEST-002").await,
+            Err(UserStoryServiceError::NotFound { .. })
+        ));
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_create_generates_ids(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let service = UserStoryService::new(repositories);
+
+        let mut request = create_test_request();
+        request.id = None;
+        let story = service.create(request).await.unwrap();
+        assert_eq!(story.id, "US-006");
+
+        let mut request = create_test_request();
+        request.id = None;
+        let criteria = ["First criteria", "Second criteria"]
+            .iter()
+            .map(|description| CreateAcceptanceCriteriaRequest {
+                id: None,
+                user_story_id: String::new(),
+                description: description.to_string(),
+            })
+            .collect();
+        let story = service
+            .create_with_criteria(request, criteria)
+            .await
+            .unwrap();
+        assert_eq!(story.user_story.id, "US-007");
+        let ids: Vec<&str> = story
+            .acceptance_criteria
+            .iter()
+            .map(|criteria| criteria.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["AC-007-1", "AC-007-2"]);
+    }
+
+    #[sqlx::test]
+    async fn test_concurrent_creates_get_distinct_ids(pool: sqlx::SqlitePool) {
+        let service = Arc::new(UserStoryService::new(Repositories::new(pool)));
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for _ in 0..10 {
+            let service = service.clone();
+            tasks.spawn(async move {
+                let mut request = create_test_request();
+                request.id = None;
+                service.create(request).await.unwrap().id
+            });
+        }
+
+        let mut ids = std::collections::HashSet::new();
+        while let Some(id) = tasks.join_next().await {
+            assert!(ids.insert(id.unwrap()));
+        }
+        assert_eq!(ids.len(), 10);
+        assert!(ids.contains("US-001") && ids.contains("US-010"));
+    }
+
+    #[sqlx::test]
+    async fn test_custom_id_scheme(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let service = UserStoryService::new(repositories).with_id_scheme(IdScheme {
+            story_prefix: "SHOP-".to_string(),
+            criteria_prefix: "SHOP-AC-".to_string(),
+            story_number_width: 1,
+        });
+
+        let mut request = create_test_request();
+        request.id = None;
+        assert_eq!(service.create(request).await.unwrap().id, "SHOP-1");
+
+        // Caller-chosen ids must use the configured prefix
+        assert!(matches!(
+            service.create(create_test_request()).await,
+            Err(UserStoryServiceError::Validation { message }) if message.contains("'SHOP-'")
+        ));
+    }
+
+    #[sqlx::test]
+    async fn test_validation_policy(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let service =
+            UserStoryService::new(repositories).with_validation_policy(ValidationPolicy {
+                story_title_max_length: 10,
+                required_story_fields: vec![StoryField::Title],
+                story_id_pattern: Some(IdPattern::new("^US-[0-9]{3}$").unwrap()),
+                ..Default::default()
+            });
+
+        let mut request = create_test_request();
+        request.id = Some("US-001".to_string());
+        request.title = "Short".to_string();
+        request.description = String::new();
+        assert!(service.create(request.clone()).await.is_ok());
+
+        request.id = Some("US-002".to_string());
+        request.title = "Far too long a title".to_string();
+        assert!(matches!(
+            service.create(request.clone()).await,
+            Err(UserStoryServiceError::Validation { message }) if message.contains("10 characters")
+        ));
+
+        request.id = Some("US-TEST".to_string());
+        request.title = "Short".to_string();
+        assert!(matches!(
+            service.create(request).await,
+            Err(UserStoryServiceError::Validation { message })
+                if message.contains("should match '^US-[0-9]{3}$'")
+        ));
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_concurrent_updates_conflict(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let service = Arc::new(UserStoryService::new(repositories));
+        let read = service.get_by_id("US-002").await.unwrap();
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for title in ["Sign up", "Register"] {
+            let service = service.clone();
+            let request = UpdateUserStoryRequest {
+                title: Some(title.to_string()),
+                description: None,
+                persona: None,
+                expected_version: Some(read.version),
+            };
+            tasks.spawn(async move { service.update("US-002", request).await });
+        }
+        let results = tasks.join_all().await;
+
+        let written: Vec<&UserStory> = results.iter().filter_map(|r| r.as_ref().ok()).collect();
+        assert_eq!(written.len(), 1);
+        let conflict = results.iter().find_map(|r| r.as_ref().err()).unwrap();
+        assert!(matches!(
+            conflict,
+            UserStoryServiceError::Conflict { expected_version: 1, current }
+                if current.title == written[0].title && current.version == 2
+        ));
+
+        assert!(matches!(
+            service
+                .delete_at_version("US-002", Some(read.version))
+                .await,
+            Err(UserStoryServiceError::Conflict { .. })
+        ));
+        service.delete_at_version("US-002", Some(2)).await.unwrap();
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/user_stories.sql"))]
+    async fn test_assign_and_workload(pool: sqlx::SqlitePool) {
+        let repositories = Repositories::new(pool);
+        let service = UserStoryService::new(repositories.clone());
+        for name in ["alice", "review-bot"] {
+            repositories
+                .members
+                .create(CreateMemberRequest {
+                    name: name.to_string(),
+                    kind: MemberKind::default(),
+                })
+                .await
+                .unwrap();
+        }
+
+        let story = service
+            .assign("US-001", AssignmentRole::Assignee, "Alice", Some(1))
+            .await
+            .unwrap();
+        assert_eq!(story.assignee.as_deref(), Some("alice"));
+        assert_eq!(story.version, 2);
+        service
+            .assign("US-002", AssignmentRole::Assignee, "alice", None)
+            .await
+            .unwrap();
+        service
+            .assign("US-002", AssignmentRole::Reviewer, "review-bot", None)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            service
+                .assign("US-003", AssignmentRole::Assignee, "nobody", None)
+                .await,
+            Err(UserStoryServiceError::MemberNotFound { .. })
+        ));
+        assert!(matches!(
+            service
+                .assign("US-001", AssignmentRole::Reviewer, "alice", Some(1))
+                .await,
+            Err(UserStoryServiceError::Conflict { .. })
+        ));
+
+        let mine = service.get_by_member("alice", None).await.unwrap();
+        assert_eq!(mine.len(), 2);
+        let reviews = service
+            .get_by_member("review-bot", Some(AssignmentRole::Reviewer))
+            .await
+            .unwrap();
+        assert_eq!(reviews[0].id, "US-002");
+        assert!(service
+            .get_by_member("review-bot", Some(AssignmentRole::Assignee))
+            .await
+            .unwrap()
+            .is_empty());
+
+        let stats = service.get_statistics().await.unwrap();
+        assert_eq!(stats.stories_by_assignee["alice"], 2);
+        assert_eq!(stats.stories_by_reviewer["review-bot"], 1);
+        assert_eq!(stats.unassigned_stories, 3);
+
+        let story = service
+            .unassign("US-001", AssignmentRole::Assignee, None)
+            .await
+            .unwrap();
+        assert!(story.assignee.is_none());
+        assert_eq!(service.get_by_member("alice", None).await.unwrap().len(), 1);
+    }
 }